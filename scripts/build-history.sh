@@ -0,0 +1,20 @@
+#!/bin/bash
+# GitHub: https://github.com/JoshDFN/cleardeck
+# Build the history canister with the archive canister wasm embedded
+#
+# dfx runs this for the "history" canister (see dfx.json). The history
+# canister installs this wasm into each archive canister it creates, so the
+# archive must be built first and handed over through ARCHIVE_WASM_PATH.
+#
+# Usage: ./scripts/build-history.sh
+
+set -e
+
+cd "$(dirname "$0")/.."
+
+TARGET_DIR="$PWD/target/wasm32-unknown-unknown/release"
+
+cargo build --target wasm32-unknown-unknown --release -p archive_canister
+
+ARCHIVE_WASM_PATH="$TARGET_DIR/archive_canister.wasm" \
+    cargo build --target wasm32-unknown-unknown --release -p history_canister
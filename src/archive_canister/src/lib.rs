@@ -0,0 +1,492 @@
+// ============================================================================
+// ⚠️  CRITICAL DEPLOYMENT WARNING ⚠️
+// ============================================================================
+// Archive canisters are created and upgraded by the history canister that
+// owns them. Never reinstall one - the hands it holds exist nowhere else.
+// ============================================================================
+//
+// Hand history archive. Once the history canister passes its archive
+// threshold it creates these canisters (ICRC-3 ledger archive style) and moves
+// its oldest hand ranges into them. An archive answers the same read queries
+// as the history canister, with the same types, for the hands it holds.
+
+use candid::{CandidType, Deserialize, Principal};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap, StableCell, Storable};
+use std::borrow::Cow;
+use std::cell::RefCell;
+
+const MAX_SUMMARIES_PER_QUERY: usize = 100;
+
+// Same formatters the history canister uses - they only need the mirrored hand types
+#[path = "../../history_canister/src/ohh.rs"]
+pub mod ohh;
+#[path = "../../history_canister/src/pokerstars.rs"]
+pub mod pokerstars;
+#[path = "../../history_canister/src/replay.rs"]
+pub mod replay;
+#[path = "../../history_canister/src/storable.rs"]
+pub mod storable;
+
+// ============================================================================
+// TYPES - mirror history_canister hand records (must match history_canister types)
+// ============================================================================
+
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Hash)]
+pub enum Suit {
+    Hearts,
+    Diamonds,
+    Clubs,
+    Spades,
+}
+
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Hash)]
+pub enum Rank {
+    Two = 2,
+    Three = 3,
+    Four = 4,
+    Five = 5,
+    Six = 6,
+    Seven = 7,
+    Eight = 8,
+    Nine = 9,
+    Ten = 10,
+    Jack = 11,
+    Queen = 12,
+    King = 13,
+    Ace = 14,
+}
+
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct Card {
+    pub suit: Suit,
+    pub rank: Rank,
+}
+
+/// Poker variant the hand was played in - matches table_canister GameVariant
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum GameVariant {
+    #[default]
+    TexasHoldem,
+    PotLimitOmaha,
+    OmahaHiLo,
+    ShortDeckHoldem,
+}
+
+/// Currency the table played for - matches table_canister Currency
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum Currency {
+    #[default]
+    ICP, // Amounts in e8s
+    BTC, // Amounts in satoshis
+}
+
+impl Currency {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::ICP => "ICP",
+            Currency::BTC => "BTC",
+        }
+    }
+
+    pub fn decimals(&self) -> u8 {
+        8 // Both ICP and BTC use 8 decimals
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum HandRank {
+    HighCard(Vec<u8>),
+    Pair(u8, Vec<u8>),
+    TwoPair(u8, u8, u8),
+    ThreeOfAKind(u8, Vec<u8>),
+    Straight(u8),
+    Flush(Vec<u8>),
+    FullHouse(u8, u8),
+    FourOfAKind(u8, u8),
+    StraightFlush(u8),
+    RoyalFlush,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum PlayerAction {
+    Fold,
+    Check,
+    Call(u64),      // Amount called
+    Bet(u64),       // Amount bet
+    Raise(u64),     // Total raise amount
+    AllIn(u64),     // All-in amount
+    PostBlind(u64), // Blind posted
+    Straddle(u64),  // Live straddle posted before the deal
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ActionRecord {
+    pub seat: u8,
+    pub principal: Principal,
+    pub action: PlayerAction,
+    pub timestamp: u64,
+    pub phase: String, // "preflop", "flop", "turn", "river"
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerHandRecord {
+    pub seat: u8,
+    pub principal: Principal,
+    pub starting_chips: u64,
+    pub ending_chips: u64,
+    pub hole_cards: Option<(Card, Card)>, // Revealed at showdown or if player won
+    pub final_hand_rank: Option<HandRank>,
+    pub amount_won: u64,
+    pub position: String, // "dealer", "sb", "bb", "utg", etc.
+    #[serde(default)] // Every hole card for any variant (4 for Omaha); None on older records
+    pub all_hole_cards: Option<Vec<Card>>,
+}
+
+impl PlayerHandRecord {
+    /// Revealed hole cards regardless of variant or record age
+    pub fn revealed_hole_cards(&self) -> Vec<Card> {
+        match (&self.all_hole_cards, self.hole_cards) {
+            (Some(cards), _) => cards.clone(),
+            (None, Some((c1, c2))) => vec![c1, c2],
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ShuffleProofRecord {
+    pub seed_hash: String,      // SHA-256 hash of seed (committed before dealing)
+    pub revealed_seed: String,  // The actual seed (revealed after hand)
+    pub timestamp: u64,         // When the commitment was made
+    #[serde(default)] // None unless players mixed their own entropy into the seed
+    pub entropy: Option<PlayerEntropyRecord>,
+    #[serde(default)] // None for hands dealt before shuffles were versioned (cleardeck_shuffle::SHUFFLE_V1)
+    pub shuffle_version: Option<u32>,
+}
+
+/// Player entropy mixed into a seed: seed = SHA-256(VRF bytes || revealed nonces in seat order)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerEntropyRecord {
+    pub vrf_seed_hash: String,  // Published before any nonce was revealed
+    pub vrf_seed: String,       // Revealed with the seed
+    pub contributions: Vec<EntropyContributionRecord>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct EntropyContributionRecord {
+    pub seat: u8,
+    pub principal: Principal,
+    pub commitment: String,     // SHA-256 of the nonce, committed before the deal
+    pub nonce: Option<String>,  // None if not revealed in time - left out of the seed
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandHistoryRecord {
+    // Identifiers
+    pub hand_id: u64,           // Global unique hand ID
+    pub table_id: Principal,    // Table canister ID
+    pub hand_number: u64,       // Hand number at that table
+    pub timestamp: u64,         // When hand started
+
+    // Table config at time of hand
+    pub small_blind: u64,
+    pub big_blind: u64,
+    pub ante: u64,
+
+    // Shuffle proof (for verification)
+    pub shuffle_proof: ShuffleProofRecord,
+
+    // Players involved
+    pub players: Vec<PlayerHandRecord>,
+    pub dealer_seat: u8,
+
+    // Community cards
+    pub flop: Option<(Card, Card, Card)>,
+    pub turn: Option<Card>,
+    pub river: Option<Card>,
+
+    // All actions in order
+    pub actions: Vec<ActionRecord>,
+
+    // Pot info
+    pub total_pot: u64,
+    pub rake: u64, // If any rake is taken
+
+    // Winners
+    pub winners: Vec<WinnerRecord>,
+
+    // Summary
+    pub went_to_showdown: bool,
+
+    // Variant played (None for hands recorded before variants existed - Texas Hold'em)
+    #[serde(default)]
+    pub game_variant: Option<GameVariant>,
+
+    // Sit & Go hand - chips are tournament chips, not currency
+    #[serde(default)]
+    pub is_tournament: Option<bool>,
+
+    // Second runout when the board was run twice (flop/turn/river hold the first)
+    #[serde(default)]
+    pub second_board: Option<Vec<Card>>,
+
+    // Table currency - amounts are e8s (ICP) or sats (BTC); None on older records
+    #[serde(default)]
+    pub currency: Option<Currency>,
+
+    // Seats dealt in, in dealing order - None on older records
+    #[serde(default)]
+    pub dealt_seats: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct WinnerRecord {
+    pub seat: u8,
+    pub principal: Principal,
+    pub amount: u64,
+    pub hand_rank: Option<HandRank>,
+    pub pot_type: String, // "main", or "high"/"low" for the halves of a Hi-Lo pot
+    #[serde(default)]
+    pub run: Option<u8>, // 1 or 2 when the board was run twice
+}
+
+// Query result types
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandSummary {
+    pub hand_id: u64,
+    pub table_id: Principal,
+    pub hand_number: u64,
+    pub timestamp: u64,
+    pub player_count: u8,
+    pub total_pot: u64,
+    pub winners: Vec<WinnerRecord>,
+    pub went_to_showdown: bool,
+    pub game_variant: GameVariant,
+}
+
+/// Answer to get_hand - matches history_canister GetHandResponse
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct GetHandResponse {
+    pub hand: Option<HandHistoryRecord>,
+    pub archive: Option<Principal>, // Always None here - archives don't chain
+}
+
+/// verify_hand_shuffle report - matches history_canister DealVerification
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct DealVerification {
+    pub valid: bool,                // Seed checks out (hash and any player entropy) and every recorded card matches the replay
+    pub seed_matches_hash: bool,
+    pub entropy_matches: Option<bool>, // Seed is the VRF bytes mixed with the revealed nonces; None without player entropy
+    pub shuffle_version: u32,       // Shuffle the deck was replayed with
+    pub dealt_seats: Vec<u8>,       // Seats in dealing order
+    pub dealt_seats_inferred: bool, // Older records don't list who was dealt in - worked out from the hand
+    pub hole_cards_checked: u32,
+    pub board_cards_checked: u32,
+    pub mismatches: Vec<DealMismatch>,
+}
+
+/// Matches history_canister DealMismatch
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct DealMismatch {
+    pub location: String,           // e.g. "Seat 3 hole card 1", "Flop card 2", "Second board river"
+    pub deck_position: Option<u32>, // Where the replay deals it; None if the seat wasn't dealt in
+    pub expected: Option<Card>,     // Card at that position in the replayed deck
+    pub recorded: Card,
+}
+
+/// Install argument, sent by the history canister when it creates the archive
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ArchiveInit {
+    pub history_canister: Principal,
+    pub max_hands: u64,
+}
+
+#[derive(Clone, Debug, Default, CandidType, Deserialize)]
+struct ArchiveConfig {
+    history_canister: Option<Principal>,
+    max_hands: u64,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+// Everything lives in stable memory, so upgrades need no hooks.
+// Never reuse or renumber these IDs.
+const CONFIG_MEMORY: MemoryId = MemoryId::new(0);
+const HANDS_MEMORY: MemoryId = MemoryId::new(1);
+const SCHEMAS_MEMORY: MemoryId = MemoryId::new(2); // Record schemas - see storable.rs
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+/// Store a candid type in a stable structure
+macro_rules! impl_storable_candid {
+    ($($t:ty),*) => {$(
+        impl Storable for $t {
+            fn to_bytes(&self) -> Cow<'_, [u8]> {
+                Cow::Owned(storable::encode_record(self))
+            }
+
+            fn from_bytes(bytes: Cow<[u8]>) -> Self {
+                storable::decode_record(&bytes)
+            }
+
+            const BOUND: Bound = Bound::Unbounded;
+        }
+    )*};
+}
+
+impl_storable_candid!(HandHistoryRecord, ArchiveConfig);
+
+thread_local! {
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+    static CONFIG: RefCell<StableCell<ArchiveConfig, Memory>> = RefCell::new(
+        StableCell::init(memory(CONFIG_MEMORY), ArchiveConfig::default())
+            .expect("Failed to initialize archive config")
+    );
+    static HANDS: RefCell<StableBTreeMap<u64, HandHistoryRecord, Memory>> =
+        RefCell::new(StableBTreeMap::init(memory(HANDS_MEMORY)));
+}
+
+fn memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(id))
+}
+
+fn remaining(config: &ArchiveConfig) -> u64 {
+    config.max_hands.saturating_sub(HANDS.with(|h| h.borrow().len()))
+}
+
+#[ic_cdk::init]
+fn init(args: ArchiveInit) {
+    CONFIG.with(|c| {
+        c.borrow_mut().set(ArchiveConfig {
+            history_canister: Some(args.history_canister),
+            max_hands: args.max_hands,
+        })
+        .expect("Failed to save archive config");
+    });
+}
+
+// ============================================================================
+// WRITE FUNCTIONS (called by the history canister)
+// ============================================================================
+
+/// Store a batch of hands moved out of the history canister
+/// Returns how many more hands this archive can take
+#[ic_cdk::update]
+fn append_hands(hands: Vec<HandHistoryRecord>) -> Result<u64, String> {
+    let config = CONFIG.with(|c| c.borrow().get().clone());
+    if config.history_canister != Some(ic_cdk::api::msg_caller()) {
+        return Err("Unauthorized: only the history canister can append hands".to_string());
+    }
+    if hands.len() as u64 > remaining(&config) {
+        return Err(format!("Archive full: room for {} more hands", remaining(&config)));
+    }
+
+    HANDS.with(|h| {
+        let mut stored = h.borrow_mut();
+        for hand in hands {
+            stored.insert(hand.hand_id, hand);
+        }
+    });
+
+    Ok(remaining(&config))
+}
+
+// ============================================================================
+// QUERY FUNCTIONS
+// ============================================================================
+
+#[ic_cdk::query]
+fn get_hand(hand_id: u64) -> GetHandResponse {
+    GetHandResponse {
+        hand: HANDS.with(|h| h.borrow().get(&hand_id)),
+        archive: None,
+    }
+}
+
+/// Summaries for hand IDs the history canister pointed at this archive
+#[ic_cdk::query]
+fn get_hand_summaries(hand_ids: Vec<u64>) -> Vec<HandSummary> {
+    HANDS.with(|h| {
+        let hands = h.borrow();
+        hand_ids.iter()
+            .take(MAX_SUMMARIES_PER_QUERY)
+            .filter_map(|id| hands.get(id))
+            .map(|hand| to_summary(&hand))
+            .collect()
+    })
+}
+
+/// Check the revealed seed against its commitment, then replay the deal from it and
+/// compare every recorded card - a report of anything that doesn't match
+#[ic_cdk::query]
+fn verify_hand_shuffle(hand_id: u64) -> Result<DealVerification, String> {
+    let hand = HANDS.with(|h| h.borrow().get(&hand_id))
+        .ok_or("Hand not found")?;
+    replay::verify_deal(&hand)
+}
+
+/// One hand in PokerStars text hand history format
+#[ic_cdk::query]
+fn export_hand_pokerstars(hand_id: u64) -> Result<String, String> {
+    HANDS.with(|h| h.borrow().get(&hand_id))
+        .map(|hand| pokerstars::format_hand(&hand))
+        .ok_or_else(|| "Hand not found".to_string())
+}
+
+/// One hand as an Open Hand History JSON document
+#[ic_cdk::query]
+fn export_hand_ohh(hand_id: u64) -> Result<String, String> {
+    HANDS.with(|h| h.borrow().get(&hand_id))
+        .map(|hand| ohh::format_hand(&hand))
+        .ok_or_else(|| "Hand not found".to_string())
+}
+
+#[ic_cdk::query]
+fn get_total_hands() -> u64 {
+    HANDS.with(|h| h.borrow().len())
+}
+
+#[ic_cdk::query]
+fn remaining_capacity() -> u64 {
+    CONFIG.with(|c| remaining(c.borrow().get()))
+}
+
+// ============================================================================
+// HELPERS
+// ============================================================================
+
+fn to_summary(hand: &HandHistoryRecord) -> HandSummary {
+    HandSummary {
+        hand_id: hand.hand_id,
+        table_id: hand.table_id,
+        hand_number: hand.hand_number,
+        timestamp: hand.timestamp,
+        player_count: hand.players.len() as u8,
+        total_pot: hand.total_pot,
+        winners: hand.winners.clone(),
+        went_to_showdown: hand.went_to_showdown,
+        game_variant: hand.game_variant.unwrap_or_default(),
+    }
+}
+
+// Add hex encoding support
+mod hex {
+    pub fn decode(s: &str) -> Result<Vec<u8>, ()> {
+        if !s.len().is_multiple_of(2) {
+            return Err(());
+        }
+
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| ()))
+            .collect()
+    }
+}
+
+// Candid export
+ic_cdk::export_candid!();
@@ -0,0 +1,38 @@
+// Unit tests for archive canister core logic
+// These tests verify pure functions without IC infrastructure
+
+// =============================================================================
+// HELPERS (mirror the canister functions for testing)
+// =============================================================================
+
+fn remaining(max_hands: u64, stored: u64) -> u64 {
+    max_hands.saturating_sub(stored)
+}
+
+fn can_append(max_hands: u64, stored: u64, batch: u64) -> bool {
+    batch <= remaining(max_hands, stored)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_append_fills_to_capacity() {
+        assert!(can_append(1000, 0, 1000));
+        assert!(can_append(1000, 900, 100));
+        assert!(!can_append(1000, 901, 100));
+        assert_eq!(remaining(1000, 1000), 0);
+    }
+
+    #[test]
+    fn test_remaining_never_underflows() {
+        // max_hands is fixed at install; a smaller value can never wrap
+        assert_eq!(remaining(10, 25), 0);
+        assert!(!can_append(10, 25, 1));
+    }
+}
@@ -107,6 +107,16 @@ export const history = buildingOrTesting
     ? dummyActor()
     : createAuthenticatedActor(historyIdlFactory, historyCanisterId);
 
+// History archive canisters answer the history read queries (get_hand,
+// get_hand_summaries, verify_hand_shuffle) for the old hands they hold
+export function createHistoryArchiveActor(archiveCanisterId) {
+    if (buildingOrTesting) {
+        return dummyActor();
+    }
+
+    return createAuthenticatedActor(historyIdlFactory, archiveCanisterId);
+}
+
 // Table actor factory - creates an actor for a specific table canister
 // This is used when joining different tables that each have their own canister
 export async function createTableActor(tableCanisterId) {
@@ -0,0 +1,55 @@
+// Player entropy for tables with player_entropy enabled
+// Commits a fresh secret nonce for every hand, then reveals it once the deal closes commitments
+
+import logger from './logger.js';
+
+// hand_number -> hex nonce. Memory only: a lost nonce is simply left out of the seed
+const nonces = new Map();
+let inFlight = false;
+
+function toHex(bytes) {
+  return Array.from(bytes).map(b => b.toString(16).padStart(2, '0')).join('');
+}
+
+async function sha256Hex(bytes) {
+  return toHex(new Uint8Array(await crypto.subtle.digest('SHA-256', bytes)));
+}
+
+/**
+ * Take part in the next hand's entropy round - safe to call on every poll
+ * @param {object} tableActor - table canister actor
+ * @param {object} tableView - the caller's TableView
+ */
+export async function syncEntropy(tableActor, tableView) {
+  if (inFlight || !tableView?.config?.player_entropy?.length || !tableView.my_seat?.length) return;
+  inFlight = true;
+
+  try {
+    const nextHand = tableView.hand_number + 1n;
+    for (const hand of nonces.keys()) {
+      if (hand < nextHand) nonces.delete(hand);
+    }
+
+    const [round] = await tableActor.get_entropy_round();
+    const current = round && round.hand_number === nextHand ? round : null;
+    const closed = current !== null && current.vrf_seed_hash.length > 0;
+    const mine = current?.contributions.find(c => c.seat === tableView.my_seat[0]);
+
+    if (!mine && !closed) {
+      const nonce = crypto.getRandomValues(new Uint8Array(32));
+      const result = await tableActor.commit_entropy(await sha256Hex(nonce));
+      if ('Ok' in result) {
+        nonces.set(result.Ok, toHex(nonce));
+      } else {
+        logger.debug('Entropy commit rejected:', result.Err);
+      }
+    } else if (mine && closed && mine.nonce.length === 0 && nonces.has(nextHand)) {
+      const result = await tableActor.reveal_entropy(nonces.get(nextHand));
+      if ('Err' in result) {
+        logger.debug('Entropy reveal rejected:', result.Err);
+      }
+    }
+  } finally {
+    inFlight = false;
+  }
+}
@@ -0,0 +1,19 @@
+// ============================================================================
+// ClearDeck payouts
+// ============================================================================
+// The one copy of the prize split. Sit & Go tables and the multi-table
+// tournament canister both pay out with it, so the same pool and payout
+// table always round to the same prizes.
+
+/// Split the prize pool by the payout table (basis points per finishing
+/// position, first place first). Rounding dust goes to first place.
+pub fn tournament_payouts(prize_pool: u64, payouts_bps: &[u32]) -> Vec<u64> {
+    let mut prizes: Vec<u64> = payouts_bps.iter()
+        .map(|&bps| (prize_pool as u128 * bps as u128 / 10_000) as u64)
+        .collect();
+    let paid: u64 = prizes.iter().sum();
+    if let Some(first) = prizes.first_mut() {
+        *first += prize_pool.saturating_sub(paid);
+    }
+    prizes
+}
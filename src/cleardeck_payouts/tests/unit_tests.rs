@@ -0,0 +1,28 @@
+// Unit tests for the shared prize split
+
+use cleardeck_payouts::tournament_payouts;
+
+#[test]
+fn test_payouts_split_pool() {
+    // 6 players x 100 buy-in, 65/35
+    assert_eq!(tournament_payouts(600, &[6500, 3500]), vec![390, 210]);
+    assert_eq!(tournament_payouts(600, &[10000]), vec![600]);
+}
+
+#[test]
+fn test_payouts_dust_to_winner() {
+    // 50/30/20 of 1001 rounds down to 500/300/200 - the leftover chip goes to 1st
+    let prizes = tournament_payouts(1001, &[5000, 3000, 2000]);
+    assert_eq!(prizes, vec![501, 300, 200]);
+    assert_eq!(prizes.iter().sum::<u64>(), 1001);
+}
+
+#[test]
+fn test_payouts_huge_pool_does_not_overflow() {
+    assert_eq!(tournament_payouts(u64::MAX, &[10000]), vec![u64::MAX]);
+}
+
+#[test]
+fn test_payouts_empty_table() {
+    assert!(tournament_payouts(600, &[]).is_empty());
+}
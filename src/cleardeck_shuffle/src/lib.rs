@@ -0,0 +1,152 @@
+// ============================================================================
+// ClearDeck shuffle
+// ============================================================================
+// The one copy of the deck order and shuffle every hand is dealt from. The
+// table canister deals with it, the history and archive canisters replay
+// recorded hands with it, and the offline verifier (cleardeck_verifier)
+// checks hands with it - so a verification always runs the exact code that
+// dealt the cards. Changing anything here changes every past deal's replay:
+// a new shuffle gets a new SHUFFLE_V* and the old ones stay, untouched, for
+// the hands already dealt with them.
+// The same goes for mixing player entropy into a seed (mix_entropy).
+//
+// Cards are plain (suit index, rank) pairs so each crate can keep its own
+// Card type: suit indexes follow SUIT_ORDER, ranks run 2..=14 (Ace high).
+
+use sha2::{Digest, Sha256};
+
+/// Suit order of the unshuffled deck - indexes in deck_order point into this
+pub const SUIT_ORDER: [&str; 4] = ["Hearts", "Diamonds", "Clubs", "Spades"];
+
+pub const ACE: u8 = 14;
+
+/// The unshuffled deck as (suit index, rank) - Hearts, Diamonds, Clubs, Spades,
+/// each from `lowest_rank` (2, or 6 for the short deck) up to Ace
+pub fn deck_order(lowest_rank: u8) -> Vec<(usize, u8)> {
+    (0..SUIT_ORDER.len())
+        .flat_map(|suit| (lowest_rank..=ACE).map(move |rank| (suit, rank)))
+        .collect()
+}
+
+/// Fisher-Yates taking `hash % (i + 1)` - every hand dealt before versions were recorded
+pub const SHUFFLE_V1: u32 = 1;
+/// Fisher-Yates with rejection sampling - no modulo bias
+pub const SHUFFLE_V2: u32 = 2;
+/// The version new hands are dealt with
+pub const SHUFFLE_VERSION: u32 = SHUFFLE_V2;
+
+/// Shuffle with the version recorded in a hand's proof. None is a proof from
+/// before versions were recorded, which was always dealt with SHUFFLE_V1.
+pub fn shuffle<T>(deck: &mut [T], seed: &[u8], version: Option<u32>) -> Result<(), String> {
+    match version.unwrap_or(SHUFFLE_V1) {
+        SHUFFLE_V1 => shuffle_v1(deck, seed),
+        SHUFFLE_V2 => shuffle_v2(deck, seed),
+        other => return Err(format!("Unknown shuffle version {}", other)),
+    }
+    Ok(())
+}
+
+/// One link of the hash chain: SHA256(previous_hash || i), read as a little-endian u64
+fn next_random(hash_input: &mut Vec<u8>, i: usize) -> u64 {
+    let mut hasher = Sha256::new();
+    hasher.update(&*hash_input);
+    hasher.update([i as u8]);
+    let hash_result = hasher.finalize();
+    *hash_input = hash_result.to_vec();
+
+    // SHA256 always produces 32 bytes, so this slice is always valid
+    u64::from_le_bytes([
+        hash_result[0], hash_result[1], hash_result[2], hash_result[3],
+        hash_result[4], hash_result[5], hash_result[6], hash_result[7],
+    ])
+}
+
+/// Shuffles the deck using Fisher-Yates algorithm with SHA256 hash chaining.
+///
+/// This is a deterministic shuffle - the same seed always produces the same deck order.
+/// The algorithm is provably fair because:
+/// 1. The seed comes from IC's VRF (Verifiable Random Function)
+/// 2. SHA256 hash chaining ensures each swap is unpredictable without the seed
+/// 3. Anyone can verify by re-running this function with the revealed seed
+///
+/// # Algorithm
+/// For each position i from the last card down to 1:
+///   1. Hash(previous_hash || i) to get deterministic randomness
+///   2. Select position j = random_value mod (i+1)
+///   3. Swap cards at positions i and j
+///
+/// 2^64 is not a multiple of most (i+1), so low positions are very slightly
+/// favoured. Kept only to replay hands dealt with it - new hands use shuffle_v2.
+pub fn shuffle_v1<T>(deck: &mut [T], seed: &[u8]) {
+    let mut hash_input = seed.to_vec();
+
+    for i in (1..deck.len()).rev() {
+        let random_value = next_random(&mut hash_input, i);
+        let j = (random_value as usize) % (i + 1);
+        deck.swap(i, j);
+    }
+}
+
+/// Fisher-Yates over the same hash chain as shuffle_v1, but a random value in
+/// the incomplete block at the top of the u64 range is rejected and the chain
+/// hashed again, so every j in 0..=i is exactly as likely.
+pub fn shuffle_v2<T>(deck: &mut [T], seed: &[u8]) {
+    let mut hash_input = seed.to_vec();
+
+    for i in (1..deck.len()).rev() {
+        let j = loop {
+            if let Some(j) = unbiased_index(next_random(&mut hash_input, i), i as u64 + 1) {
+                break j as usize;
+            }
+        };
+        deck.swap(i, j);
+    }
+}
+
+/// `value % bound`, or None when value falls in the top 2^64 % bound values
+/// that would make the low results more likely
+pub fn unbiased_index(value: u64, bound: u64) -> Option<u64> {
+    let range = 1u128 << 64;
+    let accepted = range - range % bound as u128;
+    ((value as u128) < accepted).then_some(value % bound)
+}
+
+/// Hex SHA-256 of a seed - the commitment published before the deal
+pub fn seed_hash(seed: &[u8]) -> String {
+    Sha256::digest(seed).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Size of a player entropy nonce - fixed so the mixed seed's input can't be re-split
+pub const NONCE_LEN: usize = 32;
+
+/// Seed for a hand with player entropy: SHA-256 of the VRF bytes followed by
+/// every revealed nonce in seat order. Nonces that were never revealed are left
+/// out, so the seed is always at least as random as the VRF bytes alone.
+pub fn mix_entropy(vrf_seed: &[u8], nonces: &[&[u8]]) -> Vec<u8> {
+    let mut hasher = Sha256::new();
+    hasher.update(vrf_seed);
+    for nonce in nonces {
+        hasher.update(nonce);
+    }
+    hasher.finalize().to_vec()
+}
+
+/// Check a hand's player entropy: the VRF bytes match the hash published when
+/// commitments closed, every revealed nonce matches its commitment, and mixing
+/// them gives the seed the deck was shuffled with.
+/// `contributions` are (commitment, revealed nonce) in seat order.
+pub fn verify_entropy(seed: &[u8], vrf_seed_hash: &str, vrf_seed: &[u8], contributions: &[(&str, Option<&[u8]>)]) -> bool {
+    if seed_hash(vrf_seed) != vrf_seed_hash.to_lowercase() {
+        return false;
+    }
+    let mut nonces = Vec::new();
+    for &(commitment, nonce) in contributions {
+        if let Some(nonce) = nonce {
+            if nonce.len() != NONCE_LEN || seed_hash(nonce) != commitment.to_lowercase() {
+                return false;
+            }
+            nonces.push(nonce);
+        }
+    }
+    mix_entropy(vrf_seed, &nonces) == seed
+}
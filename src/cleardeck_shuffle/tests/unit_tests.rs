@@ -0,0 +1,126 @@
+// Unit tests for the shared shuffle
+// A change that breaks these breaks the replay of every hand already dealt
+
+use cleardeck_shuffle::{
+    deck_order, mix_entropy, seed_hash, shuffle, shuffle_v1, shuffle_v2, unbiased_index, verify_entropy, ACE,
+    SHUFFLE_V1, SHUFFLE_V2, SHUFFLE_VERSION,
+};
+use sha2::{Digest, Sha256};
+
+#[test]
+fn test_deck_order() {
+    let deck = deck_order(2);
+    assert_eq!(deck.len(), 52);
+    assert_eq!(deck[0], (0, 2)); // Two of Hearts first
+    assert_eq!(deck[12], (0, ACE));
+    assert_eq!(deck[51], (3, ACE)); // Ace of Spades last
+
+    let short = deck_order(6);
+    assert_eq!(short.len(), 36);
+    assert_eq!(short[0], (0, 6));
+}
+
+#[test]
+fn test_shuffle_known_seed() {
+    // Worked out independently: 3s 4s 7c 7d 4h 6s
+    let mut deck = deck_order(2);
+    shuffle_v1(&mut deck, &[0x42; 32]);
+    assert_eq!(deck[..6].to_vec(), vec![(3, 3), (3, 4), (2, 7), (1, 7), (0, 4), (3, 6)]);
+}
+
+#[test]
+fn test_shuffle_versions() {
+    let seed = [0x42; 32];
+    let (mut v1, mut v2) = (deck_order(2), deck_order(2));
+    shuffle_v1(&mut v1, &seed);
+    shuffle_v2(&mut v2, &seed);
+
+    // Proofs from before versions were recorded replay with V1
+    let mut unversioned = deck_order(2);
+    shuffle(&mut unversioned, &seed, None).unwrap();
+    assert_eq!(unversioned, v1);
+
+    let mut dispatched = deck_order(2);
+    shuffle(&mut dispatched, &seed, Some(SHUFFLE_V2)).unwrap();
+    assert_eq!(dispatched, v2);
+    assert_eq!(SHUFFLE_VERSION, SHUFFLE_V2);
+
+    let mut dispatched = deck_order(2);
+    shuffle(&mut dispatched, &seed, Some(SHUFFLE_V1)).unwrap();
+    assert_eq!(dispatched, v1);
+
+    assert!(shuffle(&mut deck_order(2), &seed, Some(99)).unwrap_err().contains("99"));
+}
+
+#[test]
+fn test_unbiased_index() {
+    assert_eq!(unbiased_index(7, 3), Some(1));
+    assert_eq!(unbiased_index(u64::MAX - 1, 3), Some(2));
+    // 2^64 = 1 mod 3 - the one value past the last full block of 3 is rejected
+    assert_eq!(unbiased_index(u64::MAX, 3), None);
+    // 2^64 = 16 mod 52
+    assert_eq!(unbiased_index(u64::MAX - 16, 52), Some((u64::MAX - 16) % 52));
+    assert_eq!(unbiased_index(u64::MAX - 15, 52), None);
+    // Powers of two divide 2^64 - nothing to reject
+    assert_eq!(unbiased_index(u64::MAX, 8), Some(7));
+}
+
+#[test]
+fn test_shuffle_is_a_permutation() {
+    let mut deck = deck_order(2);
+    shuffle_v2(&mut deck, b"any seed");
+    let mut sorted = deck.clone();
+    sorted.sort();
+    assert_eq!(sorted, deck_order(2));
+    assert_ne!(deck, deck_order(2));
+}
+
+#[test]
+fn test_shuffle_deterministic() {
+    let (mut a, mut b, mut c) = (deck_order(2), deck_order(2), deck_order(2));
+    shuffle_v2(&mut a, &[7; 32]);
+    shuffle_v2(&mut b, &[7; 32]);
+    shuffle_v2(&mut c, &[8; 32]);
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_seed_hash() {
+    assert_eq!(seed_hash(&[0x42; 32]), "425ed4e4a36b30ea21b90e21c712c649e8214c29b7eaf68089d1039c6e55384c");
+}
+
+#[test]
+fn test_mix_entropy() {
+    let vrf = [0x42; 32];
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    let seed = mix_entropy(&vrf, &[&a, &b]);
+    assert_eq!(seed.len(), 32);
+    assert_ne!(seed, mix_entropy(&vrf, &[&b, &a])); // Seat order matters
+    assert_ne!(seed, mix_entropy(&vrf, &[&a]));
+    assert_eq!(mix_entropy(&vrf, &[]), Sha256::digest(vrf).to_vec()); // Nobody revealed
+}
+
+#[test]
+fn test_verify_entropy() {
+    let vrf = [0x42; 32];
+    let vrf_hash = seed_hash(&vrf);
+    let (a, b) = ([1u8; 32], [2u8; 32]);
+    let (commit_a, commit_b) = (seed_hash(&a), seed_hash(&b));
+
+    // Seat B never revealed - the seed mixes A's nonce only
+    let seed = mix_entropy(&vrf, &[&a]);
+    let contributions = [(commit_a.as_str(), Some(&a[..])), (commit_b.as_str(), None)];
+    assert!(verify_entropy(&seed, &vrf_hash, &vrf, &contributions));
+
+    // A nonce that doesn't match its commitment
+    let forged = [(commit_a.as_str(), Some(&b[..])), (commit_b.as_str(), None)];
+    assert!(!verify_entropy(&mix_entropy(&vrf, &[&b]), &vrf_hash, &vrf, &forged));
+
+    // VRF bytes that weren't the ones committed to
+    assert!(!verify_entropy(&seed, &seed_hash(&[0; 32]), &vrf, &contributions));
+
+    // A revealed nonce left out of the seed
+    let all = [(commit_a.as_str(), Some(&a[..])), (commit_b.as_str(), Some(&b[..]))];
+    assert!(!verify_entropy(&seed, &vrf_hash, &vrf, &all));
+}
@@ -0,0 +1,205 @@
+// ============================================================================
+// ClearDeck mental poker - SRA commutative encryption
+// ============================================================================
+// Building blocks for dealing a hand without anyone - the table canister
+// included - holding the plaintext deck (Shamir-Rivest-Adleman "mental
+// poker"). Every player encrypts with their own exponent modulo one shared
+// prime; exponentiation commutes, so layers can be added and removed in any
+// order:
+//
+//   1. Shuffle: in seat order, each player encrypts every card with one
+//      shuffle key and permutes the deck (shuffle_step)
+//   2. Lock: in seat order, each player swaps their shuffle layer for a key
+//      per deck position, so cards can be opened one at a time (lock_step)
+//   3. Open: a card is revealed by stripping every layer (remove_layer) -
+//      the other players' position keys for a hole card, with the owner
+//      removing the last layer privately; every player's for board cards
+//
+// Cards are encoded as quadratic residues, so ciphertexts leak nothing through
+// the Legendre symbol. Steps are not proven in zero knowledge: each player
+// commits to their secret with the shuffle step and to every position key
+// with the lock step (exponent_hash), so a key handed over for a card is
+// checked on arrival. When the hand ends each player reveals the secret their
+// keys were derived from and the audit_* functions check every step they
+// made, so a cheat is caught after the hand rather than prevented.
+//
+// The table canister coordinates the steps on tables with mental_poker set
+// (see MentalDeal there); it only ever stores ciphertexts and opened cards.
+
+use num_bigint::BigUint;
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// RFC 3526 2048-bit MODP group prime - a safe prime, p = 2q + 1
+const PRIME_HEX: &str = concat!(
+    "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E088A67CC74",
+    "020BBEA63B139B22514A08798E3404DDEF9519B3CD3A431B302B0A6DF25F1437",
+    "4FE1356D6D51C245E485B576625E7EC6F44C42E9A637ED6B0BFF5CB6F406B7ED",
+    "EE386BFB5A899FA5AE9F24117C4B1FE649286651ECE45B3DC2007CB8A163BF05",
+    "98DA48361C55D39A69163FA8FD24CF5F83655D23DCA3AD961C62F356208552BB",
+    "9ED529077096966D670C354E4ABC9804F1746C08CA18217C32905E462E36CE3B",
+    "E39E772C180E86039B2783A2EC07A28FB5C55DF06F4C52C9DE2BCBF695581718",
+    "3995497CEA956AE515D2261898FA051015728E5A8AACAA68FFFFFFFFFFFFFFFF",
+);
+
+/// Size of an encoded card or ciphertext in bytes (big-endian, zero padded)
+pub const CIPHERTEXT_LEN: usize = 256;
+
+/// Size of the secret a player derives their keys from
+pub const SECRET_LEN: usize = 32;
+
+/// The shared modulus every layer is encrypted under
+pub fn prime() -> &'static BigUint {
+    static PRIME: OnceLock<BigUint> = OnceLock::new();
+    PRIME.get_or_init(|| BigUint::parse_bytes(PRIME_HEX.as_bytes(), 16).expect("valid prime constant"))
+}
+
+/// One player's encryption layer: encrypt with e, remove with d (e * d = 1 mod p - 1)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Key {
+    e: BigUint,
+    d: BigUint,
+}
+
+impl Key {
+    /// Derive a layer key from a player's secret and what the layer is for.
+    /// Revealing the secret after the hand lets anyone re-derive every key.
+    pub fn derive(secret: &[u8], label: &str) -> Key {
+        let order = prime() - 1u32;
+        let mut bytes = Vec::with_capacity(CIPHERTEXT_LEN);
+        for counter in 0..(CIPHERTEXT_LEN / 32) as u8 {
+            let mut hasher = Sha256::new();
+            hasher.update(secret);
+            hasher.update(label.as_bytes());
+            hasher.update([counter]);
+            bytes.extend_from_slice(&hasher.finalize());
+        }
+
+        // e must be invertible mod p - 1 = 2q: any odd e other than q will do
+        let mut e = BigUint::from_bytes_be(&bytes) % &order;
+        e.set_bit(0, true);
+        loop {
+            if e > BigUint::from(1u32) {
+                if let Some(d) = e.modinv(&order) {
+                    return Key { e, d };
+                }
+            }
+            e += 2u32;
+        }
+    }
+
+    /// The key a player shuffles the whole deck with
+    pub fn for_shuffle(secret: &[u8]) -> Key {
+        Key::derive(secret, "shuffle")
+    }
+
+    /// The key locking one deck position
+    pub fn for_position(secret: &[u8], position: usize) -> Key {
+        Key::derive(secret, &format!("position {}", position))
+    }
+
+    /// What a player hands over to let others strip this layer from one card
+    pub fn decryption_exponent(&self) -> &BigUint {
+        &self.d
+    }
+
+    pub fn encrypt(&self, card: &BigUint) -> BigUint {
+        card.modpow(&self.e, prime())
+    }
+
+    pub fn decrypt(&self, card: &BigUint) -> BigUint {
+        remove_layer(card, &self.d)
+    }
+}
+
+/// Strip one layer with a revealed decryption exponent
+pub fn remove_layer(card: &BigUint, decryption_exponent: &BigUint) -> BigUint {
+    card.modpow(decryption_exponent, prime())
+}
+
+/// Card index (position in the unshuffled deck) as a quadratic residue: (index + 2)^2
+pub fn encode_card(index: usize) -> BigUint {
+    let root = BigUint::from(index as u64 + 2);
+    &root * &root
+}
+
+/// The card index a fully decrypted value stands for, if any
+pub fn decode_card(card: &BigUint, deck_size: usize) -> Option<usize> {
+    (0..deck_size).find(|&index| encode_card(index) == *card)
+}
+
+/// The unshuffled deck, encoded
+pub fn plain_deck(deck_size: usize) -> Vec<BigUint> {
+    (0..deck_size).map(encode_card).collect()
+}
+
+pub fn to_bytes(card: &BigUint) -> Vec<u8> {
+    let bytes = card.to_bytes_be();
+    let mut padded = vec![0; CIPHERTEXT_LEN.saturating_sub(bytes.len())];
+    padded.extend_from_slice(&bytes);
+    padded
+}
+
+/// Parse a ciphertext - None unless it is CIPHERTEXT_LEN bytes and below the prime
+pub fn from_bytes(bytes: &[u8]) -> Option<BigUint> {
+    let card = BigUint::from_bytes_be(bytes);
+    (bytes.len() == CIPHERTEXT_LEN && card < *prime()).then_some(card)
+}
+
+/// Parse a whole deck of ciphertexts - None if any of them is malformed
+pub fn deck_from_bytes(deck: &[Vec<u8>]) -> Option<Vec<BigUint>> {
+    deck.iter().map(|card| from_bytes(card)).collect()
+}
+
+/// What a player publishes for each position key with their lock step:
+/// hex SHA-256 of the padded decryption exponent
+pub fn exponent_hash(decryption_exponent: &BigUint) -> String {
+    cleardeck_shuffle::seed_hash(&to_bytes(decryption_exponent))
+}
+
+/// Add a shuffle layer to every card, then permute the deck.
+/// `permutation_seed` stays private - the permutation is the cleardeck_shuffle of it.
+pub fn shuffle_step(deck: &[BigUint], key: &Key, permutation_seed: &[u8]) -> Vec<BigUint> {
+    let mut shuffled: Vec<BigUint> = deck.iter().map(|card| key.encrypt(card)).collect();
+    cleardeck_shuffle::shuffle_v2(&mut shuffled, permutation_seed);
+    shuffled
+}
+
+/// Swap the shuffle layer for a key per position, derived from the same secret
+pub fn lock_step(deck: &[BigUint], shuffle_key: &Key, secret: &[u8]) -> Vec<BigUint> {
+    deck.iter()
+        .enumerate()
+        .map(|(position, card)| Key::for_position(secret, position).encrypt(&shuffle_key.decrypt(card)))
+        .collect()
+}
+
+/// Check a shuffle step once its key is revealed: stripping the layer from
+/// the output must give back exactly the input cards, in any order
+pub fn audit_shuffle_step(input: &[BigUint], output: &[BigUint], key: &Key) -> bool {
+    if input.len() != output.len() {
+        return false;
+    }
+    let mut stripped: Vec<BigUint> = output.iter().map(|card| key.decrypt(card)).collect();
+    let mut expected = input.to_vec();
+    stripped.sort();
+    expected.sort();
+    stripped == expected
+}
+
+/// Check a lock step once the player's secret is revealed: every position
+/// must hold the input card with the shuffle layer swapped for that position's key
+pub fn audit_lock_step(input: &[BigUint], output: &[BigUint], secret: &[u8]) -> bool {
+    let shuffle_key = Key::for_shuffle(secret);
+    input.len() == output.len()
+        && input.iter().zip(output).enumerate().all(|(position, (before, after))| {
+            Key::for_position(secret, position).decrypt(after) == shuffle_key.decrypt(before)
+        })
+}
+
+/// Check the position key hashes a player published once their secret is
+/// revealed: each must be the exponent_hash of that position's key
+pub fn audit_exponent_hashes(hashes: &[String], secret: &[u8]) -> bool {
+    hashes.iter().enumerate().all(|(position, hash)| {
+        exponent_hash(Key::for_position(secret, position).decryption_exponent()) == *hash
+    })
+}
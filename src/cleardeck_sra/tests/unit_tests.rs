@@ -0,0 +1,134 @@
+// Unit tests for the SRA primitives
+// Small decks keep the 2048-bit modpow affordable in debug builds
+
+use cleardeck_sra::{
+    audit_exponent_hashes, audit_lock_step, audit_shuffle_step, decode_card, deck_from_bytes, encode_card,
+    exponent_hash, from_bytes, lock_step, plain_deck, prime, remove_layer, shuffle_step, to_bytes, Key,
+    CIPHERTEXT_LEN,
+};
+use num_bigint::BigUint;
+
+const DECK_SIZE: usize = 6;
+const ALICE: &[u8] = b"alice secret";
+const BOB: &[u8] = b"bob secret";
+
+#[test]
+fn test_encode_decode() {
+    for index in 0..52 {
+        assert_eq!(decode_card(&encode_card(index), 52), Some(index));
+    }
+    assert_eq!(decode_card(&BigUint::from(5u32), 52), None);
+
+    // Quadratic residues: c^((p-1)/2) = 1 mod p
+    let half_order = (prime() - 1u32) / 2u32;
+    assert_eq!(encode_card(17).modpow(&half_order, prime()), BigUint::from(1u32));
+}
+
+#[test]
+fn test_layers_commute() {
+    let (alice, bob) = (Key::for_shuffle(ALICE), Key::for_shuffle(BOB));
+    let card = encode_card(3);
+
+    let locked = bob.encrypt(&alice.encrypt(&card));
+    assert_ne!(locked, card);
+    assert_eq!(locked, alice.encrypt(&bob.encrypt(&card)));
+
+    // Either player can strip their layer first
+    assert_eq!(alice.decrypt(&bob.decrypt(&locked)), card);
+    assert_eq!(bob.decrypt(&alice.decrypt(&locked)), card);
+    assert_eq!(remove_layer(&alice.encrypt(&card), alice.decryption_exponent()), card);
+
+    // Keys are reproducible from the secret and differ per purpose
+    assert_eq!(Key::for_shuffle(ALICE), alice);
+    assert_ne!(Key::for_position(ALICE, 0), alice);
+    assert_ne!(Key::for_position(ALICE, 0), Key::for_position(ALICE, 1));
+}
+
+#[test]
+fn test_two_player_deal() {
+    let (alice_shuffle, bob_shuffle) = (Key::for_shuffle(ALICE), Key::for_shuffle(BOB));
+    let plain = plain_deck(DECK_SIZE);
+
+    let after_alice = shuffle_step(&plain, &alice_shuffle, b"alice permutation");
+    let after_bob = shuffle_step(&after_alice, &bob_shuffle, b"bob permutation");
+    let alice_locked = lock_step(&after_bob, &alice_shuffle, ALICE);
+    let deck = lock_step(&alice_locked, &bob_shuffle, BOB);
+    assert!(deck.iter().all(|card| decode_card(card, DECK_SIZE).is_none()));
+
+    // Bob opens position 0 for Alice's hole card; she strips her own layer privately
+    let for_alice = remove_layer(&deck[0], Key::for_position(BOB, 0).decryption_exponent());
+    let hole_card = Key::for_position(ALICE, 0).decrypt(&for_alice);
+
+    // Every position opens to a distinct card of the deck
+    let mut opened: Vec<usize> = deck.iter().enumerate()
+        .map(|(position, card)| {
+            let card = Key::for_position(ALICE, position).decrypt(&Key::for_position(BOB, position).decrypt(card));
+            decode_card(&card, DECK_SIZE).unwrap()
+        })
+        .collect();
+    assert_eq!(decode_card(&hole_card, DECK_SIZE), Some(opened[0]));
+    opened.sort();
+    assert_eq!(opened, (0..DECK_SIZE).collect::<Vec<_>>());
+
+    assert!(audit_shuffle_step(&plain, &after_alice, &alice_shuffle));
+    assert!(audit_shuffle_step(&after_alice, &after_bob, &bob_shuffle));
+    assert!(audit_lock_step(&after_bob, &alice_locked, ALICE));
+    assert!(audit_lock_step(&alice_locked, &deck, BOB));
+}
+
+#[test]
+fn test_audit_catches_cheating() {
+    let alice = Key::for_shuffle(ALICE);
+    let plain = plain_deck(DECK_SIZE);
+    let shuffled = shuffle_step(&plain, &alice, b"alice permutation");
+
+    // Swapping a card for a duplicate of another
+    let mut stacked = shuffled.clone();
+    stacked[1] = stacked[0].clone();
+    assert!(!audit_shuffle_step(&plain, &stacked, &alice));
+    assert!(!audit_shuffle_step(&plain, &shuffled[1..], &alice));
+    assert!(!audit_shuffle_step(&plain, &shuffled, &Key::for_shuffle(BOB)));
+
+    // Reordering while locking
+    let mut locked = lock_step(&shuffled, &alice, ALICE);
+    assert!(audit_lock_step(&shuffled, &locked, ALICE));
+    locked.swap(0, 1);
+    assert!(!audit_lock_step(&shuffled, &locked, ALICE));
+}
+
+#[test]
+fn test_ciphertext_bytes() {
+    let card = Key::for_shuffle(ALICE).encrypt(&encode_card(0));
+    let bytes = to_bytes(&card);
+    assert_eq!(bytes.len(), CIPHERTEXT_LEN);
+    assert_eq!(from_bytes(&bytes), Some(card));
+
+    assert_eq!(to_bytes(&encode_card(0)).len(), CIPHERTEXT_LEN);
+    assert_eq!(from_bytes(&[1, 2, 3]), None);
+    assert_eq!(from_bytes(&[0xff; CIPHERTEXT_LEN]), None);
+}
+
+#[test]
+fn test_exponent_hashes() {
+    let hashes: Vec<String> = (0..DECK_SIZE)
+        .map(|position| exponent_hash(Key::for_position(ALICE, position).decryption_exponent()))
+        .collect();
+    assert!(audit_exponent_hashes(&hashes, ALICE));
+    assert!(!audit_exponent_hashes(&hashes, BOB));
+
+    // A key handed over for one position doesn't match another position's hash
+    let mut swapped = hashes.clone();
+    swapped.swap(0, 1);
+    assert!(!audit_exponent_hashes(&swapped, ALICE));
+    assert_ne!(exponent_hash(Key::for_position(ALICE, 1).decryption_exponent()), hashes[0]);
+}
+
+#[test]
+fn test_deck_from_bytes() {
+    let deck: Vec<Vec<u8>> = plain_deck(DECK_SIZE).iter().map(to_bytes).collect();
+    assert_eq!(deck_from_bytes(&deck), Some(plain_deck(DECK_SIZE)));
+
+    let mut short_card = deck.clone();
+    short_card[2].pop();
+    assert_eq!(deck_from_bytes(&short_card), None);
+}
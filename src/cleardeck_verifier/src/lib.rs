@@ -0,0 +1,253 @@
+// ============================================================================
+// ClearDeck offline shuffle verifier
+// ============================================================================
+// Checks a dealt hand without trusting any ClearDeck canister. Given a hand
+// record (or just a shuffle proof) it checks the revealed seed against the
+// commitment published before the deal, re-shuffles with cleardeck_shuffle -
+// the same crate the table canister dealt with, not a copy - and replays the
+// deal card by card with the history canister's replay module.
+//
+// Accepted input, detected automatically:
+//   - Candid, raw bytes or hex (`dfx canister call ... --output raw`):
+//     HandHistoryRecord, history/archive get_hand's GetHandResponse, or a
+//     table ShuffleProof / history ShuffleProofRecord
+//   - JSON of the same types, field names as in the .did files, principals as text
+
+use candid::{CandidType, Decode};
+use history_canister::pokerstars::{card_text, format_timestamp};
+use history_canister::{replay, EntropyContributionRecord, GameVariant, GetHandResponse, HandHistoryRecord, PlayerEntropyRecord};
+use serde::Deserialize;
+use std::fmt::Write;
+
+// Mirrors of the table canister's proof types. The table crate can't be linked
+// in next to the history crate - both export the canister entry points.
+
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum DeckType {
+    #[default]
+    Standard,
+    ShortDeck,
+}
+
+/// Table ShuffleProof (get_shuffle_proof) - a history ShuffleProofRecord decodes as one too
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ShuffleProof {
+    pub seed_hash: String,
+    pub revealed_seed: Option<String>,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub deck_type: Option<DeckType>,
+    #[serde(default)]
+    pub entropy: Option<PlayerEntropy>,
+    #[serde(default)]
+    pub shuffle_version: Option<u32>,
+}
+
+/// Table PlayerEntropy - vrf_seed stays None until the hand ends
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerEntropy {
+    pub vrf_seed_hash: String,
+    pub vrf_seed: Option<String>,
+    pub contributions: Vec<EntropyContributionRecord>,
+}
+
+/// What a dump turned out to hold
+#[derive(Debug)]
+pub enum Input {
+    Hand(Box<HandHistoryRecord>),
+    Proof(ShuffleProof), // No cards on record - check the seed and show the deck it deals
+}
+
+/// Outcome of a check, with the report to print
+#[derive(Debug)]
+pub struct Verdict {
+    pub valid: bool,
+    pub report: String,
+}
+
+pub fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let text = text.trim();
+    if text.is_empty() || !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+/// Work out what a dump is and decode it
+pub fn parse(bytes: &[u8]) -> Result<Input, String> {
+    if bytes.starts_with(b"DIDL") {
+        return parse_candid(bytes);
+    }
+    let text = std::str::from_utf8(bytes).map_err(|_| "Input is neither Candid nor text".to_string())?;
+    if text.trim_start().starts_with('{') {
+        return parse_json(text);
+    }
+    match decode_hex(text) {
+        Some(candid) if candid.starts_with(b"DIDL") => parse_candid(&candid),
+        _ => Err("Expected JSON, Candid bytes or hex-encoded Candid".to_string()),
+    }
+}
+
+fn from_response(response: GetHandResponse) -> Result<Input, String> {
+    match (response.hand, response.archive) {
+        (Some(hand), _) => Ok(Input::Hand(Box::new(hand))),
+        (None, Some(archive)) => Err(format!("The hand is archived in {} - fetch it from there", archive)),
+        (None, None) => Err("get_hand found no such hand".to_string()),
+    }
+}
+
+fn parse_candid(bytes: &[u8]) -> Result<Input, String> {
+    if let Ok(hand) = Decode!(bytes, HandHistoryRecord) {
+        return Ok(Input::Hand(Box::new(hand)));
+    }
+    // A ShuffleProofRecord's revealed_seed decodes into ShuffleProof's opt text
+    if let Ok(proof) = Decode!(bytes, ShuffleProof) {
+        return Ok(Input::Proof(proof));
+    }
+    if let Ok(Some(proof)) = Decode!(bytes, Option<ShuffleProof>) {
+        return Ok(Input::Proof(proof));
+    }
+    match Decode!(bytes, GetHandResponse) {
+        Ok(response) => from_response(response),
+        Err(_) => Err("Candid isn't a HandHistoryRecord, GetHandResponse or ShuffleProof".to_string()),
+    }
+}
+
+fn parse_json(text: &str) -> Result<Input, String> {
+    let hand_error = match serde_json::from_str::<HandHistoryRecord>(text) {
+        Ok(hand) => return Ok(Input::Hand(Box::new(hand))),
+        Err(e) => e,
+    };
+    if let Ok(proof) = serde_json::from_str::<ShuffleProof>(text) {
+        return Ok(Input::Proof(proof));
+    }
+    match serde_json::from_str::<GetHandResponse>(text) {
+        Ok(response) if response.hand.is_some() || response.archive.is_some() => from_response(response),
+        // Most dumps are hand records - that's the error worth showing
+        _ => Err(format!("JSON isn't a HandHistoryRecord, GetHandResponse or ShuffleProof: {}", hand_error)),
+    }
+}
+
+fn variant_name(variant: GameVariant) -> &'static str {
+    match variant {
+        GameVariant::TexasHoldem => "Texas Hold'em",
+        GameVariant::PotLimitOmaha => "Pot-Limit Omaha",
+        GameVariant::OmahaHiLo => "Omaha Hi-Lo",
+        GameVariant::ShortDeckHoldem => "Short Deck Hold'em",
+    }
+}
+
+fn seed_line(report: &mut String, seed_hash: &str, matches: bool) {
+    let _ = writeln!(report, "Commitment   {}", seed_hash);
+    let _ = if matches {
+        writeln!(report, "Seed         OK - sha256(revealed seed) matches the commitment")
+    } else {
+        writeln!(report, "Seed         FAILED - sha256(revealed seed) does not match the commitment")
+    };
+}
+
+fn entropy_line(report: &mut String, entropy: &PlayerEntropyRecord, matches: bool) {
+    let revealed = entropy.contributions.iter().filter(|c| c.nonce.is_some()).count();
+    let mixed = format!("{} of {} player nonces", revealed, entropy.contributions.len());
+    let _ = if matches {
+        writeln!(report, "Entropy      OK - seed is the committed VRF bytes mixed with {}", mixed)
+    } else {
+        writeln!(report, "Entropy      FAILED - the committed VRF bytes and {} don't mix into the seed", mixed)
+    };
+}
+
+const ENTROPY_INVALID: &str = "VERDICT      INVALID - the seed isn't the committed VRF bytes mixed with the players' nonces";
+
+/// Check whatever was parsed
+pub fn verify(input: &Input) -> Result<Verdict, String> {
+    match input {
+        Input::Hand(hand) => verify_hand(hand),
+        Input::Proof(proof) => verify_proof(proof),
+    }
+}
+
+fn verify_hand(hand: &HandHistoryRecord) -> Result<Verdict, String> {
+    let result = replay::verify_deal(hand)?;
+    let variant = hand.game_variant.unwrap_or_default();
+    let mut report = String::new();
+
+    let _ = writeln!(report, "Hand         {} - table {} hand #{}", hand.hand_id, hand.table_id, hand.hand_number);
+    let _ = writeln!(report, "Dealt        {} UTC, {}", format_timestamp(hand.timestamp), variant_name(variant));
+    seed_line(&mut report, &hand.shuffle_proof.seed_hash, result.seed_matches_hash);
+    let _ = writeln!(report, "Shuffle      v{}", result.shuffle_version);
+    if let (Some(entropy), Some(matches)) = (&hand.shuffle_proof.entropy, result.entropy_matches) {
+        entropy_line(&mut report, entropy, matches);
+    }
+
+    let seats: Vec<String> = result.dealt_seats.iter().map(|s| s.to_string()).collect();
+    let _ = writeln!(
+        report,
+        "Dealt seats  {} ({})",
+        seats.join(", "),
+        if result.dealt_seats_inferred { "inferred - the record predates dealt_seats" } else { "from the record" }
+    );
+    let _ = writeln!(report, "Checked      {} hole cards, {} board cards", result.hole_cards_checked, result.board_cards_checked);
+
+    for mismatch in &result.mismatches {
+        let expected = match (mismatch.deck_position, &mismatch.expected) {
+            (Some(position), Some(card)) => format!("the replay deals {} at deck position {}", card_text(card), position),
+            (Some(position), None) => format!("the deck has no card at position {}", position),
+            (None, _) => "the replay never dealt this seat in".to_string(),
+        };
+        let _ = writeln!(report, "  MISMATCH   {}: recorded {}, {}", mismatch.location, card_text(&mismatch.recorded), expected);
+    }
+
+    let _ = if result.valid {
+        writeln!(report, "VERDICT      VALID - every recorded card was dealt from the committed seed")
+    } else if !result.seed_matches_hash {
+        writeln!(report, "VERDICT      INVALID - the revealed seed is not the one committed to before the deal")
+    } else if result.entropy_matches == Some(false) {
+        writeln!(report, "{}", ENTROPY_INVALID)
+    } else {
+        writeln!(report, "VERDICT      INVALID - {} recorded card(s) don't match the replayed deal", result.mismatches.len())
+    };
+    Ok(Verdict { valid: result.valid, report })
+}
+
+fn verify_proof(proof: &ShuffleProof) -> Result<Verdict, String> {
+    let revealed = proof.revealed_seed.as_deref()
+        .ok_or("The seed hasn't been revealed yet - it is only published once the hand ends")?;
+    let seed = decode_hex(revealed).ok_or("Revealed seed is not valid hex")?;
+    let matches = cleardeck_shuffle::seed_hash(&seed) == proof.seed_hash.to_lowercase();
+    let entropy = match &proof.entropy {
+        Some(entropy) => Some(PlayerEntropyRecord {
+            vrf_seed_hash: entropy.vrf_seed_hash.clone(),
+            vrf_seed: entropy.vrf_seed.clone().ok_or("The VRF seed hasn't been revealed yet")?,
+            contributions: entropy.contributions.clone(),
+        }),
+        None => None,
+    };
+    let entropy_matches = entropy.as_ref().is_none_or(|e| replay::entropy_matches(&seed, e));
+
+    let variant = match proof.deck_type.unwrap_or_default() {
+        DeckType::Standard => GameVariant::TexasHoldem,
+        DeckType::ShortDeck => GameVariant::ShortDeckHoldem,
+    };
+    let mut deck = replay::create_deck(variant);
+    replay::shuffle_deck(&mut deck, &seed, proof.shuffle_version)?;
+
+    let mut report = String::new();
+    seed_line(&mut report, &proof.seed_hash, matches);
+    let _ = writeln!(report, "Shuffle      v{}", proof.shuffle_version.unwrap_or(cleardeck_shuffle::SHUFFLE_V1));
+    if let Some(ref entropy) = entropy {
+        entropy_line(&mut report, entropy, entropy_matches);
+    }
+    let cards: Vec<String> = deck.iter().map(card_text).collect();
+    for (row, chunk) in cards.chunks(13).enumerate() {
+        let label = if row == 0 { "Deck order" } else { "" };
+        let _ = writeln!(report, "{:<12} {}", label, chunk.join(" "));
+    }
+    let _ = if matches && !entropy_matches {
+        writeln!(report, "{}", ENTROPY_INVALID)
+    } else if matches {
+        writeln!(report, "VERDICT      VALID - compare the deck order with the cards dealt (hole cards by seat, then burn + flop, burn + turn, burn + river)")
+    } else {
+        writeln!(report, "VERDICT      INVALID - the revealed seed is not the one committed to before the deal")
+    };
+    Ok(Verdict { valid: matches && entropy_matches, report })
+}
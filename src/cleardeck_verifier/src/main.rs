@@ -0,0 +1,50 @@
+// cleardeck-verify: check a ClearDeck hand offline.
+//
+//   cleardeck-verify hand.json
+//   dfx canister call history get_hand '(42)' --output raw | cleardeck-verify -
+//
+// Exit status: 0 valid, 1 invalid, 2 unreadable input.
+
+use std::io::Read;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: cleardeck-verify <hand record or shuffle proof file | ->\n\
+    Reads JSON, Candid or hex-encoded Candid (dfx --output raw) of a HandHistoryRecord,\n\
+    get_hand response or ShuffleProof, and checks it against the shared ClearDeck shuffle.";
+
+fn read_input(path: &str) -> std::io::Result<Vec<u8>> {
+    if path == "-" {
+        let mut bytes = Vec::new();
+        std::io::stdin().read_to_end(&mut bytes)?;
+        Ok(bytes)
+    } else {
+        std::fs::read(path)
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let path = match args.as_slice() {
+        [path] if path != "-h" && path != "--help" => path,
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let checked = read_input(path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))
+        .and_then(|bytes| cleardeck_verifier::parse(&bytes))
+        .and_then(|input| cleardeck_verifier::verify(&input));
+
+    match checked {
+        Ok(verdict) => {
+            print!("{}", verdict.report);
+            ExitCode::from(if verdict.valid { 0 } else { 1 })
+        }
+        Err(e) => {
+            eprintln!("cleardeck-verify: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,181 @@
+// Unit tests for the offline verifier - input detection and verdicts
+
+use candid::{Encode, Principal};
+use cleardeck_verifier::{decode_hex, parse, verify, Input};
+use history_canister::{
+    replay, Card, GameVariant, GetHandResponse, HandHistoryRecord, PlayerHandRecord, ShuffleProofRecord,
+};
+
+const SEED: [u8; 32] = [0x42; 32];
+const SEED_HASH: &str = "425ed4e4a36b30ea21b90e21c712c649e8214c29b7eaf68089d1039c6e55384c";
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn player(seat: u8, position: &str, shown: Option<Vec<Card>>) -> PlayerHandRecord {
+    PlayerHandRecord {
+        seat,
+        principal: Principal::from_slice(&[seat + 1]),
+        starting_chips: 100,
+        ending_chips: 100,
+        hole_cards: None,
+        final_hand_rank: None,
+        amount_won: 0,
+        position: position.to_string(),
+        all_hole_cards: shown,
+    }
+}
+
+/// Heads-up hand dealt from SEED - both players show down
+fn dealt_hand() -> HandHistoryRecord {
+    dealt_hand_with(None)
+}
+
+fn dealt_hand_with(shuffle_version: Option<u32>) -> HandHistoryRecord {
+    let mut deck = replay::create_deck(GameVariant::TexasHoldem);
+    replay::shuffle_deck(&mut deck, &SEED, shuffle_version).unwrap();
+    HandHistoryRecord {
+        hand_id: 42,
+        table_id: Principal::from_slice(&[9]),
+        hand_number: 7,
+        timestamp: 1_792_238_400 * 1_000_000_000,
+        small_blind: 1,
+        big_blind: 2,
+        ante: 0,
+        shuffle_proof: ShuffleProofRecord {
+            seed_hash: SEED_HASH.to_string(),
+            revealed_seed: hex(&SEED),
+            timestamp: 0,
+            entropy: None,
+            shuffle_version,
+        },
+        players: vec![player(0, "BTN", Some(deck[0..2].to_vec())), player(1, "BB", Some(deck[2..4].to_vec()))],
+        dealt_seats: Some(vec![0, 1]),
+        dealer_seat: 0,
+        flop: Some((deck[5], deck[6], deck[7])),
+        turn: Some(deck[9]),
+        river: Some(deck[11]),
+        actions: Vec::new(),
+        total_pot: 4,
+        rake: 0,
+        winners: Vec::new(),
+        went_to_showdown: true,
+        game_variant: Some(GameVariant::TexasHoldem),
+        is_tournament: Some(false),
+        second_board: None,
+        currency: None,
+    }
+}
+
+#[test]
+fn test_decode_hex() {
+    assert_eq!(decode_hex("00ff10\n"), Some(vec![0, 255, 16]));
+    assert_eq!(decode_hex("abc"), None);
+    assert_eq!(decode_hex("zz"), None);
+}
+
+#[test]
+fn test_candid_hand_is_valid() {
+    let bytes = Encode!(&dealt_hand()).unwrap();
+    let verdict = verify(&parse(&bytes).unwrap()).unwrap();
+    assert!(verdict.valid, "{}", verdict.report);
+    assert!(verdict.report.contains("Checked      4 hole cards, 5 board cards"));
+    assert!(verdict.report.contains("VERDICT      VALID"));
+
+    // dfx --output raw prints the same bytes as hex
+    let verdict = verify(&parse(hex(&bytes).as_bytes()).unwrap()).unwrap();
+    assert!(verdict.valid);
+}
+
+#[test]
+fn test_get_hand_response() {
+    let response = GetHandResponse { hand: Some(dealt_hand()), archive: None };
+    assert!(matches!(parse(&Encode!(&response).unwrap()), Ok(Input::Hand(_))));
+
+    let archived = GetHandResponse { hand: None, archive: Some(Principal::from_slice(&[3])) };
+    assert!(parse(&Encode!(&archived).unwrap()).unwrap_err().contains("archived"));
+}
+
+#[test]
+fn test_tampered_hand_is_invalid() {
+    let mut hand = dealt_hand();
+    hand.river = hand.turn;
+    let verdict = verify(&parse(&Encode!(&hand).unwrap()).unwrap()).unwrap();
+    assert!(!verdict.valid);
+    assert!(verdict.report.contains("MISMATCH   River"), "{}", verdict.report);
+
+    let mut hand = dealt_hand();
+    hand.shuffle_proof.seed_hash = "00".repeat(32);
+    let verdict = verify(&parse(&Encode!(&hand).unwrap()).unwrap()).unwrap();
+    assert!(!verdict.valid);
+    assert!(verdict.report.contains("not the one committed to"));
+}
+
+#[test]
+fn test_json_shuffle_proof() {
+    let json = format!(r#"{{"seed_hash": "{}", "revealed_seed": "{}", "timestamp": 0}}"#, SEED_HASH, hex(&SEED));
+    let verdict = verify(&parse(json.as_bytes()).unwrap()).unwrap();
+    assert!(verdict.valid);
+    assert!(verdict.report.contains("Deck order   3s 4s 7c 7d"), "{}", verdict.report);
+
+    // Short deck proofs replay the 36-card deck
+    let json = format!(r#"{{"seed_hash": "{}", "revealed_seed": "{}", "timestamp": 0, "deck_type": "ShortDeck"}}"#, SEED_HASH, hex(&SEED));
+    let verdict = verify(&parse(json.as_bytes()).unwrap()).unwrap();
+    let cards: Vec<&str> = verdict.report.lines()
+        .filter(|line| line.starts_with("Deck order") || line.starts_with(' '))
+        .flat_map(|line| line[13..].split(' '))
+        .collect();
+    assert_eq!(cards.len(), 36);
+    assert!(!cards.iter().any(|card| "2345".contains(&card[..1])));
+
+    let unrevealed = format!(r#"{{"seed_hash": "{}", "revealed_seed": null, "timestamp": 0}}"#, SEED_HASH);
+    assert!(verify(&parse(unrevealed.as_bytes()).unwrap()).is_err());
+}
+
+#[test]
+fn test_shuffle_versions() {
+    // Hands from before versions were recorded replay with V1
+    let verdict = verify(&parse(&Encode!(&dealt_hand()).unwrap()).unwrap()).unwrap();
+    assert!(verdict.report.contains("Shuffle      v1"), "{}", verdict.report);
+
+    let hand = dealt_hand_with(Some(cleardeck_shuffle::SHUFFLE_V2));
+    let verdict = verify(&parse(&Encode!(&hand).unwrap()).unwrap()).unwrap();
+    assert!(verdict.valid, "{}", verdict.report);
+    assert!(verdict.report.contains("Shuffle      v2"));
+
+    let mut hand = dealt_hand();
+    hand.shuffle_proof.shuffle_version = Some(9);
+    assert!(verify(&parse(&Encode!(&hand).unwrap()).unwrap()).unwrap_err().contains("Unknown shuffle version 9"));
+
+    let json = format!(r#"{{"seed_hash": "{}", "revealed_seed": "{}", "timestamp": 0, "shuffle_version": 2}}"#, SEED_HASH, hex(&SEED));
+    let verdict = verify(&parse(json.as_bytes()).unwrap()).unwrap();
+    assert!(verdict.valid);
+    assert!(verdict.report.contains("Shuffle      v2"));
+}
+
+#[test]
+fn test_player_entropy_proof() {
+    let (vrf, nonce) = ([7u8; 32], [1u8; 32]);
+    let seed = cleardeck_shuffle::mix_entropy(&vrf, &[&nonce]);
+    let proof = |nonce_hex: &str| format!(
+        r#"{{"seed_hash": "{}", "revealed_seed": "{}", "timestamp": 0, "entropy": {{"vrf_seed_hash": "{}", "vrf_seed": "{}",
+            "contributions": [{{"seat": 1, "principal": "aaaaa-aa", "commitment": "{}", "nonce": "{}"}}]}}}}"#,
+        cleardeck_shuffle::seed_hash(&seed), hex(&seed), cleardeck_shuffle::seed_hash(&vrf), hex(&vrf),
+        cleardeck_shuffle::seed_hash(&nonce), nonce_hex,
+    );
+
+    let verdict = verify(&parse(proof(&hex(&nonce)).as_bytes()).unwrap()).unwrap();
+    assert!(verdict.valid, "{}", verdict.report);
+    assert!(verdict.report.contains("Entropy      OK - seed is the committed VRF bytes mixed with 1 of 1 player nonces"));
+
+    let verdict = verify(&parse(proof(&hex(&[2; 32])).as_bytes()).unwrap()).unwrap();
+    assert!(!verdict.valid);
+    assert!(verdict.report.contains("Entropy      FAILED"), "{}", verdict.report);
+}
+
+#[test]
+fn test_unreadable_input() {
+    assert!(parse(b"hello").is_err());
+    assert!(parse(b"{\"nothing\": 1}").is_err());
+}
@@ -0,0 +1,18 @@
+// Embed the archive canister wasm so the history canister can install archives.
+// scripts/build-history.sh builds the archive first and points ARCHIVE_WASM_PATH
+// at it. Without it the build still succeeds, but archiving is unavailable.
+
+use std::{env, fs, path::PathBuf};
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ARCHIVE_WASM_PATH");
+
+    let out = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set")).join("archive_canister.wasm");
+    match env::var("ARCHIVE_WASM_PATH") {
+        Ok(path) => {
+            println!("cargo:rerun-if-changed={}", path);
+            fs::copy(&path, &out).unwrap_or_else(|e| panic!("Failed to read archive wasm at {}: {}", path, e));
+        }
+        Err(_) => fs::write(&out, []).expect("Failed to write empty archive wasm"),
+    }
+}
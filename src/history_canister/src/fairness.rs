@@ -0,0 +1,179 @@
+// Fairness audit.
+//
+// Every hand that verifies (replay::verify_deal) has its revealed seed
+// replayed and what it dealt added to a running tally per deck size:
+//   - which card landed at each deck position - the whole shuffled deck
+//   - each dealt seat's first two hole cards, as a starting hand ("AKs")
+//   - the suits of the first board
+// Hands that don't verify are only counted as unverified - a forged record
+// shouldn't be able to move the numbers either way.
+//
+// The report compares each tally with what uniformly random dealing would
+// give, as a chi-square goodness of fit. Hole cards and board cards within one
+// hand come from the same deck, so those two fits are approximations; the
+// position fit is exact for a uniform shuffle.
+
+use crate::{
+    hex, replay, ChiSquare, DeckFairness, FairnessTally, GameVariant, HandHistoryRecord, HolePairCount, Suit,
+};
+
+const SUITS: [Suit; 4] = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
+const RANK_CHARS: &[u8; 13] = b"23456789TJQKA";
+
+/// Add one recorded hand to its deck's tally
+pub fn tally(tally: &mut FairnessTally, hand: &HandHistoryRecord) {
+    let Some(order) = verified_order(hand) else {
+        tally.unverified_hands += 1;
+        return;
+    };
+    let deck_size = order.len();
+    let ranks = deck_size / SUITS.len();
+    if tally.positions.is_empty() {
+        tally.positions = vec![0; deck_size * deck_size];
+        tally.hole_pairs = vec![0; ranks * ranks];
+        tally.board_suits = vec![0; SUITS.len()];
+    }
+    tally.hands += 1;
+
+    for (position, card) in order.iter().enumerate() {
+        tally.positions[position * deck_size + card] += 1;
+    }
+
+    let variant = hand.game_variant.unwrap_or_default();
+    let per_seat = replay::hole_card_count(variant);
+    let (seats, _) = replay::dealt_seats(hand);
+    for block in 0..seats.len() {
+        if let (Some(&first), Some(&second)) = (order.get(block * per_seat), order.get(block * per_seat + 1)) {
+            tally.hole_pairs[hole_pair_cell(first, second, ranks)] += 1;
+        }
+    }
+
+    let board = hand.flop.into_iter().flat_map(|(a, b, c)| [a, b, c]).chain(hand.turn).chain(hand.river);
+    for card in board {
+        if let Some(suit) = SUITS.iter().position(|s| *s == card.suit) {
+            tally.board_suits[suit] += 1;
+        }
+    }
+}
+
+/// The shuffled deck as indexes into the unshuffled deck, if the hand verifies
+fn verified_order(hand: &HandHistoryRecord) -> Option<Vec<usize>> {
+    if !replay::verify_deal(hand).is_ok_and(|result| result.valid) {
+        return None;
+    }
+    let seed = hex::decode(&hand.shuffle_proof.revealed_seed).ok()?;
+    let mut order: Vec<usize> = (0..replay::create_deck(hand.game_variant.unwrap_or_default()).len()).collect();
+    cleardeck_shuffle::shuffle(&mut order, &seed, hand.shuffle_proof.shuffle_version).ok()?;
+    Some(order)
+}
+
+/// Starting-hand grid cell for two cards (indexes into the unshuffled deck, suit
+/// major): pairs on the diagonal, suited hands at [high][low], offsuit at [low][high]
+pub fn hole_pair_cell(first: usize, second: usize, ranks: usize) -> usize {
+    let (rank_a, rank_b) = (first % ranks, second % ranks);
+    let (high, low) = (rank_a.max(rank_b), rank_a.min(rank_b));
+    if first / ranks == second / ranks {
+        high * ranks + low
+    } else {
+        low * ranks + high
+    }
+}
+
+/// Label and number of two-card combos for a grid cell - 6 for a pair, 4 suited, 12 offsuit
+fn hole_pair_cell_info(cell: usize, ranks: usize, lowest_rank: usize) -> (String, u64) {
+    let (row, column) = (cell / ranks, cell % ranks);
+    let rank_char = |r: usize| RANK_CHARS[r + lowest_rank - 2] as char;
+    match row.cmp(&column) {
+        std::cmp::Ordering::Equal => (format!("{}{}", rank_char(row), rank_char(row)), 6),
+        std::cmp::Ordering::Greater => (format!("{}{}s", rank_char(row), rank_char(column)), 4),
+        std::cmp::Ordering::Less => (format!("{}{}o", rank_char(column), rank_char(row)), 12),
+    }
+}
+
+/// Report for one deck size's tally
+pub fn deck_report(deck_size: u32, tally: &FairnessTally) -> DeckFairness {
+    let short_deck = replay::create_deck(GameVariant::ShortDeckHoldem);
+    let cards = if deck_size as usize == short_deck.len() { short_deck } else { replay::create_deck(GameVariant::TexasHoldem) };
+    let size = cards.len();
+    let ranks = size / SUITS.len();
+    let lowest_rank = cards.first().map(|c| c.rank as usize).unwrap_or(2);
+    let counts = |list: &Vec<u64>, len: usize| if list.len() == len { list.clone() } else { vec![0; len] };
+
+    let positions = counts(&tally.positions, size * size);
+    let per_cell = tally.hands as f64 / size as f64;
+    let positions_fit = chi_square(&positions, &vec![per_cell; positions.len()], ((size - 1) * (size - 1)) as u32);
+
+    let hole_counts = counts(&tally.hole_pairs, ranks * ranks);
+    let dealt: u64 = hole_counts.iter().sum();
+    let combos = (size * (size - 1) / 2) as f64;
+    let mut hole_pairs = Vec::with_capacity(hole_counts.len());
+    let mut hole_expected = Vec::with_capacity(hole_counts.len());
+    for (cell, &count) in hole_counts.iter().enumerate() {
+        let (hand, cell_combos) = hole_pair_cell_info(cell, ranks, lowest_rank);
+        let expected = dealt as f64 * cell_combos as f64 / combos;
+        hole_pairs.push(HolePairCount { hand, count, expected_x1000: (expected * 1000.0).round() as u64 });
+        hole_expected.push(expected);
+    }
+    let hole_pairs_fit = chi_square(&hole_counts, &hole_expected, (hole_counts.len() - 1) as u32);
+
+    let suit_counts = counts(&tally.board_suits, SUITS.len());
+    let per_suit = suit_counts.iter().sum::<u64>() as f64 / SUITS.len() as f64;
+    let board_suits_fit = chi_square(&suit_counts, &[per_suit; 4], 3);
+
+    DeckFairness {
+        deck_size,
+        hands: tally.hands,
+        unverified_hands: tally.unverified_hands,
+        cards,
+        position_counts: positions.chunks(size).map(<[u64]>::to_vec).collect(),
+        positions: positions_fit,
+        hole_pairs,
+        hole_pairs_fit,
+        board_suits: SUITS.iter().copied().zip(suit_counts).collect(),
+        board_suits_fit,
+    }
+}
+
+/// Pearson's chi-square of observed counts against expected ones. Cells with
+/// nothing expected are skipped; with no data at all the fit is perfect (p = 1).
+pub fn chi_square(observed: &[u64], expected: &[f64], degrees_of_freedom: u32) -> ChiSquare {
+    let statistic: f64 = observed.iter().zip(expected)
+        .filter(|(_, &e)| e > 0.0)
+        .map(|(&o, &e)| (o as f64 - e).powi(2) / e)
+        .sum();
+    ChiSquare {
+        statistic_x1000: (statistic * 1000.0).round() as u64,
+        degrees_of_freedom,
+        p_value_bps: (p_value(statistic, degrees_of_freedom) * 10_000.0).round() as u32,
+    }
+}
+
+/// Upper tail of the chi-square distribution, by the Wilson-Hilferty cube-root
+/// normal approximation - within a fraction of a percent for the degrees of freedom used here
+pub fn p_value(statistic: f64, degrees_of_freedom: u32) -> f64 {
+    if degrees_of_freedom == 0 || statistic <= 0.0 {
+        return 1.0;
+    }
+    let k = degrees_of_freedom as f64;
+    let variance = 2.0 / (9.0 * k);
+    let z = ((statistic / k).cbrt() - (1.0 - variance)) / variance.sqrt();
+    (0.5 * erfc(z / std::f64::consts::SQRT_2)).clamp(0.0, 1.0)
+}
+
+/// Complementary error function (Numerical Recipes erfcc, |error| < 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let tail = t * (-z * z - 1.265_512_23
+        + t * (1.000_023_68
+        + t * (0.374_091_96
+        + t * (0.096_784_18
+        + t * (-0.186_288_06
+        + t * (0.278_868_07
+        + t * (-1.135_203_98
+        + t * (1.488_515_87
+        + t * (-0.822_152_23
+        + t * 0.170_872_77)))))))))
+        .exp();
+    if x >= 0.0 { tail } else { 2.0 - tail }
+}
@@ -1,7 +1,37 @@
 use candid::{CandidType, Deserialize, Principal};
+use ic_cdk::management_canister::{
+    create_canister_with_extra_cycles, install_code, CanisterInstallMode, CanisterSettings,
+    CreateCanisterArgs, InstallCodeArgs,
+};
+use ic_stable_structures::memory_manager::{MemoryId, MemoryManager, VirtualMemory};
+use ic_stable_structures::storable::Bound;
+use ic_stable_structures::{DefaultMemoryImpl, Memory as _, StableBTreeMap, Storable};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
 
+// Archive canister wasm, embedded by build.rs (empty when built without it)
+const ARCHIVE_WASM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/archive_canister.wasm"));
+const MAX_ARCHIVE_BATCH_BYTES: usize = 1_500_000; // Stay well under the 2MB inter-canister message limit
+const MAX_SUMMARIES_PER_QUERY: usize = 100;
+const MAX_EXPORT_HANDS: u64 = 200;
+const MAX_EXPORT_BYTES: usize = 1_500_000; // Query replies are capped at 3MB - leave room
+const MAX_EXPORT_SCAN: usize = 5_000;      // Index entries looked at per call, matching or not
+const SESSION_GAP_NS: u64 = 30 * 60 * 1_000_000_000; // A longer break at a table starts a new session
+const MAX_SESSIONS_PER_QUERY: u64 = 200;
+const MAX_SEARCH_RESULTS: u64 = 100;
+const MAX_SEARCH_STEPS: u32 = 10_000;     // Index seeks per search_hands call
+const MAX_BACKFILL_HANDS: u64 = 2_000;
+
+pub mod fairness;
+pub mod ohh;
+pub mod pokerstars;
+pub mod replay;
+pub mod search;
+pub mod stats;
+pub mod storable;
+
 // ============================================================================
 // TYPES - Hand history data structures
 // ============================================================================
@@ -37,6 +67,37 @@ pub struct Card {
     pub rank: Rank,
 }
 
+/// Poker variant the hand was played in - matches table_canister GameVariant
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum GameVariant {
+    #[default]
+    TexasHoldem,
+    PotLimitOmaha,
+    OmahaHiLo,
+    ShortDeckHoldem,
+}
+
+/// Currency the table played for - matches table_canister Currency
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum Currency {
+    #[default]
+    ICP, // Amounts in e8s
+    BTC, // Amounts in satoshis
+}
+
+impl Currency {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::ICP => "ICP",
+            Currency::BTC => "BTC",
+        }
+    }
+
+    pub fn decimals(&self) -> u8 {
+        8 // Both ICP and BTC use 8 decimals
+    }
+}
+
 #[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
 pub enum HandRank {
     HighCard(Vec<u8>),
@@ -60,6 +121,7 @@ pub enum PlayerAction {
     Raise(u64),     // Total raise amount
     AllIn(u64),     // All-in amount
     PostBlind(u64), // Blind posted
+    Straddle(u64),  // Live straddle posted before the deal
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -81,6 +143,19 @@ pub struct PlayerHandRecord {
     pub final_hand_rank: Option<HandRank>,
     pub amount_won: u64,
     pub position: String, // "dealer", "sb", "bb", "utg", etc.
+    #[serde(default)] // Every hole card for any variant (4 for Omaha); None on older records
+    pub all_hole_cards: Option<Vec<Card>>,
+}
+
+impl PlayerHandRecord {
+    /// Revealed hole cards regardless of variant or record age
+    pub fn revealed_hole_cards(&self) -> Vec<Card> {
+        match (&self.all_hole_cards, self.hole_cards) {
+            (Some(cards), _) => cards.clone(),
+            (None, Some((c1, c2))) => vec![c1, c2],
+            (None, None) => Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -88,6 +163,26 @@ pub struct ShuffleProofRecord {
     pub seed_hash: String,      // SHA-256 hash of seed (committed before dealing)
     pub revealed_seed: String,  // The actual seed (revealed after hand)
     pub timestamp: u64,         // When the commitment was made
+    #[serde(default)] // None unless players mixed their own entropy into the seed
+    pub entropy: Option<PlayerEntropyRecord>,
+    #[serde(default)] // None for hands dealt before shuffles were versioned (cleardeck_shuffle::SHUFFLE_V1)
+    pub shuffle_version: Option<u32>,
+}
+
+/// Player entropy mixed into a seed: seed = SHA-256(VRF bytes || revealed nonces in seat order)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerEntropyRecord {
+    pub vrf_seed_hash: String,  // Published before any nonce was revealed
+    pub vrf_seed: String,       // Revealed with the seed
+    pub contributions: Vec<EntropyContributionRecord>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct EntropyContributionRecord {
+    pub seat: u8,
+    pub principal: Principal,
+    pub commitment: String,     // SHA-256 of the nonce, committed before the deal
+    pub nonce: Option<String>,  // None if not revealed in time - left out of the seed
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -127,6 +222,26 @@ pub struct HandHistoryRecord {
 
     // Summary
     pub went_to_showdown: bool,
+
+    // Variant played (None for hands recorded before variants existed - Texas Hold'em)
+    #[serde(default)]
+    pub game_variant: Option<GameVariant>,
+
+    // Sit & Go hand - chips are tournament chips, not currency
+    #[serde(default)]
+    pub is_tournament: Option<bool>,
+
+    // Second runout when the board was run twice (flop/turn/river hold the first)
+    #[serde(default)]
+    pub second_board: Option<Vec<Card>>,
+
+    // Table currency - amounts are e8s (ICP) or sats (BTC); None on older records
+    #[serde(default)]
+    pub currency: Option<Currency>,
+
+    // Seats dealt in, in dealing order - None on older records
+    #[serde(default)]
+    pub dealt_seats: Option<Vec<u8>>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -135,7 +250,30 @@ pub struct WinnerRecord {
     pub principal: Principal,
     pub amount: u64,
     pub hand_rank: Option<HandRank>,
-    pub pot_type: String, // "main" or "side_1", "side_2", etc.
+    pub pot_type: String, // "main", or "high"/"low" for the halves of a Hi-Lo pot
+    #[serde(default)]
+    pub run: Option<u8>, // 1 or 2 when the board was run twice
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentResultRecord {
+    pub principal: Principal,
+    pub position: u8,   // 1 = winner
+    pub prize: u64,     // Paid to the player's escrow balance
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentRecord {
+    pub tournament_id: u64,     // Assigned by this canister
+    pub table_id: Principal,    // Table canister that ran the Sit & Go
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub buy_in: u64,
+    pub fee: u64,
+    pub prize_pool: u64,
+    pub entrants: u8,
+    pub hands_played: u64,
+    pub results: Vec<TournamentResultRecord>, // Ordered by finishing position
 }
 
 // Query result types
@@ -149,6 +287,7 @@ pub struct HandSummary {
     pub total_pot: u64,
     pub winners: Vec<WinnerRecord>,
     pub went_to_showdown: bool,
+    pub game_variant: GameVariant,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -160,38 +299,562 @@ pub struct PlayerStats {
     pub biggest_pot_won: u64,
     pub showdowns_won: u64,
     pub showdowns_total: u64,
+    // VPIP, PFR and friends - None for players with no hands since they were added
+    #[serde(default)]
+    pub advanced: Option<AdvancedStats>,
+}
+
+/// Raw counts behind the advanced stats - each rate is one count over its opportunities
+#[derive(Clone, Debug, Default, CandidType, Deserialize, PartialEq, Eq)]
+pub struct StatCounters {
+    pub hands: u64,
+    pub vpip: u64,                            // Voluntarily put chips in preflop
+    pub pfr: u64,                             // Raised preflop
+    pub three_bet_opportunities: u64,         // Faced a single preflop raise
+    pub three_bets: u64,
+    pub fold_to_three_bet_opportunities: u64, // Opened and faced a 3-bet
+    pub folds_to_three_bet: u64,
+    pub cbet_opportunities: u64,              // Preflop aggressor, first to bet on the flop
+    pub cbets: u64,
+    pub postflop_bets_raises: u64,            // Aggression factor = bets and raises / calls
+    pub postflop_calls: u64,
+    pub saw_flop: u64,
+    pub went_to_showdown: u64,                // WTSD = went_to_showdown / saw_flop
+    pub won_at_showdown: u64,                 // W$SD = won_at_showdown / went_to_showdown
+}
+
+/// Advanced stats overall, by position ("BTN", "SB", "BB", "Seat N") and by stakes
+#[derive(Clone, Debug, Default, CandidType, Deserialize)]
+pub struct AdvancedStats {
+    pub overall: StatCounters,
+    pub by_position: Vec<(String, StatCounters)>,
+    pub by_stakes: Vec<(String, StatCounters)>, // "0.01/0.02 ICP", or "Tournament"
+}
+
+/// Rates for one slice of a player's hands, in basis points (10_000 = 100%)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct StatLine {
+    pub label: String,
+    pub counters: StatCounters,
+    pub vpip_bps: u32,
+    pub pfr_bps: u32,
+    pub three_bet_bps: u32,
+    pub fold_to_three_bet_bps: u32,
+    pub cbet_bps: u32,
+    pub aggression_factor_x100: Option<u32>, // None until the player has called postflop
+    pub wtsd_bps: u32,
+    pub wsd_bps: u32,
+}
+
+/// One player's cash results at one table on one UTC day
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct DailyResult {
+    pub currency: Currency,
+    pub hands: u64,
+    pub profit: i64, // e8s or sats
+    pub mbb: i64,    // Profit in thousandths of a big blind, summed hand by hand
+}
+
+/// A run of hands at one table with no break longer than SESSION_GAP_NS
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct Session {
+    pub table_id: Principal,
+    pub currency: Currency,
+    pub started_at: u64,
+    pub ended_at: u64, // Start of the last hand
+    pub hands: u64,
+    pub profit: i64,
+    pub mbb: i64,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct SessionSummary {
+    pub session: Session,
+    pub bb_per_100_x100: i64, // bb/100, times 100
+}
+
+/// One day on a profit graph - the running totals are what gets plotted
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct ProfitPoint {
+    pub day_start: u64, // Midnight UTC, nanoseconds
+    pub hands: u64,
+    pub profit: i64,
+    pub cumulative_hands: u64,
+    pub cumulative_profit: i64,
+}
+
+/// Profit over a date range at one table, or across every table in a currency (table_id None)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ProfitSeries {
+    pub table_id: Option<Principal>,
+    pub currency: Currency,
+    pub hands: u64,
+    pub profit: i64,
+    pub bb_per_100_x100: i64,
+    pub points: Vec<ProfitPoint>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct AdvancedStatsReport {
+    pub principal: Principal,
+    pub overall: StatLine,
+    pub by_position: Vec<StatLine>,
+    pub by_stakes: Vec<StatLine>,
+}
+
+/// When and how the history canister moves old hands into archive canisters
+/// (ICRC-3 ledger archive style). Archiving is off until an admin sets these.
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ArchiveOptions {
+    pub trigger_threshold: u64,          // Archive once more than this many hands are held here
+    pub num_hands_to_archive: u64,       // Oldest hands moved per archiving run
+    pub max_hands_per_archive: u64,      // A new archive is created when the last one is full
+    pub cycles_for_archive_creation: u64, // Cycles each new archive starts with
+}
+
+/// An archive canister and the hand IDs it holds
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ArchiveInfo {
+    pub canister_id: Principal,
+    pub start: u64, // First hand_id held
+    pub end: u64,   // One past the last hand_id held
+    pub remaining_capacity: u64,
+}
+
+/// Install argument for archive canisters - matches archive_canister ArchiveInit
+#[derive(Clone, Debug, CandidType, Deserialize)]
+struct ArchiveInit {
+    history_canister: Principal,
+    max_hands: u64,
+}
+
+/// Answer to get_hand - the hand itself, or the archive that holds it
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct GetHandResponse {
+    pub hand: Option<HandHistoryRecord>,
+    pub archive: Option<Principal>, // Ask this canister's get_hand instead
+}
+
+/// Hands in a page that live in an archive - fetch with its get_hand_summaries
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct ArchivedHands {
+    pub canister_id: Principal,
+    pub hand_ids: Vec<u64>,
+}
+
+/// A page of hands, newest first - archived ones come back as pointers
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandsPage {
+    pub hands: Vec<HandSummary>,
+    pub archived: Vec<ArchivedHands>,
+}
+
+/// Bulk hand export (PokerStars or OHH) - one player's or one table's hands between two times
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandExportRequest {
+    pub player: Option<Principal>,   // Set exactly one of player / table_id
+    pub table_id: Option<Principal>,
+    pub from_timestamp: u64,         // Inclusive, nanoseconds
+    pub to_timestamp: u64,           // Inclusive, nanoseconds
+    pub after_hand_id: Option<u64>,  // next_cursor from the previous page
+    pub limit: u64,                  // Hands per page, at most MAX_EXPORT_HANDS
+}
+
+/// A page of exported hands, oldest first, separated by blank lines
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandExport {
+    pub text: String,
+    pub hand_count: u64,
+    pub next_cursor: Option<u64>,     // Pass as after_hand_id; None when the range is done
+    pub archived: Vec<ArchivedHands>, // Not filtered by time - export them from the archive
+}
+
+/// HandRank without its kickers, weakest first
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HandRankCategory {
+    HighCard,
+    Pair,
+    TwoPair,
+    ThreeOfAKind,
+    Straight,
+    Flush,
+    FullHouse,
+    FourOfAKind,
+    StraightFlush,
+    RoyalFlush,
+}
+
+/// How search_hands combines the players in a filter
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum PlayerMatch {
+    All, // Every listed player was in the hand
+    Any, // At least one was
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct Stakes {
+    pub small_blind: u64,
+    pub big_blind: u64,
+}
+
+/// search_hands filter - every field that is set must match
+#[derive(Clone, Debug, CandidType, Deserialize, Default)]
+pub struct HandSearchFilter {
+    pub from_timestamp: Option<u64>,                // Inclusive, nanoseconds
+    pub to_timestamp: Option<u64>,                  // Inclusive, nanoseconds
+    pub stakes: Option<Stakes>,                     // In either currency unless currency is set too
+    pub currency: Option<Currency>,
+    pub min_pot: Option<u64>,
+    pub max_pot: Option<u64>,
+    pub showdown_only: bool,
+    pub min_winning_rank: Option<HandRankCategory>, // e.g. FourOfAKind for quads or better
+    pub position: Option<String>,                   // "BTN", "SB", "BB", "Seat N" - needs players
+    pub players: Vec<Principal>,
+    pub players_match: Option<PlayerMatch>,         // Defaults to Any
+}
+
+/// Where a search page stopped - results are ordered newest first by (timestamp, hand_id)
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct SearchCursor {
+    pub timestamp: u64,
+    pub hand_id: u64,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HandSearchPage {
+    pub hands: Vec<HandSummary>,
+    pub archived: Vec<ArchivedHands>,     // Matches that have moved to an archive
+    pub next_cursor: Option<SearchCursor>, // None when there are no more matches
+}
+
+/// verify_hand_shuffle report - the seed checked against its commitment, then
+/// the whole deal replayed from it and compared with the recorded cards
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct DealVerification {
+    pub valid: bool,                // Seed checks out (hash and any player entropy) and every recorded card matches the replay
+    pub seed_matches_hash: bool,
+    pub entropy_matches: Option<bool>, // Seed is the VRF bytes mixed with the revealed nonces; None without player entropy
+    pub shuffle_version: u32,       // Shuffle the deck was replayed with
+    pub dealt_seats: Vec<u8>,       // Seats in dealing order
+    pub dealt_seats_inferred: bool, // Older records don't list who was dealt in - worked out from the hand
+    pub hole_cards_checked: u32,
+    pub board_cards_checked: u32,
+    pub mismatches: Vec<DealMismatch>,
+}
+
+/// A recorded card the replayed deck doesn't agree with
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct DealMismatch {
+    pub location: String,           // e.g. "Seat 3 hole card 1", "Flop card 2", "Second board river"
+    pub deck_position: Option<u32>, // Where the replay deals it; None if the seat wasn't dealt in
+    pub expected: Option<Card>,     // Card at that position in the replayed deck
+    pub recorded: Card,
+}
+
+/// Running counts of what verified hands dealt, for one deck size - see fairness.rs
+#[derive(Clone, Debug, CandidType, Deserialize, Default)]
+pub struct FairnessTally {
+    pub hands: u64,            // Verified hands counted
+    pub unverified_hands: u64, // Replay didn't match the record - left out of every count
+    pub positions: Vec<u64>,   // [deck position * deck size + card], cards in unshuffled deck order
+    pub hole_pairs: Vec<u64>,  // Starting-hand grid, [row * ranks + column] - see fairness::hole_pair_cell
+    pub board_suits: Vec<u64>, // Hearts, Diamonds, Clubs, Spades
+}
+
+/// get_fairness_report - is the shuffle dealing uniformly random cards?
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct FairnessReport {
+    pub decks: Vec<DeckFairness>, // One per deck size dealt
+    pub backfill_pending: bool,   // Hands recorded before the audit existed aren't all counted yet
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct DeckFairness {
+    pub deck_size: u32,
+    pub hands: u64,
+    pub unverified_hands: u64,
+    pub cards: Vec<Card>,                // Unshuffled deck order - the columns of position_counts
+    pub position_counts: Vec<Vec<u64>>,  // [deck position][card]: how often each card landed at each position
+    pub positions: ChiSquare,            // Every card equally likely at every position
+    pub hole_pairs: Vec<HolePairCount>,  // Every starting hand, e.g. "AA", "AKs", "AKo"
+    pub hole_pairs_fit: ChiSquare,       // Against each hand's share of the possible combos (6 pair, 4 suited, 12 offsuit)
+    pub board_suits: Vec<(Suit, u64)>,
+    pub board_suits_fit: ChiSquare,      // Every suit equally likely on the board
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HolePairCount {
+    pub hand: String,
+    pub count: u64,
+    pub expected_x1000: u64,
+}
+
+/// Chi-square goodness of fit against uniformly random dealing.
+/// Only meaningful once every expected count is at least ~5.
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub struct ChiSquare {
+    pub statistic_x1000: u64,
+    pub degrees_of_freedom: u32,
+    pub p_value_bps: u32, // Chance a fair shuffle scores this badly or worse; rigged dealing drives it towards 0
 }
 
 // ============================================================================
 // STATE
 // ============================================================================
 
+// Hand records, their indexes and player stats live in stable memory so they
+// never pass through pre_upgrade/post_upgrade. Each structure gets its own
+// virtual memory - never reuse or renumber these IDs.
+const UPGRADES_MEMORY: MemoryId = MemoryId::new(0);
+const HANDS_MEMORY: MemoryId = MemoryId::new(1);
+const HANDS_BY_TABLE_MEMORY: MemoryId = MemoryId::new(2);
+const HANDS_BY_PLAYER_MEMORY: MemoryId = MemoryId::new(3);
+const HAND_IDS_BY_TABLE_HAND_MEMORY: MemoryId = MemoryId::new(4);
+const PLAYER_STATS_MEMORY: MemoryId = MemoryId::new(5);
+const TABLE_HAND_COUNTS_MEMORY: MemoryId = MemoryId::new(6);
+const DAILY_RESULTS_MEMORY: MemoryId = MemoryId::new(7);
+const SESSIONS_MEMORY: MemoryId = MemoryId::new(8);
+const OPEN_SESSIONS_MEMORY: MemoryId = MemoryId::new(9);
+const SEARCH_INDEX_MEMORY: MemoryId = MemoryId::new(10);
+const HAND_POTS_MEMORY: MemoryId = MemoryId::new(11);
+const FAIRNESS_MEMORY: MemoryId = MemoryId::new(12);
+const SCHEMAS_MEMORY: MemoryId = MemoryId::new(13); // Record schemas - see storable.rs
+const HAND_SEEDS_MEMORY: MemoryId = MemoryId::new(14);
+
+type Memory = VirtualMemory<DefaultMemoryImpl>;
+
+/// Store a candid type in a stable structure
+macro_rules! impl_storable_candid {
+    ($($t:ty),*) => {$(
+        impl Storable for $t {
+            fn to_bytes(&self) -> Cow<'_, [u8]> {
+                Cow::Owned(storable::encode_record(self))
+            }
+
+            fn from_bytes(bytes: Cow<[u8]>) -> Self {
+                storable::decode_record(&bytes)
+            }
+
+            const BOUND: Bound = Bound::Unbounded;
+        }
+    )*};
+}
+
+impl_storable_candid!(HandHistoryRecord, PlayerStats, DailyResult, Session, FairnessTally);
+
 thread_local! {
-    static STATE: RefCell<HistoryState> = RefCell::new(HistoryState::default());
+    static MEMORY_MANAGER: RefCell<MemoryManager<DefaultMemoryImpl>> =
+        RefCell::new(MemoryManager::init(DefaultMemoryImpl::default()));
+    static STATE: RefCell<HistoryState> = RefCell::new(HistoryState::init());
+    // True while hands are being moved to an archive - one run at a time
+    static ARCHIVING: RefCell<bool> = const { RefCell::new(false) };
+}
+
+fn memory(id: MemoryId) -> Memory {
+    MEMORY_MANAGER.with(|m| m.borrow().get(id))
 }
 
-#[derive(Default)]
 struct HistoryState {
     // All hand records, keyed by hand_id
-    hands: BTreeMap<u64, HandHistoryRecord>,
+    hands: StableBTreeMap<u64, HandHistoryRecord, Memory>,
+
+    // Index: (table_id, hand_id) - a range over one table gives its hands in order
+    hands_by_table: StableBTreeMap<(Principal, u64), (), Memory>,
 
-    // Index: table_id -> list of hand_ids
-    hands_by_table: BTreeMap<Principal, Vec<u64>>,
+    // Index: (player principal, hand_id)
+    hands_by_player: StableBTreeMap<(Principal, u64), (), Memory>,
 
-    // Index: player principal -> list of hand_ids
-    hands_by_player: BTreeMap<Principal, Vec<u64>>,
+    // Index: (table_id, hand_number) -> hand_id, so table retries never record a hand twice
+    hand_ids_by_table_hand: StableBTreeMap<(Principal, u64), u64, Memory>,
+
+    // hand_id -> SHA-256 of its shuffle seed_hash, so retries are checked without
+    // reading the record. Kept for archived hands, like the index above.
+    hand_seeds: StableBTreeMap<u64, [u8; 32], Memory>,
 
     // Player stats cache
-    player_stats: BTreeMap<Principal, PlayerStats>,
+    player_stats: StableBTreeMap<Principal, PlayerStats, Memory>,
+
+    // Hands recorded per table, so counting doesn't walk the index
+    table_hand_counts: StableBTreeMap<Principal, u64, Memory>,
+
+    // Profit time series: (player, UTC day, table) -> that day's results
+    daily_results: StableBTreeMap<(Principal, u64, Principal), DailyResult, Memory>,
+
+    // Sessions: (player, started_at, table) -> session
+    sessions: StableBTreeMap<(Principal, u64, Principal), Session, Memory>,
+
+    // (player, table) -> started_at of the player's latest session there
+    open_sessions: StableBTreeMap<(Principal, Principal), u64, Memory>,
+
+    // search_hands index - see search.rs for the key layout. Kept for archived
+    // hands too, so searches can point at the archive holding a match.
+    search_index: StableBTreeMap<Vec<u8>, (), Memory>,
+
+    // hand_id -> total pot, for search_hands pot filters
+    hand_pots: StableBTreeMap<u64, u64, Memory>,
+
+    // Deck size -> what verified hands dealt, for get_fairness_report. Kept when hands are archived.
+    fairness: StableBTreeMap<u32, FairnessTally, Memory>,
+
+    // Everything below is small and saved to UPGRADES_MEMORY in pre_upgrade
 
     // Next hand ID
     next_hand_id: u64,
 
+    // Finished Sit & Go tournaments, keyed by tournament_id
+    tournaments: BTreeMap<u64, TournamentRecord>,
+
+    // Index: player principal -> list of tournament_ids (rebuilt on upgrade)
+    tournaments_by_player: BTreeMap<Principal, Vec<u64>>,
+
+    // Index: (table_id, started_at) -> tournament_id, so a resent record isn't counted twice (rebuilt on upgrade)
+    tournament_ids_by_table_start: BTreeMap<(Principal, u64), u64>,
+
+    // Next tournament ID
+    next_tournament_id: u64,
+
     // Authorized table canisters that can write history
     authorized_tables: Vec<Principal>,
 
     // Admin principal
     admin: Option<Principal>,
+
+    // Archiving settings (None = never archive) and the archives created so far, oldest first
+    archive_options: Option<ArchiveOptions>,
+    archives: Vec<ArchiveInfo>,
+
+    // Hands from fairness_live_from on were tallied by record_hand; backfill_fairness
+    // works through the earlier ones from fairness_backfill_next
+    fairness_backfill_next: u64,
+    fairness_live_from: u64,
+
+    // Hands moved over from the heap layout are stored by post_upgrade but indexed
+    // by migrate_legacy_hands, from legacy_index_next up to legacy_index_end
+    legacy_index_next: u64,
+    legacy_index_end: u64,
+}
+
+impl HistoryState {
+    fn init() -> Self {
+        Self {
+            hands: StableBTreeMap::init(memory(HANDS_MEMORY)),
+            hands_by_table: StableBTreeMap::init(memory(HANDS_BY_TABLE_MEMORY)),
+            hands_by_player: StableBTreeMap::init(memory(HANDS_BY_PLAYER_MEMORY)),
+            hand_ids_by_table_hand: StableBTreeMap::init(memory(HAND_IDS_BY_TABLE_HAND_MEMORY)),
+            hand_seeds: StableBTreeMap::init(memory(HAND_SEEDS_MEMORY)),
+            player_stats: StableBTreeMap::init(memory(PLAYER_STATS_MEMORY)),
+            table_hand_counts: StableBTreeMap::init(memory(TABLE_HAND_COUNTS_MEMORY)),
+            daily_results: StableBTreeMap::init(memory(DAILY_RESULTS_MEMORY)),
+            sessions: StableBTreeMap::init(memory(SESSIONS_MEMORY)),
+            open_sessions: StableBTreeMap::init(memory(OPEN_SESSIONS_MEMORY)),
+            search_index: StableBTreeMap::init(memory(SEARCH_INDEX_MEMORY)),
+            hand_pots: StableBTreeMap::init(memory(HAND_POTS_MEMORY)),
+            fairness: StableBTreeMap::init(memory(FAIRNESS_MEMORY)),
+            next_hand_id: 1,
+            tournaments: BTreeMap::new(),
+            tournaments_by_player: BTreeMap::new(),
+            tournament_ids_by_table_start: BTreeMap::new(),
+            next_tournament_id: 1,
+            authorized_tables: Vec::new(),
+            admin: None,
+            archive_options: None,
+            archives: Vec::new(),
+            fairness_backfill_next: 1,
+            fairness_live_from: 1,
+            legacy_index_next: 0,
+            legacy_index_end: 0,
+        }
+    }
+
+    /// Archive canister holding a hand that is no longer stored here
+    fn archive_for(&self, hand_id: u64) -> Option<Principal> {
+        self.archives.iter()
+            .find(|a| a.start <= hand_id && hand_id < a.end)
+            .map(|a| a.canister_id)
+    }
+
+    /// Summaries for local hands, pointers for archived ones
+    fn hands_page(&self, hand_ids: Vec<u64>) -> HandsPage {
+        let mut page = HandsPage { hands: Vec::new(), archived: Vec::new() };
+        for hand_id in hand_ids {
+            if let Some(hand) = self.hands.get(&hand_id) {
+                page.hands.push(to_summary(&hand));
+            } else if let Some(canister_id) = self.archive_for(hand_id) {
+                match page.archived.iter_mut().find(|a| a.canister_id == canister_id) {
+                    Some(group) => group.hand_ids.push(hand_id),
+                    None => page.archived.push(ArchivedHands { canister_id, hand_ids: vec![hand_id] }),
+                }
+            }
+        }
+        page
+    }
+
+    /// Store a hand that already has its hand_id, updating every index
+    fn insert_hand(&mut self, record: HandHistoryRecord) {
+        self.index_hand(&record);
+        self.hands.insert(record.hand_id, record);
+    }
+
+    fn index_hand(&mut self, record: &HandHistoryRecord) {
+        let hand_id = record.hand_id;
+        self.hands_by_table.insert((record.table_id, hand_id), ());
+        let count = self.table_hand_counts.get(&record.table_id).unwrap_or(0);
+        self.table_hand_counts.insert(record.table_id, count + 1);
+        for player in &record.players {
+            self.hands_by_player.insert((player.principal, hand_id), ());
+        }
+        // Later hands win after a table reset, even when an older one is indexed late
+        let key = (record.table_id, record.hand_number);
+        if self.hand_ids_by_table_hand.get(&key).is_none_or(|id| id < hand_id) {
+            self.hand_ids_by_table_hand.insert(key, hand_id);
+        }
+        self.hand_seeds.insert(hand_id, seed_fingerprint(&record.shuffle_proof.seed_hash));
+        self.index_for_search(record);
+    }
+
+    fn legacy_migration_pending(&self) -> bool {
+        self.legacy_index_next < self.legacy_index_end
+    }
+
+    /// Add a hand's dealt cards to its deck's fairness tally
+    fn tally_fairness(&mut self, record: &HandHistoryRecord) {
+        let deck_size = replay::create_deck(record.game_variant.unwrap_or_default()).len() as u32;
+        let mut tally = self.fairness.get(&deck_size).unwrap_or_default();
+        fairness::tally(&mut tally, record);
+        self.fairness.insert(deck_size, tally);
+    }
+
+    fn index_for_search(&mut self, record: &HandHistoryRecord) {
+        for key in search::hand_keys(record) {
+            self.search_index.insert(key, ());
+        }
+        self.hand_pots.insert(record.hand_id, record.total_pot);
+    }
+}
+
+/// Fixed-size stand-in for a shuffle's seed_hash, whatever the table sent
+fn seed_fingerprint(seed_hash: &str) -> [u8; 32] {
+    Sha256::digest(seed_hash.as_bytes()).into()
+}
+
+/// Sorts after every other principal, to close a range over keys that contain one
+fn max_principal() -> Principal {
+    Principal::from_slice(&[0xFF; 29])
+}
+
+/// Hand IDs in an (owner, hand_id) index, newest first
+fn hand_ids_newest_first(
+    index: &StableBTreeMap<(Principal, u64), (), Memory>,
+    owner: Principal,
+    offset: u64,
+    limit: u64,
+) -> Vec<u64> {
+    index.keys_range((owner, 0)..=(owner, u64::MAX))
+        .rev()
+        .skip(offset as usize)
+        .take(limit as usize)
+        .map(|(_, hand_id)| hand_id)
+        .collect()
 }
 
 // ============================================================================
@@ -203,7 +866,6 @@ fn init() {
     STATE.with(|s| {
         let mut state = s.borrow_mut();
         state.admin = Some(ic_cdk::api::msg_caller());
-        state.next_hand_id = 1;
     });
 }
 
@@ -244,6 +906,102 @@ fn get_authorized_tables() -> Vec<Principal> {
     STATE.with(|s| s.borrow().authorized_tables.clone())
 }
 
+/// Add hands recorded before search_hands existed to its index, oldest first.
+/// Returns the last hand indexed while there may be more - pass it back as after_hand_id.
+#[ic_cdk::update]
+fn backfill_search_index(after_hand_id: Option<u64>, limit: u64) -> Result<Option<u64>, String> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.admin != Some(ic_cdk::api::msg_caller()) {
+            return Err("Unauthorized".to_string());
+        }
+        let start = match after_hand_id {
+            Some(id) => id.checked_add(1).ok_or("Cursor is past the last hand")?,
+            None => 0,
+        };
+        let limit = limit.clamp(1, MAX_BACKFILL_HANDS);
+        let batch: Vec<HandHistoryRecord> = state.hands.range(start..).take(limit as usize).map(|(_, h)| h).collect();
+        for hand in &batch {
+            state.index_for_search(hand);
+        }
+        Ok(batch.last().filter(|_| batch.len() as u64 == limit).map(|h| h.hand_id))
+    })
+}
+
+/// Index hands moved over from the heap layout, oldest first: table and player
+/// indexes, search index and profit series. post_upgrade only stores them, so
+/// the upgrade doesn't grow with the number of hands. Returns how many hand IDs are still to go.
+#[ic_cdk::update]
+fn migrate_legacy_hands(limit: u64) -> Result<u64, String> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.admin != Some(ic_cdk::api::msg_caller()) {
+            return Err("Unauthorized".to_string());
+        }
+        let (start, end) = (state.legacy_index_next, state.legacy_index_end);
+        let limit = limit.clamp(1, MAX_BACKFILL_HANDS);
+        let batch: Vec<HandHistoryRecord> = state.hands.range(start..end).take(limit as usize).map(|(_, h)| h).collect();
+        for hand in &batch {
+            for player in &hand.players {
+                update_player_results(&mut state, player, hand);
+            }
+            state.index_hand(hand);
+        }
+        state.legacy_index_next = match batch.last() {
+            Some(hand) if batch.len() as u64 == limit => hand.hand_id + 1,
+            _ => end,
+        };
+        Ok(end - state.legacy_index_next)
+    })
+}
+
+/// Add pot buckets to the search index for hands indexed before it had them, oldest
+/// first. Archived hands are covered too - their index entries and pot sizes stay here.
+/// Returns where it stopped while there may be more - pass it back as `after`.
+#[ic_cdk::update]
+fn backfill_pot_index(after: Option<SearchCursor>, limit: u64) -> Result<Option<SearchCursor>, String> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.admin != Some(ic_cdk::api::msg_caller()) {
+            return Err("Unauthorized".to_string());
+        }
+        let limit = limit.clamp(1, MAX_BACKFILL_HANDS);
+        let positions = search::time_positions(&state.search_index, after.map(|c| (c.timestamp, c.hand_id)), limit);
+        for &(timestamp, hand_id) in &positions {
+            if let Some(pot) = state.hand_pots.get(&hand_id) {
+                state.search_index.insert(search::pot_key(pot, (timestamp, hand_id)), ());
+            }
+        }
+        Ok(positions.last()
+            .filter(|_| positions.len() as u64 == limit)
+            .map(|&(timestamp, hand_id)| SearchCursor { timestamp, hand_id }))
+    })
+}
+
+/// Add hands recorded before the fairness audit existed to its tallies, oldest first.
+/// Each hand is only ever counted once. Returns how many hand IDs are still to go.
+#[ic_cdk::update]
+fn backfill_fairness(limit: u64) -> Result<u64, String> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.admin != Some(ic_cdk::api::msg_caller()) {
+            return Err("Unauthorized".to_string());
+        }
+        let (start, end) = (state.fairness_backfill_next, state.fairness_live_from);
+        let limit = limit.clamp(1, MAX_BACKFILL_HANDS);
+        let batch: Vec<HandHistoryRecord> = state.hands.range(start..end).take(limit as usize).map(|(_, h)| h).collect();
+        for hand in &batch {
+            state.tally_fairness(hand);
+        }
+        // Hands already archived can't be replayed here - skip past them
+        state.fairness_backfill_next = match batch.last() {
+            Some(hand) if batch.len() as u64 == limit => hand.hand_id + 1,
+            _ => end,
+        };
+        Ok(end - state.fairness_backfill_next)
+    })
+}
+
 // ============================================================================
 // WRITE FUNCTIONS (called by table canister)
 // ============================================================================
@@ -252,7 +1010,7 @@ fn get_authorized_tables() -> Vec<Principal> {
 fn record_hand(record: HandHistoryRecord) -> Result<u64, String> {
     let caller = ic_cdk::api::msg_caller();
 
-    STATE.with(|s| {
+    let result = STATE.with(|s| {
         let mut state = s.borrow_mut();
 
         // SECURITY FIX: Verify caller is authorized (either a registered table or admin)
@@ -264,6 +1022,21 @@ fn record_hand(record: HandHistoryRecord) -> Result<u64, String> {
             return Err("Unauthorized: table not registered. Admin must add table via authorize_table.".to_string());
         }
 
+        // Idempotent on (table_id, hand_number): a retried send returns the hand_id it already got.
+        // A different shuffle means the table was reset and is counting hands again - record it.
+        let key = (record.table_id, record.hand_number);
+        if let Some(existing_id) = state.hand_ids_by_table_hand.get(&key) {
+            let same_shuffle = match state.hand_seeds.get(&existing_id) {
+                Some(seed) => seed == seed_fingerprint(&record.shuffle_proof.seed_hash),
+                // Recorded before seeds were kept - only a local hand can still be checked
+                None => state.hands.get(&existing_id)
+                    .is_some_and(|h| h.shuffle_proof.seed_hash == record.shuffle_proof.seed_hash),
+            };
+            if same_shuffle {
+                return Ok(existing_id);
+            }
+        }
+
         // Assign hand ID
         let hand_id = state.next_hand_id;
         state.next_hand_id += 1;
@@ -272,44 +1045,55 @@ fn record_hand(record: HandHistoryRecord) -> Result<u64, String> {
         let mut final_record = record;
         final_record.hand_id = hand_id;
 
-        // Update indexes
-        state.hands_by_table
-            .entry(final_record.table_id)
-            .or_default()
-            .push(hand_id);
-
+        // Update player stats
+        let counters = stats::hand_counters(&final_record);
         for player in &final_record.players {
-            state.hands_by_player
-                .entry(player.principal)
-                .or_default()
-                .push(hand_id);
-
-            // Update player stats
-            update_player_stats(&mut state, player, &final_record);
+            update_player_stats(&mut state, player, &final_record, counters.get(&player.seat));
+            update_player_results(&mut state, player, &final_record);
         }
 
-        // Store the record
-        state.hands.insert(hand_id, final_record);
+        // Store the record and update indexes
+        state.tally_fairness(&final_record);
+        state.insert_hand(final_record);
 
         Ok(hand_id)
-    })
+    });
+
+    if result.is_ok() {
+        maybe_archive();
+    }
+    result
 }
 
-fn update_player_stats(state: &mut HistoryState, player: &PlayerHandRecord, hand: &HandHistoryRecord) {
-    let stats = state.player_stats.entry(player.principal).or_insert(PlayerStats {
-        principal: player.principal,
+fn empty_stats(principal: Principal) -> PlayerStats {
+    PlayerStats {
+        principal,
         hands_played: 0,
         hands_won: 0,
         total_winnings: 0,
         biggest_pot_won: 0,
         showdowns_won: 0,
         showdowns_total: 0,
-    });
+        advanced: None,
+    }
+}
+
+fn update_player_stats(
+    state: &mut HistoryState,
+    player: &PlayerHandRecord,
+    hand: &HandHistoryRecord,
+    counters: Option<&StatCounters>,
+) {
+    let mut stats = state.player_stats.get(&player.principal)
+        .unwrap_or_else(|| empty_stats(player.principal));
 
     stats.hands_played += 1;
 
-    let profit = player.ending_chips as i64 - player.starting_chips as i64;
-    stats.total_winnings += profit;
+    // Tournament chips have no currency value - tournament profit comes from prizes
+    if hand.is_tournament != Some(true) {
+        let profit = player.ending_chips as i64 - player.starting_chips as i64;
+        stats.total_winnings += profit;
+    }
 
     if player.amount_won > 0 {
         stats.hands_won += 1;
@@ -321,123 +1105,638 @@ fn update_player_stats(state: &mut HistoryState, player: &PlayerHandRecord, hand
     // Check if went to showdown
     if hand.went_to_showdown {
         // Player went to showdown if they have revealed cards and didn't fold
-        if player.hole_cards.is_some() {
+        if !player.revealed_hole_cards().is_empty() {
             stats.showdowns_total += 1;
             if player.amount_won > 0 {
                 stats.showdowns_won += 1;
             }
         }
     }
+
+    if let Some(counters) = counters {
+        let advanced = stats.advanced.get_or_insert_with(AdvancedStats::default);
+        stats::record(advanced, &player.position, &stats::stakes_label(hand), counters);
+    }
+
+    state.player_stats.insert(player.principal, stats);
+}
+
+/// Add a cash hand to the player's daily and session profit series.
+/// Tournament chips have no currency value, so tournament hands are left out.
+fn update_player_results(state: &mut HistoryState, player: &PlayerHandRecord, hand: &HandHistoryRecord) {
+    if hand.is_tournament == Some(true) {
+        return;
+    }
+    let currency = hand.currency.unwrap_or_default();
+    let profit = player.ending_chips as i64 - player.starting_chips as i64;
+    let mbb = stats::milli_big_blinds(profit, hand.big_blind);
+
+    let day_key = (player.principal, stats::day_of(hand.timestamp), hand.table_id);
+    let mut day = state.daily_results.get(&day_key)
+        .unwrap_or(DailyResult { currency, hands: 0, profit: 0, mbb: 0 });
+    day.hands += 1;
+    day.profit += profit;
+    day.mbb += mbb;
+    state.daily_results.insert(day_key, day);
+
+    // Carry on the latest session at this table, or start a new one after a break.
+    // A hand older than the latest session (migrated legacy hands) joins the
+    // session it falls in instead.
+    let open_key = (player.principal, hand.table_id);
+    let latest = state.open_sessions.get(&open_key);
+    let current = match latest {
+        Some(started_at) if hand.timestamp < started_at => state.sessions
+            .range((player.principal, 0, Principal::management_canister())..=(player.principal, hand.timestamp, max_principal()))
+            .rev()
+            .find(|((_, _, table_id), _)| *table_id == hand.table_id)
+            .map(|(_, session)| session),
+        Some(started_at) => state.sessions.get(&(player.principal, started_at, hand.table_id)),
+        None => None,
+    }.filter(|session| stats::continues_session(session, hand.timestamp));
+    let mut session = current.unwrap_or(Session {
+        table_id: hand.table_id,
+        currency,
+        started_at: hand.timestamp,
+        ended_at: hand.timestamp,
+        hands: 0,
+        profit: 0,
+        mbb: 0,
+    });
+    session.ended_at = session.ended_at.max(hand.timestamp);
+    session.hands += 1;
+    session.profit += profit;
+    session.mbb += mbb;
+    if latest.is_none_or(|started_at| started_at <= session.started_at) {
+        state.open_sessions.insert(open_key, session.started_at);
+    }
+    state.sessions.insert((player.principal, session.started_at, hand.table_id), session);
+}
+
+/// Record a finished Sit & Go with every entrant's finishing position.
+/// Idempotent on (table_id, started_at): a table resending the record gets the
+/// tournament_id it was first given, and prizes and buy-ins are counted once.
+#[ic_cdk::update]
+fn record_tournament(record: TournamentRecord) -> Result<u64, String> {
+    let caller = ic_cdk::api::msg_caller();
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        let is_authorized = state.authorized_tables.contains(&caller)
+            || state.admin == Some(caller);
+
+        if !is_authorized {
+            return Err("Unauthorized: table not registered. Admin must add table via authorize_table.".to_string());
+        }
+
+        let key = (record.table_id, record.started_at);
+        if let Some(&existing_id) = state.tournament_ids_by_table_start.get(&key) {
+            return Ok(existing_id);
+        }
+
+        // Assign tournament ID
+        let tournament_id = state.next_tournament_id;
+        state.next_tournament_id += 1;
+
+        let mut final_record = record;
+        final_record.tournament_id = tournament_id;
+
+        for result in &final_record.results {
+            state.tournaments_by_player
+                .entry(result.principal)
+                .or_default()
+                .push(tournament_id);
+
+            // Tournament profit is the prize minus what it cost to enter
+            let mut stats = state.player_stats.get(&result.principal)
+                .unwrap_or_else(|| empty_stats(result.principal));
+            stats.total_winnings += result.prize as i64
+                - final_record.buy_in as i64
+                - final_record.fee as i64;
+            state.player_stats.insert(result.principal, stats);
+        }
+
+        state.tournament_ids_by_table_start.insert(key, tournament_id);
+        state.tournaments.insert(tournament_id, final_record);
+
+        Ok(tournament_id)
+    })
 }
 
 // ============================================================================
-// QUERY FUNCTIONS
+// ARCHIVING - move the oldest hands into archive canisters
 // ============================================================================
 
+/// Admin: set (or with None, turn off) archiving
+#[ic_cdk::update]
+fn set_archive_options(options: Option<ArchiveOptions>) -> Result<(), String> {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if state.admin != Some(ic_cdk::api::msg_caller()) {
+            return Err("Unauthorized".to_string());
+        }
+
+        if let Some(ref o) = options {
+            if o.trigger_threshold == 0 || o.num_hands_to_archive == 0 || o.max_hands_per_archive == 0 {
+                return Err("Archive thresholds must be greater than zero".to_string());
+            }
+            if o.num_hands_to_archive > o.trigger_threshold {
+                return Err("Can't archive more hands per run than the trigger threshold".to_string());
+            }
+        }
+
+        state.archive_options = options;
+        Ok(())
+    })
+}
+
 #[ic_cdk::query]
-fn get_hand(hand_id: u64) -> Option<HandHistoryRecord> {
-    STATE.with(|s| s.borrow().hands.get(&hand_id).cloned())
+fn get_archive_options() -> Option<ArchiveOptions> {
+    STATE.with(|s| s.borrow().archive_options.clone())
 }
 
+/// Archive canisters, oldest hands first
 #[ic_cdk::query]
-fn get_hands_by_table(table_id: Principal, offset: u64, limit: u64) -> Vec<HandSummary> {
-    STATE.with(|s| {
+fn get_archives() -> Vec<ArchiveInfo> {
+    STATE.with(|s| s.borrow().archives.clone())
+}
+
+/// Start an archiving run if we're past the threshold and none is running
+fn maybe_archive() {
+    let due = STATE.with(|s| {
         let state = s.borrow();
+        // Legacy hands are archived once they're indexed - the indexes outlive them
+        !state.legacy_migration_pending()
+            && state.archive_options.as_ref().is_some_and(|o| state.hands.len() > o.trigger_threshold)
+    });
+    if !due || ARCHIVING.with(|a| *a.borrow()) {
+        return;
+    }
 
-        let hand_ids = state.hands_by_table.get(&table_id);
-        if hand_ids.is_none() {
-            return vec![];
+    ARCHIVING.with(|a| *a.borrow_mut() = true);
+    ic_cdk::futures::spawn(async {
+        if let Err(e) = archive_oldest_hands().await {
+            ic_cdk::println!("Archiving failed: {}", e);
         }
+        ARCHIVING.with(|a| *a.borrow_mut() = false);
+    });
+}
 
-        let hand_ids = hand_ids.unwrap();
+/// Move up to num_hands_to_archive of the oldest hands out, creating archives as they fill.
+/// Hands are only deleted here once an archive has accepted them.
+async fn archive_oldest_hands() -> Result<(), String> {
+    let options = STATE.with(|s| s.borrow().archive_options.clone())
+        .ok_or("Archiving is turned off")?;
+
+    let mut left = options.num_hands_to_archive;
+    while left > 0 {
+        let (archive_id, room) = archive_with_room(&options).await?;
+        let batch = STATE.with(|s| next_archive_batch(&s.borrow(), left.min(room)));
+        let last_hand_id = match batch.last() {
+            Some(hand) => hand.hand_id,
+            None => break,
+        };
+
+        let remaining_capacity = ic_cdk::call::Call::unbounded_wait(archive_id, "append_hands")
+            .with_arg(&batch)
+            .await
+            .map_err(|e| format!("Failed to call archive {}: {:?}", archive_id, e))?
+            .candid::<Result<u64, String>>()
+            .map_err(|e| format!("Failed to decode archive response: {:?}", e))??;
+
+        STATE.with(|s| {
+            let mut state = s.borrow_mut();
+            for hand in &batch {
+                state.hands.remove(&hand.hand_id);
+                // Its (table_id, hand_number) entry stays, so a late retry still gets this hand_id
+                if !state.hand_seeds.contains_key(&hand.hand_id) {
+                    state.hand_seeds.insert(hand.hand_id, seed_fingerprint(&hand.shuffle_proof.seed_hash));
+                }
+            }
+            if let Some(archive) = state.archives.last_mut() {
+                archive.end = last_hand_id + 1;
+                archive.remaining_capacity = remaining_capacity;
+            }
+        });
+        left = left.saturating_sub(batch.len() as u64);
+    }
+
+    Ok(())
+}
+
+/// Oldest local hands, up to `max_hands` and one inter-canister message
+fn next_archive_batch(state: &HistoryState, max_hands: u64) -> Vec<HandHistoryRecord> {
+    let mut batch = Vec::new();
+    let mut bytes = 0;
+    for (_, hand) in state.hands.iter().take(max_hands as usize) {
+        let size = hand.to_bytes().len();
+        if !batch.is_empty() && bytes + size > MAX_ARCHIVE_BATCH_BYTES {
+            break;
+        }
+        bytes += size;
+        batch.push(hand);
+    }
+    batch
+}
+
+/// The newest archive if it has room, otherwise a freshly created one
+async fn archive_with_room(options: &ArchiveOptions) -> Result<(Principal, u64), String> {
+    if let Some(archive) = STATE.with(|s| s.borrow().archives.last().cloned()) {
+        if archive.remaining_capacity > 0 {
+            return Ok((archive.canister_id, archive.remaining_capacity));
+        }
+    }
+    create_archive(options).await
+}
+
+async fn create_archive(options: &ArchiveOptions) -> Result<(Principal, u64), String> {
+    if ARCHIVE_WASM.is_empty() {
+        return Err("This build doesn't embed the archive wasm - build with scripts/build-history.sh".to_string());
+    }
+
+    // The history canister upgrades its archives; the admin can too
+    let self_id = ic_cdk::api::canister_self();
+    let admin = STATE.with(|s| s.borrow().admin);
+    let settings = CanisterSettings {
+        controllers: Some(std::iter::once(self_id).chain(admin).collect()),
+        ..Default::default()
+    };
+    let canister_id = create_canister_with_extra_cycles(
+        &CreateCanisterArgs { settings: Some(settings) },
+        options.cycles_for_archive_creation as u128,
+    )
+    .await
+    .map_err(|e| format!("Failed to create archive canister: {:?}", e))?
+    .canister_id;
+
+    let init = ArchiveInit { history_canister: self_id, max_hands: options.max_hands_per_archive };
+    install_code(&InstallCodeArgs {
+        mode: CanisterInstallMode::Install,
+        canister_id,
+        wasm_module: ARCHIVE_WASM.to_vec(),
+        arg: candid::encode_one(init).map_err(|e| format!("Failed to encode archive init: {:?}", e))?,
+    })
+    .await
+    .map_err(|e| format!("Failed to install archive {}: {:?}", canister_id, e))?;
+
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        // Archiving always takes the oldest hands, so this archive starts at the oldest one left
+        let start = state.hands.first_key_value().map(|(id, _)| id).unwrap_or(state.next_hand_id);
+        state.archives.push(ArchiveInfo {
+            canister_id,
+            start,
+            end: start,
+            remaining_capacity: options.max_hands_per_archive,
+        });
+    });
+
+    Ok((canister_id, options.max_hands_per_archive))
+}
+
+// ============================================================================
+// QUERY FUNCTIONS
+// ============================================================================
+
+/// A hand, or the archive canister to ask for it
+#[ic_cdk::query]
+fn get_hand(hand_id: u64) -> GetHandResponse {
+    STATE.with(|s| {
+        let state = s.borrow();
+        match state.hands.get(&hand_id) {
+            Some(hand) => GetHandResponse { hand: Some(hand), archive: None },
+            None => GetHandResponse { hand: None, archive: state.archive_for(hand_id) },
+        }
+    })
+}
 
-        // Get hands in reverse order (newest first)
+/// Summaries for specific hand IDs held here (archives answer the same query)
+#[ic_cdk::query]
+fn get_hand_summaries(hand_ids: Vec<u64>) -> Vec<HandSummary> {
+    STATE.with(|s| {
+        let state = s.borrow();
         hand_ids.iter()
-            .rev()
-            .skip(offset as usize)
-            .take(limit as usize)
+            .take(MAX_SUMMARIES_PER_QUERY)
             .filter_map(|id| state.hands.get(id))
-            .map(|h| to_summary(h))
+            .map(|h| to_summary(&h))
             .collect()
     })
 }
 
 #[ic_cdk::query]
-fn get_hands_by_player(player: Principal, offset: u64, limit: u64) -> Vec<HandSummary> {
+fn get_hands_by_table(table_id: Principal, offset: u64, limit: u64) -> HandsPage {
     STATE.with(|s| {
         let state = s.borrow();
 
-        let hand_ids = state.hands_by_player.get(&player);
-        if hand_ids.is_none() {
-            return vec![];
-        }
+        // Newest first
+        state.hands_page(hand_ids_newest_first(&state.hands_by_table, table_id, offset, limit))
+    })
+}
+
+#[ic_cdk::query]
+fn get_hands_by_player(player: Principal, offset: u64, limit: u64) -> HandsPage {
+    STATE.with(|s| {
+        let state = s.borrow();
 
-        let hand_ids = hand_ids.unwrap();
+        state.hands_page(hand_ids_newest_first(&state.hands_by_player, player, offset, limit))
+    })
+}
 
-        hand_ids.iter()
+#[ic_cdk::query]
+fn get_recent_hands(limit: u64) -> Vec<HandSummary> {
+    STATE.with(|s| {
+        let state = s.borrow();
+
+        state.hands.iter()
             .rev()
-            .skip(offset as usize)
             .take(limit as usize)
-            .filter_map(|id| state.hands.get(id))
-            .map(|h| to_summary(h))
+            .map(|(_, h)| to_summary(&h))
             .collect()
     })
 }
 
 #[ic_cdk::query]
-fn get_recent_hands(limit: u64) -> Vec<HandSummary> {
+fn get_tournament(tournament_id: u64) -> Option<TournamentRecord> {
+    STATE.with(|s| s.borrow().tournaments.get(&tournament_id).cloned())
+}
+
+#[ic_cdk::query]
+fn get_tournaments_by_player(player: Principal, offset: u64, limit: u64) -> Vec<TournamentRecord> {
     STATE.with(|s| {
         let state = s.borrow();
 
-        state.hands.iter()
+        let tournament_ids = match state.tournaments_by_player.get(&player) {
+            Some(ids) => ids,
+            None => return vec![],
+        };
+
+        // Newest first
+        tournament_ids.iter()
             .rev()
+            .skip(offset as usize)
             .take(limit as usize)
-            .map(|(_, h)| to_summary(h))
+            .filter_map(|id| state.tournaments.get(id).cloned())
             .collect()
     })
 }
 
 #[ic_cdk::query]
 fn get_player_stats(player: Principal) -> Option<PlayerStats> {
-    STATE.with(|s| s.borrow().player_stats.get(&player).cloned())
+    STATE.with(|s| s.borrow().player_stats.get(&player))
+}
+
+/// VPIP, PFR, 3-bet, fold to 3-bet, c-bet, AF, WTSD and W$SD - overall, by position and by stakes
+#[ic_cdk::query]
+fn get_player_advanced_stats(player: Principal) -> Option<AdvancedStatsReport> {
+    let stats = STATE.with(|s| s.borrow().player_stats.get(&player))?;
+    let advanced = stats.advanced?;
+    Some(AdvancedStatsReport {
+        principal: player,
+        overall: stats::stat_line("All", &advanced.overall),
+        by_position: advanced.by_position.iter().map(|(label, c)| stats::stat_line(label, c)).collect(),
+        by_stakes: advanced.by_stakes.iter().map(|(label, c)| stats::stat_line(label, c)).collect(),
+    })
+}
+
+/// Cumulative profit by day between two times - a series per table and one per currency.
+/// Built as hands are recorded, so a graph never needs the hands themselves.
+#[ic_cdk::query]
+fn get_profit_graph(player: Principal, from_timestamp: u64, to_timestamp: u64) -> Vec<ProfitSeries> {
+    if from_timestamp > to_timestamp {
+        return Vec::new();
+    }
+    STATE.with(|s| {
+        let state = s.borrow();
+        let first = (player, stats::day_of(from_timestamp), Principal::management_canister());
+        let last = (player, stats::day_of(to_timestamp), max_principal());
+        let days: Vec<((Principal, u64, Principal), DailyResult)> = state.daily_results.range(first..=last).collect();
+        stats::profit_series(&days)
+    })
+}
+
+/// Sessions that started between two times, newest first, with their bb/100
+#[ic_cdk::query]
+fn get_sessions(player: Principal, from_timestamp: u64, to_timestamp: u64, limit: u64) -> Vec<SessionSummary> {
+    if from_timestamp > to_timestamp {
+        return Vec::new();
+    }
+    STATE.with(|s| {
+        let state = s.borrow();
+        let first = (player, from_timestamp, Principal::management_canister());
+        let last = (player, to_timestamp, max_principal());
+        state.sessions
+            .range(first..=last)
+            .rev()
+            .take(limit.min(MAX_SESSIONS_PER_QUERY) as usize)
+            .map(|(_, session)| SessionSummary {
+                bb_per_100_x100: stats::bb_per_100_x100(session.mbb, session.hands),
+                session,
+            })
+            .collect()
+    })
+}
+
+/// Hands matching every set field of `filter`, newest first. Each indexed filter
+/// is a range in the search index and they're intersected by seeking, so cost
+/// follows the matches rather than the number of hands. Pages stop at `limit`
+/// or the seek budget - pass next_cursor back as `cursor` until it comes back None.
+#[ic_cdk::query]
+fn search_hands(filter: HandSearchFilter, cursor: Option<SearchCursor>, limit: u64) -> Result<HandSearchPage, String> {
+    let from = filter.from_timestamp.unwrap_or(0);
+    let to = filter.to_timestamp.unwrap_or(u64::MAX);
+    if from > to {
+        return Err("from_timestamp is after to_timestamp".to_string());
+    }
+    let min_pot = filter.min_pot.unwrap_or(0);
+    let max_pot = filter.max_pot.unwrap_or(u64::MAX);
+    if min_pot > max_pot {
+        return Err("min_pot is above max_pot".to_string());
+    }
+    let streams = search::streams(&filter)?;
+    let limit = limit.clamp(1, MAX_SEARCH_RESULTS);
+    let mut at = cursor.map(|c| (c.timestamp, c.hand_id)).unwrap_or((to, u64::MAX)).min((to, u64::MAX));
+
+    STATE.with(|s| {
+        let state = s.borrow();
+        let mut budget = MAX_SEARCH_STEPS;
+        let mut hand_ids = Vec::new();
+        let next = loop {
+            if hand_ids.len() as u64 == limit {
+                break Some(at);
+            }
+            match search::next_match(&state.search_index, &streams, from, at, &mut budget) {
+                search::Step::Done => break None,
+                search::Step::OutOfBudget(position) => break Some(position),
+                search::Step::Match((timestamp, hand_id)) => {
+                    // Pot size is checked per match - it counts against the budget like a seek
+                    budget = budget.saturating_sub(1);
+                    let pot = state.hand_pots.get(&hand_id).unwrap_or(0);
+                    if (min_pot..=max_pot).contains(&pot) {
+                        hand_ids.push(hand_id);
+                    }
+                    match search::previous((timestamp, hand_id)) {
+                        Some(previous) if previous.0 >= from => at = previous,
+                        _ => break None,
+                    }
+                }
+            }
+        };
+
+        let page = state.hands_page(hand_ids);
+        Ok(HandSearchPage {
+            hands: page.hands,
+            archived: page.archived,
+            next_cursor: next.map(|(timestamp, hand_id)| SearchCursor { timestamp, hand_id }),
+        })
+    })
 }
 
 #[ic_cdk::query]
 fn get_total_hands() -> u64 {
-    STATE.with(|s| s.borrow().hands.len() as u64)
+    // Hands held here plus every archived range
+    STATE.with(|s| {
+        let state = s.borrow();
+        state.hands.len() + state.archives.iter().map(|a| a.end - a.start).sum::<u64>()
+    })
 }
 
 #[ic_cdk::query]
 fn get_table_hand_count(table_id: Principal) -> u64 {
     STATE.with(|s| {
-        s.borrow().hands_by_table
+        s.borrow().table_hand_counts
             .get(&table_id)
-            .map(|v| v.len() as u64)
             .unwrap_or(0)
     })
 }
 
+/// Check the revealed seed against its commitment, then replay the deal from it and
+/// compare every recorded card - a report of anything that doesn't match
+#[ic_cdk::query]
+fn verify_hand_shuffle(hand_id: u64) -> Result<DealVerification, String> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        match state.hands.get(&hand_id) {
+            Some(hand) => replay::verify_deal(&hand),
+            None => Err(match state.archive_for(hand_id) {
+                Some(archive) => format!("Hand {} is archived in {} - verify it there", hand_id, archive),
+                None => "Hand not found".to_string(),
+            }),
+        }
+    })
+}
+
+/// Card, starting-hand and board-suit frequencies over every verified hand, each
+/// with a chi-square fit against uniformly random dealing - see fairness.rs
 #[ic_cdk::query]
-fn verify_hand_shuffle(hand_id: u64) -> Result<bool, String> {
+fn get_fairness_report() -> FairnessReport {
     STATE.with(|s| {
         let state = s.borrow();
+        FairnessReport {
+            decks: state.fairness.iter().map(|(deck_size, tally)| fairness::deck_report(deck_size, &tally)).collect(),
+            backfill_pending: state.fairness_backfill_next < state.fairness_live_from,
+        }
+    })
+}
+
+/// One hand in PokerStars text hand history format
+#[ic_cdk::query]
+fn export_hand_pokerstars(hand_id: u64) -> Result<String, String> {
+    export_hand(hand_id, pokerstars::format_hand)
+}
+
+/// A player's or table's hands in a time range, in PokerStars format, oldest first.
+/// Pages stop at `limit` hands, the reply size cap or the scan budget - keep
+/// calling with next_cursor until it comes back None.
+#[ic_cdk::query]
+fn export_hands_pokerstars(request: HandExportRequest) -> Result<HandExport, String> {
+    export_hands(request, pokerstars::format_hand)
+}
+
+/// One hand as an Open Hand History JSON document
+#[ic_cdk::query]
+fn export_hand_ohh(hand_id: u64) -> Result<String, String> {
+    export_hand(hand_id, ohh::format_hand)
+}
+
+/// Bulk Open Hand History export - same paging as export_hands_pokerstars
+#[ic_cdk::query]
+fn export_hands_ohh(request: HandExportRequest) -> Result<HandExport, String> {
+    export_hands(request, ohh::format_hand)
+}
 
-        let hand = state.hands.get(&hand_id)
-            .ok_or("Hand not found")?;
+fn export_hand(hand_id: u64, format: fn(&HandHistoryRecord) -> String) -> Result<String, String> {
+    STATE.with(|s| {
+        let state = s.borrow();
+        match state.hands.get(&hand_id) {
+            Some(hand) => Ok(format(&hand)),
+            None => Err(match state.archive_for(hand_id) {
+                Some(archive) => format!("Hand {} is archived in {} - export it there", hand_id, archive),
+                None => "Hand not found".to_string(),
+            }),
+        }
+    })
+}
 
-        let proof = &hand.shuffle_proof;
+/// Walk a player's or table's index oldest first, formatting hands in the time range.
+/// Hands are separated by blank lines.
+fn export_hands(request: HandExportRequest, format: fn(&HandHistoryRecord) -> String) -> Result<HandExport, String> {
+    let (index_owner, by_player) = match (request.player, request.table_id) {
+        (Some(player), None) => (player, true),
+        (None, Some(table_id)) => (table_id, false),
+        _ => return Err("Set exactly one of player or table_id".to_string()),
+    };
+    if request.from_timestamp > request.to_timestamp {
+        return Err("from_timestamp is after to_timestamp".to_string());
+    }
+    let limit = request.limit.clamp(1, MAX_EXPORT_HANDS);
+    let start = match request.after_hand_id {
+        Some(id) => id.checked_add(1).ok_or("Cursor is past the last hand")?,
+        None => 0,
+    };
 
-        // Verify SHA-256(revealed_seed) == seed_hash
-        use sha2::{Sha256, Digest};
+    STATE.with(|s| {
+        let state = s.borrow();
+        let index = if by_player { &state.hands_by_player } else { &state.hands_by_table };
+        let mut export = HandExport { text: String::new(), hand_count: 0, next_cursor: None, archived: Vec::new() };
 
-        let seed_bytes = hex::decode(&proof.revealed_seed)
-            .map_err(|_| "Invalid revealed seed hex")?;
+        for (scanned, (_, hand_id)) in index.keys_range((index_owner, start)..=(index_owner, u64::MAX)).enumerate() {
+            if scanned == MAX_EXPORT_SCAN || export.hand_count == limit {
+                return Ok(export);
+            }
+            let hand = match state.hands.get(&hand_id) {
+                Some(hand) => hand,
+                None => {
+                    if let Some(canister_id) = state.archive_for(hand_id) {
+                        match export.archived.iter_mut().find(|a| a.canister_id == canister_id) {
+                            Some(group) => group.hand_ids.push(hand_id),
+                            None => export.archived.push(ArchivedHands { canister_id, hand_ids: vec![hand_id] }),
+                        }
+                    }
+                    export.next_cursor = Some(hand_id);
+                    continue;
+                }
+            };
+            if hand.timestamp < request.from_timestamp || hand.timestamp > request.to_timestamp {
+                export.next_cursor = Some(hand_id);
+                continue;
+            }
 
-        let mut hasher = Sha256::new();
-        hasher.update(&seed_bytes);
-        let computed_hash = hex::encode(hasher.finalize());
+            let text = format(&hand);
+            if export.text.len() + text.len() + 2 > MAX_EXPORT_BYTES && export.hand_count > 0 {
+                return Ok(export);
+            }
+            if export.hand_count > 0 {
+                export.text.push_str("\n\n");
+            }
+            export.text.push_str(&text);
+            export.hand_count += 1;
+            export.next_cursor = Some(hand_id);
+        }
 
-        Ok(computed_hash == proof.seed_hash)
+        // Walked to the end of the index
+        export.next_cursor = None;
+        Ok(export)
     })
 }
 
@@ -455,6 +1754,7 @@ fn to_summary(hand: &HandHistoryRecord) -> HandSummary {
         total_pot: hand.total_pot,
         winners: hand.winners.clone(),
         went_to_showdown: hand.went_to_showdown,
+        game_variant: hand.game_variant.unwrap_or_default(),
     }
 }
 
@@ -470,50 +1770,140 @@ mod hex {
             .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| ()))
             .collect()
     }
-
-    pub fn encode(bytes: impl AsRef<[u8]>) -> String {
-        bytes.as_ref().iter().map(|b| format!("{:02x}", b)).collect()
-    }
 }
 
 // ============================================================================
 // UPGRADE HOOKS - Persist state across upgrades
 // ============================================================================
 
+/// State kept on the heap, written to UPGRADES_MEMORY in pre_upgrade.
+/// Hands, indexes and stats are already in stable memory, so upgrades don't
+/// grow with the number of recorded hands.
+#[derive(CandidType, Deserialize)]
+struct UpgradeState {
+    next_hand_id: u64,
+    authorized_tables: Vec<Principal>,
+    admin: Option<Principal>,
+    tournaments: Vec<TournamentRecord>,
+    next_tournament_id: u64,
+    #[serde(default)]
+    archive_options: Option<ArchiveOptions>,
+    #[serde(default)]
+    archives: Option<Vec<ArchiveInfo>>,
+    #[serde(default)] // None before the fairness audit - every hand so far still needs backfilling
+    fairness_backfill: Option<(u64, u64)>, // (fairness_backfill_next, fairness_live_from)
+    #[serde(default)]
+    legacy_index: Option<(u64, u64)>, // (legacy_index_next, legacy_index_end)
+}
+
+/// Layout written by versions that kept everything on the heap and saved it
+/// with stable_save. Only read once, to migrate into stable structures.
+/// Its hands_by_table/hands_by_player indexes are skipped - they're rebuilt from the hands.
 #[derive(CandidType, Deserialize)]
 struct PersistentState {
     hands: Vec<(u64, HandHistoryRecord)>,
-    hands_by_table: Vec<(Principal, Vec<u64>)>,
-    hands_by_player: Vec<(Principal, Vec<u64>)>,
     player_stats: Vec<(Principal, PlayerStats)>,
     next_hand_id: u64,
     authorized_tables: Vec<Principal>,
     admin: Option<Principal>,
+    #[serde(default)]
+    tournaments: Option<Vec<TournamentRecord>>,
+    #[serde(default)]
+    next_tournament_id: Option<u64>,
 }
 
 #[ic_cdk::pre_upgrade]
 fn pre_upgrade() {
     let state = STATE.with(|s| {
         let s = s.borrow();
-        PersistentState {
-            hands: s.hands.iter().map(|(k, v)| (*k, v.clone())).collect(),
-            hands_by_table: s.hands_by_table.iter().map(|(k, v)| (*k, v.clone())).collect(),
-            hands_by_player: s.hands_by_player.iter().map(|(k, v)| (*k, v.clone())).collect(),
-            player_stats: s.player_stats.iter().map(|(k, v)| (*k, v.clone())).collect(),
+        UpgradeState {
             next_hand_id: s.next_hand_id,
             authorized_tables: s.authorized_tables.clone(),
             admin: s.admin,
+            tournaments: s.tournaments.values().cloned().collect(),
+            next_tournament_id: s.next_tournament_id,
+            archive_options: s.archive_options.clone(),
+            archives: Some(s.archives.clone()),
+            fairness_backfill: Some((s.fairness_backfill_next, s.fairness_live_from)),
+            legacy_index: Some((s.legacy_index_next, s.legacy_index_end)),
         }
     });
 
-    if let Err(e) = ic_cdk::storage::stable_save((state,)) {
-        ic_cdk::println!("CRITICAL: Failed to save state to stable memory: {:?}", e);
-        // Log but don't panic - allow upgrade to proceed
+    match candid::encode_one(&state) {
+        Ok(bytes) => {
+            // Length-prefixed so post_upgrade knows how much to read
+            let memory = memory(UPGRADES_MEMORY);
+            let len = bytes.len() as u64;
+            let pages_needed = (8 + len).div_ceil(WASM_PAGE_SIZE);
+            if memory.size() < pages_needed {
+                memory.grow(pages_needed - memory.size());
+            }
+            memory.write(0, &len.to_le_bytes());
+            memory.write(8, &bytes);
+        }
+        Err(e) => {
+            ic_cdk::println!("CRITICAL: Failed to save state to stable memory: {:?}", e);
+            // Log but don't panic - allow upgrade to proceed
+        }
+    }
+}
+
+const WASM_PAGE_SIZE: u64 = 65_536;
+
+/// True when stable memory still holds the pre-stable-structures stable_save layout
+fn has_legacy_layout() -> bool {
+    if ic_cdk::stable::stable_size() == 0 {
+        return false;
     }
+    let mut magic = [0u8; 3];
+    ic_cdk::stable::stable_read(0, &mut magic);
+    &magic != b"MGR"
 }
 
 #[ic_cdk::post_upgrade]
 fn post_upgrade() {
+    // Must run before STATE is first touched - initializing the memory manager
+    // overwrites the legacy layout
+    if has_legacy_layout() {
+        migrate_legacy_state();
+        return;
+    }
+
+    let memory = memory(UPGRADES_MEMORY);
+    let mut len_bytes = [0u8; 8];
+    memory.read(0, &mut len_bytes);
+    let mut bytes = vec![0u8; u64::from_le_bytes(len_bytes) as usize];
+    memory.read(8, &mut bytes);
+
+    let state: UpgradeState = match candid::decode_one(&bytes) {
+        Ok(s) => s,
+        Err(e) => {
+            // FAIL LOUDLY - do NOT silently lose hand history!
+            // If this panics, the upgrade will be rejected and the old code will remain.
+            panic!("CRITICAL: Failed to restore state from stable memory: {:?}. \
+                    Upgrade REJECTED to protect hand history. \
+                    If you used --mode reinstall, that DESTROYS ALL DATA. \
+                    Always use --mode upgrade for production canisters.", e);
+        }
+    };
+
+    STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        s.next_hand_id = state.next_hand_id;
+        s.authorized_tables = state.authorized_tables;
+        s.admin = state.admin;
+        restore_tournaments(&mut s, state.tournaments);
+        s.next_tournament_id = state.next_tournament_id;
+        s.archive_options = state.archive_options;
+        s.archives = state.archives.unwrap_or_default();
+        (s.fairness_backfill_next, s.fairness_live_from) = state.fairness_backfill.unwrap_or((1, state.next_hand_id));
+        (s.legacy_index_next, s.legacy_index_end) = state.legacy_index.unwrap_or_default();
+    });
+}
+
+/// One-time move from the heap layout into stable structures. Only the hands
+/// themselves are stored here - migrate_legacy_hands indexes them afterwards.
+fn migrate_legacy_state() {
     let restore_result: Result<(PersistentState,), _> = ic_cdk::storage::stable_restore();
 
     let state = match restore_result {
@@ -530,34 +1920,41 @@ fn post_upgrade() {
     };
 
     STATE.with(|s| {
-        let mut new_state = HistoryState::default();
-        
-        // Restore hands
-        for (k, v) in state.hands {
-            new_state.hands.insert(k, v);
-        }
-        
-        // Restore indexes
-        for (k, v) in state.hands_by_table {
-            new_state.hands_by_table.insert(k, v);
-        }
-        
-        for (k, v) in state.hands_by_player {
-            new_state.hands_by_player.insert(k, v);
-        }
-        
-        // Restore player stats
+        let mut s = s.borrow_mut();
+
+        // Profit series didn't exist yet - migrate_legacy_hands builds them with the indexes
+        s.legacy_index_next = state.hands.iter().map(|(id, _)| *id).min().unwrap_or(0);
+        s.legacy_index_end = state.next_hand_id;
+        for (hand_id, hand) in state.hands {
+            s.hands.insert(hand_id, hand);
+        }
+
         for (k, v) in state.player_stats {
-            new_state.player_stats.insert(k, v);
-        }
-        
-        new_state.next_hand_id = state.next_hand_id;
-        new_state.authorized_tables = state.authorized_tables;
-        new_state.admin = state.admin;
-        
-        *s.borrow_mut() = new_state;
+            s.player_stats.insert(k, v);
+        }
+
+        s.next_hand_id = state.next_hand_id;
+        s.fairness_live_from = state.next_hand_id; // The migrated hands are left to backfill_fairness
+        s.authorized_tables = state.authorized_tables;
+        s.admin = state.admin;
+        restore_tournaments(&mut s, state.tournaments.unwrap_or_default());
+        s.next_tournament_id = state.next_tournament_id.unwrap_or(1);
     });
 }
 
+/// Restore tournaments and rebuild the per-player and (table, start) indexes
+fn restore_tournaments(state: &mut HistoryState, tournaments: Vec<TournamentRecord>) {
+    for record in tournaments {
+        state.tournament_ids_by_table_start.insert((record.table_id, record.started_at), record.tournament_id);
+        for result in &record.results {
+            state.tournaments_by_player
+                .entry(result.principal)
+                .or_default()
+                .push(record.tournament_id);
+        }
+        state.tournaments.insert(record.tournament_id, record);
+    }
+}
+
 // Candid export
 ic_cdk::export_candid!();
@@ -0,0 +1,344 @@
+// Open Hand History (OHH) JSON export.
+//
+// Maps a HandHistoryRecord onto the OHH standard (hh-specs.handhistory.org)
+// so analytics tools can import hands without a ClearDeck-specific parser.
+// Anything OHH has no field for - the shuffle proof, the hand's place in
+// ClearDeck and the second runout - goes in the "cleardeck" site extension.
+//
+// Amounts are decimal coins (ICP e8s / BTC sats over 1e8) for cash hands and
+// plain chips for tournament hands. Action amounts are what the action added
+// to the pot, so they sum to each player's contribution.
+
+use crate::pokerstars::{big_blind_seat, card_text, civil_from_days, small_blind_seat, table_size, Street, Units};
+use crate::{Card, GameVariant, HandHistoryRecord, PlayerAction, PlayerHandRecord, WinnerRecord};
+use serde::Serialize;
+
+pub const OHH_SPEC_VERSION: &str = "1.4.7";
+const SITE_NAME: &str = "ClearDeck";
+
+#[derive(Serialize)]
+pub struct OhhDocument {
+    pub ohh: Ohh,
+}
+
+#[derive(Serialize)]
+pub struct Ohh {
+    pub spec_version: &'static str,
+    pub site_name: &'static str,
+    pub network_name: &'static str,
+    pub internal_version: &'static str,
+    pub tournament: bool,
+    pub game_number: String,
+    pub start_date_utc: String,
+    pub table_name: String,
+    pub table_handle: String,
+    pub table_size: u8,
+    pub game_type: &'static str,
+    pub bet_limit: BetLimit,
+    pub currency: &'static str,
+    pub dealer_seat: u8,
+    pub small_blind_amount: f64,
+    pub big_blind_amount: f64,
+    pub ante_amount: f64,
+    pub flags: Vec<&'static str>,
+    pub players: Vec<OhhPlayer>,
+    pub rounds: Vec<Round>,
+    pub pots: Vec<Pot>,
+    pub cleardeck: SiteExtension,
+}
+
+#[derive(Serialize)]
+pub struct BetLimit {
+    pub bet_type: &'static str, // "NL" or "PL"
+}
+
+#[derive(Serialize)]
+pub struct OhhPlayer {
+    pub id: u8, // Table seat index - what actions and wins refer to
+    pub seat: u8,
+    pub name: String,
+    pub starting_stack: f64,
+}
+
+#[derive(Serialize)]
+pub struct Round {
+    pub id: u32,
+    pub street: &'static str, // "Preflop", "Flop", "Turn", "River", "Showdown"
+    pub cards: Vec<String>,   // Community cards dealt on this street
+    pub actions: Vec<Action>,
+}
+
+#[derive(Serialize)]
+pub struct Action {
+    pub action_number: u32,
+    pub player_id: u8,
+    pub action: &'static str,
+    pub amount: f64,
+    pub is_allin: bool,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Pot {
+    pub number: u32,
+    pub amount: f64,
+    pub rake: f64,
+    pub player_wins: Vec<PlayerWin>,
+}
+
+#[derive(Serialize)]
+pub struct PlayerWin {
+    pub player_id: u8,
+    pub win_amount: f64,
+    pub contributed_rake: f64,
+}
+
+/// ClearDeck fields with no place in the OHH schema
+#[derive(Serialize)]
+pub struct SiteExtension {
+    pub hand_id: u64,
+    pub hand_number: u64,
+    pub game_variant: &'static str,
+    pub shuffle_proof: ShuffleProofExtension,
+    pub pot_types: Vec<String>, // WinnerRecord.pot_type for each entry in pots, e.g. "main", "high", "low"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub second_board: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct ShuffleProofExtension {
+    pub seed_hash: String,
+    pub revealed_seed: String,
+    pub committed_at: u64, // Nanoseconds since the epoch
+    pub shuffle_version: u32, // cleardeck_shuffle::SHUFFLE_V* the deck was shuffled with
+}
+
+/// "2026-10-17T12:00:00Z"
+fn iso_timestamp(timestamp_ns: u64) -> String {
+    let secs = timestamp_ns / 1_000_000_000;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year, month, day, secs_of_day / 3600, (secs_of_day / 60) % 60, secs_of_day % 60
+    )
+}
+
+/// Amount in the units OHH expects - coins for cash hands, chips for tournaments
+fn amount(units: Units, value: u64) -> f64 {
+    match units {
+        Units::Chips => value as f64,
+        Units::Coins(currency) => value as f64 / 10u64.pow(currency.decimals() as u32) as f64,
+    }
+}
+
+fn card_strings(cards: &[Card]) -> Vec<String> {
+    cards.iter().map(card_text).collect()
+}
+
+fn street_name(phase: &str) -> &'static str {
+    match phase {
+        "flop" => "Flop",
+        "turn" => "Turn",
+        "river" => "River",
+        _ => "Preflop",
+    }
+}
+
+fn variant_name(variant: GameVariant) -> &'static str {
+    match variant {
+        GameVariant::TexasHoldem => "TexasHoldem",
+        GameVariant::PotLimitOmaha => "PotLimitOmaha",
+        GameVariant::OmahaHiLo => "OmahaHiLo",
+        GameVariant::ShortDeckHoldem => "ShortDeckHoldem",
+    }
+}
+
+/// A pot as the table awarded it - pot_type and run
+type PotKey = (String, Option<u8>);
+
+/// Pots in the order winners were paid - one per pot_type and run
+fn pots(hand: &HandHistoryRecord, units: Units) -> (Vec<Pot>, Vec<String>) {
+    let mut groups: Vec<(PotKey, Vec<&WinnerRecord>)> = Vec::new();
+    for winner in &hand.winners {
+        let key = (winner.pot_type.clone(), winner.run);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, winners)) => winners.push(winner),
+            None => groups.push((key, vec![winner])),
+        }
+    }
+
+    let mut pots = Vec::new();
+    let mut pot_types = Vec::new();
+    for (i, ((pot_type, _), winners)) in groups.into_iter().enumerate() {
+        // Rake comes out before anything is awarded - book it against the first pot
+        let rake = if i == 0 { hand.rake } else { 0 };
+        let mut player_wins: Vec<PlayerWin> = Vec::new();
+        for winner in &winners {
+            match player_wins.iter_mut().find(|w| w.player_id == winner.seat) {
+                Some(win) => win.win_amount += amount(units, winner.amount),
+                None => player_wins.push(PlayerWin {
+                    player_id: winner.seat,
+                    win_amount: amount(units, winner.amount),
+                    contributed_rake: 0.0,
+                }),
+            }
+        }
+        let won: u64 = winners.iter().map(|w| w.amount).sum();
+        pots.push(Pot {
+            number: i as u32,
+            amount: amount(units, won.saturating_add(rake)),
+            rake: amount(units, rake),
+            player_wins,
+        });
+        pot_types.push(pot_type);
+    }
+    (pots, pot_types)
+}
+
+/// Map one hand onto the OHH schema
+pub fn to_ohh(hand: &HandHistoryRecord) -> OhhDocument {
+    let units = Units::for_hand(hand);
+    let variant = hand.game_variant.unwrap_or_default();
+    let mut players: Vec<&PlayerHandRecord> = hand.players.iter().collect();
+    players.sort_by_key(|p| p.seat);
+
+    let mut rounds: Vec<Round> = Vec::new();
+    let mut action_number = 0u32;
+    let mut next_action = |player_id: u8, action: &'static str, value: u64, is_allin: bool, cards: Vec<String>| {
+        action_number += 1;
+        Action { action_number, player_id, action, amount: amount(units, value), is_allin, cards }
+    };
+
+    // Antes, blinds and straddles open the preflop round
+    let mut stacks: std::collections::BTreeMap<u8, u64> = players.iter().map(|p| (p.seat, p.starting_chips)).collect();
+    let mut street = Street::new();
+    let mut preflop = Vec::new();
+    if hand.ante > 0 {
+        for player in &players {
+            let ante = hand.ante.min(stacks[&player.seat]);
+            *stacks.entry(player.seat).or_insert(0) -= ante;
+            preflop.push(next_action(player.seat, "Post Ante", ante, ante == player.starting_chips, Vec::new()));
+        }
+    }
+    for (seat, blind, label) in [(small_blind_seat(hand), hand.small_blind, "Post SB"), (big_blind_seat(hand), hand.big_blind, "Post BB")] {
+        if let Some(seat) = seat {
+            let stack = stacks.get(&seat).copied().unwrap_or(0);
+            let posted = blind.min(stack);
+            street.post(seat, posted);
+            preflop.push(next_action(seat, label, posted, posted == stack, Vec::new()));
+        }
+    }
+
+    // Board cards by street
+    let mut board: Vec<Card> = Vec::new();
+    if let Some((c1, c2, c3)) = hand.flop {
+        board.extend([c1, c2, c3]);
+    }
+    board.extend(hand.turn);
+    board.extend(hand.river);
+
+    for (id, phase, cards) in [(0u32, "preflop", 0..0), (1, "flop", 0..3), (2, "turn", 3..4), (3, "river", 4..5)] {
+        if cards.end > board.len() {
+            break;
+        }
+        let mut actions = if phase == "preflop" { std::mem::take(&mut preflop) } else { Vec::new() };
+        if phase != "preflop" {
+            street = Street::new();
+        }
+        for record in hand.actions.iter().filter(|a| a.phase == phase) {
+            let seat = record.seat;
+            let previous_bet = street.current_bet;
+            let (added, raised) = street.apply(seat, &record.action);
+            let (action, is_allin) = match record.action {
+                PlayerAction::Fold => ("Fold", false),
+                PlayerAction::Check => ("Check", false),
+                PlayerAction::Call(_) => ("Call", false),
+                PlayerAction::Bet(_) => ("Bet", false),
+                PlayerAction::Raise(_) => ("Raise", false),
+                PlayerAction::AllIn(_) if !raised => ("Call", true),
+                PlayerAction::AllIn(_) if previous_bet == 0 => ("Bet", true),
+                PlayerAction::AllIn(_) => ("Raise", true),
+                PlayerAction::PostBlind(_) => ("Post Extra Blind", false),
+                PlayerAction::Straddle(_) => ("Straddle", false),
+            };
+            actions.push(next_action(seat, action, added, is_allin, Vec::new()));
+        }
+        rounds.push(Round { id, street: street_name(phase), cards: card_strings(&board[cards]), actions });
+    }
+
+    if hand.went_to_showdown {
+        let actions = players.iter()
+            .filter(|p| !p.revealed_hole_cards().is_empty())
+            .map(|p| next_action(p.seat, "Shows Cards", 0, false, card_strings(&p.revealed_hole_cards())))
+            .collect();
+        rounds.push(Round { id: rounds.len() as u32, street: "Showdown", cards: Vec::new(), actions });
+    }
+
+    let (pots, pot_types) = pots(hand, units);
+    let mut flags = Vec::new();
+    if hand.second_board.is_some() {
+        flags.push("Run_It_Twice");
+    }
+
+    OhhDocument {
+        ohh: Ohh {
+            spec_version: OHH_SPEC_VERSION,
+            site_name: SITE_NAME,
+            network_name: SITE_NAME,
+            internal_version: env!("CARGO_PKG_VERSION"),
+            tournament: hand.is_tournament.unwrap_or(false),
+            game_number: hand.hand_id.to_string(),
+            start_date_utc: iso_timestamp(hand.timestamp),
+            table_name: hand.table_id.to_text(),
+            table_handle: hand.table_id.to_text(),
+            table_size: table_size(hand) as u8,
+            game_type: match variant {
+                GameVariant::PotLimitOmaha => "Omaha",
+                GameVariant::OmahaHiLo => "OmahaHiLo",
+                GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => "Holdem",
+            },
+            bet_limit: BetLimit {
+                bet_type: match variant {
+                    GameVariant::PotLimitOmaha | GameVariant::OmahaHiLo => "PL",
+                    GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => "NL",
+                },
+            },
+            currency: hand.currency.unwrap_or_default().symbol(),
+            dealer_seat: hand.dealer_seat + 1,
+            small_blind_amount: amount(units, hand.small_blind),
+            big_blind_amount: amount(units, hand.big_blind),
+            ante_amount: amount(units, hand.ante),
+            flags,
+            players: players.iter()
+                .map(|p| OhhPlayer {
+                    id: p.seat,
+                    seat: p.seat + 1,
+                    name: p.principal.to_text(),
+                    starting_stack: amount(units, p.starting_chips),
+                })
+                .collect(),
+            rounds,
+            pots,
+            cleardeck: SiteExtension {
+                hand_id: hand.hand_id,
+                hand_number: hand.hand_number,
+                game_variant: variant_name(variant),
+                shuffle_proof: ShuffleProofExtension {
+                    seed_hash: hand.shuffle_proof.seed_hash.clone(),
+                    revealed_seed: hand.shuffle_proof.revealed_seed.clone(),
+                    committed_at: hand.shuffle_proof.timestamp,
+                    shuffle_version: hand.shuffle_proof.shuffle_version.unwrap_or(cleardeck_shuffle::SHUFFLE_V1),
+                },
+                pot_types,
+                second_board: hand.second_board.as_deref().map(card_strings),
+            },
+        },
+    }
+}
+
+/// One hand as an OHH JSON document
+pub fn format_hand(hand: &HandHistoryRecord) -> String {
+    serde_json::to_string(&to_ohh(hand)).expect("OHH document always serializes")
+}
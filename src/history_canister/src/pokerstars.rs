@@ -0,0 +1,485 @@
+// PokerStars text hand history export.
+//
+// Renders a HandHistoryRecord in the plain-text format PokerStars writes, so
+// hands can be loaded into trackers and replayers (HoldemManager, PokerTracker,
+// hand converters). Pure string formatting - no canister state - so the
+// archive canister compiles this same file for the hands it holds.
+//
+// Amounts are ICP e8s or BTC sats, shown as 8-decimal coin amounts with the
+// currency in the header. Tournament hands are in chips and shown as integers.
+
+use crate::{ActionRecord, Card, Currency, GameVariant, HandHistoryRecord, HandRank, PlayerAction, PlayerHandRecord, Rank, Suit};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const NANOS_PER_SEC: u64 = 1_000_000_000;
+
+/// How the amounts in a hand are written
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Units {
+    Chips,              // Tournament chips - whole numbers
+    Coins(Currency),    // e8s or sats - 8 decimals
+}
+
+impl Units {
+    pub fn for_hand(hand: &HandHistoryRecord) -> Self {
+        if hand.is_tournament.unwrap_or(false) {
+            Units::Chips
+        } else {
+            Units::Coins(hand.currency.unwrap_or_default())
+        }
+    }
+
+    pub fn format(&self, amount: u64) -> String {
+        match self {
+            Units::Chips => amount.to_string(),
+            Units::Coins(currency) => format_amount(amount, *currency),
+        }
+    }
+}
+
+/// Smallest units as a coin amount, keeping at least 2 decimals
+/// e.g. 150_000_000 e8s -> "1.50", 50_000 sats -> "0.0005"
+pub fn format_amount(amount: u64, currency: Currency) -> String {
+    let scale = 10u64.pow(currency.decimals() as u32);
+    let whole = amount / scale;
+    let fraction = format!("{:0width$}", amount % scale, width = currency.decimals() as usize);
+    let trimmed = fraction.trim_end_matches('0');
+    let shown = if trimmed.len() < 2 { &fraction[..2] } else { trimmed };
+    format!("{}.{}", whole, shown)
+}
+
+/// "Ah", "Td", "2c" style
+pub fn card_text(card: &Card) -> String {
+    let rank = match card.rank {
+        Rank::Two => '2',
+        Rank::Three => '3',
+        Rank::Four => '4',
+        Rank::Five => '5',
+        Rank::Six => '6',
+        Rank::Seven => '7',
+        Rank::Eight => '8',
+        Rank::Nine => '9',
+        Rank::Ten => 'T',
+        Rank::Jack => 'J',
+        Rank::Queen => 'Q',
+        Rank::King => 'K',
+        Rank::Ace => 'A',
+    };
+    let suit = match card.suit {
+        Suit::Hearts => 'h',
+        Suit::Diamonds => 'd',
+        Suit::Clubs => 'c',
+        Suit::Spades => 's',
+    };
+    format!("{}{}", rank, suit)
+}
+
+fn cards_text(cards: &[Card]) -> String {
+    cards.iter().map(card_text).collect::<Vec<_>>().join(" ")
+}
+
+fn rank_name(rank: u8) -> &'static str {
+    match rank {
+        2 => "Deuce",
+        3 => "Three",
+        4 => "Four",
+        5 => "Five",
+        6 => "Six",
+        7 => "Seven",
+        8 => "Eight",
+        9 => "Nine",
+        10 => "Ten",
+        11 => "Jack",
+        12 => "Queen",
+        13 => "King",
+        _ => "Ace",
+    }
+}
+
+fn rank_plural(rank: u8) -> String {
+    match rank {
+        6 => "Sixes".to_string(),
+        _ => format!("{}s", rank_name(rank)),
+    }
+}
+
+/// Lowest card of a straight topped by `high` - the wheel (and short deck's A-6-7-8-9) starts at the Ace
+fn straight_low(high: u8, variant: GameVariant) -> u8 {
+    match (high, variant) {
+        (5, _) | (9, GameVariant::ShortDeckHoldem) => 14,
+        _ => high.saturating_sub(4),
+    }
+}
+
+/// Hand description as PokerStars words it, e.g. "a pair of Aces"
+pub fn hand_rank_text(rank: &HandRank, variant: GameVariant) -> String {
+    match rank {
+        HandRank::HighCard(kickers) => format!("high card {}", rank_name(kickers.first().copied().unwrap_or(14))),
+        HandRank::Pair(r, _) => format!("a pair of {}", rank_plural(*r)),
+        HandRank::TwoPair(high, low, _) => format!("two pair, {} and {}", rank_plural(*high), rank_plural(*low)),
+        HandRank::ThreeOfAKind(r, _) => format!("three of a kind, {}", rank_plural(*r)),
+        HandRank::Straight(high) => format!(
+            "a straight, {} to {}", rank_name(straight_low(*high, variant)), rank_name(*high)
+        ),
+        HandRank::Flush(ranks) => format!("a flush, {} high", rank_name(ranks.first().copied().unwrap_or(14))),
+        HandRank::FullHouse(trips, pair) => format!("a full house, {} full of {}", rank_plural(*trips), rank_plural(*pair)),
+        HandRank::FourOfAKind(r, _) => format!("four of a kind, {}", rank_plural(*r)),
+        HandRank::StraightFlush(high) => format!(
+            "a straight flush, {} to {}", rank_name(straight_low(*high, variant)), rank_name(*high)
+        ),
+        HandRank::RoyalFlush => "a Royal Flush".to_string(),
+    }
+}
+
+fn game_name(variant: GameVariant) -> &'static str {
+    match variant {
+        GameVariant::TexasHoldem => "Hold'em No Limit",
+        GameVariant::PotLimitOmaha => "Omaha Pot Limit",
+        GameVariant::OmahaHiLo => "Omaha Hi/Lo Pot Limit",
+        GameVariant::ShortDeckHoldem => "6+ Hold'em No Limit",
+    }
+}
+
+/// "YYYY/MM/DD HH:MM:SS" for a timestamp in nanoseconds since the epoch (UTC)
+pub fn format_timestamp(timestamp_ns: u64) -> String {
+    let secs = timestamp_ns / NANOS_PER_SEC;
+    let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+    let secs_of_day = secs % 86_400;
+    format!(
+        "{:04}/{:02}/{:02} {:02}:{:02}:{:02}",
+        year, month, day, secs_of_day / 3600, (secs_of_day / 60) % 60, secs_of_day % 60
+    )
+}
+
+/// Days since 1970-01-01 to (year, month, day) - Howard Hinnant's algorithm
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// Table size for the "N-max" line - the record only knows which seats were filled
+pub(crate) fn table_size(hand: &HandHistoryRecord) -> usize {
+    let seats = hand.players.iter().map(|p| p.seat as usize + 1).max().unwrap_or(0);
+    match seats.max(hand.players.len()) {
+        0..=2 => 2,
+        3..=6 => 6,
+        7..=9 => 9,
+        _ => 10,
+    }
+}
+
+fn player_name(player: &PlayerHandRecord) -> String {
+    player.principal.to_text()
+}
+
+/// Seat that posted the small blind - heads-up the button posts it
+pub(crate) fn small_blind_seat(hand: &HandHistoryRecord) -> Option<u8> {
+    hand.players.iter()
+        .find(|p| p.position == "SB")
+        .or_else(|| if hand.players.len() == 2 { hand.players.iter().find(|p| p.position == "BTN") } else { None })
+        .map(|p| p.seat)
+}
+
+pub(crate) fn big_blind_seat(hand: &HandHistoryRecord) -> Option<u8> {
+    hand.players.iter().find(|p| p.position == "BB").map(|p| p.seat)
+}
+
+/// Chips put in on the current street and the bet to match, so actions read as
+/// "raises X to Y" / "calls X" the way PokerStars writes them
+pub(crate) struct Street {
+    committed: BTreeMap<u8, u64>,
+    pub(crate) current_bet: u64,
+}
+
+impl Street {
+    pub(crate) fn new() -> Self {
+        Street { committed: BTreeMap::new(), current_bet: 0 }
+    }
+
+    pub(crate) fn committed(&self, seat: u8) -> u64 {
+        self.committed.get(&seat).copied().unwrap_or(0)
+    }
+
+    pub(crate) fn post(&mut self, seat: u8, amount: u64) {
+        let total = self.committed.entry(seat).or_insert(0);
+        *total = total.saturating_add(amount);
+        self.current_bet = self.current_bet.max(*total);
+    }
+
+    /// Put an action's chips in - returns what it added and whether it raised the bet to match
+    pub(crate) fn apply(&mut self, seat: u8, action: &PlayerAction) -> (u64, bool) {
+        let committed = self.committed(seat);
+        let previous_bet = self.current_bet;
+        let added = match *action {
+            PlayerAction::Fold | PlayerAction::Check => 0,
+            PlayerAction::Call(amount) | PlayerAction::PostBlind(amount) | PlayerAction::Straddle(amount) => amount,
+            PlayerAction::Bet(total) | PlayerAction::Raise(total) | PlayerAction::AllIn(total) => total.saturating_sub(committed),
+        };
+        self.post(seat, added);
+        (added, self.current_bet > previous_bet)
+    }
+
+    /// Action text without the player's name, e.g. "raises 0.04 to 0.06"
+    fn describe(&mut self, action: &ActionRecord, units: Units) -> String {
+        let seat = action.seat;
+        let committed = self.committed(seat);
+        match action.action {
+            PlayerAction::Fold => "folds".to_string(),
+            PlayerAction::Check => "checks".to_string(),
+            PlayerAction::Call(amount) => {
+                self.post(seat, amount);
+                format!("calls {}", units.format(amount))
+            }
+            PlayerAction::Bet(amount) => {
+                self.post(seat, amount.saturating_sub(committed));
+                format!("bets {}", units.format(amount))
+            }
+            PlayerAction::Raise(to) => {
+                let by = to.saturating_sub(self.current_bet);
+                self.post(seat, to.saturating_sub(committed));
+                format!("raises {} to {}", units.format(by), units.format(to))
+            }
+            PlayerAction::AllIn(total) => {
+                let previous_bet = self.current_bet;
+                self.post(seat, total.saturating_sub(committed));
+                if total <= previous_bet {
+                    format!("calls {} and is all-in", units.format(total.saturating_sub(committed)))
+                } else if previous_bet == 0 {
+                    format!("bets {} and is all-in", units.format(total))
+                } else {
+                    format!("raises {} to {} and is all-in", units.format(total - previous_bet), units.format(total))
+                }
+            }
+            PlayerAction::PostBlind(amount) => {
+                self.post(seat, amount);
+                format!("posts {}", units.format(amount))
+            }
+            PlayerAction::Straddle(amount) => {
+                self.post(seat, amount);
+                format!("posts straddle {}", units.format(amount))
+            }
+        }
+    }
+}
+
+/// "*** TURN *** [Ah Kd 2c] [5s]" for the first `count` cards of a board
+fn street_header(label: &str, board: &[Card], count: usize) -> String {
+    if count == 3 {
+        format!("*** {} *** [{}]", label, cards_text(&board[..3]))
+    } else {
+        format!("*** {} *** [{}] [{}]", label, cards_text(&board[..count - 1]), card_text(&board[count - 1]))
+    }
+}
+
+/// Render one hand in PokerStars text format
+pub fn format_hand(hand: &HandHistoryRecord) -> String {
+    let units = Units::for_hand(hand);
+    let variant = hand.game_variant.unwrap_or_default();
+    let names: BTreeMap<u8, String> = hand.players.iter().map(|p| (p.seat, player_name(p))).collect();
+    let name = |seat: u8| names.get(&seat).cloned().unwrap_or_else(|| format!("Seat {}", seat + 1));
+    let mut out = String::new();
+
+    // Header
+    let stakes = match units {
+        Units::Chips => format!("({}/{})", hand.small_blind, hand.big_blind),
+        Units::Coins(currency) => format!(
+            "({}/{} {})", units.format(hand.small_blind), units.format(hand.big_blind), currency.symbol()
+        ),
+    };
+    let _ = writeln!(
+        out, "PokerStars Hand #{}: {} {} - {} UTC",
+        hand.hand_id, game_name(variant), stakes, format_timestamp(hand.timestamp)
+    );
+    let _ = writeln!(
+        out, "Table '{}' {}-max Seat #{} is the button",
+        hand.table_id.to_text(), table_size(hand), hand.dealer_seat as u32 + 1
+    );
+
+    let mut players: Vec<&PlayerHandRecord> = hand.players.iter().collect();
+    players.sort_by_key(|p| p.seat);
+    for player in &players {
+        let _ = writeln!(
+            out, "Seat {}: {} ({} in chips)",
+            player.seat as u32 + 1, player_name(player), units.format(player.starting_chips)
+        );
+    }
+
+    // Antes and blinds aren't in the action list - rebuild them from the table config
+    let mut stacks: BTreeMap<u8, u64> = players.iter().map(|p| (p.seat, p.starting_chips)).collect();
+    let mut invested: BTreeMap<u8, u64> = BTreeMap::new();
+    let mut street = Street::new();
+    if hand.ante > 0 {
+        for player in &players {
+            let ante = hand.ante.min(stacks[&player.seat]);
+            *stacks.entry(player.seat).or_insert(0) -= ante;
+            *invested.entry(player.seat).or_insert(0) += ante;
+            let _ = writeln!(out, "{}: posts the ante {}", name(player.seat), units.format(ante));
+        }
+    }
+    let sb_seat = small_blind_seat(hand);
+    let bb_seat = big_blind_seat(hand);
+    for (seat, blind, label) in [(sb_seat, hand.small_blind, "small"), (bb_seat, hand.big_blind, "big")] {
+        if let Some(seat) = seat {
+            let amount = blind.min(stacks.get(&seat).copied().unwrap_or(0));
+            street.post(seat, amount);
+            *invested.entry(seat).or_insert(0) += amount;
+            let _ = writeln!(out, "{}: posts {} blind {}", name(seat), label, units.format(amount));
+        }
+    }
+    for action in hand.actions.iter().filter(|a| matches!(a.action, PlayerAction::Straddle(_))) {
+        let text = street.describe(action, units);
+        if let PlayerAction::Straddle(amount) = action.action {
+            *invested.entry(action.seat).or_insert(0) += amount;
+        }
+        let _ = writeln!(out, "{}: {}", name(action.seat), text);
+    }
+
+    // Boards - the first runout, and the second when the hand was run twice
+    let mut board: Vec<Card> = Vec::new();
+    if let Some((c1, c2, c3)) = hand.flop {
+        board.extend([c1, c2, c3]);
+    }
+    board.extend(hand.turn);
+    board.extend(hand.river);
+    let second_board = hand.second_board.as_ref().filter(|b| b.len() == 5 && board.len() == 5);
+    let run_twice_from = second_board.map(|second| {
+        board.iter().zip(second.iter()).take_while(|(a, b)| a == b).count()
+    });
+
+    // Betting rounds
+    let mut folded_on: BTreeMap<u8, &str> = BTreeMap::new();
+    let _ = writeln!(out, "*** HOLE CARDS ***");
+    for (phase, label, count) in [("preflop", "", 0), ("flop", "FLOP", 3), ("turn", "TURN", 4), ("river", "RIVER", 5)] {
+        if count > 0 {
+            if board.len() < count {
+                break;
+            }
+            street = Street::new();
+            let run_label = match run_twice_from {
+                Some(shared) if shared < count => format!("FIRST {}", label),
+                _ => label.to_string(),
+            };
+            let _ = writeln!(out, "{}", street_header(&run_label, &board, count));
+        }
+        for action in hand.actions.iter().filter(|a| a.phase == phase) {
+            if matches!(action.action, PlayerAction::Straddle(_)) {
+                continue;
+            }
+            let before = street.committed(action.seat);
+            let text = street.describe(action, units);
+            let after = street.committed(action.seat);
+            *invested.entry(action.seat).or_insert(0) += after - before;
+            if action.action == PlayerAction::Fold {
+                folded_on.insert(action.seat, phase);
+            }
+            let _ = writeln!(out, "{}: {}", name(action.seat), text);
+        }
+    }
+    if let (Some(second), Some(shared)) = (second_board, run_twice_from) {
+        for (label, count) in [("SECOND FLOP", 3), ("SECOND TURN", 4), ("SECOND RIVER", 5)] {
+            if shared < count {
+                let _ = writeln!(out, "{}", street_header(label, second, count));
+            }
+        }
+    }
+
+    // Showdown and pots
+    let collected = |out: &mut String, run: Option<u8>| {
+        for winner in hand.winners.iter().filter(|w| run.is_none_or(|r| w.run.unwrap_or(1) == r)) {
+            let pot = match winner.pot_type.as_str() {
+                "main" => "pot".to_string(),
+                other => format!("{} pot", other),
+            };
+            let _ = writeln!(out, "{} collected {} from {}", name(winner.seat), units.format(winner.amount), pot);
+        }
+    };
+    if hand.went_to_showdown {
+        let _ = writeln!(out, "{}", if second_board.is_some() { "*** FIRST SHOW DOWN ***" } else { "*** SHOW DOWN ***" });
+        for player in &players {
+            let cards = player.revealed_hole_cards();
+            if cards.is_empty() {
+                continue;
+            }
+            let rank = player.final_hand_rank.as_ref()
+                .map(|r| format!(" ({})", hand_rank_text(r, variant)))
+                .unwrap_or_default();
+            let _ = writeln!(out, "{}: shows [{}]{}", name(player.seat), cards_text(&cards), rank);
+        }
+        if second_board.is_some() {
+            collected(&mut out, Some(1));
+            let _ = writeln!(out, "*** SECOND SHOW DOWN ***");
+            collected(&mut out, Some(2));
+        } else {
+            collected(&mut out, None);
+        }
+    } else {
+        collected(&mut out, None);
+    }
+
+    // Summary
+    let _ = writeln!(out, "*** SUMMARY ***");
+    let _ = writeln!(out, "Total pot {} | Rake {}", units.format(hand.total_pot), units.format(hand.rake));
+    match second_board {
+        Some(second) => {
+            let _ = writeln!(out, "Hand was run twice");
+            let _ = writeln!(out, "FIRST Board [{}]", cards_text(&board));
+            let _ = writeln!(out, "SECOND Board [{}]", cards_text(second));
+        }
+        None if !board.is_empty() => {
+            let _ = writeln!(out, "Board [{}]", cards_text(&board));
+        }
+        None => {}
+    }
+    for player in &players {
+        let role = if player.seat == hand.dealer_seat {
+            " (button)"
+        } else if Some(player.seat) == sb_seat {
+            " (small blind)"
+        } else if Some(player.seat) == bb_seat {
+            " (big blind)"
+        } else {
+            ""
+        };
+        let won: u64 = hand.winners.iter().filter(|w| w.seat == player.seat).map(|w| w.amount).sum();
+        let cards = player.revealed_hole_cards();
+        let rank = player.final_hand_rank.as_ref().map(|r| hand_rank_text(r, variant));
+        let result = match folded_on.get(&player.seat) {
+            Some(&phase) => {
+                let street = match phase {
+                    "preflop" => "before Flop",
+                    "flop" => "on the Flop",
+                    "turn" => "on the Turn",
+                    _ => "on the River",
+                };
+                let voluntary = invested.get(&player.seat).copied().unwrap_or(0) > hand.ante.min(player.starting_chips);
+                if phase == "preflop" && !voluntary {
+                    format!("folded {} (didn't bet)", street)
+                } else {
+                    format!("folded {}", street)
+                }
+            }
+            None if !cards.is_empty() && won > 0 => format!(
+                "showed [{}] and won ({}){}", cards_text(&cards), units.format(won),
+                rank.map(|r| format!(" with {}", r)).unwrap_or_default()
+            ),
+            None if !cards.is_empty() => format!(
+                "showed [{}] and lost{}", cards_text(&cards),
+                rank.map(|r| format!(" with {}", r)).unwrap_or_default()
+            ),
+            None if won > 0 => format!("collected ({})", units.format(won)),
+            None => "mucked".to_string(),
+        };
+        let _ = writeln!(out, "Seat {}: {}{} {}", player.seat as u32 + 1, player_name(player), role, result);
+    }
+
+    out
+}
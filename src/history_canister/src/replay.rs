@@ -0,0 +1,196 @@
+// Deal replay.
+//
+// Rebuilds a recorded hand's deck from its revealed seed with the shuffle
+// the table dealt it with (cleardeck_shuffle, in the version the proof
+// records - hands from before versions were dealt with V1), then walks the table's dealing
+// order and checks every recorded card against the card at its position:
+//   - a block of hole cards per dealt seat, in dealt_seats order
+//   - burn + flop, burn + turn, burn + river
+//   - when the board was run twice, the second runout's burns and cards
+//     straight after the first river
+// Hole cards are only on record for players who showed, so those are the
+// ones checked; the board is always checked in full. When players mixed in
+// their own entropy, the seed must also rebuild from the VRF bytes and nonces.
+
+use crate::{hex, Card, DealMismatch, DealVerification, GameVariant, HandHistoryRecord, PlayerEntropyRecord, Rank, Suit};
+
+/// The table's unshuffled deck, from the shared cleardeck_shuffle order
+pub fn create_deck(variant: GameVariant) -> Vec<Card> {
+    let suits = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
+    let ranks = [
+        Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
+        Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
+        Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
+    ];
+    let lowest_rank = match variant {
+        GameVariant::ShortDeckHoldem => Rank::Six,
+        _ => Rank::Two,
+    };
+    cleardeck_shuffle::deck_order(lowest_rank as u8)
+        .into_iter()
+        .map(|(suit, rank)| Card { suit: suits[suit], rank: ranks[(rank - Rank::Two as u8) as usize] })
+        .collect()
+}
+
+/// The table's shuffle - the same cleardeck_shuffle code that dealt the hand,
+/// in the proof's version (None for hands dealt before versions were recorded)
+pub fn shuffle_deck(deck: &mut [Card], seed: &[u8], version: Option<u32>) -> Result<(), String> {
+    cleardeck_shuffle::shuffle(deck, seed, version)
+}
+
+/// Whether a seed is the VRF bytes mixed with the revealed player nonces
+pub fn entropy_matches(seed: &[u8], entropy: &PlayerEntropyRecord) -> bool {
+    let Ok(vrf_seed) = hex::decode(&entropy.vrf_seed) else {
+        return false;
+    };
+    let nonces: Result<Vec<Option<Vec<u8>>>, _> = entropy.contributions.iter()
+        .map(|c| c.nonce.as_deref().map(hex::decode).transpose())
+        .collect();
+    let Ok(nonces) = nonces else {
+        return false;
+    };
+    let contributions: Vec<(&str, Option<&[u8]>)> = entropy.contributions.iter()
+        .zip(&nonces)
+        .map(|(c, nonce)| (c.commitment.as_str(), nonce.as_deref()))
+        .collect();
+    cleardeck_shuffle::verify_entropy(seed, &entropy.vrf_seed_hash, &vrf_seed, &contributions)
+}
+
+pub fn hole_card_count(variant: GameVariant) -> usize {
+    match variant {
+        GameVariant::PotLimitOmaha | GameVariant::OmahaHiLo => 4,
+        GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => 2,
+    }
+}
+
+/// Seats dealt in, in dealing order, and whether they had to be worked out.
+/// Older records don't say - the table dealt every active player in seat order,
+/// so take the seated players who took part in the hand in any visible way.
+pub fn dealt_seats(hand: &HandHistoryRecord) -> (Vec<u8>, bool) {
+    if let Some(seats) = &hand.dealt_seats {
+        return (seats.clone(), false);
+    }
+    let mut seats: Vec<u8> = hand.players.iter()
+        .filter(|p| {
+            matches!(p.position.as_str(), "BTN" | "SB" | "BB")
+                || !p.revealed_hole_cards().is_empty()
+                || hand.actions.iter().any(|a| a.seat == p.seat)
+                || hand.winners.iter().any(|w| w.seat == p.seat)
+        })
+        .map(|p| p.seat)
+        .collect();
+    seats.sort_unstable();
+    (seats, true)
+}
+
+/// Deck positions of board cards `from..5`, dealing from `next` - a burn before
+/// the flop, the turn and the river. Also returns the position after the river.
+fn board_positions(from: usize, mut next: usize) -> (Vec<usize>, usize) {
+    let mut positions = Vec::new();
+    for card in from..5 {
+        if matches!(card, 0 | 3 | 4) {
+            next += 1; // Burn
+        }
+        positions.push(next);
+        next += 1;
+    }
+    (positions, next)
+}
+
+fn board_label(second_board: bool, card: usize) -> String {
+    let street = match card {
+        0..=2 => format!("flop card {}", card + 1),
+        3 => "turn".to_string(),
+        _ => "river".to_string(),
+    };
+    if second_board {
+        format!("Second board {}", street)
+    } else {
+        format!("{}{}", street[..1].to_uppercase(), &street[1..])
+    }
+}
+
+/// Recorded cards against their replayed positions
+fn check(deck: &[Card], cards: impl Iterator<Item = (String, usize, Card)>, mismatches: &mut Vec<DealMismatch>) -> u32 {
+    let mut checked = 0;
+    for (location, position, recorded) in cards {
+        checked += 1;
+        let expected = deck.get(position).copied();
+        if expected != Some(recorded) {
+            mismatches.push(DealMismatch { location, deck_position: Some(position as u32), expected, recorded });
+        }
+    }
+    checked
+}
+
+/// Replay a hand's deal from its revealed seed and compare it with the record
+pub fn verify_deal(hand: &HandHistoryRecord) -> Result<DealVerification, String> {
+    let proof = &hand.shuffle_proof;
+    let seed = hex::decode(&proof.revealed_seed).map_err(|_| "Invalid revealed seed hex")?;
+    let seed_matches_hash = cleardeck_shuffle::seed_hash(&seed) == proof.seed_hash.to_lowercase();
+    let entropy_matches = proof.entropy.as_ref().map(|entropy| entropy_matches(&seed, entropy));
+
+    let variant = hand.game_variant.unwrap_or_default();
+    let mut deck = create_deck(variant);
+    shuffle_deck(&mut deck, &seed, proof.shuffle_version)?;
+    let per_seat = hole_card_count(variant);
+    let (seats, dealt_seats_inferred) = dealt_seats(hand);
+    let mut mismatches = Vec::new();
+
+    // Hole cards - each dealt seat gets the next block
+    let mut hole_cards_checked = 0;
+    for player in &hand.players {
+        let shown = player.revealed_hole_cards();
+        if shown.is_empty() {
+            continue;
+        }
+        match seats.iter().position(|s| *s == player.seat) {
+            Some(block) => {
+                let cards = shown.into_iter().enumerate().map(|(i, card)| {
+                    (format!("Seat {} hole card {}", player.seat, i + 1), block * per_seat + i, card)
+                });
+                hole_cards_checked += check(&deck, cards, &mut mismatches);
+            }
+            None => {
+                hole_cards_checked += shown.len() as u32;
+                mismatches.extend(shown.into_iter().enumerate().map(|(i, recorded)| DealMismatch {
+                    location: format!("Seat {} hole card {} (seat wasn't dealt in)", player.seat, i + 1),
+                    deck_position: None,
+                    expected: None,
+                    recorded,
+                }));
+            }
+        }
+    }
+
+    // Board - burns and cards after the last hole card
+    let board: Vec<Card> = hand.flop.into_iter()
+        .flat_map(|(a, b, c)| [a, b, c])
+        .chain(hand.turn)
+        .chain(hand.river)
+        .collect();
+    let (positions, after_river) = board_positions(0, seats.len() * per_seat);
+    let cards = board.iter().zip(&positions).enumerate().map(|(i, (card, position))| (board_label(false, i), *position, *card));
+    let mut board_cards_checked = check(&deck, cards, &mut mismatches);
+
+    // Second runout - shares the cards dealt before the all-in, then carries on after the first river
+    if let Some(second) = &hand.second_board {
+        let shared = second.iter().zip(&board).take_while(|(a, b)| a == b).count();
+        let (positions, _) = board_positions(shared, after_river);
+        let cards = second.iter().skip(shared).zip(&positions).enumerate()
+            .map(|(i, (card, position))| (board_label(true, shared + i), *position, *card));
+        board_cards_checked += check(&deck, cards, &mut mismatches);
+    }
+
+    Ok(DealVerification {
+        valid: seed_matches_hash && entropy_matches != Some(false) && mismatches.is_empty(),
+        seed_matches_hash,
+        entropy_matches,
+        shuffle_version: proof.shuffle_version.unwrap_or(cleardeck_shuffle::SHUFFLE_V1),
+        dealt_seats: seats,
+        dealt_seats_inferred,
+        hole_cards_checked,
+        board_cards_checked,
+        mismatches,
+    })
+}
@@ -0,0 +1,285 @@
+// Hand search.
+//
+// search_hands is answered from one stable index whose keys are
+//     [tag][attribute][timestamp BE][hand_id BE]
+// so each attribute value's hands sit together in time order. Every indexed
+// filter becomes a stream of (timestamp, hand_id) positions - a union of key
+// ranges when it matches several values, like "quads or better" or "any of
+// these players". Streams are intersected by leapfrogging: seek each one to
+// its newest position at or below a target until they all agree, so a query
+// only reads index entries near its matches and never walks every hand.
+//
+// Attributes are length-prefixed so no value's keys can run into another's.
+//
+// Pot size is indexed by log2 bucket. A pot range covers whole buckets, so the
+// buckets at its ends also hold pots outside it - the exact pot is still
+// checked against each match.
+
+use crate::{Currency, HandHistoryRecord, HandRank, HandRankCategory, HandSearchFilter, PlayerMatch};
+use candid::Principal;
+use ic_stable_structures::{Memory, StableBTreeMap};
+use std::ops::Bound;
+
+const TAG_TIME: u8 = 0;
+const TAG_PLAYER: u8 = 1;
+const TAG_PLAYER_POSITION: u8 = 2;
+const TAG_STAKES: u8 = 3;
+const TAG_CURRENCY: u8 = 4;
+const TAG_SHOWDOWN: u8 = 5;
+const TAG_WINNING_RANK: u8 = 6;
+const TAG_POT: u8 = 7;
+
+/// (timestamp, hand_id) - search results come newest first in this order
+pub type Position = (u64, u64);
+
+/// Hands matching one filter - the union of the index ranges under these prefixes
+pub struct Stream {
+    prefixes: Vec<Vec<u8>>,
+}
+
+/// What a leapfrog run ended with
+#[derive(Debug, PartialEq, Eq)]
+pub enum Step {
+    Match(Position),
+    OutOfBudget(Position), // Resume from this position next call
+    Done,
+}
+
+fn principal_attr(principal: &Principal) -> Vec<u8> {
+    let bytes = principal.as_slice();
+    let mut attr = Vec::with_capacity(bytes.len() + 1);
+    attr.push(bytes.len() as u8);
+    attr.extend_from_slice(bytes);
+    attr
+}
+
+fn prefix(tag: u8, attr: &[u8]) -> Vec<u8> {
+    let mut prefix = Vec::with_capacity(attr.len() + 1);
+    prefix.push(tag);
+    prefix.extend_from_slice(attr);
+    prefix
+}
+
+fn player_prefix(player: &Principal) -> Vec<u8> {
+    prefix(TAG_PLAYER, &principal_attr(player))
+}
+
+fn player_position_prefix(player: &Principal, position: &str) -> Vec<u8> {
+    let mut attr = principal_attr(player);
+    attr.push(position.len().min(u8::MAX as usize) as u8);
+    attr.extend(position.bytes().take(u8::MAX as usize));
+    prefix(TAG_PLAYER_POSITION, &attr)
+}
+
+fn currency_code(currency: Currency) -> u8 {
+    match currency {
+        Currency::ICP => 0,
+        Currency::BTC => 1,
+    }
+}
+
+fn stakes_prefix(currency: Currency, small_blind: u64, big_blind: u64) -> Vec<u8> {
+    let mut attr = vec![currency_code(currency)];
+    attr.extend_from_slice(&small_blind.to_be_bytes());
+    attr.extend_from_slice(&big_blind.to_be_bytes());
+    prefix(TAG_STAKES, &attr)
+}
+
+fn key(prefix: &[u8], (timestamp, hand_id): Position) -> Vec<u8> {
+    let mut key = Vec::with_capacity(prefix.len() + 16);
+    key.extend_from_slice(prefix);
+    key.extend_from_slice(&timestamp.to_be_bytes());
+    key.extend_from_slice(&hand_id.to_be_bytes());
+    key
+}
+
+fn position_of(key: &[u8]) -> Position {
+    let tail = &key[key.len() - 16..];
+    let timestamp = u64::from_be_bytes(tail[..8].try_into().expect("8 bytes"));
+    let hand_id = u64::from_be_bytes(tail[8..].try_into().expect("8 bytes"));
+    (timestamp, hand_id)
+}
+
+/// The position just below this one
+pub fn previous(position: Position) -> Option<Position> {
+    match position {
+        (timestamp, 0) => timestamp.checked_sub(1).map(|t| (t, u64::MAX)),
+        (timestamp, hand_id) => Some((timestamp, hand_id - 1)),
+    }
+}
+
+/// Category a HandRank falls in - the order is hand strength
+pub fn rank_category(rank: &HandRank) -> HandRankCategory {
+    match rank {
+        HandRank::HighCard(_) => HandRankCategory::HighCard,
+        HandRank::Pair(..) => HandRankCategory::Pair,
+        HandRank::TwoPair(..) => HandRankCategory::TwoPair,
+        HandRank::ThreeOfAKind(..) => HandRankCategory::ThreeOfAKind,
+        HandRank::Straight(_) => HandRankCategory::Straight,
+        HandRank::Flush(_) => HandRankCategory::Flush,
+        HandRank::FullHouse(..) => HandRankCategory::FullHouse,
+        HandRank::FourOfAKind(..) => HandRankCategory::FourOfAKind,
+        HandRank::StraightFlush(_) => HandRankCategory::StraightFlush,
+        HandRank::RoyalFlush => HandRankCategory::RoyalFlush,
+    }
+}
+
+const ALL_CATEGORIES: [HandRankCategory; 10] = [
+    HandRankCategory::HighCard,
+    HandRankCategory::Pair,
+    HandRankCategory::TwoPair,
+    HandRankCategory::ThreeOfAKind,
+    HandRankCategory::Straight,
+    HandRankCategory::Flush,
+    HandRankCategory::FullHouse,
+    HandRankCategory::FourOfAKind,
+    HandRankCategory::StraightFlush,
+    HandRankCategory::RoyalFlush,
+];
+
+fn rank_prefix(category: HandRankCategory) -> Vec<u8> {
+    let code = ALL_CATEGORIES.iter().position(|c| *c == category).unwrap_or(0) as u8;
+    prefix(TAG_WINNING_RANK, &[code])
+}
+
+/// log2 bucket of a pot: 0 for an empty pot, n for pots in [2^(n-1), 2^n)
+pub fn pot_bucket(pot: u64) -> u8 {
+    (u64::BITS - pot.leading_zeros()) as u8
+}
+
+fn pot_prefix(bucket: u8) -> Vec<u8> {
+    prefix(TAG_POT, &[bucket])
+}
+
+pub fn pot_key(pot: u64, at: Position) -> Vec<u8> {
+    key(&pot_prefix(pot_bucket(pot)), at)
+}
+
+/// Every index key for a hand
+pub fn hand_keys(hand: &HandHistoryRecord) -> Vec<Vec<u8>> {
+    let at = (hand.timestamp, hand.hand_id);
+    let currency = hand.currency.unwrap_or_default();
+    let mut keys = vec![
+        key(&prefix(TAG_TIME, &[]), at),
+        key(&stakes_prefix(currency, hand.small_blind, hand.big_blind), at),
+        key(&prefix(TAG_CURRENCY, &[currency_code(currency)]), at),
+        pot_key(hand.total_pot, at),
+    ];
+    for player in &hand.players {
+        keys.push(key(&player_prefix(&player.principal), at));
+        keys.push(key(&player_position_prefix(&player.principal, &player.position), at));
+    }
+    if hand.went_to_showdown {
+        keys.push(key(&prefix(TAG_SHOWDOWN, &[]), at));
+    }
+    if let Some(best) = hand.winners.iter().filter_map(|w| w.hand_rank.as_ref()).map(rank_category).max() {
+        keys.push(key(&rank_prefix(best), at));
+    }
+    keys
+}
+
+/// Index streams for a filter, all of which a hand must be in.
+/// Pot size only narrows to buckets - check it against each match.
+pub fn streams(filter: &HandSearchFilter) -> Result<Vec<Stream>, String> {
+    let mut streams = Vec::new();
+    let currencies: Vec<Currency> = match filter.currency {
+        Some(currency) => vec![currency],
+        None => vec![Currency::ICP, Currency::BTC],
+    };
+
+    if filter.position.is_some() && filter.players.is_empty() {
+        return Err("position needs at least one player to apply to".to_string());
+    }
+    let player_prefix = |player: &Principal| match &filter.position {
+        Some(position) => player_position_prefix(player, position),
+        None => player_prefix(player),
+    };
+    match filter.players_match.unwrap_or(PlayerMatch::Any) {
+        PlayerMatch::All => streams.extend(filter.players.iter().map(|p| Stream { prefixes: vec![player_prefix(p)] })),
+        PlayerMatch::Any if !filter.players.is_empty() => {
+            streams.push(Stream { prefixes: filter.players.iter().map(player_prefix).collect() });
+        }
+        PlayerMatch::Any => {}
+    }
+    if let Some(stakes) = &filter.stakes {
+        streams.push(Stream {
+            prefixes: currencies.iter().map(|c| stakes_prefix(*c, stakes.small_blind, stakes.big_blind)).collect(),
+        });
+    } else if filter.currency.is_some() {
+        streams.push(Stream { prefixes: currencies.iter().map(|c| prefix(TAG_CURRENCY, &[currency_code(*c)])).collect() });
+    }
+    if filter.showdown_only {
+        streams.push(Stream { prefixes: vec![prefix(TAG_SHOWDOWN, &[])] });
+    }
+    if let Some(min) = filter.min_winning_rank {
+        streams.push(Stream {
+            prefixes: ALL_CATEGORIES.iter().filter(|c| **c >= min).map(|c| rank_prefix(*c)).collect(),
+        });
+    }
+
+    if filter.min_pot.is_some() || filter.max_pot.is_some() {
+        let low = pot_bucket(filter.min_pot.unwrap_or(0));
+        let high = pot_bucket(filter.max_pot.unwrap_or(u64::MAX));
+        if low > 0 || high < pot_bucket(u64::MAX) {
+            streams.push(Stream { prefixes: (low..=high).map(pot_prefix).collect() });
+        }
+    }
+
+    // No indexed filter - walk hands by time
+    if streams.is_empty() {
+        streams.push(Stream { prefixes: vec![prefix(TAG_TIME, &[])] });
+    }
+    Ok(streams)
+}
+
+/// Positions of every indexed hand after `after`, oldest first - archived hands included
+pub fn time_positions<M: Memory>(index: &StableBTreeMap<Vec<u8>, (), M>, after: Option<Position>, limit: u64) -> Vec<Position> {
+    let time = prefix(TAG_TIME, &[]);
+    let start = match after {
+        Some(position) => Bound::Excluded(key(&time, position)),
+        None => Bound::Included(key(&time, (0, 0))),
+    };
+    index.keys_range((start, Bound::Included(key(&time, (u64::MAX, u64::MAX)))))
+        .take(limit as usize)
+        .map(|k| position_of(&k))
+        .collect()
+}
+
+/// Newest position in a stream between `from` (timestamp) and `at` inclusive
+fn seek<M: Memory>(index: &StableBTreeMap<Vec<u8>, (), M>, stream: &Stream, from: u64, at: Position) -> Option<Position> {
+    stream.prefixes.iter()
+        .filter_map(|p| index.keys_range(key(p, (from, 0))..=key(p, at)).next_back())
+        .map(|k| position_of(&k))
+        .max()
+}
+
+/// Newest position at or below `at` that every stream has. Each seek costs one unit of `budget`.
+pub fn next_match<M: Memory>(
+    index: &StableBTreeMap<Vec<u8>, (), M>,
+    streams: &[Stream],
+    from: u64,
+    at: Position,
+    budget: &mut u32,
+) -> Step {
+    let mut target = at;
+    loop {
+        let mut lowered = false;
+        for stream in streams {
+            if *budget == 0 {
+                return Step::OutOfBudget(target);
+            }
+            *budget -= 1;
+            match seek(index, stream, from, target) {
+                None => return Step::Done,
+                Some(position) if position < target => {
+                    target = position;
+                    lowered = true;
+                }
+                Some(_) => {}
+            }
+        }
+        if !lowered {
+            return Step::Match(target);
+        }
+    }
+}
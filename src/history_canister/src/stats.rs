@@ -0,0 +1,279 @@
+// Player statistics.
+//
+// Derives the usual tracker stats - VPIP, PFR, 3-bet, fold to 3-bet, c-bet,
+// aggression factor, WTSD and W$SD - from one hand's action list as counts
+// per seat. record_hand adds them to each player's running totals, which are
+// also kept per position and per stake level. Rates are only worked out when
+// asked for, so the stored counts can always be re-sliced.
+//
+// Also the arithmetic behind the profit time series: daily buckets, sessions
+// and bb/100.
+
+use crate::pokerstars::{big_blind_seat, format_amount, small_blind_seat, Street};
+use crate::{
+    AdvancedStats, Currency, DailyResult, HandHistoryRecord, PlayerAction, ProfitPoint, ProfitSeries, Session,
+    StatCounters, StatLine, SESSION_GAP_NS,
+};
+use candid::Principal;
+use std::collections::{BTreeMap, BTreeSet};
+
+const DAY_NS: u64 = 86_400 * 1_000_000_000;
+
+impl StatCounters {
+    pub fn add(&mut self, other: &StatCounters) {
+        self.hands += other.hands;
+        self.vpip += other.vpip;
+        self.pfr += other.pfr;
+        self.three_bet_opportunities += other.three_bet_opportunities;
+        self.three_bets += other.three_bets;
+        self.fold_to_three_bet_opportunities += other.fold_to_three_bet_opportunities;
+        self.folds_to_three_bet += other.folds_to_three_bet;
+        self.cbet_opportunities += other.cbet_opportunities;
+        self.cbets += other.cbets;
+        self.postflop_bets_raises += other.postflop_bets_raises;
+        self.postflop_calls += other.postflop_calls;
+        self.saw_flop += other.saw_flop;
+        self.went_to_showdown += other.went_to_showdown;
+        self.won_at_showdown += other.won_at_showdown;
+    }
+}
+
+/// Stake level a hand is filed under - cash hands by blinds and currency,
+/// tournament hands together since their blinds keep rising
+pub fn stakes_label(hand: &HandHistoryRecord) -> String {
+    if hand.is_tournament == Some(true) {
+        return "Tournament".to_string();
+    }
+    let currency = hand.currency.unwrap_or_default();
+    format!(
+        "{}/{} {}",
+        format_amount(hand.small_blind, currency), format_amount(hand.big_blind, currency), currency.symbol()
+    )
+}
+
+/// Add one hand's counts to a player's overall, position and stakes totals
+pub fn record(advanced: &mut AdvancedStats, position: &str, stakes: &str, counters: &StatCounters) {
+    advanced.overall.add(counters);
+    for (list, label) in [(&mut advanced.by_position, position), (&mut advanced.by_stakes, stakes)] {
+        match list.iter_mut().find(|(l, _)| l == label) {
+            Some((_, totals)) => totals.add(counters),
+            None => list.push((label.to_string(), counters.clone())),
+        }
+    }
+}
+
+fn bps(count: u64, opportunities: u64) -> u32 {
+    if opportunities == 0 {
+        return 0;
+    }
+    (count as u128 * 10_000 / opportunities as u128) as u32
+}
+
+/// Rates for a set of counters
+pub fn stat_line(label: &str, counters: &StatCounters) -> StatLine {
+    StatLine {
+        label: label.to_string(),
+        counters: counters.clone(),
+        vpip_bps: bps(counters.vpip, counters.hands),
+        pfr_bps: bps(counters.pfr, counters.hands),
+        three_bet_bps: bps(counters.three_bets, counters.three_bet_opportunities),
+        fold_to_three_bet_bps: bps(counters.folds_to_three_bet, counters.fold_to_three_bet_opportunities),
+        cbet_bps: bps(counters.cbets, counters.cbet_opportunities),
+        aggression_factor_x100: (counters.postflop_calls > 0)
+            .then(|| (counters.postflop_bets_raises as u128 * 100 / counters.postflop_calls as u128) as u32),
+        wtsd_bps: bps(counters.went_to_showdown, counters.saw_flop),
+        wsd_bps: bps(counters.won_at_showdown, counters.went_to_showdown),
+    }
+}
+
+/// Each seated player's counts for one hand
+pub fn hand_counters(hand: &HandHistoryRecord) -> BTreeMap<u8, StatCounters> {
+    let mut counters: BTreeMap<u8, StatCounters> = hand.players.iter()
+        .map(|p| (p.seat, StatCounters { hands: 1, ..Default::default() }))
+        .collect();
+
+    // Preflop - blinds and straddles set the bet, every raise after that is a bet level
+    let mut street = Street::new();
+    for (seat, blind) in [(small_blind_seat(hand), hand.small_blind), (big_blind_seat(hand), hand.big_blind)] {
+        if let Some(seat) = seat {
+            street.post(seat, blind);
+        }
+    }
+    let mut raises = 0u32;
+    let mut opener: Option<u8> = None;
+    let mut last_aggressor: Option<u8> = None;
+    let mut opener_faced_three_bet = false;
+    let mut faced_open: BTreeSet<u8> = BTreeSet::new();
+    let mut vpip: BTreeSet<u8> = BTreeSet::new();
+    let mut pfr: BTreeSet<u8> = BTreeSet::new();
+    let mut folded_preflop: BTreeSet<u8> = BTreeSet::new();
+
+    for action in hand.actions.iter().filter(|a| a.phase == "preflop") {
+        let seat = action.seat;
+        let (_, raised) = street.apply(seat, &action.action);
+        let Some(c) = counters.get_mut(&seat) else { continue };
+        if matches!(action.action, PlayerAction::Straddle(_) | PlayerAction::PostBlind(_)) {
+            continue;
+        }
+
+        // Facing an open raise - a chance to 3-bet, taken once per hand
+        if raises == 1 && Some(seat) != opener && faced_open.insert(seat) {
+            c.three_bet_opportunities += 1;
+            if raised {
+                c.three_bets += 1;
+            }
+        }
+        // The opener facing a 3-bet
+        if raises == 2 && Some(seat) == opener && !opener_faced_three_bet {
+            opener_faced_three_bet = true;
+            c.fold_to_three_bet_opportunities += 1;
+            if action.action == PlayerAction::Fold {
+                c.folds_to_three_bet += 1;
+            }
+        }
+
+        match action.action {
+            PlayerAction::Fold => {
+                folded_preflop.insert(seat);
+            }
+            PlayerAction::Check => {}
+            _ => {
+                vpip.insert(seat);
+            }
+        }
+        if raised {
+            raises += 1;
+            opener = opener.or(Some(seat));
+            last_aggressor = Some(seat);
+            pfr.insert(seat);
+        }
+    }
+
+    // Postflop - aggression, and whether the preflop aggressor continued on the flop
+    let saw_flop: BTreeSet<u8> = if hand.flop.is_some() {
+        counters.keys().filter(|seat| !folded_preflop.contains(seat)).copied().collect()
+    } else {
+        BTreeSet::new()
+    };
+    let mut aggressor_acted_on_flop = false;
+    for phase in ["flop", "turn", "river"] {
+        let mut street = Street::new();
+        for action in hand.actions.iter().filter(|a| a.phase == phase) {
+            let seat = action.seat;
+            let bet_before = street.current_bet;
+            let (_, raised) = street.apply(seat, &action.action);
+            let Some(c) = counters.get_mut(&seat) else { continue };
+
+            if phase == "flop" && Some(seat) == last_aggressor && !aggressor_acted_on_flop {
+                aggressor_acted_on_flop = true;
+                if bet_before == 0 {
+                    c.cbet_opportunities += 1;
+                    if raised {
+                        c.cbets += 1;
+                    }
+                }
+            }
+            match action.action {
+                PlayerAction::Fold | PlayerAction::Check => {}
+                _ if raised => c.postflop_bets_raises += 1,
+                _ => c.postflop_calls += 1,
+            }
+        }
+    }
+
+    for player in &hand.players {
+        let Some(c) = counters.get_mut(&player.seat) else { continue };
+        c.vpip = vpip.contains(&player.seat) as u64;
+        c.pfr = pfr.contains(&player.seat) as u64;
+        if saw_flop.contains(&player.seat) {
+            c.saw_flop = 1;
+            if hand.went_to_showdown && !player.revealed_hole_cards().is_empty() {
+                c.went_to_showdown = 1;
+                c.won_at_showdown = (player.amount_won > 0) as u64;
+            }
+        }
+    }
+
+    counters
+}
+
+// ============================================================================
+// PROFIT TIME SERIES
+// ============================================================================
+
+/// UTC day number of a timestamp in nanoseconds
+pub fn day_of(timestamp: u64) -> u64 {
+    timestamp / DAY_NS
+}
+
+/// Profit in thousandths of a big blind, so hands at different stakes add up
+pub fn milli_big_blinds(profit: i64, big_blind: u64) -> i64 {
+    if big_blind == 0 {
+        return 0;
+    }
+    (profit as i128 * 1000 / big_blind as i128) as i64
+}
+
+/// bb/100 times 100 (so 250 = 2.5 bb/100)
+pub fn bb_per_100_x100(mbb: i64, hands: u64) -> i64 {
+    if hands == 0 {
+        return 0;
+    }
+    (mbb as i128 * 10 / hands as i128) as i64
+}
+
+/// Whether a hand starting at `timestamp` belongs to `session` rather than a new one
+pub fn continues_session(session: &Session, timestamp: u64) -> bool {
+    timestamp >= session.started_at && timestamp <= session.ended_at.saturating_add(SESSION_GAP_NS)
+}
+
+/// One series from daily results in day order - same-day entries share a point
+fn build_series<'a>(
+    table_id: Option<Principal>,
+    currency: Currency,
+    days: impl Iterator<Item = (u64, &'a DailyResult)>,
+) -> ProfitSeries {
+    let mut points: Vec<ProfitPoint> = Vec::new();
+    let (mut hands, mut profit, mut mbb) = (0u64, 0i64, 0i64);
+    for (day, result) in days {
+        hands += result.hands;
+        profit += result.profit;
+        mbb += result.mbb;
+        match points.last_mut() {
+            Some(point) if point.day_start == day * DAY_NS => {
+                point.hands += result.hands;
+                point.profit += result.profit;
+                point.cumulative_hands = hands;
+                point.cumulative_profit = profit;
+            }
+            _ => points.push(ProfitPoint {
+                day_start: day * DAY_NS,
+                hands: result.hands,
+                profit: result.profit,
+                cumulative_hands: hands,
+                cumulative_profit: profit,
+            }),
+        }
+    }
+    ProfitSeries { table_id, currency, hands, profit, bb_per_100_x100: bb_per_100_x100(mbb, hands), points }
+}
+
+/// A series per table, then one per currency across all tables.
+/// `days` are (player, day, table) entries in key order.
+pub fn profit_series(days: &[((Principal, u64, Principal), DailyResult)]) -> Vec<ProfitSeries> {
+    let tables: BTreeSet<Principal> = days.iter().map(|((_, _, table), _)| *table).collect();
+    let mut series: Vec<ProfitSeries> = tables.into_iter()
+        .map(|table| {
+            let mut entries = days.iter().filter(|((_, _, t), _)| *t == table).peekable();
+            let currency = entries.peek().map(|(_, r)| r.currency).unwrap_or_default();
+            build_series(Some(table), currency, entries.map(|((_, day, _), r)| (*day, r)))
+        })
+        .collect();
+    for currency in [Currency::ICP, Currency::BTC] {
+        if days.iter().any(|(_, r)| r.currency == currency) {
+            let entries = days.iter().filter(|(_, r)| r.currency == currency).map(|((_, day, _), r)| (*day, r));
+            series.push(build_series(None, currency, entries));
+        }
+    }
+    series
+}
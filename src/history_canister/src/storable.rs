@@ -0,0 +1,80 @@
+// Stable-memory encoding for Candid records.
+//
+// Records are stored as Candid values without the message header. The header
+// (the type table, often longer than the values) is kept once per schema in
+// the crate's SCHEMAS_MEMORY, and each record starts with
+// [COMPACT_TAG, schema id (u32 LE)]. Decoding with the header a record was
+// written under keeps Candid's opt-field upgrades working. Records written
+// before this layout start with "DIDL" and decode as they are.
+//
+// Shared with the archive canister, which includes this file by path.
+
+use super::{memory, Memory, SCHEMAS_MEMORY};
+use candid::{CandidType, Deserialize};
+use ic_stable_structures::StableBTreeMap;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+
+const COMPACT_TAG: u8 = 0xFF;
+
+thread_local! {
+    // Schema id -> Candid header of stored records, and the reverse for writing
+    static SCHEMAS: RefCell<StableBTreeMap<u32, Vec<u8>, Memory>> =
+        RefCell::new(StableBTreeMap::init(memory(SCHEMAS_MEMORY)));
+    static SCHEMA_IDS: RefCell<BTreeMap<Vec<u8>, u32>> = const { RefCell::new(BTreeMap::new()) };
+}
+
+/// Candid message header ("DIDL" and the type table) for T
+fn candid_header<T: CandidType>() -> Vec<u8> {
+    let mut types = candid::ser::TypeSerialize::new();
+    types.push_type(&T::ty()).expect("Failed to build record type");
+    types.serialize().expect("Failed to encode record type");
+    let mut header = b"DIDL".to_vec();
+    header.extend_from_slice(types.get_result());
+    header
+}
+
+/// ID of a schema header, registering it the first time it is seen
+fn schema_id(header: Vec<u8>) -> u32 {
+    SCHEMA_IDS.with(|ids| {
+        let mut ids = ids.borrow_mut();
+        SCHEMAS.with(|schemas| {
+            let mut schemas = schemas.borrow_mut();
+            // Reload after an upgrade - the heap copy starts empty
+            if ids.len() as u64 != schemas.len() {
+                *ids = schemas.iter().map(|(id, header)| (header, id)).collect();
+            }
+            if let Some(&id) = ids.get(&header) {
+                return id;
+            }
+            let id = schemas.len() as u32;
+            schemas.insert(id, header.clone());
+            ids.insert(header, id);
+            id
+        })
+    })
+}
+
+pub fn encode_record<T: CandidType>(record: &T) -> Vec<u8> {
+    let mut values = candid::ser::ValueSerializer::new();
+    record.idl_serialize(&mut values).expect("Failed to encode record for stable memory");
+    let mut bytes = Vec::with_capacity(5 + values.get_result().len());
+    bytes.push(COMPACT_TAG);
+    bytes.extend_from_slice(&schema_id(candid_header::<T>()).to_le_bytes());
+    bytes.extend_from_slice(values.get_result());
+    bytes
+}
+
+pub fn decode_record<T: CandidType + for<'de> Deserialize<'de>>(bytes: &[u8]) -> T {
+    let decoded = match bytes.split_first() {
+        Some((&COMPACT_TAG, rest)) if rest.len() >= 4 => {
+            let id = u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]);
+            let mut message = SCHEMAS.with(|s| s.borrow().get(&id))
+                .expect("Record written under an unknown schema");
+            message.extend_from_slice(&rest[4..]);
+            candid::decode_one(&message)
+        }
+        _ => candid::decode_one(bytes),
+    };
+    decoded.expect("Failed to decode record from stable memory")
+}
@@ -184,6 +184,50 @@ fn paginate<T: Clone>(items: &[T], offset: usize, limit: usize) -> Vec<T> {
         .collect()
 }
 
+/// Archive canister (by number) holding hand IDs start..end
+struct ArchiveInfo {
+    canister_id: u64,
+    start: u64,
+    end: u64,
+}
+
+fn archive_for(archives: &[ArchiveInfo], hand_id: u64) -> Option<u64> {
+    archives.iter()
+        .find(|a| a.start <= hand_id && hand_id < a.end)
+        .map(|a| a.canister_id)
+}
+
+/// Split a page of hand IDs into local hands and per-archive pointers
+fn hands_page(local: &[u64], archives: &[ArchiveInfo], hand_ids: &[u64]) -> (Vec<u64>, Vec<(u64, Vec<u64>)>) {
+    let mut hands = Vec::new();
+    let mut archived: Vec<(u64, Vec<u64>)> = Vec::new();
+    for &hand_id in hand_ids {
+        if local.contains(&hand_id) {
+            hands.push(hand_id);
+        } else if let Some(canister_id) = archive_for(archives, hand_id) {
+            match archived.iter_mut().find(|(c, _)| *c == canister_id) {
+                Some((_, ids)) => ids.push(hand_id),
+                None => archived.push((canister_id, vec![hand_id])),
+            }
+        }
+    }
+    (hands, archived)
+}
+
+/// How many of the oldest hands (by encoded size) fit in one archive batch
+fn archive_batch_len(sizes: &[usize], max_hands: usize, max_bytes: usize) -> usize {
+    let mut count = 0;
+    let mut bytes = 0;
+    for &size in sizes.iter().take(max_hands) {
+        if count > 0 && bytes + size > max_bytes {
+            break;
+        }
+        bytes += size;
+        count += 1;
+    }
+    count
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -499,6 +543,47 @@ mod tests {
         assert!(result.is_empty());
     }
 
+    // =========================================================================
+    // ARCHIVE TESTS
+    // =========================================================================
+
+    fn two_archives() -> Vec<ArchiveInfo> {
+        vec![
+            ArchiveInfo { canister_id: 100, start: 1, end: 11 },
+            ArchiveInfo { canister_id: 200, start: 11, end: 16 },
+        ]
+    }
+
+    #[test]
+    fn test_archive_for_hand_ranges() {
+        let archives = two_archives();
+        assert_eq!(archive_for(&archives, 1), Some(100));
+        assert_eq!(archive_for(&archives, 10), Some(100));
+        assert_eq!(archive_for(&archives, 11), Some(200));
+        // Still held locally (or never recorded)
+        assert_eq!(archive_for(&archives, 16), None);
+        assert_eq!(archive_for(&archives, 0), None);
+    }
+
+    #[test]
+    fn test_hands_page_groups_archived_hands() {
+        let local: Vec<u64> = (16..=20).collect();
+        // Newest first, crossing from local hands into both archives
+        let page = [18, 17, 16, 12, 9, 3];
+        let (hands, archived) = hands_page(&local, &two_archives(), &page);
+        assert_eq!(hands, vec![18, 17, 16]);
+        assert_eq!(archived, vec![(200, vec![12]), (100, vec![9, 3])]);
+    }
+
+    #[test]
+    fn test_archive_batch_respects_message_size() {
+        // Always sends at least one hand, even an oversized one
+        assert_eq!(archive_batch_len(&[5_000], 10, 1_000), 1);
+        assert_eq!(archive_batch_len(&[400, 400, 400], 10, 1_000), 2);
+        assert_eq!(archive_batch_len(&[100, 100, 100], 2, 1_000), 2);
+        assert_eq!(archive_batch_len(&[], 10, 1_000), 0);
+    }
+
     // =========================================================================
     // INDEX TESTS (logic for hands_by_table and hands_by_player)
     // =========================================================================
@@ -536,6 +621,95 @@ mod tests {
         assert_eq!(hand_ids[99], 100); // Last hand
     }
 
+    #[test]
+    fn test_stable_index_range_newest_first() {
+        use candid::Principal;
+        use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+
+        // (owner, hand_id) keys - a range over one owner yields only that owner's hands
+        let mut index: StableBTreeMap<(Principal, u64), (), _> = StableBTreeMap::init(DefaultMemoryImpl::default());
+        let table_a = Principal::from_slice(&[1]);
+        let table_b = Principal::from_slice(&[2]);
+        for hand_id in 1..=5 {
+            let table = if hand_id % 2 == 0 { table_b } else { table_a };
+            index.insert((table, hand_id), ());
+        }
+
+        let newest_first = |owner: Principal, offset: usize, limit: usize| -> Vec<u64> {
+            index.keys_range((owner, 0)..=(owner, u64::MAX))
+                .rev()
+                .skip(offset)
+                .take(limit)
+                .map(|(_, id)| id)
+                .collect()
+        };
+        assert_eq!(newest_first(table_a, 0, 10), vec![5, 3, 1]);
+        assert_eq!(newest_first(table_a, 1, 1), vec![3]);
+        assert_eq!(newest_first(table_b, 0, 10), vec![4, 2]);
+        assert!(newest_first(Principal::anonymous(), 0, 10).is_empty());
+    }
+
+    #[test]
+    fn test_record_hand_idempotent_on_table_and_hand_number() {
+        // (table_id, hand_number) -> hand_id, hand_id -> seed_hash - neither needs the hand record
+        let mut by_table_hand: BTreeMap<(u64, u64), u64> = BTreeMap::new();
+        let mut seeds: BTreeMap<u64, &str> = BTreeMap::new();
+        let mut next_hand_id = 0u64;
+
+        let mut record = |table: u64, hand_number: u64, seed_hash: &'static str| -> u64 {
+            if let Some(&id) = by_table_hand.get(&(table, hand_number)) {
+                if seeds.get(&id) == Some(&seed_hash) {
+                    return id;
+                }
+            }
+            let id = next_hand_id;
+            next_hand_id += 1;
+            by_table_hand.insert((table, hand_number), id);
+            seeds.insert(id, seed_hash);
+            id
+        };
+
+        assert_eq!(record(1, 1, "aa"), 0);
+        // Retry of the same hand gets the same ID
+        assert_eq!(record(1, 1, "aa"), 0);
+        // Same hand number on another table is a different hand
+        assert_eq!(record(2, 1, "bb"), 1);
+        // Table reset - hand 1 again but a different shuffle
+        assert_eq!(record(1, 1, "cc"), 2);
+        assert_eq!(record(1, 1, "cc"), 2);
+        // Archiving only removes the record - the key and seed stay, so a late retry is still caught
+        assert_eq!(record(2, 1, "bb"), 1);
+    }
+
+    #[test]
+    fn test_record_tournament_idempotent_on_table_and_start() {
+        // (table_id, started_at) -> tournament_id; winnings are only counted for a new tournament
+        type Ledger = (BTreeMap<(u64, u64), u64>, BTreeMap<&'static str, i64>);
+        fn record(ledger: &mut Ledger, table: u64, started_at: u64, results: &[(&'static str, u64)], entry: u64) -> u64 {
+            let (by_table_start, winnings) = ledger;
+            if let Some(&id) = by_table_start.get(&(table, started_at)) {
+                return id;
+            }
+            let id = by_table_start.len() as u64 + 1;
+            for &(player, prize) in results {
+                *winnings.entry(player).or_default() += prize as i64 - entry as i64;
+            }
+            by_table_start.insert((table, started_at), id);
+            id
+        }
+        let mut ledger: Ledger = Default::default();
+
+        let results = [("alice", 300), ("bob", 0), ("carol", 0)];
+        assert_eq!(record(&mut ledger, 1, 100, &results, 110), 1);
+        // Outbox retry after a lost reply - same ID, prizes not paid twice
+        assert_eq!(record(&mut ledger, 1, 100, &results, 110), 1);
+        assert_eq!(ledger.1["alice"], 190);
+        assert_eq!(ledger.1["bob"], -110);
+        // The table's next Sit & Go is a new tournament
+        assert_eq!(record(&mut ledger, 1, 900, &results, 110), 2);
+        assert_eq!(ledger.1["alice"], 380);
+    }
+
     // =========================================================================
     // AUTHORIZATION TESTS (Logic Only)
     // =========================================================================
@@ -582,4 +756,780 @@ mod tests {
         // Unauthorized
         assert!(!is_authorized(50, &authorized_tables, admin));
     }
+
+    // =========================================================================
+    // HAND EXPORT, STATS AND PROFIT SERIES TESTS (pure modules - tested directly)
+    // =========================================================================
+
+    mod hand_records {
+        use candid::Principal;
+        use history_canister::{fairness, ohh, replay, search, stats};
+        use ic_stable_structures::{DefaultMemoryImpl, StableBTreeMap};
+        use history_canister::pokerstars::{format_amount, format_hand, format_timestamp};
+        use serde_json::Value;
+        use history_canister::{
+            ActionRecord, AdvancedStats, Card, Currency, DailyResult, GameVariant, HandHistoryRecord, HandRank,
+            DealMismatch, HandRankCategory, HandSearchFilter, PlayerAction, PlayerHandRecord, PlayerMatch, Rank, Session,
+            ShuffleProofRecord, Stakes, Suit, WinnerRecord, EntropyContributionRecord, PlayerEntropyRecord,
+            FairnessTally,
+        };
+
+        const E6: u64 = 1_000_000; // 0.01 ICP
+
+        fn card(rank: Rank, suit: Suit) -> Card {
+            Card { rank, suit }
+        }
+
+        fn player(seat: u8, position: &str, chips: u64, shown: Option<Vec<Card>>, rank: Option<HandRank>) -> PlayerHandRecord {
+            PlayerHandRecord {
+                seat,
+                principal: Principal::from_slice(&[seat + 1]),
+                starting_chips: chips,
+                ending_chips: chips,
+                hole_cards: None,
+                final_hand_rank: rank,
+                amount_won: 0,
+                position: position.to_string(),
+                all_hole_cards: shown,
+            }
+        }
+
+        fn act(seat: u8, action: PlayerAction, phase: &str) -> ActionRecord {
+            ActionRecord {
+                seat,
+                principal: Principal::from_slice(&[seat + 1]),
+                action,
+                timestamp: 0,
+                phase: phase.to_string(),
+            }
+        }
+
+        fn name(seat: u8) -> String {
+            Principal::from_slice(&[seat + 1]).to_text()
+        }
+
+        /// Three-handed ICP hand: BTN raises, SB folds, BB calls and loses at showdown
+        fn sample_hand() -> HandHistoryRecord {
+            HandHistoryRecord {
+                hand_id: 42,
+                table_id: Principal::from_slice(&[9]),
+                hand_number: 7,
+                timestamp: 1_792_238_400 * 1_000_000_000, // 2026-10-17 12:00:00 UTC
+                small_blind: E6,
+                big_blind: 2 * E6,
+                ante: 0,
+                shuffle_proof: ShuffleProofRecord { seed_hash: String::new(), revealed_seed: String::new(), timestamp: 0, entropy: None, shuffle_version: None },
+                players: vec![
+                    player(0, "BTN", 200 * E6, Some(vec![card(Rank::Ace, Suit::Spades), card(Rank::Queen, Suit::Spades)]),
+                        Some(HandRank::Pair(14, vec![13, 12, 9]))),
+                    player(1, "SB", 100 * E6, None, None),
+                    player(2, "BB", 150 * E6, Some(vec![card(Rank::Jack, Suit::Clubs), card(Rank::Ten, Suit::Clubs)]),
+                        Some(HandRank::HighCard(vec![14, 13, 11, 10, 9]))),
+                ],
+                dealer_seat: 0,
+                flop: Some((card(Rank::Ace, Suit::Hearts), card(Rank::King, Suit::Diamonds), card(Rank::Two, Suit::Clubs))),
+                turn: Some(card(Rank::Five, Suit::Spades)),
+                river: Some(card(Rank::Nine, Suit::Hearts)),
+                actions: vec![
+                    act(0, PlayerAction::Raise(6 * E6), "preflop"),
+                    act(1, PlayerAction::Fold, "preflop"),
+                    act(2, PlayerAction::Call(4 * E6), "preflop"),
+                    act(2, PlayerAction::Check, "flop"),
+                    act(0, PlayerAction::Bet(10 * E6), "flop"),
+                    act(2, PlayerAction::Call(10 * E6), "flop"),
+                    act(2, PlayerAction::Check, "turn"),
+                    act(0, PlayerAction::Check, "turn"),
+                    act(2, PlayerAction::Check, "river"),
+                    act(0, PlayerAction::Check, "river"),
+                ],
+                total_pot: 33 * E6,
+                rake: E6,
+                winners: vec![WinnerRecord {
+                    seat: 0,
+                    principal: Principal::from_slice(&[1]),
+                    amount: 32 * E6,
+                    hand_rank: Some(HandRank::Pair(14, vec![13, 12, 9])),
+                    pot_type: "main".to_string(),
+                    run: None,
+                }],
+                went_to_showdown: true,
+                game_variant: Some(GameVariant::TexasHoldem),
+                is_tournament: Some(false),
+                second_board: None,
+                currency: Some(Currency::ICP),
+                dealt_seats: Some(vec![0, 1, 2]),
+            }
+        }
+
+        #[test]
+        fn test_stored_records_leave_out_candid_header() {
+            use ic_stable_structures::Storable;
+            use std::borrow::Cow;
+
+            let hand = sample_hand();
+            let full = candid::encode_one(&hand).unwrap();
+            let stored = hand.to_bytes().into_owned();
+            assert!(stored.len() < full.len());
+            // The header is kept once per schema, not per record
+            assert_eq!(stored, hand.to_bytes().into_owned());
+            let read = HandHistoryRecord::from_bytes(Cow::Owned(stored));
+            assert_eq!(candid::encode_one(&read).unwrap(), full);
+
+            // Records written with the full Candid message still read back
+            let old = HandHistoryRecord::from_bytes(Cow::Owned(full.clone()));
+            assert_eq!(candid::encode_one(&old).unwrap(), full);
+        }
+
+        #[test]
+        fn test_pokerstars_amount_format() {
+            assert_eq!(format_amount(150_000_000, Currency::ICP), "1.50");
+            assert_eq!(format_amount(100_000_000, Currency::ICP), "1.00");
+            assert_eq!(format_amount(0, Currency::ICP), "0.00");
+            assert_eq!(format_amount(50_000, Currency::BTC), "0.0005");
+            assert_eq!(format_amount(1, Currency::BTC), "0.00000001");
+        }
+
+        #[test]
+        fn test_pokerstars_timestamp_is_utc_civil_date() {
+            assert_eq!(format_timestamp(0), "1970/01/01 00:00:00");
+            assert_eq!(format_timestamp(1_709_251_199 * 1_000_000_000), "2024/02/29 23:59:59");
+        }
+
+        #[test]
+        fn test_pokerstars_cash_hand() {
+            let text = format_hand(&sample_hand());
+            let lines: Vec<&str> = text.lines().collect();
+
+            assert_eq!(lines[0], "PokerStars Hand #42: Hold'em No Limit (0.01/0.02 ICP) - 2026/10/17 12:00:00 UTC");
+            assert!(lines[1].ends_with("' 6-max Seat #1 is the button"));
+            assert_eq!(lines[2], format!("Seat 1: {} (2.00 in chips)", name(0)));
+            assert!(lines.contains(&format!("{}: posts small blind 0.01", name(1)).as_str()));
+            assert!(lines.contains(&format!("{}: posts big blind 0.02", name(2)).as_str()));
+            assert!(lines.contains(&format!("{}: raises 0.04 to 0.06", name(0)).as_str()));
+            assert!(lines.contains(&format!("{}: calls 0.04", name(2)).as_str()));
+            assert!(lines.contains(&"*** FLOP *** [Ah Kd 2c]"));
+            assert!(lines.contains(&"*** TURN *** [Ah Kd 2c] [5s]"));
+            assert!(lines.contains(&"*** RIVER *** [Ah Kd 2c 5s] [9h]"));
+            assert!(lines.contains(&format!("{}: shows [As Qs] (a pair of Aces)", name(0)).as_str()));
+            assert!(lines.contains(&format!("{} collected 0.32 from pot", name(0)).as_str()));
+            assert!(lines.contains(&"Total pot 0.33 | Rake 0.01"));
+            assert!(lines.contains(&"Board [Ah Kd 2c 5s 9h]"));
+            assert!(lines.contains(&format!("Seat 1: {} (button) showed [As Qs] and won (0.32) with a pair of Aces", name(0)).as_str()));
+            assert!(lines.contains(&format!("Seat 2: {} (small blind) folded before Flop", name(1)).as_str()));
+            assert!(lines.contains(&format!("Seat 3: {} (big blind) showed [Jc Tc] and lost with high card Ace", name(2)).as_str()));
+
+            // Streets come in order
+            let pos = |needle: &str| text.find(needle).unwrap();
+            assert!(pos("*** HOLE CARDS ***") < pos("*** FLOP ***"));
+            assert!(pos("*** RIVER ***") < pos("*** SHOW DOWN ***"));
+            assert!(pos("*** SHOW DOWN ***") < pos("*** SUMMARY ***"));
+        }
+
+        #[test]
+        fn test_pokerstars_heads_up_tournament_all_in() {
+            let mut hand = sample_hand();
+            hand.is_tournament = Some(true);
+            hand.small_blind = 10;
+            hand.big_blind = 20;
+            hand.ante = 5;
+            hand.players = vec![player(0, "BTN", 500, None, None), player(1, "BB", 300, None, None)];
+            hand.flop = None;
+            hand.turn = None;
+            hand.river = None;
+            hand.actions = vec![
+                act(0, PlayerAction::Raise(60), "preflop"),
+                act(1, PlayerAction::AllIn(295), "preflop"),
+                act(0, PlayerAction::Fold, "preflop"),
+            ];
+            hand.total_pot = 90;
+            hand.rake = 0;
+            hand.winners[0].seat = 1;
+            hand.winners[0].amount = 90;
+            hand.went_to_showdown = false;
+
+            let text = format_hand(&hand);
+            let lines: Vec<&str> = text.lines().collect();
+
+            // Chips, no currency; heads-up the button posts the small blind
+            assert!(lines[0].contains("Hold'em No Limit (10/20) - "));
+            assert!(lines.contains(&format!("{}: posts the ante 5", name(0)).as_str()));
+            assert!(lines.contains(&format!("{}: posts small blind 10", name(0)).as_str()));
+            assert!(lines.contains(&format!("{}: raises 40 to 60", name(0)).as_str()));
+            assert!(lines.contains(&format!("{}: raises 235 to 295 and is all-in", name(1)).as_str()));
+            assert!(lines.contains(&format!("{} collected 90 from pot", name(1)).as_str()));
+            assert!(!text.contains("SHOW DOWN"));
+            assert!(!text.contains("Board ["));
+            assert!(lines.contains(&format!("Seat 1: {} (button) folded before Flop", name(0)).as_str()));
+            assert!(lines.contains(&format!("Seat 2: {} (big blind) collected (90)", name(1)).as_str()));
+        }
+
+        #[test]
+        fn test_ohh_cash_hand() {
+            let doc: Value = serde_json::from_str(&ohh::format_hand(&sample_hand())).unwrap();
+            let hand = &doc["ohh"];
+
+            assert_eq!(hand["spec_version"], ohh::OHH_SPEC_VERSION);
+            assert_eq!(hand["game_number"], "42");
+            assert_eq!(hand["start_date_utc"], "2026-10-17T12:00:00Z");
+            assert_eq!(hand["game_type"], "Holdem");
+            assert_eq!(hand["bet_limit"]["bet_type"], "NL");
+            assert_eq!(hand["currency"], "ICP");
+            assert_eq!(hand["tournament"], false);
+            assert_eq!(hand["dealer_seat"], 1);
+            assert_eq!(hand["small_blind_amount"], 0.01);
+            assert_eq!(hand["players"][2]["name"], name(2));
+            assert_eq!(hand["players"][2]["starting_stack"], 1.5);
+
+            // Blinds open preflop; action amounts are what each action put in
+            let preflop = &hand["rounds"][0];
+            assert_eq!(preflop["street"], "Preflop");
+            let actions: Vec<(&str, f64)> = preflop["actions"].as_array().unwrap().iter()
+                .map(|a| (a["action"].as_str().unwrap(), a["amount"].as_f64().unwrap()))
+                .collect();
+            assert_eq!(actions, vec![("Post SB", 0.01), ("Post BB", 0.02), ("Raise", 0.06), ("Fold", 0.0), ("Call", 0.04)]);
+            assert_eq!(preflop["actions"][4]["action_number"], 5);
+
+            assert_eq!(hand["rounds"][1]["street"], "Flop");
+            assert_eq!(hand["rounds"][1]["cards"], serde_json::json!(["Ah", "Kd", "2c"]));
+            assert_eq!(hand["rounds"][3]["cards"], serde_json::json!(["9h"]));
+            let showdown = &hand["rounds"][4];
+            assert_eq!(showdown["street"], "Showdown");
+            assert_eq!(showdown["actions"][0]["action"], "Shows Cards");
+            assert_eq!(showdown["actions"][0]["cards"], serde_json::json!(["As", "Qs"]));
+
+            assert_eq!(hand["pots"][0]["amount"], 0.33);
+            assert_eq!(hand["pots"][0]["rake"], 0.01);
+            assert_eq!(hand["pots"][0]["player_wins"][0]["player_id"], 0);
+            assert_eq!(hand["pots"][0]["player_wins"][0]["win_amount"], 0.32);
+
+            // Shuffle proof rides along as a site extension
+            assert_eq!(hand["cleardeck"]["hand_number"], 7);
+            assert_eq!(hand["cleardeck"]["pot_types"], serde_json::json!(["main"]));
+            assert!(hand["cleardeck"]["shuffle_proof"]["seed_hash"].is_string());
+        }
+
+        #[test]
+        fn test_ohh_hi_lo_pots_and_all_in() {
+            let mut hand = sample_hand();
+            hand.game_variant = Some(GameVariant::OmahaHiLo);
+            hand.is_tournament = Some(true);
+            hand.small_blind = 10;
+            hand.big_blind = 20;
+            hand.rake = 0;
+            hand.actions = vec![
+                act(0, PlayerAction::Raise(60), "preflop"),
+                act(1, PlayerAction::Fold, "preflop"),
+                act(2, PlayerAction::AllIn(150), "preflop"),
+                act(0, PlayerAction::Call(90), "preflop"),
+            ];
+            hand.winners = vec![
+                WinnerRecord { seat: 0, principal: Principal::from_slice(&[1]), amount: 155, hand_rank: None, pot_type: "high".to_string(), run: None },
+                WinnerRecord { seat: 2, principal: Principal::from_slice(&[3]), amount: 155, hand_rank: None, pot_type: "low".to_string(), run: None },
+            ];
+            hand.total_pot = 310;
+
+            let doc: Value = serde_json::from_str(&ohh::format_hand(&hand)).unwrap();
+            let hand = &doc["ohh"];
+
+            assert_eq!(hand["game_type"], "OmahaHiLo");
+            assert_eq!(hand["bet_limit"]["bet_type"], "PL");
+            assert_eq!(hand["tournament"], true);
+            assert_eq!(hand["big_blind_amount"], 20.0);
+
+            let all_in = &hand["rounds"][0]["actions"][4];
+            assert_eq!(all_in["action"], "Raise");
+            assert_eq!(all_in["amount"], 130.0); // 150 total, 20 already in as the big blind
+            assert_eq!(all_in["is_allin"], true);
+
+            // Each half of a Hi-Lo pot is its own OHH pot
+            assert_eq!(hand["pots"].as_array().unwrap().len(), 2);
+            assert_eq!(hand["pots"][1]["player_wins"][0]["player_id"], 2);
+            assert_eq!(hand["cleardeck"]["pot_types"], serde_json::json!(["high", "low"]));
+        }
+
+        #[test]
+        fn test_advanced_stats_single_raised_pot() {
+            let mut hand = sample_hand();
+            hand.players[0].amount_won = 32 * E6;
+            let counters = stats::hand_counters(&hand);
+
+            // Button opened, c-bet the flop and won at showdown
+            let btn = &counters[&0];
+            assert_eq!((btn.vpip, btn.pfr, btn.three_bet_opportunities), (1, 1, 0));
+            assert_eq!((btn.cbet_opportunities, btn.cbets), (1, 1));
+            assert_eq!((btn.postflop_bets_raises, btn.postflop_calls), (1, 0));
+            assert_eq!((btn.saw_flop, btn.went_to_showdown, btn.won_at_showdown), (1, 1, 1));
+
+            // Small blind passed on a 3-bet and never saw the flop
+            let sb = &counters[&1];
+            assert_eq!((sb.vpip, sb.pfr, sb.three_bet_opportunities, sb.three_bets), (0, 0, 1, 0));
+            assert_eq!(sb.saw_flop, 0);
+
+            // Big blind called twice and lost at showdown
+            let bb = &counters[&2];
+            assert_eq!((bb.vpip, bb.pfr, bb.three_bet_opportunities), (1, 0, 1));
+            assert_eq!((bb.postflop_bets_raises, bb.postflop_calls), (0, 1));
+            assert_eq!((bb.saw_flop, bb.went_to_showdown, bb.won_at_showdown), (1, 1, 0));
+        }
+
+        #[test]
+        fn test_advanced_stats_three_bet_and_fold() {
+            let mut hand = sample_hand();
+            hand.flop = None;
+            hand.turn = None;
+            hand.river = None;
+            hand.went_to_showdown = false;
+            hand.actions = vec![
+                act(0, PlayerAction::Raise(6 * E6), "preflop"),
+                act(1, PlayerAction::Raise(18 * E6), "preflop"),
+                act(2, PlayerAction::Fold, "preflop"),
+                act(0, PlayerAction::Fold, "preflop"),
+            ];
+            let counters = stats::hand_counters(&hand);
+
+            assert_eq!((counters[&0].fold_to_three_bet_opportunities, counters[&0].folds_to_three_bet), (1, 1));
+            assert_eq!((counters[&1].three_bet_opportunities, counters[&1].three_bets, counters[&1].pfr), (1, 1, 1));
+            // Facing a 3-bet is not a 3-bet opportunity
+            assert_eq!(counters[&2].three_bet_opportunities, 0);
+            assert!(counters.values().all(|c| c.saw_flop == 0 && c.cbet_opportunities == 0));
+        }
+
+        #[test]
+        fn test_advanced_stats_totals_by_position_and_stakes() {
+            let hand = sample_hand();
+            assert_eq!(stats::stakes_label(&hand), "0.01/0.02 ICP");
+            let counters = stats::hand_counters(&hand);
+
+            let mut advanced = AdvancedStats::default();
+            stats::record(&mut advanced, "BTN", "0.01/0.02 ICP", &counters[&0]);
+            stats::record(&mut advanced, "BB", "0.01/0.02 ICP", &counters[&2]);
+            stats::record(&mut advanced, "BTN", "Tournament", &counters[&0]);
+
+            assert_eq!(advanced.overall.hands, 3);
+            assert_eq!(advanced.by_position.len(), 2);
+            assert_eq!(advanced.by_position[0].1.hands, 2);
+            assert_eq!(advanced.by_stakes.iter().map(|(l, _)| l.as_str()).collect::<Vec<_>>(), vec!["0.01/0.02 ICP", "Tournament"]);
+
+            // 2 of 3 hands raised preflop; 2 bets per 1 call postflop
+            let line = stats::stat_line("All", &advanced.overall);
+            assert_eq!(line.vpip_bps, 10_000);
+            assert_eq!(line.pfr_bps, 6_666);
+            assert_eq!(line.aggression_factor_x100, Some(200));
+            assert_eq!(line.wtsd_bps, 10_000);
+            assert_eq!(line.wsd_bps, 0); // sample_hand leaves amount_won at 0
+            assert_eq!(stats::stat_line("BTN", &advanced.by_position[0].1).aggression_factor_x100, None);
+        }
+
+        #[test]
+        fn test_bb_per_100_across_stakes() {
+            // +0.30 ICP at 0.01/0.02 is 15 big blinds; -1 chip at a 1-chip big blind is one
+            assert_eq!(stats::milli_big_blinds(30_000_000, 2 * E6), 15_000);
+            assert_eq!(stats::milli_big_blinds(-1, 1), -1_000);
+            assert_eq!(stats::milli_big_blinds(100, 0), 0);
+
+            // 14 big blinds over 10 hands is 140 bb/100
+            assert_eq!(stats::bb_per_100_x100(15_000 - 1_000, 10), 14_000);
+            assert_eq!(stats::bb_per_100_x100(-500, 200), -25); // -0.25 bb/100
+            assert_eq!(stats::bb_per_100_x100(1_000, 0), 0);
+        }
+
+        #[test]
+        fn test_session_ends_after_a_break() {
+            const MINUTE: u64 = 60 * 1_000_000_000;
+            let session = Session {
+                table_id: Principal::from_slice(&[9]),
+                currency: Currency::ICP,
+                started_at: 100 * MINUTE,
+                ended_at: 160 * MINUTE,
+                hands: 40,
+                profit: 0,
+                mbb: 0,
+            };
+            assert!(stats::continues_session(&session, 189 * MINUTE));
+            assert!(!stats::continues_session(&session, 191 * MINUTE));
+            assert!(!stats::continues_session(&session, 99 * MINUTE));
+        }
+
+        #[test]
+        fn test_profit_series_per_table_and_currency() {
+            let player = Principal::from_slice(&[1]);
+            let (table_a, table_b, table_c) = (Principal::from_slice(&[10]), Principal::from_slice(&[11]), Principal::from_slice(&[12]));
+            let day = |currency, hands, profit| DailyResult { currency, hands, profit, mbb: profit * 10 };
+            // Key order: player, day, table
+            let days = vec![
+                ((player, 20_000, table_a), day(Currency::ICP, 10, 100)),
+                ((player, 20_000, table_b), day(Currency::ICP, 5, -50)),
+                ((player, 20_001, table_a), day(Currency::ICP, 10, 20)),
+                ((player, 20_001, table_c), day(Currency::BTC, 4, 8)),
+            ];
+            let series = stats::profit_series(&days);
+            assert_eq!(series.len(), 5); // 3 tables, then ICP and BTC
+
+            let a = &series[0];
+            assert_eq!(a.table_id, Some(table_a));
+            assert_eq!((a.hands, a.profit), (20, 120));
+            assert_eq!(a.points.iter().map(|p| p.cumulative_profit).collect::<Vec<_>>(), vec![100, 120]);
+            assert_eq!(a.points[1].day_start, 20_001 * 86_400 * 1_000_000_000);
+
+            // Both ICP tables share a point on the first day
+            let icp = &series[3];
+            assert_eq!((icp.table_id, icp.currency), (None, Currency::ICP));
+            assert_eq!(icp.points.len(), 2);
+            assert_eq!((icp.points[0].hands, icp.points[0].profit), (15, 50));
+            assert_eq!((icp.points[1].cumulative_hands, icp.points[1].cumulative_profit), (25, 70));
+            assert_eq!(icp.bb_per_100_x100, stats::bb_per_100_x100(700, 25));
+
+            let btc = &series[4];
+            assert_eq!((btc.currency, btc.hands, btc.profit), (Currency::BTC, 4, 8));
+        }
+
+        /// Every match for a filter, newest first, with the seek count it took
+        fn search_all(hands: &[HandHistoryRecord], filter: &HandSearchFilter) -> (Vec<u64>, u32) {
+            let mut index = StableBTreeMap::new(DefaultMemoryImpl::default());
+            for hand in hands {
+                for key in search::hand_keys(hand) {
+                    index.insert(key, ());
+                }
+            }
+            let streams = search::streams(filter).unwrap();
+            let mut at = (u64::MAX, u64::MAX);
+            let mut budget = 1_000;
+            let mut found = Vec::new();
+            while let search::Step::Match(position) = search::next_match(&index, &streams, 0, at, &mut budget) {
+                found.push(position.1);
+                match search::previous(position) {
+                    Some(previous) => at = previous,
+                    None => break,
+                }
+            }
+            (found, 1_000 - budget)
+        }
+
+        #[test]
+        fn test_rank_category_order() {
+            assert_eq!(search::rank_category(&HandRank::Pair(14, vec![13, 12, 9])), HandRankCategory::Pair);
+            assert_eq!(search::rank_category(&HandRank::RoyalFlush), HandRankCategory::RoyalFlush);
+            assert!(HandRankCategory::FourOfAKind > HandRankCategory::FullHouse);
+            assert!(HandRankCategory::StraightFlush > HandRankCategory::FourOfAKind);
+        }
+
+        #[test]
+        fn test_search_index_filters() {
+            // Hand n at time n: quads in hand 3, BTC in hand 5, no showdown in hand 4
+            let mut hands: Vec<HandHistoryRecord> = (1..=6).map(|n| {
+                let mut hand = sample_hand();
+                hand.hand_id = n;
+                hand.timestamp = n * 1_000;
+                hand
+            }).collect();
+            hands[2].winners[0].hand_rank = Some(HandRank::FourOfAKind(9, 14));
+            hands[3].went_to_showdown = false;
+            hands[4].currency = Some(Currency::BTC);
+            hands[5].players.remove(1);
+            hands[0].total_pot = 0;
+            hands[1].total_pot = 500 * E6;
+
+            let all = |filter: HandSearchFilter| search_all(&hands, &filter).0;
+            assert_eq!(all(HandSearchFilter::default()), vec![6, 5, 4, 3, 2, 1]);
+            assert_eq!(all(HandSearchFilter { min_winning_rank: Some(HandRankCategory::FourOfAKind), ..Default::default() }), vec![3]);
+            assert_eq!(all(HandSearchFilter { showdown_only: true, ..Default::default() }), vec![6, 5, 3, 2, 1]);
+            assert_eq!(all(HandSearchFilter { currency: Some(Currency::BTC), ..Default::default() }), vec![5]);
+
+            // Pots by log2 bucket - the exact size is checked per match by search_hands
+            assert_eq!((search::pot_bucket(0), search::pot_bucket(1), search::pot_bucket(3), search::pot_bucket(4)), (0, 1, 2, 3));
+            assert_eq!(all(HandSearchFilter { min_pot: Some(100 * E6), ..Default::default() }), vec![2]);
+            assert_eq!(all(HandSearchFilter { max_pot: Some(0), ..Default::default() }), vec![1]);
+            assert_eq!(all(HandSearchFilter { min_pot: Some(E6), max_pot: Some(100 * E6), ..Default::default() }), vec![6, 5, 4, 3]);
+
+            // Same blinds in both currencies unless one is picked
+            let stakes = Some(Stakes { small_blind: E6, big_blind: 2 * E6 });
+            assert_eq!(all(HandSearchFilter { stakes: stakes.clone(), ..Default::default() }).len(), 6);
+            assert_eq!(all(HandSearchFilter { stakes, currency: Some(Currency::ICP), ..Default::default() }), vec![6, 4, 3, 2, 1]);
+
+            // Seat 1 sat out hand 6; seat 9 never played
+            let (seat_0, seat_1, nobody) = (Principal::from_slice(&[1]), Principal::from_slice(&[2]), Principal::from_slice(&[10]));
+            let players = |players: Vec<Principal>, players_match| HandSearchFilter {
+                players, players_match: Some(players_match), ..Default::default()
+            };
+            assert_eq!(all(players(vec![seat_0, seat_1], PlayerMatch::All)), vec![5, 4, 3, 2, 1]);
+            assert_eq!(all(players(vec![seat_1, nobody], PlayerMatch::Any)), vec![5, 4, 3, 2, 1]);
+            assert!(all(players(vec![seat_0, nobody], PlayerMatch::All)).is_empty());
+            assert_eq!(all(HandSearchFilter { position: Some("BTN".to_string()), ..players(vec![seat_0], PlayerMatch::Any) }).len(), 6);
+            assert!(all(HandSearchFilter { position: Some("BB".to_string()), ..players(vec![seat_0], PlayerMatch::Any) }).is_empty());
+            assert!(search::streams(&HandSearchFilter { position: Some("BTN".to_string()), ..Default::default() }).is_err());
+
+            // Filters combine
+            let filter = HandSearchFilter {
+                showdown_only: true,
+                currency: Some(Currency::ICP),
+                ..players(vec![seat_1], PlayerMatch::Any)
+            };
+            assert_eq!(all(filter), vec![3, 2, 1]);
+        }
+
+        #[test]
+        fn test_time_positions_walk_every_hand_oldest_first() {
+            let mut index = StableBTreeMap::new(DefaultMemoryImpl::default());
+            for n in 1..=5u64 {
+                let mut hand = sample_hand();
+                hand.hand_id = n;
+                hand.timestamp = n * 1_000;
+                for key in search::hand_keys(&hand) {
+                    index.insert(key, ());
+                }
+            }
+            let first = search::time_positions(&index, None, 2);
+            assert_eq!(first, vec![(1_000, 1), (2_000, 2)]);
+            assert_eq!(search::time_positions(&index, first.last().copied(), 10), vec![(3_000, 3), (4_000, 4), (5_000, 5)]);
+            assert!(search::time_positions(&index, Some((5_000, 5)), 10).is_empty());
+        }
+
+        #[test]
+        fn test_search_seeks_past_non_matches() {
+            // One quads hand among 500 - found without visiting the others
+            let hands: Vec<HandHistoryRecord> = (1..=500).map(|n| {
+                let mut hand = sample_hand();
+                hand.hand_id = n;
+                hand.timestamp = n;
+                if n == 250 {
+                    hand.winners[0].hand_rank = Some(HandRank::FourOfAKind(9, 14));
+                }
+                hand
+            }).collect();
+            let filter = HandSearchFilter {
+                showdown_only: true,
+                min_winning_rank: Some(HandRankCategory::FourOfAKind),
+                ..Default::default()
+            };
+            let (found, seeks) = search_all(&hands, &filter);
+            assert_eq!(found, vec![250]);
+            assert!(seeks < 10, "took {} seeks", seeks);
+        }
+
+        const SEED: [u8; 32] = [0x42; 32];
+
+        fn hex_text(bytes: &[u8]) -> String {
+            bytes.iter().map(|b| format!("{:02x}", b)).collect()
+        }
+
+        /// sample_hand dealt from SEED: hole cards in seat order, then burn + flop, burn + turn, burn + river
+        fn replayed_hand() -> (HandHistoryRecord, Vec<Card>) {
+            dealt_from(&SEED)
+        }
+
+        fn dealt_from(seed: &[u8]) -> (HandHistoryRecord, Vec<Card>) {
+            dealt_with(seed, None)
+        }
+
+        fn dealt_with(seed: &[u8], shuffle_version: Option<u32>) -> (HandHistoryRecord, Vec<Card>) {
+            let mut deck = replay::create_deck(GameVariant::TexasHoldem);
+            replay::shuffle_deck(&mut deck, seed, shuffle_version).unwrap();
+            let mut hand = sample_hand();
+            hand.shuffle_proof.shuffle_version = shuffle_version;
+            hand.shuffle_proof.revealed_seed = hex_text(seed);
+            hand.shuffle_proof.seed_hash = cleardeck_shuffle::seed_hash(seed);
+            hand.players[0].all_hole_cards = Some(deck[0..2].to_vec());
+            hand.players[2].all_hole_cards = Some(deck[4..6].to_vec());
+            hand.flop = Some((deck[7], deck[8], deck[9]));
+            hand.turn = Some(deck[11]);
+            hand.river = Some(deck[13]);
+            (hand, deck)
+        }
+
+        #[test]
+        fn test_replay_matches_table_shuffle() {
+            // Worked out independently from the table's create_deck + shuffle_deck
+            let mut deck = replay::create_deck(GameVariant::TexasHoldem);
+            assert_eq!(deck.len(), 52);
+            replay::shuffle_deck(&mut deck, &SEED, None).unwrap();
+            assert_eq!(deck[..4].to_vec(), vec![
+                card(Rank::Three, Suit::Spades), card(Rank::Four, Suit::Spades),
+                card(Rank::Seven, Suit::Clubs), card(Rank::Seven, Suit::Diamonds),
+            ]);
+            assert_eq!(replay::create_deck(GameVariant::ShortDeckHoldem).len(), 36);
+        }
+
+        #[test]
+        fn test_verify_deal_accepts_replayed_hand() {
+            let (hand, _) = replayed_hand();
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(report.valid, "{:?}", report.mismatches);
+            assert!(report.seed_matches_hash);
+            assert_eq!((report.hole_cards_checked, report.board_cards_checked), (4, 5));
+            assert!(!report.dealt_seats_inferred);
+        }
+
+        #[test]
+        fn test_verify_deal_shuffle_versions() {
+            let (hand, _) = replayed_hand();
+            assert_eq!(replay::verify_deal(&hand).unwrap().shuffle_version, cleardeck_shuffle::SHUFFLE_V1);
+
+            let (hand, _) = dealt_with(&SEED, Some(cleardeck_shuffle::SHUFFLE_V2));
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(report.valid, "{:?}", report.mismatches);
+            assert_eq!(report.shuffle_version, cleardeck_shuffle::SHUFFLE_V2);
+
+            let (mut hand, _) = replayed_hand();
+            hand.shuffle_proof.shuffle_version = Some(9);
+            assert_eq!(replay::verify_deal(&hand).unwrap_err(), "Unknown shuffle version 9");
+        }
+
+        #[test]
+        fn test_verify_deal_reports_mismatches() {
+            let (mut hand, deck) = replayed_hand();
+            hand.river = Some(deck[12]); // The burn card
+            hand.players[2].all_hole_cards = Some(vec![deck[5], deck[4]]);
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(!report.valid);
+            assert!(report.seed_matches_hash);
+            assert_eq!(report.mismatches.len(), 3);
+            assert_eq!(report.mismatches[0].location, "Seat 2 hole card 1");
+            assert_eq!(report.mismatches[2], DealMismatch {
+                location: "River".to_string(),
+                deck_position: Some(13),
+                expected: Some(deck[13]),
+                recorded: deck[12],
+            });
+
+            // A different seed than the one committed to
+            let (mut hand, _) = replayed_hand();
+            hand.shuffle_proof.seed_hash = "00".repeat(32);
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(!report.valid && !report.seed_matches_hash && report.mismatches.is_empty());
+        }
+
+        #[test]
+        fn test_verify_deal_with_player_entropy() {
+            // Seat 0 revealed, seat 2 let the reveal window close
+            let (vrf, nonce_0, nonce_2) = ([7u8; 32], [1u8; 32], [2u8; 32]);
+            let seed = cleardeck_shuffle::mix_entropy(&vrf, &[&nonce_0]);
+            let contribution = |seat: u8, nonce: &[u8], revealed: bool| EntropyContributionRecord {
+                seat,
+                principal: Principal::from_slice(&[seat + 1]),
+                commitment: cleardeck_shuffle::seed_hash(nonce),
+                nonce: revealed.then(|| hex_text(nonce)),
+            };
+            let (mut hand, _) = dealt_from(&seed);
+            hand.shuffle_proof.entropy = Some(PlayerEntropyRecord {
+                vrf_seed_hash: cleardeck_shuffle::seed_hash(&vrf),
+                vrf_seed: hex_text(&vrf),
+                contributions: vec![contribution(0, &nonce_0, true), contribution(2, &nonce_2, false)],
+            });
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(report.valid, "{:?}", report);
+            assert_eq!(report.entropy_matches, Some(true));
+
+            // Claiming seat 2's nonce was mixed in too
+            let mut forged = hand.clone();
+            if let Some(ref mut entropy) = forged.shuffle_proof.entropy {
+                entropy.contributions[1].nonce = Some(hex_text(&nonce_2));
+            }
+            let report = replay::verify_deal(&forged).unwrap();
+            assert!(!report.valid && report.seed_matches_hash);
+            assert_eq!(report.entropy_matches, Some(false));
+
+            // Hands without player entropy don't report on it
+            assert_eq!(replay::verify_deal(&replayed_hand().0).unwrap().entropy_matches, None);
+        }
+
+        #[test]
+        fn test_fairness_tally() {
+            let mut tally = FairnessTally::default();
+            fairness::tally(&mut tally, &replayed_hand().0);
+            assert_eq!((tally.hands, tally.unverified_hands), (1, 0));
+            assert_eq!(tally.positions.iter().sum::<u64>(), 52);
+            assert_eq!(tally.hole_pairs.iter().sum::<u64>(), 3);
+            assert_eq!(tally.board_suits.iter().sum::<u64>(), 5);
+
+            // A record that doesn't replay moves nothing but the unverified count
+            let (mut forged, deck) = replayed_hand();
+            forged.river = Some(deck[12]);
+            fairness::tally(&mut tally, &forged);
+            assert_eq!((tally.hands, tally.unverified_hands), (1, 1));
+            assert_eq!(tally.positions.iter().sum::<u64>(), 52);
+
+            // Deck starts 3s 4s 7c 7d 4h 6s - seats 0, 1, 2 were dealt 43s, 77, 64o
+            let report = fairness::deck_report(52, &tally);
+            assert_eq!(report.cards[40], card(Rank::Three, Suit::Spades));
+            assert_eq!(report.position_counts[0][40], 1);
+            assert_eq!(report.hole_pairs.len(), 169);
+            let count = |hand: &str| report.hole_pairs.iter().find(|p| p.hand == hand).map(|p| (p.count, p.expected_x1000));
+            assert_eq!(count("43s"), Some((1, 9))); // 3 hands dealt * 4 / 1326 combos
+            assert_eq!(count("77"), Some((1, 14)));
+            assert_eq!(count("64o"), Some((1, 27)));
+            assert_eq!(count("AKo"), Some((0, 27)));
+            assert_eq!(report.positions.degrees_of_freedom, 51 * 51);
+            assert_eq!(report.board_suits_fit.degrees_of_freedom, 3);
+        }
+
+        #[test]
+        fn test_fairness_of_a_fair_shuffle() {
+            let mut tally = FairnessTally::default();
+            for i in 0..500u32 {
+                let seed = cleardeck_shuffle::mix_entropy(&i.to_le_bytes(), &[]);
+                fairness::tally(&mut tally, &dealt_with(&seed, Some(cleardeck_shuffle::SHUFFLE_V2)).0);
+            }
+            let report = fairness::deck_report(52, &tally);
+            assert_eq!(report.hands, 500);
+            // Nowhere near the 1% a rigged deck would soon fall under
+            for fit in [&report.positions, &report.hole_pairs_fit, &report.board_suits_fit] {
+                assert!(fit.p_value_bps > 100, "{:?}", fit);
+            }
+
+            // Stacking the deck: every card back where it started
+            let mut stacked = tally.clone();
+            for position in 0..52 {
+                stacked.positions[position * 52 + position] += 500;
+            }
+            stacked.hands += 500;
+            assert_eq!(fairness::deck_report(52, &stacked).positions.p_value_bps, 0);
+        }
+
+        #[test]
+        fn test_chi_square() {
+            let even = fairness::chi_square(&[10, 10, 10, 10], &[10.0; 4], 3);
+            assert_eq!((even.statistic_x1000, even.p_value_bps), (0, 10_000));
+            let skewed = fairness::chi_square(&[40, 0, 0, 0], &[10.0; 4], 3);
+            assert_eq!((skewed.statistic_x1000, skewed.p_value_bps), (120_000, 0));
+
+            // Table values: the 5% critical point for 10 and 100 degrees of freedom
+            assert!((fairness::p_value(18.307, 10) - 0.05).abs() < 0.002);
+            assert!((fairness::p_value(124.342, 100) - 0.05).abs() < 0.001);
+            assert_eq!(fairness::p_value(0.0, 10), 1.0);
+
+            // Nothing dealt yet
+            let empty = fairness::deck_report(36, &FairnessTally::default());
+            assert_eq!((empty.position_counts.len(), empty.hole_pairs.len()), (36, 81));
+            assert_eq!(empty.positions.p_value_bps, 10_000);
+        }
+
+        #[test]
+        fn test_verify_deal_infers_seats_on_older_records() {
+            // A player sitting out was on record but not dealt in
+            let (mut hand, _) = replayed_hand();
+            hand.dealt_seats = None;
+            hand.players.push(player(4, "Seat 4", 50 * E6, None, None));
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(report.valid, "{:?}", report.mismatches);
+            assert!(report.dealt_seats_inferred);
+            assert_eq!(report.dealt_seats, vec![0, 1, 2]);
+        }
+
+        #[test]
+        fn test_verify_deal_second_board() {
+            // All-in on the flop and run twice: the second turn and river follow the first river
+            let (mut hand, deck) = replayed_hand();
+            let (a, b, c) = hand.flop.unwrap();
+            hand.second_board = Some(vec![a, b, c, deck[15], deck[17]]);
+            let report = replay::verify_deal(&hand).unwrap();
+            assert!(report.valid, "{:?}", report.mismatches);
+            assert_eq!(report.board_cards_checked, 7);
+
+            hand.second_board = Some(vec![a, b, c, deck[14], deck[17]]);
+            let report = replay::verify_deal(&hand).unwrap();
+            assert_eq!(report.mismatches.len(), 1);
+            assert_eq!(report.mismatches[0].location, "Second board turn");
+            assert_eq!(report.mismatches[0].deck_position, Some(15));
+        }
+    }
 }
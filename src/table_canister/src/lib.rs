@@ -12,10 +12,15 @@
 // ============================================================================
 
 use candid::{CandidType, Deserialize, Principal, Nat};
+use cleardeck_payouts::tournament_payouts;
 use ic_cdk::management_canister::raw_rand;
 use sha2::{Sha224, Sha256, Digest};
 use std::cell::RefCell;
 use std::collections::HashMap;
+use std::future::Future;
+use std::thread::LocalKey;
+use std::time::Duration;
+use ic_cdk_timers::TimerId;
 use icrc_ledger_types::icrc1::account::Account;
 use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
 use icrc_ledger_types::icrc2::transfer_from::{TransferFromArgs, TransferFromError};
@@ -30,6 +35,11 @@ const DEFAULT_TIME_BANK_SECS: u64 = 30;
 const AUTO_DEAL_DELAY_NS: u64 = 3_000_000_000;
 const RELOAD_TIMEOUT_SECS: u64 = 60;
 const SITTING_OUT_KICK_SECS: u64 = 120; // Auto-kick sitting out players after 2 minutes
+const TIMER_SLACK_NS: u64 = 1_000_000; // Fire canister timers 1ms after their deadline
+const AUTO_DEAL_RETRY_MAX_NS: u64 = 300_000_000_000; // Failed auto-deals back off from AUTO_DEAL_DELAY_NS up to 5 minutes
+const FIXED_LIMIT_MAX_RAISES: u64 = 3; // Fixed-limit: a bet plus 3 raises per street (uncapped heads-up)
+const MAX_RAKE_BPS: u32 = 1_000; // Rake can never exceed 10% of a pot
+const RUN_IT_TWICE_WINDOW_SECS: u64 = 10; // How long all-in players have to agree to run it twice
 
 // ICP Ledger canister ID (mainnet)
 const ICP_LEDGER_CANISTER: &str = "ryjl3-tyaaa-aaaaa-aaaba-cai";
@@ -62,6 +72,11 @@ const MAX_HEARTBEATS_PER_SECOND: u32 = 2;
 // Cleanup thresholds to prevent unbounded memory growth
 const MAX_HAND_HISTORY_ENTRIES: usize = 100; // Keep last 100 hands in local history
 const MAX_SHOWN_CARDS_HANDS: usize = 10; // Track shown cards for last 10 hands
+const MAX_HISTORY_OUTBOX_ENTRIES: usize = 5_000; // Hand records waiting for the history canister
+pub const MAX_ADMIN_ACCESS_ENTRIES: usize = 1_000; // Controller reads of table state kept in the public log
+const HISTORY_FLUSH_BATCH: usize = 20; // Records sent per outbox timer tick
+const HISTORY_RETRY_BASE_NS: u64 = 5_000_000_000; // First retry after 5 seconds, doubling from there
+const HISTORY_RETRY_MAX_NS: u64 = 600_000_000_000; // Never wait more than 10 minutes between retries
 const RATE_LIMIT_CLEANUP_AGE_NS: u64 = 60_000_000_000; // Clean up rate limit entries older than 1 minute
 const CLEANUP_INTERVAL_NS: u64 = 30_000_000_000; // Run cleanup every 30 seconds
 
@@ -135,6 +150,82 @@ impl Currency {
     }
 }
 
+/// Poker variant dealt at the table
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum GameVariant {
+    #[default]
+    TexasHoldem,   // No-Limit Hold'em: 2 hole cards, any 5 of 7
+    PotLimitOmaha, // PLO: 4 hole cards, exactly 2 hole + 3 board, pot-limit betting
+    OmahaHiLo,     // PLO8: pot-limit Omaha, pot split between best high and best 8-or-better low
+    ShortDeckHoldem, // 6+ Hold'em: 36-card deck, flush beats full house, A-6-7-8-9 is the low straight
+}
+
+impl GameVariant {
+    /// Number of hole cards dealt to each player
+    pub fn hole_card_count(&self) -> usize {
+        match self {
+            GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => 2,
+            GameVariant::PotLimitOmaha | GameVariant::OmahaHiLo => 4,
+        }
+    }
+
+    /// Betting structure used when the table config doesn't choose one
+    pub fn default_betting_structure(&self) -> BettingStructure {
+        match self {
+            GameVariant::PotLimitOmaha | GameVariant::OmahaHiLo => BettingStructure::PotLimit,
+            GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => BettingStructure::NoLimit,
+        }
+    }
+
+    /// Whether each pot is split between the best high and the best qualifying low
+    pub fn is_hi_lo(&self) -> bool {
+        matches!(self, GameVariant::OmahaHiLo)
+    }
+
+    /// Deck the variant is dealt from, which also decides the hand ranking rules
+    pub fn deck_type(&self) -> DeckType {
+        match self {
+            GameVariant::ShortDeckHoldem => DeckType::ShortDeck,
+            _ => DeckType::Standard,
+        }
+    }
+
+    pub fn short_name(&self) -> &'static str {
+        match self {
+            GameVariant::TexasHoldem => "NLHE",
+            GameVariant::PotLimitOmaha => "PLO",
+            GameVariant::OmahaHiLo => "PLO8",
+            GameVariant::ShortDeckHoldem => "6+",
+        }
+    }
+}
+
+/// How bet and raise sizes are limited
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum BettingStructure {
+    NoLimit,    // Bet or raise any amount up to your stack
+    PotLimit,   // Raise up to the size of the pot after calling
+    FixedLimit, // Small bet preflop/flop, big bet (2x) turn/river, raises capped per street
+}
+
+impl BettingStructure {
+    pub fn name(&self) -> &'static str {
+        match self {
+            BettingStructure::NoLimit => "no-limit",
+            BettingStructure::PotLimit => "pot-limit",
+            BettingStructure::FixedLimit => "fixed-limit",
+        }
+    }
+}
+
+/// Deck composition and the hand ranking rules that go with it
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Default)]
+pub enum DeckType {
+    #[default]
+    Standard,  // 52 cards, standard rankings, A-2-3-4-5 is the lowest straight
+    ShortDeck, // 36 cards (deuces through fives removed), flush beats full house, A-6-7-8-9 is the lowest straight
+}
+
 #[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Suit {
     Hearts,
@@ -186,6 +277,34 @@ pub enum HandRank {
     RoyalFlush,
 }
 
+impl HandRank {
+    /// Category strength under the given ranking rules (higher is better).
+    /// Short deck has fewer flush cards, so a flush outranks a full house.
+    fn category(&self, deck: DeckType) -> u8 {
+        match self {
+            HandRank::HighCard(_) => 0,
+            HandRank::Pair(_, _) => 1,
+            HandRank::TwoPair(_, _, _) => 2,
+            HandRank::ThreeOfAKind(_, _) => 3,
+            HandRank::Straight(_) => 4,
+            HandRank::Flush(_) if deck == DeckType::ShortDeck => 6,
+            HandRank::Flush(_) => 5,
+            HandRank::FullHouse(_, _) if deck == DeckType::ShortDeck => 5,
+            HandRank::FullHouse(_, _) => 6,
+            HandRank::FourOfAKind(_, _) => 7,
+            HandRank::StraightFlush(_) => 8,
+            HandRank::RoyalFlush => 9,
+        }
+    }
+
+    /// Compares two hands under the given ranking rules.
+    /// Within the same category the derived ordering (kickers) decides.
+    pub fn compare(&self, other: &HandRank, deck: DeckType) -> std::cmp::Ordering {
+        self.category(deck).cmp(&other.category(deck))
+            .then_with(|| self.cmp(other))
+    }
+}
+
 #[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
 pub enum PlayerAction {
     Fold,
@@ -194,6 +313,7 @@ pub enum PlayerAction {
     Bet(u64),
     Raise(u64),
     AllIn,
+    Straddle(u64), // Posted before the deal - history only, never a valid player_action
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
@@ -239,7 +359,7 @@ pub struct Player {
     pub principal: Principal,
     pub seat: u8,
     pub chips: u64,
-    pub hole_cards: Option<(Card, Card)>,
+    pub hole_cards: Option<Vec<Card>>, // 2 cards for Hold'em, 4 for Omaha
     pub current_bet: u64,
     pub total_bet_this_hand: u64,
     pub has_folded: bool,
@@ -253,6 +373,8 @@ pub struct Player {
     pub broke_at: Option<u64>, // Timestamp when player hit 0 chips (for reload timer)
     #[serde(default)] // For backwards compatibility
     pub sitting_out_since: Option<u64>, // Timestamp when player started sitting out (for auto-kick)
+    #[serde(default)] // opt, not bool: candid drops a saved seat missing a non-opt field
+    pub straddle_next_hand: Option<bool>, // Opted in to straddle on the next deal (cleared every hand)
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -268,6 +390,175 @@ pub struct ShuffleProof {
     pub seed_hash: String,
     pub revealed_seed: Option<String>,
     pub timestamp: u64,
+    #[serde(default)] // None for proofs made before short deck existed (52-card deck)
+    pub deck_type: Option<DeckType>,
+    #[serde(default)] // None when no player contributed entropy - the seed is the VRF bytes
+    pub entropy: Option<PlayerEntropy>,
+    #[serde(default)] // None for proofs made before shuffles were versioned (cleardeck_shuffle::SHUFFLE_V1)
+    pub shuffle_version: Option<u32>,
+}
+
+/// What an attempt to deal the next hand did
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub enum DealOutcome {
+    Dealt(ShuffleProof),
+    // Player entropy commitments closed - the hand is dealt once players reveal or the window ends
+    AwaitingReveals { reveal_deadline: u64 },
+    // Mental-poker table: the players started shuffling and locking the deck - the hand is dealt once they're done
+    AwaitingShuffle { step_deadline: u64 },
+}
+
+/// One player's entropy for a hand - committed before the deal, revealed once commitments close
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct EntropyContribution {
+    pub seat: u8,
+    pub principal: Principal,
+    pub commitment: String, // Hex SHA-256 of the nonce
+    pub nonce: Option<String>, // Hex 32-byte nonce - None if not revealed in time (left out of the seed)
+}
+
+/// Player entropy mixed into a hand's seed: SHA-256(VRF bytes || revealed nonces in seat order)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerEntropy {
+    pub vrf_seed_hash: String, // Published when commitments closed, before any nonce was revealed
+    pub vrf_seed: Option<String>, // Revealed with the seed when the hand ends
+    pub contributions: Vec<EntropyContribution>, // Seat order
+}
+
+/// Commit-reveal round for the next hand's player entropy
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct EntropyRound {
+    pub hand_number: u64, // The hand the nonces will be mixed into
+    pub contributions: Vec<EntropyContribution>, // Seat order
+    pub vrf_seed_hash: Option<String>, // Set when the deal closes commitments - players then reveal
+    pub reveal_deadline: Option<u64>, // The hand is dealt without unrevealed nonces after this
+}
+
+/// Optional commit-reveal round letting players mix their own randomness into the seed
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct PlayerEntropyConfig {
+    pub reveal_timeout_secs: u64, // Time to reveal once commitments close
+}
+
+/// Mental-poker dealing: the players shuffle and lock the deck between them with
+/// cleardeck_sra, so the table never holds a card before the players open it
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MentalPokerConfig {
+    pub step_timeout_secs: u64, // Time for each shuffle, lock, card key or secret step - stalling forfeits
+}
+
+/// Where a mental-poker deal stands
+#[derive(Clone, Copy, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum MentalStage {
+    Shuffle, // Participants take turns in seat order adding a shuffle layer and permuting the deck
+    Lock, // Participants take turns swapping their shuffle layer for a key per deck position
+    Ready, // The locked deck waits for the deal
+    OpenHoleCards, // Everyone but the owner hands over their key to each hole card
+    Betting, // Nothing to hand over until the next street
+    OpenBoard, // Everyone hands over their key to the next street's cards
+    OpenRunout, // Everyone hands over their key to the rest of the board (all-in)
+    OpenShowdown, // Players left in the hand hand over the last key to their own hole cards - stalling mucks
+    Audit, // The betting is over: everyone reveals their secret
+    Verify, // The table checks each revealed secret against every step its owner made, one player per tick
+    Done, // Audited - the hand was settled or voided
+}
+
+/// A player in a mental-poker deal. Participants are in seat order, which is the order of the steps.
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MentalParticipant {
+    pub seat: u8,
+    pub principal: Principal,
+    pub secret_commitment: Option<String>, // Hex SHA-256 of the secret - sent with the shuffle step
+    pub key_hashes: Vec<String>, // cleardeck_sra::exponent_hash of every position key - sent with the lock step
+    pub secret: Option<Vec<u8>>, // Revealed for the audit
+    pub audit_passed: Option<bool>, // Set once the audit has checked (or given up on) this player
+}
+
+/// A decryption exponent handed over for one deck position
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MentalCardKey {
+    pub position: u32,
+    pub exponent: Vec<u8>, // cleardeck_sra::to_bytes of Key::for_position(secret, position).decryption_exponent()
+}
+
+/// A deck position dealt in a mental-poker hand
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MentalCard {
+    pub position: u32,
+    pub owner: Option<u8>, // Seat holding it as a hole card (None for board cards)
+    pub keys: Vec<(u8, Vec<u8>)>, // (seat, decryption exponent) handed over so far
+    pub partial: Vec<u8>, // The locked card with every layer handed over so far removed
+    pub card: Option<Card>, // Once every layer is off
+}
+
+/// The mental-poker deal for one hand - the only deck a mental-poker table holds
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MentalDeal {
+    pub deal_id: u64, // When the shuffle started - steps name it, so none lands in a restarted deal
+    pub hand_number: u64,
+    pub stage: MentalStage,
+    pub deadline: Option<u64>, // The current step times out after this
+    pub participants: Vec<MentalParticipant>,
+    pub decks: Vec<Vec<Vec<u8>>>, // The plain deck, then the deck after every shuffle step, then after every lock step
+    pub cards: Vec<MentalCard>, // Positions dealt so far
+    pub opening: Vec<u32>, // Positions the current Open* stage collects keys for
+    pub corrupt: bool, // A fully opened position wasn't a card - the audit finds whose step was bad
+    pub forfeits: Vec<u8>, // Seats that stalled or failed the audit - the hand is void
+}
+
+impl MentalDeal {
+    /// Cards in the deck
+    pub fn deck_size(&self) -> usize {
+        self.decks.first().map_or(0, Vec::len)
+    }
+
+    /// Index of the participant whose shuffle or lock step is due
+    pub fn turn(&self) -> Option<usize> {
+        let steps_done = self.decks.len().saturating_sub(1);
+        match self.stage {
+            MentalStage::Shuffle => Some(steps_done),
+            MentalStage::Lock => Some(steps_done - self.participants.len()),
+            _ => None,
+        }
+    }
+
+    /// Whether `seat` still owes a key for `card` in the current Open* stage
+    pub fn owes_key(&self, card: &MentalCard, seat: u8) -> bool {
+        self.opening.contains(&card.position)
+            && !card.keys.iter().any(|(s, _)| *s == seat)
+            && !(self.stage == MentalStage::OpenHoleCards && card.owner == Some(seat))
+    }
+
+    /// Seats that still owe a key in the current Open* stage
+    pub fn owing_seats(&self) -> Vec<u8> {
+        self.participants.iter()
+            .map(|p| p.seat)
+            .filter(|&seat| self.cards.iter().any(|card| self.owes_key(card, seat)))
+            .collect()
+    }
+
+    /// Check participant `index`'s revealed secret against their commitments and both of their steps
+    pub fn audit_participant(&self, index: usize) -> bool {
+        let n = self.participants.len();
+        let participant = &self.participants[index];
+        let secret = match participant.secret {
+            Some(ref secret) => secret,
+            None => return false,
+        };
+        if participant.secret_commitment.as_deref() != Some(cleardeck_shuffle::seed_hash(secret).as_str()) {
+            return false;
+        }
+        let deck = |i: usize| self.decks.get(i).and_then(|d| cleardeck_sra::deck_from_bytes(d));
+        let (shuffle_in, shuffle_out, lock_in, lock_out) =
+            match (deck(index), deck(index + 1), deck(n + index), deck(n + index + 1)) {
+                (Some(a), Some(b), Some(c), Some(d)) => (a, b, c, d),
+                _ => return false,
+            };
+        participant.key_hashes.len() == self.deck_size()
+            && cleardeck_sra::audit_shuffle_step(&shuffle_in, &shuffle_out, &cleardeck_sra::Key::for_shuffle(secret))
+            && cleardeck_sra::audit_lock_step(&lock_in, &lock_out, secret)
+            && cleardeck_sra::audit_exponent_hashes(&participant.key_hashes, secret)
+    }
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -282,6 +573,142 @@ pub struct TableConfig {
     pub time_bank_secs: u64, // Time bank per player
     #[serde(default)] // Backwards compatibility - defaults to ICP
     pub currency: Currency, // ICP or BTC
+    #[serde(default)] // Backwards compatibility - None means Texas Hold'em
+    pub game_variant: Option<GameVariant>,
+    #[serde(default)] // None means the variant's usual structure (pot-limit Omaha, no-limit otherwise)
+    pub betting_structure: Option<BettingStructure>,
+    #[serde(default)] // None means a cash game
+    pub tournament: Option<TournamentConfig>,
+    #[serde(default)] // None means no rake
+    pub rake: Option<RakeConfig>,
+    #[serde(default)] // None/false means all-in boards are always run once
+    pub run_it_twice: Option<bool>,
+    #[serde(default)] // None means no straddles
+    pub straddles: Option<StraddleConfig>,
+    #[serde(default)] // None means the seed is the VRF bytes alone
+    pub player_entropy: Option<PlayerEntropyConfig>,
+    #[serde(default)] // None means the table shuffles and deals from its own seed
+    pub mental_poker: Option<MentalPokerConfig>,
+}
+
+impl TableConfig {
+    pub fn variant(&self) -> GameVariant {
+        self.game_variant.unwrap_or_default()
+    }
+
+    pub fn betting_structure(&self) -> BettingStructure {
+        self.betting_structure.unwrap_or_else(|| self.variant().default_betting_structure())
+    }
+}
+
+/// Live straddle settings - each straddle is twice the previous blind and buys the last option pre-flop
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct StraddleConfig {
+    pub max_straddles: u8, // UTG straddle plus re-straddles clockwise, e.g. 1 = UTG straddle only
+    pub allow_button_straddle: bool, // Mississippi: the button may straddle instead, the blinds then act first
+}
+
+/// Rake cap that applies once at least `min_players` were dealt into the hand
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct RakeCap {
+    pub min_players: u8,
+    pub cap: u64,
+}
+
+/// Cash game rake - taken from each pot before it is awarded and credited to the treasury
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct RakeConfig {
+    pub percent_bps: u32, // Share of the pot in basis points, e.g. 500 = 5%
+    pub caps: Vec<RakeCap>, // The cap with the highest min_players <= players dealt applies (none = uncapped)
+    pub no_flop_no_drop: bool, // Hands that end before the flop are not raked
+}
+
+/// One level of a tournament blind schedule
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct BlindLevel {
+    pub small_blind: u64,
+    pub big_blind: u64,
+    pub ante: u64,
+    pub duration_secs: u64, // The last level lasts until the tournament ends
+}
+
+/// Sit & Go settings - the table runs a single-table tournament instead of a cash game
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentConfig {
+    pub buy_in: u64, // Goes into the prize pool (e8s or satoshis, taken from escrow)
+    pub fee: u64, // Kept by the house, not part of the prize pool
+    pub starting_chips: u64, // Tournament chips - they have no currency value
+    pub blind_levels: Vec<BlindLevel>,
+    pub payouts_bps: Vec<u32>, // Prize pool share per finishing place in basis points, e.g. [6500, 3500]
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum TournamentStatus {
+    Registering, // Waiting for every seat to fill - entries can still be refunded
+    Running,
+    Finished,
+}
+
+/// Finishing position of a tournament entrant
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentResult {
+    pub principal: Principal,
+    pub position: u8, // 1 = winner
+    pub prize: u64, // Paid to escrow when the tournament finishes
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentState {
+    pub status: TournamentStatus,
+    pub entrants: Vec<Principal>,
+    pub prize_pool: u64,
+    pub fees_collected: u64, // Paid to the treasury when the tournament starts
+    pub level: u32, // Index into TournamentConfig.blind_levels
+    pub level_ends_at: Option<u64>, // When the next level starts (None before start and on the last level)
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub results: Vec<TournamentResult>, // Added as players bust, winner last
+}
+
+/// Set while a multi-table tournament canister is running this table
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttTableState {
+    pub tournament_id: u64,
+    pub paused: bool, // No new hands are dealt while paused (the current hand plays out)
+    pub blinds: BlindLevel, // Applied from the next hand dealt
+}
+
+/// A tournament stack seated at this table
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttStack {
+    pub principal: Principal,
+    pub seat: u8,
+    pub chips: u64,
+}
+
+/// A player who lost their last chip in the hand just played
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttBust {
+    pub principal: Principal,
+    pub starting_chips: u64, // Stack at the start of the hand - bigger stacks finish higher
+}
+
+/// Sent to the tournament canister after every hand
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttHandReport {
+    pub tournament_id: u64,
+    pub hand_number: u64,
+    pub stacks: Vec<MttStack>, // Players still seated, after the hand
+    pub busted: Vec<MttBust>, // Already removed from their seats
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttTableStatus {
+    pub tournament_id: u64,
+    pub paused: bool,
+    pub hand_in_progress: bool,
+    pub hand_number: u64,
+    pub stacks: Vec<MttStack>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -305,10 +732,44 @@ pub struct TableState {
     pub shuffle_proof: Option<ShuffleProof>,
     pub hand_number: u64,
     pub last_aggressor: Option<u8>,
-    pub bb_has_option: bool, // True if BB still has option to raise when limped to
+    pub bb_has_option: bool, // True if BB (or the last straddler) still has option to raise when limped to
     pub first_hand: bool, // Track if this is the first hand (for dealer button init)
     pub auto_deal_at: Option<u64>, // Timestamp for when to auto-deal next hand (nanoseconds)
     pub last_action: Option<LastActionInfo>, // Last action taken - for UI display
+    #[serde(default)] // None for cash games
+    pub tournament: Option<TournamentState>,
+    #[serde(default)] // Some while a multi-table tournament canister drives this table
+    pub mtt: Option<MttTableState>,
+    #[serde(default)] // Some while all-in players vote on running the board twice
+    pub run_it_twice: Option<RunItTwiceVote>,
+    #[serde(default)] // The second runout when the board was run twice (community_cards is the first)
+    pub second_board: Option<Vec<Card>>,
+    #[serde(default)] // (seat, amount) of the last straddle this hand - that seat has the pre-flop option
+    pub straddle: Option<(u8, u64)>,
+    #[serde(default)] // Seats dealt into this hand, in dealing order - lets history replay the deal
+    pub dealt_seats: Option<Vec<u8>>,
+    #[serde(default)] // Player entropy commitments for the next hand (player_entropy tables)
+    pub entropy_round: Option<EntropyRound>,
+    #[serde(default)] // The current or next hand's deal on mental_poker tables (deck stays empty there)
+    pub mental: Option<MentalDeal>,
+}
+
+/// A controller read of the table state - public, so players can see what the house looked at and when
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct AdminAccess {
+    pub timestamp: u64,
+    pub caller: Principal,
+    pub method: String,
+    pub hand_number: u64,
+    pub hand_in_progress: bool, // Read while cards were live (the deck and hole cards were still sealed)
+}
+
+/// Run-it-twice vote - every player left in the hand must agree before the window closes
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct RunItTwiceVote {
+    pub seats: Vec<u8>, // Players still in the hand
+    pub accepted: Vec<u8>, // Seats that agreed so far
+    pub expires_at: u64, // No answer by then counts as declining
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -330,7 +791,11 @@ pub struct Winner {
     pub principal: Principal,
     pub amount: u64,
     pub hand_rank: Option<HandRank>,
-    pub cards: Option<(Card, Card)>,
+    pub cards: Option<Vec<Card>>,
+    #[serde(default)] // "main" for whole pots, "high"/"low" for Hi-Lo halves, "void" for a void mental-poker hand; None in old history
+    pub pot_type: Option<String>,
+    #[serde(default)] // 1 or 2 when the board was run twice - each run settles half of every pot
+    pub run: Option<u8>,
 }
 
 /// Player info for hand history (all players who went to showdown)
@@ -338,7 +803,7 @@ pub struct Winner {
 pub struct ShowdownPlayer {
     pub seat: u8,
     pub principal: Principal,
-    pub cards: Option<(Card, Card)>,
+    pub cards: Option<Vec<Card>>,
     pub hand_rank: Option<HandRank>,
     pub amount_won: u64,
 }
@@ -352,6 +817,10 @@ pub struct HandHistory {
     pub community_cards: Vec<Card>,
     #[serde(default)] // For backwards compatibility with old state that doesn't have this field
     pub showdown_players: Vec<ShowdownPlayer>, // All players who went to showdown (not just winners)
+    #[serde(default)] // None for hands recorded before variants existed (Texas Hold'em)
+    pub game_variant: Option<GameVariant>,
+    #[serde(default)] // Second runout when the board was run twice
+    pub second_board: Option<Vec<Card>>,
 }
 
 #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -372,13 +841,14 @@ pub struct PlayerView {
     pub principal: Principal,
     pub seat: u8,
     pub chips: u64,
-    pub hole_cards: Option<(Card, Card)>,  // None if not visible to viewer
+    pub hole_cards: Option<Vec<Card>>,  // None if not visible to viewer
     pub current_bet: u64,
     pub has_folded: bool,
     pub is_all_in: bool,
     pub status: PlayerStatus,
     pub is_self: bool,  // True if this is the viewer's own seat
     pub display_name: Option<String>,  // Custom display name set by player
+    pub straddle_next_hand: bool, // Opted in to straddle on the next deal
 }
 
 /// Complete view of the table from a specific player's perspective
@@ -407,9 +877,13 @@ pub struct TableView {
     pub last_hand_winners: Vec<Winner>,  // Winners from the last completed hand
     pub call_amount: u64, // Amount needed to call (convenience field)
     pub can_check: bool, // Whether check is valid
-    pub can_raise: bool, // Whether raise is valid
-    pub min_bet: u64, // Minimum bet amount
+    pub can_raise: bool, // Whether raise is valid (false once fixed-limit raising is capped)
+    pub min_bet: u64, // Minimum total bet/raise-to for the caller (big blind when not seated)
+    pub max_bet: u64, // Maximum total bet/raise-to for the caller (structure limit, capped by stack)
     pub last_action: Option<LastActionInfo>, // Last action taken - for UI notification
+    pub tournament: Option<TournamentState>, // Sit & Go progress (None for cash games)
+    pub run_it_twice: Option<RunItTwiceVote>, // Open run-it-twice vote, if any
+    pub second_board: Option<Vec<Card>>, // Second runout when the board was run twice
 }
 
 // ============================================================================
@@ -440,8 +914,10 @@ thread_local! {
     static CONTROLLERS: RefCell<Vec<Principal>> = RefCell::new(Vec::new());
     // Rate limiting: caller -> (last_action_time, count_in_window)
     static RATE_LIMITS: RefCell<HashMap<Principal, (u64, u32)>> = RefCell::new(HashMap::new());
-    // Seed bytes for shuffle - only revealed when hand ends
+    // Seed bytes for shuffle - never returned by any query, only published by reveal_seed_on_hand_end
     static CURRENT_SEED: RefCell<Option<Vec<u8>>> = RefCell::new(None);
+    // VRF bytes player entropy is mixed with - drawn when commitments close, revealed with the seed
+    static CURRENT_VRF_SEED: RefCell<Option<Vec<u8>>> = const { RefCell::new(None) };
     // Last withdrawal time per user - for cooldown enforcement
     static LAST_WITHDRAWAL: RefCell<HashMap<Principal, u64>> = RefCell::new(HashMap::new());
     // Deposit verification rate limiting: caller -> (window_start, count_in_window)
@@ -454,6 +930,25 @@ thread_local! {
     static HEARTBEAT_RATE_LIMITS: RefCell<HashMap<Principal, (u64, u32)>> = RefCell::new(HashMap::new());
     // Last cleanup timestamp to throttle cleanup operations
     static LAST_CLEANUP: RefCell<u64> = RefCell::new(0);
+    // Armed canister timers as (deadline_ns, timer_id) - not persisted, re-armed in post_upgrade
+    static AUTO_DEAL_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+    static ACTION_TIMEOUT_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+    static BLIND_LEVEL_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+    static MENTAL_POKER_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+    // Auto-deals that failed in a row (raw_rand errors etc.) - drives the retry backoff, not persisted
+    static AUTO_DEAL_FAILURES: RefCell<u32> = const { RefCell::new(0) };
+    // Multi-table tournament canister allowed to seat stacks and move escrow
+    static TOURNAMENT_COORDINATOR: RefCell<Option<Principal>> = const { RefCell::new(None) };
+    // Rake and tournament fees not yet withdrawn, and the account withdrawals are paid to
+    static TREASURY_BALANCE: RefCell<u64> = const { RefCell::new(0) };
+    static TREASURY_ACCOUNT: RefCell<Option<Account>> = const { RefCell::new(None) };
+    // Hand records not yet accepted by the history canister (persisted across upgrades)
+    static HISTORY_OUTBOX: RefCell<HistoryOutbox> = RefCell::new(HistoryOutbox::default());
+    static HISTORY_OUTBOX_TIMER: RefCell<Option<(u64, TimerId)>> = const { RefCell::new(None) };
+    // True while a flush is awaiting the history canister - one flush at a time keeps records in order
+    static HISTORY_FLUSH_IN_FLIGHT: RefCell<bool> = const { RefCell::new(false) };
+    // Controller reads of the table state, oldest first (persisted across upgrades)
+    static ADMIN_ACCESS_LOG: RefCell<Vec<AdminAccess>> = const { RefCell::new(Vec::new()) };
 }
 
 // ============================================================================
@@ -481,6 +976,54 @@ fn require_controller() -> Result<(), String> {
     Ok(())
 }
 
+/// Log a controller read in ADMIN_ACCESS_LOG. Query-mode calls can't persist
+/// the entry, so they are refused rather than served unlogged.
+fn record_admin_access(method: &str) -> Result<(), String> {
+    let entry = TABLE.with(|t| {
+        admin_access_entry(
+            t.borrow().as_ref(),
+            method,
+            ic_cdk::api::in_replicated_execution(),
+            ic_cdk::api::msg_caller(),
+            ic_cdk::api::time(),
+        )
+    })?;
+    ADMIN_ACCESS_LOG.with(|log| push_admin_access(&mut log.borrow_mut(), entry));
+    Ok(())
+}
+
+/// The log entry for a controller read - refused unless the call is replicated (an update)
+pub fn admin_access_entry(
+    state: Option<&TableState>,
+    method: &str,
+    replicated: bool,
+    caller: Principal,
+    now: u64,
+) -> Result<AdminAccess, String> {
+    if !replicated {
+        return Err(format!("{} must be called as an update so the access is logged", method));
+    }
+    let (hand_number, hand_in_progress) = state
+        .map(|s| (s.hand_number, !matches!(s.phase, GamePhase::WaitingForPlayers | GamePhase::HandComplete)))
+        .unwrap_or_default();
+    Ok(AdminAccess {
+        timestamp: now,
+        caller,
+        method: method.to_string(),
+        hand_number,
+        hand_in_progress,
+    })
+}
+
+/// Append to the access log, dropping the oldest entries past MAX_ADMIN_ACCESS_ENTRIES
+pub fn push_admin_access(log: &mut Vec<AdminAccess>, entry: AdminAccess) {
+    log.push(entry);
+    if log.len() > MAX_ADMIN_ACCESS_ENTRIES {
+        let excess = log.len() - MAX_ADMIN_ACCESS_ENTRIES;
+        log.drain(0..excess);
+    }
+}
+
 /// Get the currency configuration for this table
 fn get_table_currency() -> Currency {
     TABLE_CONFIG.with(|c| {
@@ -625,6 +1168,7 @@ mod history_types {
         Raise(u64),
         AllIn(u64),
         PostBlind(u64),
+        Straddle(u64),
     }
 
     #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -646,6 +1190,7 @@ mod history_types {
         pub final_hand_rank: Option<HandRank>,
         pub amount_won: u64,
         pub position: String,
+        pub all_hole_cards: Option<Vec<Card>>,
     }
 
     #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -653,6 +1198,23 @@ mod history_types {
         pub seed_hash: String,
         pub revealed_seed: String,
         pub timestamp: u64,
+        pub entropy: Option<HistoryPlayerEntropyRecord>,
+        pub shuffle_version: Option<u32>,
+    }
+
+    #[derive(Clone, Debug, CandidType, Deserialize)]
+    pub struct HistoryPlayerEntropyRecord {
+        pub vrf_seed_hash: String,
+        pub vrf_seed: String,
+        pub contributions: Vec<HistoryEntropyContributionRecord>,
+    }
+
+    #[derive(Clone, Debug, CandidType, Deserialize)]
+    pub struct HistoryEntropyContributionRecord {
+        pub seat: u8,
+        pub principal: Principal,
+        pub commitment: String,
+        pub nonce: Option<String>,
     }
 
     #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -662,6 +1224,7 @@ mod history_types {
         pub amount: u64,
         pub hand_rank: Option<HandRank>,
         pub pot_type: String,
+        pub run: Option<u8>,
     }
 
     #[derive(Clone, Debug, CandidType, Deserialize)]
@@ -684,11 +1247,79 @@ mod history_types {
         pub rake: u64,
         pub winners: Vec<HistoryWinnerRecord>,
         pub went_to_showdown: bool,
+        pub game_variant: Option<GameVariant>,
+        pub is_tournament: Option<bool>,
+        pub second_board: Option<Vec<Card>>,
+        pub currency: Option<Currency>,
+        pub dealt_seats: Option<Vec<u8>>,
+    }
+
+    #[derive(Clone, Debug, CandidType, Deserialize)]
+    pub struct HistoryTournamentResult {
+        pub principal: Principal,
+        pub position: u8,
+        pub prize: u64,
+    }
+
+    #[derive(Clone, Debug, CandidType, Deserialize)]
+    pub struct TournamentRecord {
+        pub tournament_id: u64, // Assigned by history canister
+        pub table_id: Principal,
+        pub started_at: u64,
+        pub finished_at: u64,
+        pub buy_in: u64,
+        pub fee: u64,
+        pub prize_pool: u64,
+        pub entrants: u8,
+        pub hands_played: u64,
+        pub results: Vec<HistoryTournamentResult>,
     }
 }
 
 use history_types::*;
 
+/// A hand record waiting to be accepted by the history canister
+#[derive(Clone, Debug, CandidType, Deserialize)]
+struct PendingHandRecord {
+    record: HandHistoryRecord,
+    attempts: u32,
+}
+
+/// A finished Sit & Go waiting to be accepted by the history canister
+#[derive(Clone, Debug, CandidType, Deserialize)]
+struct PendingTournamentRecord {
+    record: TournamentRecord,
+    attempts: u32,
+}
+
+/// Durable queue of hand and tournament records for the history canister.
+/// Records leave the queue only once the history canister returns Ok - it
+/// dedupes hands on (table_id, hand_number) and tournaments on
+/// (table_id, started_at), so resending is always safe.
+#[derive(Clone, Debug, Default, CandidType, Deserialize)]
+struct HistoryOutbox {
+    entries: Vec<PendingHandRecord>,
+    #[serde(default)] // Finished Sit & Gos, sent once no hands are queued
+    tournaments: Option<Vec<PendingTournamentRecord>>,
+    consecutive_failures: u32,
+    retry_at: u64, // No sends before this time (ns) while backing off
+    failed_attempts: u64, // Total failed sends
+    dropped: u64, // Oldest records evicted because the queue was full
+    last_error: Option<String>,
+}
+
+/// History outbox health, for controllers
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct HistoryOutboxStatus {
+    pub depth: u64,
+    pub failed_attempts: u64,
+    pub consecutive_failures: u32,
+    pub dropped: u64,
+    pub oldest_hand_number: Option<u64>,
+    pub next_attempt_at: Option<u64>,
+    pub last_error: Option<String>,
+}
+
 /// Set the history canister ID (controller only)
 /// Pass None to clear/disable history recording
 #[ic_cdk::update]
@@ -697,9 +1328,35 @@ fn set_history_canister(canister_id: Option<Principal>) -> Result<(), String> {
     HISTORY_ID.with(|h| {
         *h.borrow_mut() = canister_id;
     });
+    // A new target gets a fresh attempt at anything still queued
+    HISTORY_OUTBOX.with(|o| {
+        let mut outbox = o.borrow_mut();
+        outbox.consecutive_failures = 0;
+        outbox.retry_at = 0;
+    });
+    schedule_history_outbox_timer();
     Ok(())
 }
 
+/// Admin: Hand and tournament records queued for the history canister and how sending is going
+/// Controller only
+#[ic_cdk::query]
+fn get_history_outbox_status() -> Result<HistoryOutboxStatus, String> {
+    require_controller()?;
+    Ok(HISTORY_OUTBOX.with(|o| {
+        let outbox = o.borrow();
+        HistoryOutboxStatus {
+            depth: (outbox.entries.len() + outbox.tournaments.as_ref().map_or(0, Vec::len)) as u64,
+            failed_attempts: outbox.failed_attempts,
+            consecutive_failures: outbox.consecutive_failures,
+            dropped: outbox.dropped,
+            oldest_hand_number: outbox.entries.iter().map(|e| e.record.hand_number).min(),
+            next_attempt_at: (!outbox.is_empty()).then_some(outbox.retry_at),
+            last_error: outbox.last_error.clone(),
+        }
+    }))
+}
+
 /// Get the history canister ID
 #[ic_cdk::query]
 fn get_history_canister() -> Option<Principal> {
@@ -756,26 +1413,36 @@ fn get_display_name(principal: Principal) -> Option<String> {
     })
 }
 
-/// Record a completed hand to the history canister (fire and forget)
-fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdown: bool) {
-    let history_id = match HISTORY_ID.with(|h| *h.borrow()) {
-        Some(id) => id,
-        None => return, // No history canister configured, skip recording
-    };
+/// Queue a completed hand for the history canister - the outbox timer sends it
+fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdown: bool, rake: u64) {
+    if HISTORY_ID.with(|h| h.borrow().is_none()) {
+        return; // No history canister configured, skip recording
+    }
     let table_id = ic_cdk::api::canister_self();
 
     // Get the shuffle proof
     let shuffle_proof = match &state.shuffle_proof {
         Some(proof) => {
-            let revealed_seed = HAND_HISTORY.with(|h| {
+            let (revealed_seed, entropy) = HAND_HISTORY.with(|h| {
                 h.borrow().last()
-                    .and_then(|hh| hh.shuffle_proof.revealed_seed.clone())
+                    .map(|hh| (hh.shuffle_proof.revealed_seed.clone(), hh.shuffle_proof.entropy.clone()))
                     .unwrap_or_default()
             });
             HistoryShuffleProofRecord {
                 seed_hash: proof.seed_hash.clone(),
-                revealed_seed,
+                revealed_seed: revealed_seed.unwrap_or_default(),
                 timestamp: proof.timestamp,
+                shuffle_version: proof.shuffle_version,
+                entropy: entropy.map(|e| HistoryPlayerEntropyRecord {
+                    vrf_seed_hash: e.vrf_seed_hash,
+                    vrf_seed: e.vrf_seed.unwrap_or_default(),
+                    contributions: e.contributions.into_iter().map(|c| HistoryEntropyContributionRecord {
+                        seat: c.seat,
+                        principal: c.principal,
+                        commitment: c.commitment,
+                        nonce: c.nonce,
+                    }).collect(),
+                }),
             }
         }
         None => return, // No proof, don't record
@@ -807,20 +1474,23 @@ fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdo
 
                 // Only include hole cards if shown (at showdown or voluntarily)
                 let show_cards = went_to_showdown && !p.has_folded;
+                let shown = if show_cards { p.hole_cards.clone() } else { None };
 
                 HistoryPlayerHandRecord {
                     seat: i as u8,
                     principal: p.principal,
                     starting_chips: starting,
                     ending_chips: p.chips,
-                    hole_cards: if show_cards { p.hole_cards } else { None },
-                    final_hand_rank: if show_cards {
-                        p.hole_cards.as_ref().map(|cards| evaluate_hand(cards, &state.community_cards))
-                    } else {
-                        None
-                    },
+                    // Legacy two-card field is only filled for Hold'em; all_hole_cards covers every variant
+                    hole_cards: shown.as_ref().and_then(|cards| match cards.as_slice() {
+                        [c1, c2] => Some((*c1, *c2)),
+                        _ => None,
+                    }),
+                    final_hand_rank: shown.as_ref()
+                        .map(|cards| evaluate_hand(cards, &state.community_cards, state.config.variant())),
                     amount_won,
                     position,
+                    all_hole_cards: shown,
                 }
             })
         })
@@ -837,6 +1507,7 @@ fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdo
                 PlayerAction::Bet(amt) => HistoryPlayerAction::Bet(*amt),
                 PlayerAction::Raise(amt) => HistoryPlayerAction::Raise(*amt),
                 PlayerAction::AllIn => HistoryPlayerAction::AllIn(action.amount),
+                PlayerAction::Straddle(amt) => HistoryPlayerAction::Straddle(*amt),
             };
 
             HistoryActionRecord {
@@ -859,7 +1530,8 @@ fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdo
             principal: w.principal,
             amount: w.amount,
             hand_rank: w.hand_rank.clone(),
-            pot_type: "main".to_string(),
+            pot_type: w.pot_type.clone().unwrap_or_else(|| "main".to_string()),
+            run: w.run,
         }
     }).collect();
 
@@ -872,8 +1544,8 @@ fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdo
     let turn = state.community_cards.get(3).copied();
     let river = state.community_cards.get(4).copied();
 
-    // Calculate total pot from what winners received
-    let total_pot: u64 = winners.iter().map(|w| w.amount).sum();
+    // Calculate total pot from what winners received plus the rake taken before awarding
+    let total_pot: u64 = winners.iter().map(|w| w.amount).sum::<u64>().saturating_add(rake);
 
     let record = HandHistoryRecord {
         hand_id: 0, // Will be assigned by history canister
@@ -891,37 +1563,201 @@ fn record_hand_to_history(state: &TableState, winners: &[Winner], went_to_showdo
         river,
         actions,
         total_pot,
-        rake: 0,
+        rake,
         winners: history_winners,
         went_to_showdown,
+        game_variant: Some(state.config.variant()),
+        is_tournament: Some(is_tournament_table(state)),
+        second_board: state.second_board.clone(),
+        currency: Some(state.config.currency),
+        dealt_seats: state.dealt_seats.clone(),
     };
 
-    // Async call to history canister - best effort but log errors
-    ic_cdk::futures::spawn(async move {
-        let call_result = ic_cdk::call::Call::unbounded_wait(history_id, "record_hand")
-            .with_arg(record)
-            .await;
+    HISTORY_OUTBOX.with(|o| {
+        let mut outbox = o.borrow_mut();
+        outbox.entries.push(PendingHandRecord { record, attempts: 0 });
+        if outbox.entries.len() > MAX_HISTORY_OUTBOX_ENTRIES {
+            // History has been unreachable for thousands of hands - keep the newest
+            outbox.entries.remove(0);
+            outbox.dropped += 1;
+        }
+    });
+    schedule_history_outbox_timer();
+}
 
-        // Log errors for debugging but don't fail the hand
-        match call_result {
-            Ok(response) => {
-                match response.candid::<(Result<u64, String>,)>() {
-                    Ok((Ok(_hand_id),)) => {
-                        // Success - history recorded
-                    }
-                    Ok((Err(e),)) => {
-                        ic_cdk::println!("History canister rejected record: {}", e);
+/// Backoff before the next send after `consecutive_failures` failed sends in a row
+fn history_retry_delay_ns(consecutive_failures: u32) -> u64 {
+    if consecutive_failures == 0 {
+        return 0;
+    }
+    let doublings = (consecutive_failures - 1).min(16);
+    HISTORY_RETRY_BASE_NS.saturating_mul(1 << doublings).min(HISTORY_RETRY_MAX_NS)
+}
+
+/// Arm the outbox timer for the next send, if anything is queued
+fn schedule_history_outbox_timer() {
+    let idle = HISTORY_ID.with(|h| h.borrow().is_none())
+        || HISTORY_FLUSH_IN_FLIGHT.with(|f| *f.borrow());
+    let deadline = if idle {
+        None
+    } else {
+        HISTORY_OUTBOX.with(|o| {
+            let outbox = o.borrow();
+            (!outbox.is_empty()).then_some(outbox.retry_at)
+        })
+    };
+    rearm_timer(&HISTORY_OUTBOX_TIMER, deadline, on_history_outbox_timer);
+}
+
+async fn on_history_outbox_timer() {
+    HISTORY_OUTBOX_TIMER.with(|t| *t.borrow_mut() = None);
+    HISTORY_FLUSH_IN_FLIGHT.with(|f| *f.borrow_mut() = true);
+    flush_history_outbox().await;
+    HISTORY_FLUSH_IN_FLIGHT.with(|f| *f.borrow_mut() = false);
+    schedule_history_outbox_timer();
+}
+
+/// A queued record, as sent to the history canister
+enum OutboxRecord {
+    Hand(Box<HandHistoryRecord>),
+    Tournament(TournamentRecord),
+}
+
+impl HistoryOutbox {
+    fn is_empty(&self) -> bool {
+        self.entries.is_empty() && self.tournaments.as_ref().is_none_or(Vec::is_empty)
+    }
+
+    /// Oldest queued hand, or once every hand is in, the oldest finished tournament
+    fn next_record(&self) -> Option<OutboxRecord> {
+        if let Some(entry) = self.entries.first() {
+            return Some(OutboxRecord::Hand(Box::new(entry.record.clone())));
+        }
+        self.tournaments.as_ref()?.first().map(|e| OutboxRecord::Tournament(e.record.clone()))
+    }
+
+    /// Take a sent record off the queue, or on failure move it to the back.
+    /// The queue may have moved while the call was awaited (new records,
+    /// evictions), so the record is looked up again by its key.
+    fn settle(&mut self, sent: &OutboxRecord, accepted: bool) {
+        match sent {
+            OutboxRecord::Hand(record) => {
+                let key = (record.hand_number, &record.shuffle_proof.seed_hash);
+                let pos = self.entries.iter()
+                    .position(|e| (e.record.hand_number, &e.record.shuffle_proof.seed_hash) == key);
+                if let Some(mut entry) = pos.map(|pos| self.entries.remove(pos)) {
+                    if !accepted {
+                        entry.attempts = entry.attempts.saturating_add(1);
+                        self.entries.push(entry);
                     }
-                    Err(e) => {
-                        ic_cdk::println!("Failed to decode history response: {:?}", e);
+                }
+            }
+            OutboxRecord::Tournament(record) => {
+                let tournaments = self.tournaments.get_or_insert_with(Vec::new);
+                let pos = tournaments.iter().position(|e| e.record.started_at == record.started_at);
+                if let Some(mut entry) = pos.map(|pos| tournaments.remove(pos)) {
+                    if !accepted {
+                        entry.attempts = entry.attempts.saturating_add(1);
+                        tournaments.push(entry);
                     }
                 }
             }
-            Err(e) => {
-                ic_cdk::println!("Failed to call history canister: {:?}", e);
+        }
+    }
+}
+
+/// Send queued records oldest first, stopping at the first failure.
+/// A failed record moves to the back of the queue so one record the history
+/// canister keeps rejecting can't hold up every hand behind it.
+async fn flush_history_outbox() {
+    let history_id = match HISTORY_ID.with(|h| *h.borrow()) {
+        Some(id) => id,
+        None => return,
+    };
+
+    for _ in 0..HISTORY_FLUSH_BATCH {
+        let record = match HISTORY_OUTBOX.with(|o| o.borrow().next_record()) {
+            Some(record) => record,
+            None => return,
+        };
+
+        let (label, call) = match &record {
+            OutboxRecord::Hand(hand) => (
+                format!("Hand {}", hand.hand_number),
+                ic_cdk::call::Call::unbounded_wait(history_id, "record_hand").with_arg(&**hand).await,
+            ),
+            OutboxRecord::Tournament(tournament) => (
+                format!("Tournament started at {}", tournament.started_at),
+                ic_cdk::call::Call::unbounded_wait(history_id, "record_tournament").with_arg(tournament).await,
+            ),
+        };
+        let result = match call {
+            Ok(response) => match response.candid::<Result<u64, String>>() {
+                Ok(Ok(_id)) => Ok(()),
+                Ok(Err(e)) => Err(format!("History canister rejected record: {}", e)),
+                Err(e) => Err(format!("Failed to decode history response: {:?}", e)),
+            },
+            Err(e) => Err(format!("Failed to call history canister: {:?}", e)),
+        };
+
+        let failed = HISTORY_OUTBOX.with(|o| {
+            let mut outbox = o.borrow_mut();
+            outbox.settle(&record, result.is_ok());
+            match result {
+                Ok(()) => {
+                    outbox.consecutive_failures = 0;
+                    outbox.retry_at = 0;
+                    false
+                }
+                Err(e) => {
+                    ic_cdk::println!("{} not recorded to history: {}", label, e);
+                    outbox.failed_attempts += 1;
+                    outbox.consecutive_failures = outbox.consecutive_failures.saturating_add(1);
+                    outbox.retry_at = ic_cdk::api::time()
+                        .saturating_add(history_retry_delay_ns(outbox.consecutive_failures));
+                    outbox.last_error = Some(e);
+                    true
+                }
             }
+        });
+        if failed {
+            return;
         }
+    }
+}
+
+/// Queue a finished Sit & Go for the history canister
+fn record_tournament_to_history(state: &TableState) {
+    if HISTORY_ID.with(|h| h.borrow().is_none()) {
+        return; // No history canister configured, skip recording
+    }
+    let (config, tournament) = match (&state.config.tournament, &state.tournament) {
+        (Some(c), Some(t)) => (c, t),
+        _ => return,
+    };
+
+    let record = TournamentRecord {
+        tournament_id: 0, // Will be assigned by history canister
+        table_id: ic_cdk::api::canister_self(),
+        started_at: tournament.started_at.unwrap_or(0),
+        finished_at: tournament.finished_at.unwrap_or(0),
+        buy_in: config.buy_in,
+        fee: config.fee,
+        prize_pool: tournament.prize_pool,
+        entrants: tournament.entrants.len() as u8,
+        hands_played: state.hand_number,
+        results: tournament.results.iter().map(|r| HistoryTournamentResult {
+            principal: r.principal,
+            position: r.position,
+            prize: r.prize,
+        }).collect(),
+    };
+
+    HISTORY_OUTBOX.with(|o| {
+        o.borrow_mut().tournaments.get_or_insert_with(Vec::new)
+            .push(PendingTournamentRecord { record, attempts: 0 });
     });
+    schedule_history_outbox_timer();
 }
 
 // ============================================================================
@@ -975,6 +1811,11 @@ fn canister_id() -> Principal {
 /// Transfer tokens (ICP or ckBTC) from canister to a player (for withdrawals/payouts)
 /// Uses the table's configured currency
 async fn transfer_tokens(to: Principal, amount: u64) -> Result<u64, String> {
+    transfer_tokens_to_account(Account { owner: to, subaccount: None }, amount).await
+}
+
+/// Transfer tokens to any ledger account, including subaccounts (e.g. the rake treasury)
+async fn transfer_tokens_to_account(to: Account, amount: u64) -> Result<u64, String> {
     use icrc_ledger_types::icrc1::transfer::{TransferArg, TransferError};
 
     let currency = get_table_currency();
@@ -988,10 +1829,7 @@ async fn transfer_tokens(to: Principal, amount: u64) -> Result<u64, String> {
 
     let transfer_args = TransferArg {
         from_subaccount: None,
-        to: Account {
-            owner: to,
-            subaccount: None,
-        },
+        to,
         fee: None, // Use default fee
         created_at_time: None,
         memo: None,
@@ -1780,11 +2618,18 @@ fn buy_in(seat: u8, amount: u64) -> Result<(), String> {
             currency.format_amount(amount)));
     }
 
-    TABLE.with(|t| {
+    let result = TABLE.with(|t| {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
         let currency = state.config.currency;
 
+        if state.tournament.is_some() {
+            return Err("This is a Sit & Go table - register with join_table".to_string());
+        }
+        if state.mtt.is_some() {
+            return Err("This table is running a tournament".to_string());
+        }
+
         // Validate buy-in amount
         if amount < state.config.min_buy_in {
             return Err(format!("Minimum buy-in is {}", currency.format_amount(state.config.min_buy_in)));
@@ -1845,6 +2690,7 @@ fn buy_in(seat: u8, amount: u64) -> Result<(), String> {
             is_sitting_out_next_hand: false,
             broke_at: None,
             sitting_out_since,
+            straddle_next_hand: None,
         });
 
         // Auto-start if we now have enough players and waiting for players
@@ -1860,7 +2706,10 @@ fn buy_in(seat: u8, amount: u64) -> Result<(), String> {
         }
 
         Ok(())
-    })
+    });
+
+    schedule_table_timers();
+    result
 }
 
 /// Reload chips from escrow (for players already seated who need more chips)
@@ -1888,6 +2737,10 @@ fn reload(amount: u64) -> Result<u64, String> {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
+        if is_tournament_table(state) {
+            return Err("Reloads are not allowed in a tournament".to_string());
+        }
+
         // Can't reload during a hand
         if state.phase != GamePhase::WaitingForPlayers && state.phase != GamePhase::HandComplete {
             return Err("Cannot reload during a hand".to_string());
@@ -1916,8 +2769,19 @@ fn reload(amount: u64) -> Result<u64, String> {
         if player.status == PlayerStatus::SittingOut {
             player.status = PlayerStatus::Active;
         }
+        let chips = player.chips;
+
+        // A reload can bring the table back to 2+ players - schedule the next deal
+        if state.auto_deal_at.is_none() {
+            let active_count = state.players.iter()
+                .filter(|p| p.as_ref().map(|p| p.status == PlayerStatus::Active && p.chips > 0).unwrap_or(false))
+                .count();
+            if active_count >= 2 {
+                state.auto_deal_at = Some(ic_cdk::api::time() + AUTO_DEAL_DELAY_NS);
+            }
+        }
 
-        Ok(player.chips)
+        Ok(chips)
     });
 
     // If the table operation failed, refund the escrow (with overflow protection)
@@ -1929,6 +2793,8 @@ fn reload(amount: u64) -> Result<u64, String> {
         });
     }
 
+    schedule_table_timers();
+
     result
 }
 
@@ -1958,6 +2824,14 @@ fn cash_out() -> Result<u64, String> {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
+        // Tournament chips can't be cashed out - only a registration can be refunded
+        if state.mtt.is_some() {
+            return Err("Tournament stacks can't be cashed out".to_string());
+        }
+        if state.tournament.is_some() {
+            return unregister_tournament_entry(state, caller);
+        }
+
         for (i, player_opt) in state.players.iter_mut().enumerate() {
             if let Some(player) = player_opt {
                 if player.principal == caller {
@@ -1978,6 +2852,8 @@ fn cash_out() -> Result<u64, String> {
         balances.insert(caller, current.saturating_add(chips));
     });
 
+    schedule_table_timers();
+
     Ok(chips)
 }
 
@@ -1988,6 +2864,7 @@ fn cash_out() -> Result<u64, String> {
 #[ic_cdk::init]
 fn init(config: TableConfig) {
     init_table_state(config);
+    schedule_table_timers();
 }
 
 /// Reset the table (controller only) - CAUTION: destroys all state
@@ -1996,6 +2873,7 @@ fn reset_table(config: TableConfig) -> Result<(), String> {
     require_controller()?;
     validate_config(&config)?;
     init_table_state(config);
+    schedule_table_timers();
     Ok(())
 }
 
@@ -2121,6 +2999,60 @@ fn admin_get_table_chips() -> Result<u64, String> {
     })
 }
 
+/// Admin: Set the account treasury withdrawals are paid to (principal + optional subaccount)
+/// Controller only
+#[ic_cdk::update]
+fn set_treasury_account(account: Option<Account>) -> Result<(), String> {
+    require_controller()?;
+    TREASURY_ACCOUNT.with(|t| {
+        *t.borrow_mut() = account;
+    });
+    Ok(())
+}
+
+/// Admin: Rake and tournament fees not yet withdrawn, and where they will be paid
+/// Controller only
+#[ic_cdk::query]
+fn get_treasury() -> Result<(u64, Option<Account>), String> {
+    require_controller()?;
+    Ok((
+        TREASURY_BALANCE.with(|t| *t.borrow()),
+        TREASURY_ACCOUNT.with(|t| *t.borrow()),
+    ))
+}
+
+/// Admin: Withdraw collected rake and fees to the treasury account
+/// The ledger fee is deducted from the amount, like player withdrawals
+/// Controller only
+#[ic_cdk::update]
+async fn withdraw_treasury(amount: u64) -> Result<u64, String> {
+    require_controller()?;
+    let account = TREASURY_ACCOUNT.with(|t| *t.borrow())
+        .ok_or("Treasury account not set")?;
+
+    // Deduct before the transfer so concurrent withdrawals can't overdraw
+    TREASURY_BALANCE.with(|t| {
+        let mut treasury = t.borrow_mut();
+        if amount > *treasury {
+            let currency = get_table_currency();
+            return Err(format!("Insufficient treasury balance. Have: {}, requested: {}",
+                currency.format_amount(*treasury),
+                currency.format_amount(amount)));
+        }
+        *treasury -= amount;
+        Ok(())
+    })?;
+
+    match transfer_tokens_to_account(account, amount).await {
+        Ok(block_index) => Ok(block_index),
+        Err(e) => {
+            // Refund the treasury on failure
+            credit_treasury(amount);
+            Err(e)
+        }
+    }
+}
+
 /// Admin: Re-initialize the table (for recovery after upgrade issues)
 /// Controller only
 #[ic_cdk::update]
@@ -2132,6 +3064,7 @@ fn admin_reinit_table(config: TableConfig) -> Result<(), String> {
 
     // Initialize the table
     init_table_state(config);
+    schedule_table_timers();
 
     Ok(())
 }
@@ -2161,20 +3094,24 @@ fn validate_config(config: &TableConfig) -> Result<(), String> {
         return Err("big_blind cannot be more than 10x small_blind".to_string());
     }
 
-    // Validate buy-in
-    if config.min_buy_in == 0 {
-        return Err("min_buy_in must be greater than 0".to_string());
-    }
-    if config.max_buy_in < config.min_buy_in {
-        return Err("max_buy_in must be >= min_buy_in".to_string());
-    }
-    // Standard poker: min buy-in should be at least 20 big blinds
-    if config.min_buy_in < config.big_blind * 10 {
-        return Err("min_buy_in should be at least 10 big blinds".to_string());
-    }
-    // Max buy-in sanity check (1000 big blinds)
-    if config.max_buy_in > config.big_blind * 1000 {
-        return Err("max_buy_in cannot exceed 1000 big blinds".to_string());
+    // Validate buy-in (tournaments use TournamentConfig.buy_in instead)
+    if let Some(ref tournament) = config.tournament {
+        validate_tournament_config(tournament, config.max_players)?;
+    } else {
+        if config.min_buy_in == 0 {
+            return Err("min_buy_in must be greater than 0".to_string());
+        }
+        if config.max_buy_in < config.min_buy_in {
+            return Err("max_buy_in must be >= min_buy_in".to_string());
+        }
+        // Standard poker: min buy-in should be at least 20 big blinds
+        if config.min_buy_in < config.big_blind * 10 {
+            return Err("min_buy_in should be at least 10 big blinds".to_string());
+        }
+        // Max buy-in sanity check (1000 big blinds)
+        if config.max_buy_in > config.big_blind * 1000 {
+            return Err("max_buy_in cannot exceed 1000 big blinds".to_string());
+        }
     }
 
     // Validate timeouts (reasonable bounds)
@@ -2190,6 +3127,96 @@ fn validate_config(config: &TableConfig) -> Result<(), String> {
         return Err("ante cannot exceed big_blind".to_string());
     }
 
+    if let Some(ref straddles) = config.straddles {
+        if config.betting_structure() == BettingStructure::FixedLimit {
+            return Err("straddles are not supported in fixed-limit games".to_string());
+        }
+        // Everyone but the blinds can straddle
+        if straddles.max_straddles == 0 || straddles.max_straddles > config.max_players.saturating_sub(2) {
+            return Err("max_straddles must be between 1 and max_players - 2".to_string());
+        }
+    }
+
+    if let Some(ref entropy) = config.player_entropy {
+        // Every deal waits for the reveals, so keep the wait short
+        if entropy.reveal_timeout_secs == 0 || entropy.reveal_timeout_secs > 60 {
+            return Err("reveal_timeout_secs must be between 1 and 60".to_string());
+        }
+    }
+
+    if let Some(ref mental) = config.mental_poker {
+        if mental.step_timeout_secs == 0 || mental.step_timeout_secs > 120 {
+            return Err("step_timeout_secs must be between 1 and 120".to_string());
+        }
+        // The players shuffle the deck themselves - there is no seed to mix entropy into
+        if config.player_entropy.is_some() {
+            return Err("mental_poker tables can't also take player_entropy".to_string());
+        }
+        // A stalled step voids the hand, which a tournament can't absorb
+        if config.tournament.is_some() {
+            return Err("mental_poker is not supported on tournament tables".to_string());
+        }
+        if config.run_it_twice == Some(true) {
+            return Err("mental_poker tables can't run the board twice".to_string());
+        }
+    }
+
+    if let Some(ref rake) = config.rake {
+        // Tournament chips have no currency value - the house takes the entry fee instead
+        if config.tournament.is_some() {
+            return Err("rake is not supported on tournament tables".to_string());
+        }
+        validate_rake_config(rake)?;
+    }
+
+    Ok(())
+}
+
+/// Validate cash game rake settings
+fn validate_rake_config(config: &RakeConfig) -> Result<(), String> {
+    if config.percent_bps > MAX_RAKE_BPS {
+        return Err(format!("rake percent_bps cannot exceed {}", MAX_RAKE_BPS));
+    }
+    for (i, cap) in config.caps.iter().enumerate() {
+        if cap.min_players < 2 {
+            return Err(format!("rake cap {} must apply to at least 2 players", i + 1));
+        }
+        if config.caps[..i].iter().any(|c| c.min_players == cap.min_players) {
+            return Err(format!("rake cap {} repeats min_players {}", i + 1, cap.min_players));
+        }
+    }
+    Ok(())
+}
+
+/// Validate Sit & Go settings
+fn validate_tournament_config(config: &TournamentConfig, max_players: u8) -> Result<(), String> {
+    if config.buy_in == 0 {
+        return Err("tournament buy_in must be greater than 0".to_string());
+    }
+    if config.blind_levels.is_empty() {
+        return Err("tournament needs at least one blind level".to_string());
+    }
+    for (i, level) in config.blind_levels.iter().enumerate() {
+        if level.small_blind == 0 || level.big_blind < level.small_blind {
+            return Err(format!("blind level {} must have 0 < small_blind <= big_blind", i + 1));
+        }
+        if level.ante > level.big_blind {
+            return Err(format!("blind level {} ante cannot exceed big_blind", i + 1));
+        }
+        // Levels shorter than a minute would advance mid-hand every hand
+        if level.duration_secs < 60 {
+            return Err(format!("blind level {} must last at least 60 seconds", i + 1));
+        }
+    }
+    if config.starting_chips < config.blind_levels[0].big_blind * 10 {
+        return Err("starting_chips should be at least 10 big blinds of the first level".to_string());
+    }
+    if config.payouts_bps.is_empty() || config.payouts_bps.len() > max_players as usize {
+        return Err("payouts_bps must have between 1 and max_players entries".to_string());
+    }
+    if config.payouts_bps.iter().map(|&b| b as u64).sum::<u64>() != 10_000 {
+        return Err("payouts_bps must add up to 10000".to_string());
+    }
     Ok(())
 }
 
@@ -2200,7 +3227,7 @@ fn init_table_state(config: TableConfig) {
     }
 
     // Apply defaults for optional config fields
-    let config = TableConfig {
+    let mut config = TableConfig {
         small_blind: config.small_blind,
         big_blind: config.big_blind,
         min_buy_in: config.min_buy_in,
@@ -2218,7 +3245,32 @@ fn init_table_state(config: TableConfig) {
             config.time_bank_secs
         },
         currency: config.currency, // ICP or BTC
+        game_variant: config.game_variant,
+        betting_structure: config.betting_structure,
+        tournament: config.tournament,
+        rake: config.rake,
+        run_it_twice: config.run_it_twice,
+        straddles: config.straddles,
+        player_entropy: config.player_entropy,
+        mental_poker: config.mental_poker,
     };
+    // Tournament blinds come from the level schedule, starting at level 1
+    if let Some(first) = config.tournament.as_ref().and_then(|t| t.blind_levels.first()).cloned() {
+        config.small_blind = first.small_blind;
+        config.big_blind = first.big_blind;
+        config.ante = first.ante;
+    }
+    let tournament = config.tournament.as_ref().map(|_| TournamentState {
+        status: TournamentStatus::Registering,
+        entrants: Vec::new(),
+        prize_pool: 0,
+        fees_collected: 0,
+        level: 0,
+        level_ends_at: None,
+        started_at: None,
+        finished_at: None,
+        results: Vec::new(),
+    });
 
     // Store config separately so get_max_players works before first hand
     TABLE_CONFIG.with(|c| {
@@ -2252,6 +3304,14 @@ fn init_table_state(config: TableConfig) {
             first_hand: true, // Track first hand for dealer button init
             auto_deal_at: None,
             last_action: None,
+            tournament,
+            mtt: None,
+            run_it_twice: None,
+            second_board: None,
+            straddle: None,
+            dealt_seats: None,
+            entropy_round: None,
+            mental: None,
         });
     });
 
@@ -2265,83 +3325,69 @@ fn init_table_state(config: TableConfig) {
 // DECK & SHUFFLING
 // ============================================================================
 
-/// Creates a standard 52-card deck in a fixed order (Hearts, Diamonds, Clubs, Spades)
-/// Each suit contains cards 2-A in ascending order
-fn create_deck() -> Vec<Card> {
+/// Creates a deck in a fixed order (Hearts, Diamonds, Clubs, Spades)
+/// Each suit contains cards 2-A in ascending order; the short deck starts at 6.
+/// The order itself comes from cleardeck_shuffle, shared with every verifier.
+fn create_deck(deck_type: DeckType) -> Vec<Card> {
     let suits = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
     let ranks = [
         Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
         Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
         Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
     ];
+    let lowest_rank = match deck_type {
+        DeckType::Standard => Rank::Two,
+        DeckType::ShortDeck => Rank::Six,
+    };
 
-    let mut deck = Vec::with_capacity(52);
-    for suit in suits {
-        for rank in ranks {
-            deck.push(Card { suit, rank });
-        }
-    }
-    deck
+    cleardeck_shuffle::deck_order(lowest_rank.value())
+        .into_iter()
+        .map(|(suit, rank)| Card { suit: suits[suit], rank: ranks[(rank - Rank::Two.value()) as usize] })
+        .collect()
 }
 
-/// Shuffles the deck using Fisher-Yates algorithm with SHA256 hash chaining.
+/// Shuffles the deck with cleardeck_shuffle's Fisher-Yates over SHA256 hash chaining,
+/// in the given version (None for proofs from before versions were recorded).
 ///
-/// This is a deterministic shuffle - the same seed always produces the same deck order.
-/// The algorithm is provably fair because:
-/// 1. The seed comes from IC's VRF (Verifiable Random Function)
-/// 2. SHA256 hash chaining ensures each swap is unpredictable without the seed
-/// 3. Anyone can verify by re-running this function with the revealed seed
-///
-/// # Algorithm
-/// For each position i from 51 down to 1:
-///   1. Hash(previous_hash || i) to get deterministic randomness
-///   2. Select position j = random_value mod (i+1)
-///   3. Swap cards at positions i and j
-fn shuffle_deck(deck: &mut Vec<Card>, seed: &[u8]) {
-    let mut hash_input = seed.to_vec();
-
-    for i in (1..deck.len()).rev() {
-        let mut hasher = Sha256::new();
-        hasher.update(&hash_input);
-        hasher.update(&[i as u8]);
-        let hash_result = hasher.finalize();
-
-        // SHA256 always produces 32 bytes, so this slice is always valid
-        let random_value = u64::from_le_bytes([
-            hash_result[0], hash_result[1], hash_result[2], hash_result[3],
-            hash_result[4], hash_result[5], hash_result[6], hash_result[7],
-        ]);
-        let j = (random_value as usize) % (i + 1);
-
-        deck.swap(i, j);
-        hash_input = hash_result.to_vec();
-    }
+/// The history canister, archives and the offline verifier replay hands with the
+/// same crate, so anyone can re-run exactly this shuffle with the revealed seed.
+fn shuffle_deck(deck: &mut [Card], seed: &[u8], version: Option<u32>) -> Result<(), String> {
+    cleardeck_shuffle::shuffle(deck, seed, version)
 }
 
 // ============================================================================
 // HAND EVALUATION
 // ============================================================================
 
-/// Evaluates a player's best 5-card hand from their 2 hole cards and up to 5 community cards.
+/// Evaluates a player's best 5-card hand for the table's game variant.
 ///
-/// Generates all possible 5-card combinations from the 7 available cards and returns
-/// the highest-ranking hand according to standard poker hand rankings:
+/// Hold'em may use any 5 of the 7 cards; Omaha must use exactly 2 hole cards
+/// and exactly 3 community cards. Hands are ranked by standard poker rankings:
 /// Royal Flush > Straight Flush > Four of a Kind > Full House > Flush >
 /// Straight > Three of a Kind > Two Pair > One Pair > High Card
-fn evaluate_hand(hole_cards: &(Card, Card), community: &[Card]) -> HandRank {
+/// Short deck swaps Flush and Full House (see `HandRank::compare`).
+fn evaluate_hand(hole_cards: &[Card], community: &[Card], variant: GameVariant) -> HandRank {
+    let deck = variant.deck_type();
+    match variant {
+        GameVariant::TexasHoldem | GameVariant::ShortDeckHoldem => evaluate_holdem_hand(hole_cards, community, deck),
+        GameVariant::PotLimitOmaha | GameVariant::OmahaHiLo => evaluate_omaha_hand(hole_cards, community, deck),
+    }
+}
+
+/// Best 5-card hand from the hole cards plus community cards, using any combination
+fn evaluate_holdem_hand(hole_cards: &[Card], community: &[Card], deck: DeckType) -> HandRank {
     let mut all_cards: Vec<Card> = Vec::with_capacity(7);
-    all_cards.push(hole_cards.0);
-    all_cards.push(hole_cards.1);
+    all_cards.extend_from_slice(hole_cards);
     all_cards.extend_from_slice(community);
 
     // Generate all 5-card combinations and find the best
     let mut best_rank: Option<HandRank> = None;
 
     for combo in combinations(&all_cards, 5) {
-        let rank = evaluate_five_cards(&combo);
+        let rank = evaluate_five_cards(&combo, deck);
         match &best_rank {
             None => best_rank = Some(rank),
-            Some(current) if rank > *current => best_rank = Some(rank),
+            Some(current) if rank.compare(current, deck).is_gt() => best_rank = Some(rank),
             _ => {}
         }
     }
@@ -2349,6 +3395,27 @@ fn evaluate_hand(hole_cards: &(Card, Card), community: &[Card]) -> HandRank {
     best_rank.unwrap_or(HandRank::HighCard(vec![]))
 }
 
+/// Best Omaha hand: every pair of hole cards combined with every 3 community cards.
+/// A board flush or straight does not count unless two hole cards complete it.
+fn evaluate_omaha_hand(hole_cards: &[Card], community: &[Card], deck: DeckType) -> HandRank {
+    let mut best_rank: Option<HandRank> = None;
+
+    for hole_pair in combinations(hole_cards, 2) {
+        for board_three in combinations(community, 3) {
+            let mut five = hole_pair.clone();
+            five.extend_from_slice(&board_three);
+            let rank = evaluate_five_cards(&five, deck);
+            match &best_rank {
+                None => best_rank = Some(rank),
+                Some(current) if rank.compare(current, deck).is_gt() => best_rank = Some(rank),
+                _ => {}
+            }
+        }
+    }
+
+    best_rank.unwrap_or(HandRank::HighCard(vec![]))
+}
+
 fn combinations(cards: &[Card], k: usize) -> Vec<Vec<Card>> {
     let mut result = Vec::new();
     let n = cards.len();
@@ -2382,7 +3449,7 @@ fn combinations(cards: &[Card], k: usize) -> Vec<Vec<Card>> {
     result
 }
 
-fn evaluate_five_cards(cards: &[Card]) -> HandRank {
+fn evaluate_five_cards(cards: &[Card], deck: DeckType) -> HandRank {
     let mut ranks: Vec<u8> = cards.iter().map(|c| c.rank.value()).collect();
     ranks.sort_by(|a, b| b.cmp(a)); // Sort descending
 
@@ -2395,8 +3462,8 @@ fn evaluate_five_cards(cards: &[Card]) -> HandRank {
     }
 
     let is_flush = suits.values().any(|&count| count >= 5);
-    let is_straight = check_straight(&ranks);
-    let straight_high = if is_straight { get_straight_high(&ranks) } else { 0 };
+    let is_straight = check_straight(&ranks, deck);
+    let straight_high = if is_straight { get_straight_high(&ranks, deck) } else { 0 };
 
     // Royal Flush
     if is_flush && is_straight && straight_high == 14 {
@@ -2479,10 +3546,51 @@ fn evaluate_five_cards(cards: &[Card]) -> HandRank {
     HandRank::HighCard(ranks)
 }
 
+/// Evaluates 5 cards for the low half of a Hi-Lo pot (8 or better).
+///
+/// Aces play low and straights and flushes don't count against a low. All five
+/// ranks must be different and 8 or lower to qualify. Returns the ranks highest
+/// first so that a smaller vector is a better low: 5-4-3-2-A (the wheel) is best.
+fn evaluate_low_five_cards(cards: &[Card]) -> Option<Vec<u8>> {
+    let mut ranks: Vec<u8> = cards.iter()
+        .map(|c| if c.rank == Rank::Ace { 1 } else { c.rank.value() })
+        .collect();
+    ranks.sort_by(|a, b| b.cmp(a));
+    ranks.dedup();
+
+    if ranks.len() != 5 || ranks[0] > 8 {
+        return None;
+    }
+
+    Some(ranks)
+}
+
+/// Best qualifying Omaha low, using exactly 2 hole cards and 3 community cards.
+/// Returns None when the player has no 8-or-better low.
+fn evaluate_omaha_low_hand(hole_cards: &[Card], community: &[Card]) -> Option<Vec<u8>> {
+    let mut best_low: Option<Vec<u8>> = None;
+
+    for hole_pair in combinations(hole_cards, 2) {
+        for board_three in combinations(community, 3) {
+            let mut five = hole_pair.clone();
+            five.extend_from_slice(&board_three);
+            if let Some(low) = evaluate_low_five_cards(&five) {
+                match &best_low {
+                    Some(current) if low >= *current => {}
+                    _ => best_low = Some(low),
+                }
+            }
+        }
+    }
+
+    best_low
+}
+
 /// Detects if ranks form a straight and returns the high card.
 /// Returns Some(high_card) if straight found, None otherwise.
-/// Handles wheel (A-2-3-4-5) as a special case with high card 5.
-fn detect_straight(ranks: &[u8]) -> Option<u8> {
+/// Handles the wheel as a special case: A-2-3-4-5 (high card 5) with a standard
+/// deck, A-6-7-8-9 (high card 9) with a short deck.
+fn detect_straight(ranks: &[u8], deck: DeckType) -> Option<u8> {
     let mut sorted: Vec<u8> = ranks.to_vec();
     sorted.sort_by(|a, b| b.cmp(a));
     sorted.dedup();
@@ -2491,11 +3599,14 @@ fn detect_straight(ranks: &[u8]) -> Option<u8> {
         return None;
     }
 
-    // Check for wheel (A-2-3-4-5) first - it's the lowest straight
-    // Must check before regular straights since A-5-4-3-2 window won't match
-    if sorted.contains(&14) && sorted.contains(&5) && sorted.contains(&4)
-        && sorted.contains(&3) && sorted.contains(&2) {
-        return Some(5); // Wheel's high card is 5
+    // Check for wheel (A-2-3-4-5, or A-6-7-8-9 in short deck) first - it's the lowest straight
+    // Must check before regular straights since the ace-low window won't match
+    let wheel_low_cards: [u8; 4] = match deck {
+        DeckType::Standard => [2, 3, 4, 5],
+        DeckType::ShortDeck => [6, 7, 8, 9],
+    };
+    if sorted.contains(&14) && wheel_low_cards.iter().all(|r| sorted.contains(r)) {
+        return Some(wheel_low_cards[3]); // Wheel's high card is 5 (9 in short deck)
     }
 
     // Check for regular straight (highest first)
@@ -2508,12 +3619,12 @@ fn detect_straight(ranks: &[u8]) -> Option<u8> {
     None
 }
 
-fn check_straight(ranks: &[u8]) -> bool {
-    detect_straight(ranks).is_some()
+fn check_straight(ranks: &[u8], deck: DeckType) -> bool {
+    detect_straight(ranks, deck).is_some()
 }
 
-fn get_straight_high(ranks: &[u8]) -> u8 {
-    detect_straight(ranks).unwrap_or(0)
+fn get_straight_high(ranks: &[u8], deck: DeckType) -> u8 {
+    detect_straight(ranks, deck).unwrap_or(0)
 }
 
 // ============================================================================
@@ -2521,8 +3632,21 @@ fn get_straight_high(ranks: &[u8]) -> u8 {
 // ============================================================================
 
 #[ic_cdk::update]
-async fn start_new_hand() -> Result<ShuffleProof, String> {
+async fn start_new_hand() -> Result<DealOutcome, String> {
     check_rate_limit()?;
+    let result = deal_new_hand().await;
+    schedule_table_timers();
+    result
+}
+
+/// Shuffle and deal the next hand.
+/// Shared by the public start_new_hand endpoint and the auto-deal timer.
+/// With player entropy commitments the first attempt only closes them and
+/// returns AwaitingReveals; the deal comes on the attempt after everyone
+/// reveals or the window closes. Mental-poker tables don't shuffle at all:
+/// the first attempt has the players start shuffling (AwaitingShuffle) and
+/// the hand is dealt from their locked deck once it is ready.
+async fn deal_new_hand() -> Result<DealOutcome, String> {
     // SECURITY: Check all preconditions BEFORE calling raw_rand to prevent cycle drain
     // Any caller can call this, so we must validate everything first
     let precondition_check = TABLE.with(|t| {
@@ -2537,6 +3661,10 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
             return Err("Cannot start new hand: a hand is already in progress".to_string());
         }
 
+        if !can_deal_hands(state) {
+            return Err("Sit & Go is not running".to_string());
+        }
+
         // Count active players BEFORE calling raw_rand to prevent cycle drain
         let active_count = state.players.iter()
             .filter(|p| p.as_ref().map(|p| p.status == PlayerStatus::Active && p.chips > 0).unwrap_or(false))
@@ -2546,33 +3674,67 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
             return Err("Need at least 2 active players with chips".to_string());
         }
 
-        Ok(())
+        Ok((state.config.variant().deck_type(), entropy_stage(state, ic_cdk::api::time())?, mental_deal_stage(state)?))
     });
 
     // Return early if preconditions fail - before any expensive operations
-    precondition_check?;
-
-    // Now safe to call raw_rand - we've verified the hand can actually start
-    let random_bytes = raw_rand().await
-        .map_err(|e| format!("Failed to get randomness: {:?}", e))?;
+    let (deck_type, entropy_stage, mental_stage) = precondition_check?;
 
-    let mut hasher = Sha256::new();
-    hasher.update(&random_bytes);
-    let seed_hash = hex::encode(hasher.finalize());
-    let timestamp = ic_cdk::api::time();
+    // Mental-poker tables draw no seed: the seed hash commits to the players' locked deck
+    let locked_deck_hash = match mental_stage {
+        Some(MentalDealStage::Prepare) => return begin_mental_shuffle(),
+        Some(MentalDealStage::Deal(hash)) => Some(hash),
+        None => None,
+    };
 
-    let mut deck = create_deck();
-    shuffle_deck(&mut deck, &random_bytes);
+    let (random_bytes, entropy) = match entropy_stage {
+        _ if locked_deck_hash.is_some() => (Vec::new(), None),
+        EntropyStage::VrfOnly => {
+            // Now safe to call raw_rand - we've verified the hand can actually start
+            let random_bytes = raw_rand().await
+                .map_err(|e| format!("Failed to get randomness: {:?}", e))?;
+            (random_bytes, None)
+        }
+        EntropyStage::CloseCommitments => return close_entropy_commitments().await,
+        EntropyStage::Mix(round) => {
+            let vrf_seed = CURRENT_VRF_SEED.with(|s| s.borrow().clone())
+                .ok_or("VRF seed for player entropy is missing")?;
+            let nonces: Vec<Vec<u8>> = round.contributions.iter()
+                .filter_map(|c| c.nonce.as_ref().and_then(|n| hex::decode(n).ok()))
+                .collect();
+            let nonces: Vec<&[u8]> = nonces.iter().map(Vec::as_slice).collect();
+            let entropy = PlayerEntropy {
+                vrf_seed_hash: round.vrf_seed_hash.unwrap_or_default(),
+                vrf_seed: None, // Revealed with the seed when the hand ends
+                contributions: round.contributions,
+            };
+            (cleardeck_shuffle::mix_entropy(&vrf_seed, &nonces), Some(entropy))
+        }
+    };
 
-    // Store seed securely - will only be revealed when hand ends
-    CURRENT_SEED.with(|s| {
-        *s.borrow_mut() = Some(random_bytes.clone());
+    let seed_hash = locked_deck_hash.clone().unwrap_or_else(|| {
+        let mut hasher = Sha256::new();
+        hasher.update(&random_bytes);
+        hex::encode(hasher.finalize())
     });
+    let timestamp = ic_cdk::api::time();
+
+    let mut deck = Vec::new();
+    let shuffle_version = if locked_deck_hash.is_some() {
+        None
+    } else {
+        deck = create_deck(deck_type);
+        shuffle_deck(&mut deck, &random_bytes, Some(cleardeck_shuffle::SHUFFLE_VERSION))?;
+        Some(cleardeck_shuffle::SHUFFLE_VERSION)
+    };
 
     let proof = ShuffleProof {
         seed_hash: seed_hash.clone(),
         revealed_seed: None, // Never revealed until hand ends
         timestamp,
+        deck_type: Some(deck_type),
+        entropy: entropy.clone(),
+        shuffle_version,
     };
 
     let result_proof = TABLE.with(|t| {
@@ -2584,6 +3746,12 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
             return Err("Cannot start new hand: a hand is already in progress".to_string());
         }
 
+        // Tournaments: everyone left is dealt in at the current blind level
+        if is_tournament_table(state) {
+            deal_in_tournament_players(state);
+            apply_tournament_blinds(state);
+        }
+
         // Handle players who wanted to sit out next hand
         for player in state.players.iter_mut().flatten() {
             if player.is_sitting_out_next_hand {
@@ -2609,6 +3777,12 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
         if active_count < 2 {
             return Err("Need at least 2 active players with chips".to_string());
         }
+        if locked_deck_hash.is_some() && state.mental.as_ref().is_none_or(|deal| {
+            deal.stage != MentalStage::Ready
+                || deal.participants.iter().map(|p| p.seat).ne(mental_participant_seats(state))
+        }) {
+            return Err("The shuffled deck no longer matches the players in the hand".to_string());
+        }
 
         // Move dealer button - on first hand, find first active player
         if state.first_hand {
@@ -2633,12 +3807,16 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
         state.deck = deck;
         state.deck_index = 0;
         state.community_cards.clear();
+        state.second_board = None;
+        state.run_it_twice = None;
+        state.straddle = None;
         state.pot = 0;
         state.side_pots.clear();
         state.current_bet = state.config.big_blind;
         state.min_raise = state.config.big_blind;
         state.phase = GamePhase::PreFlop;
         state.shuffle_proof = Some(proof.clone());
+        state.entropy_round = None; // Mixed into this hand's seed (or never closed)
         state.hand_number += 1;
         state.last_aggressor = None;
         state.bb_has_option = true; // BB gets option to raise if limped to
@@ -2704,39 +3882,83 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
         }
 
         // Deal hole cards to active players with chips (with bounds checking)
+        // Mental-poker hole cards stay locked - their owners open them privately
+        let cards_per_player = state.config.variant().hole_card_count();
+        let deck_len = state.mental.as_ref().map_or(state.deck.len(), MentalDeal::deck_size);
+        let mut dealt_seats = Vec::new();
         for player in state.players.iter_mut().flatten() {
             if player.status == PlayerStatus::Active {
-                // Check we have enough cards (need 2 cards, so index+2 must be <= len)
-                if state.deck_index + 2 <= state.deck.len() {
-                    let card1 = state.deck[state.deck_index];
-                    let card2 = state.deck[state.deck_index + 1];
-                    player.hole_cards = Some((card1, card2));
-                    state.deck_index += 2;
+                // Check we have enough cards left for this player's full hand
+                if state.deck_index + cards_per_player <= deck_len {
+                    if state.mental.is_none() {
+                        let cards = state.deck[state.deck_index..state.deck_index + cards_per_player].to_vec();
+                        player.hole_cards = Some(cards);
+                    }
+                    state.deck_index += cards_per_player;
+                    dealt_seats.push(player.seat);
                 }
             }
         }
+        state.dealt_seats = Some(dealt_seats.clone());
+
+        // Live straddles (never heads-up) - opting in only lasts for this deal
+        let straddles = match state.config.straddles.clone() {
+            Some(config) if active_count > 2 => post_straddles(state, &config),
+            _ => Vec::new(),
+        };
+        for player in state.players.iter_mut().flatten() {
+            player.straddle_next_hand = None;
+        }
 
-        // Action starts left of big blind
-        state.action_on = find_next_active_seat_with_chips(state, state.big_blind_seat);
+        // Action starts left of big blind (or of the last straddler)
+        state.action_on = find_next_active_seat_with_chips(state, option_seat(state));
 
-        // Start action timer using config timeout
+        // Start action timer using config timeout (mental poker: once the hole cards are open)
         let now = ic_cdk::api::time();
-        let timeout_ns = state.config.action_timeout_secs * 1_000_000_000;
-        state.action_timer = Some(ActionTimer {
-            player_seat: state.action_on,
-            started_at: now,
-            expires_at: now + timeout_ns,
-            using_time_bank: false,
-        });
+        if state.mental.is_some() {
+            open_mental_hole_cards(state, &dealt_seats, cards_per_player, now);
+        } else {
+            let timeout_ns = state.config.action_timeout_secs * 1_000_000_000;
+            state.action_timer = Some(ActionTimer {
+                player_seat: state.action_on,
+                started_at: now,
+                expires_at: now + timeout_ns,
+                using_time_bank: false,
+            });
+        }
 
-        Ok(proof.clone())
+        Ok((proof.clone(), straddles))
     })?;
+    let (result_proof, straddles) = result_proof;
+
+    // Store seed securely - will only be revealed when hand ends.
+    // Done after the deal succeeds so a concurrent caller that lost the race
+    // (e.g. the auto-deal timer vs a client) can't overwrite the live seed.
+    CURRENT_SEED.with(|s| {
+        *s.borrow_mut() = locked_deck_hash.is_none().then_some(random_bytes);
+    });
+    if entropy.is_none() {
+        CURRENT_VRF_SEED.with(|s| *s.borrow_mut() = None);
+    }
 
     // Clear shown cards from previous hand
     SHOWN_CARDS.with(|s| s.borrow_mut().clear());
 
     // Save to history (seed NOT revealed yet - will be revealed when hand ends)
-    CURRENT_ACTIONS.with(|a| a.borrow_mut().clear());
+    // Straddles are the only pre-deal postings recorded as actions
+    CURRENT_ACTIONS.with(|a| {
+        let mut actions = a.borrow_mut();
+        actions.clear();
+        for (seat, amount) in straddles {
+            actions.push(ActionRecord {
+                seat,
+                action: PlayerAction::Straddle(amount),
+                timestamp,
+                phase: "preflop".to_string(),
+                amount,
+            });
+        }
+    });
     HAND_HISTORY.with(|h| {
         h.borrow_mut().push(HandHistory {
             hand_number: TABLE.with(|t| t.borrow().as_ref().map(|s| s.hand_number).unwrap_or(0)),
@@ -2744,78 +3966,311 @@ async fn start_new_hand() -> Result<ShuffleProof, String> {
                 seed_hash,
                 revealed_seed: None, // Will be set when hand completes
                 timestamp,
+                deck_type: Some(deck_type),
+                entropy,
+                shuffle_version,
             },
             actions: Vec::new(),
             winners: Vec::new(),
             community_cards: Vec::new(),
             showdown_players: Vec::new(),
+            game_variant: TABLE.with(|t| t.borrow().as_ref().map(|s| s.config.variant())),
+            second_board: None,
         });
     });
 
-    Ok(result_proof)
+    Ok(DealOutcome::Dealt(result_proof))
 }
 
-/// Reveal the seed and update both table state and history
-/// Called only when hand ends (showdown or single winner by fold)
-fn reveal_seed_on_hand_end(state: &mut TableState) {
-    // Get the stored seed and reveal it
-    let revealed = CURRENT_SEED.with(|s| {
-        s.borrow_mut().take().map(|seed| hex::encode(&seed))
-    });
-
-    // Get the expected seed_hash from the current state to ensure correct matching
-    let expected_seed_hash = state.shuffle_proof.as_ref().map(|p| p.seed_hash.clone());
-
-    // Update table state's shuffle proof
-    if let Some(ref mut proof) = state.shuffle_proof {
-        proof.revealed_seed = revealed.clone();
-    }
+/// Where the next hand's player entropy round stands when a deal is attempted
+enum EntropyStage {
+    VrfOnly, // Nobody committed - the seed is the VRF bytes
+    CloseCommitments, // Draw the VRF bytes and open the reveal window - the deal comes after
+    Mix(EntropyRound), // Everyone revealed or the window closed - mix what was revealed
+}
 
-    // Update history's shuffle proof - find entry by hand_number AND seed_hash for safety
-    HAND_HISTORY.with(|h| {
-        let mut history = h.borrow_mut();
-        // Find the entry matching this hand's hand_number and seed_hash
-        if let Some(entry) = history.iter_mut().rev().find(|e| {
-            e.hand_number == state.hand_number &&
-            expected_seed_hash.as_ref().map_or(true, |hash| &e.shuffle_proof.seed_hash == hash)
-        }) {
-            entry.shuffle_proof.revealed_seed = revealed;
+fn entropy_stage(state: &TableState, now: u64) -> Result<EntropyStage, String> {
+    let round = match state.entropy_round {
+        Some(ref round) if round.hand_number == state.hand_number + 1 && !round.contributions.is_empty() => round,
+        _ => return Ok(EntropyStage::VrfOnly),
+    };
+    match round.reveal_deadline {
+        None => Ok(EntropyStage::CloseCommitments),
+        Some(deadline) if now >= deadline || round.contributions.iter().all(|c| c.nonce.is_some()) => {
+            Ok(EntropyStage::Mix(round.clone()))
         }
-    });
+        Some(_) => Err("Waiting for players to reveal their entropy".to_string()),
+    }
 }
 
-/// Find next active seat that can act (not folded, not all-in)
-fn find_next_active_seat(state: &TableState, from_seat: u8) -> u8 {
-    let num_seats = state.players.len();
-    let mut seat = (from_seat as usize + 1) % num_seats;
+/// Close commitments for the next hand: draw its VRF bytes, publish their hash and
+/// give players reveal_timeout_secs to reveal. The VRF bytes are fixed before any
+/// nonce is known and every nonce was fixed before the VRF bytes existed, so
+/// neither side can steer the seed. The auto-deal timer deals once the window closes.
+async fn close_entropy_commitments() -> Result<DealOutcome, String> {
+    let vrf_seed = raw_rand().await
+        .map_err(|e| format!("Failed to get randomness: {:?}", e))?;
+    let now = ic_cdk::api::time();
 
-    for _ in 0..num_seats {
-        if let Some(ref player) = state.players[seat] {
-            if player.status == PlayerStatus::Active && !player.has_folded && !player.is_all_in {
-                return seat as u8;
-            }
-        }
-        seat = (seat + 1) % num_seats;
-    }
+    let reveal_deadline = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let timeout_secs = state.config.player_entropy.as_ref().map_or(0, |e| e.reveal_timeout_secs);
+        let deadline = now + timeout_secs * 1_000_000_000;
+
+        let round = state.entropy_round.as_mut()
+            .filter(|r| r.reveal_deadline.is_none())
+            .ok_or("Entropy commitments are already closed")?;
+        round.vrf_seed_hash = Some(cleardeck_shuffle::seed_hash(&vrf_seed));
+        round.reveal_deadline = Some(deadline);
+        state.auto_deal_at = Some(deadline); // Brought forward once everyone has revealed
+        Ok::<u64, String>(deadline)
+    })?;
+    CURRENT_VRF_SEED.with(|s| *s.borrow_mut() = Some(vrf_seed));
 
-    from_seat
+    Ok(DealOutcome::AwaitingReveals { reveal_deadline })
 }
 
-/// Find next active seat with chips (for dealer/blind positions)
-fn find_next_active_seat_with_chips(state: &TableState, from_seat: u8) -> u8 {
-    let num_seats = state.players.len();
-    let mut seat = (from_seat as usize + 1) % num_seats;
+/// Commit to a nonce for the next hand (player_entropy tables only).
+/// `commitment` is the hex SHA-256 of a 32-byte nonce the client keeps secret
+/// until the deal closes commitments. Committing again replaces the earlier one.
+/// Returns the hand number the nonce will be mixed into.
+#[ic_cdk::update]
+fn commit_entropy(commitment: String) -> Result<u64, String> {
+    let caller = ic_cdk::api::msg_caller();
+    let commitment = commitment.to_lowercase();
+    if commitment.len() != 64 || hex::decode(&commitment).is_err() {
+        return Err("Commitment must be a hex SHA-256 hash".to_string());
+    }
 
-    for _ in 0..num_seats {
-        if let Some(ref player) = state.players[seat] {
-            if player.status == PlayerStatus::Active && player.chips > 0 {
-                return seat as u8;
-            }
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        if state.config.player_entropy.is_none() {
+            return Err("This table does not take player entropy".to_string());
         }
-        seat = (seat + 1) % num_seats;
-    }
+        let seat = state.players.iter()
+            .flatten()
+            .find(|p| p.principal == caller)
+            .map(|p| p.seat)
+            .ok_or("Not at table")?;
 
-    from_seat
+        let hand_number = state.hand_number + 1;
+        if state.entropy_round.as_ref().is_none_or(|r| r.hand_number != hand_number) {
+            state.entropy_round = Some(EntropyRound {
+                hand_number,
+                contributions: Vec::new(),
+                vrf_seed_hash: None,
+                reveal_deadline: None,
+            });
+        }
+        let round = state.entropy_round.as_mut().ok_or("Table not initialized")?;
+        if round.reveal_deadline.is_some() {
+            return Err("Commitments for the next hand are closed".to_string());
+        }
+
+        round.contributions.retain(|c| c.seat != seat);
+        round.contributions.push(EntropyContribution { seat, principal: caller, commitment, nonce: None });
+        round.contributions.sort_by_key(|c| c.seat);
+        Ok(hand_number)
+    })
+}
+
+/// Reveal the nonce committed to with commit_entropy, once the deal has closed commitments.
+/// The hand is dealt as soon as every committed player has revealed.
+#[ic_cdk::update]
+fn reveal_entropy(nonce: String) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+    let nonce = hex::decode(&nonce).map_err(|_| "Nonce is not valid hex".to_string())?;
+    if nonce.len() != cleardeck_shuffle::NONCE_LEN {
+        return Err(format!("Nonce must be {} bytes", cleardeck_shuffle::NONCE_LEN));
+    }
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let round = state.entropy_round.as_mut().ok_or("No entropy round is open")?;
+        match round.reveal_deadline {
+            None => return Err("Commitments are still open - reveal once the deal closes them".to_string()),
+            Some(deadline) if now >= deadline => return Err("The reveal window has closed".to_string()),
+            Some(_) => {}
+        }
+
+        let contribution = round.contributions.iter_mut()
+            .find(|c| c.principal == caller)
+            .ok_or("You have no commitment for this hand")?;
+        if contribution.nonce.is_some() {
+            return Err("Nonce already revealed".to_string());
+        }
+        if cleardeck_shuffle::seed_hash(&nonce) != contribution.commitment {
+            return Err("Nonce does not match your commitment".to_string());
+        }
+        contribution.nonce = Some(hex::encode(&nonce));
+
+        if round.contributions.iter().all(|c| c.nonce.is_some()) {
+            state.auto_deal_at = Some(now);
+        }
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
+/// Seat with the last pre-flop option: the last straddler, or the big blind
+fn option_seat(state: &TableState) -> u8 {
+    state.straddle.map(|(seat, _)| seat).unwrap_or(state.big_blind_seat)
+}
+
+/// Plan this hand's straddles as (seat, amount) in posting order.
+/// `order` runs clockwise from UTG to the button as (seat, chips, opted in); each straddle
+/// doubles the previous blind and the chain stops at the first player who can't or won't.
+/// A button straddle, when allowed and taken, replaces the UTG chain.
+fn plan_straddles(order: &[(u8, u64, bool)], big_blind: u64, config: &StraddleConfig) -> Vec<(u8, u64)> {
+    let straddle = big_blind.saturating_mul(2);
+    if config.allow_button_straddle {
+        if let Some(&(seat, chips, true)) = order.last() {
+            if chips > straddle {
+                return vec![(seat, straddle)];
+            }
+        }
+    }
+
+    let mut amount = straddle;
+    let mut straddles = Vec::new();
+    for &(seat, chips, opted_in) in order.iter().take(config.max_straddles as usize) {
+        if !opted_in || chips <= amount {
+            break;
+        }
+        straddles.push((seat, amount));
+        amount = amount.saturating_mul(2);
+    }
+    straddles
+}
+
+/// Post the straddles players opted in to after the blinds; returns them for the action history
+fn post_straddles(state: &mut TableState, config: &StraddleConfig) -> Vec<(u8, u64)> {
+    // UTG round to the button - the blinds can't straddle
+    let mut order = Vec::new();
+    let mut seat = find_next_active_seat_with_chips(state, state.big_blind_seat);
+    while seat != state.small_blind_seat && seat != state.big_blind_seat && order.len() < state.players.len() {
+        if let Some(ref p) = state.players[seat as usize] {
+            order.push((seat, p.chips, p.straddle_next_hand.unwrap_or(false)));
+        }
+        seat = find_next_active_seat_with_chips(state, seat);
+    }
+
+    let straddles = plan_straddles(&order, state.config.big_blind, config);
+    for &(seat, amount) in &straddles {
+        if let Some(ref mut p) = state.players[seat as usize] {
+            p.chips -= amount;
+            p.current_bet = amount;
+            p.total_bet_this_hand = p.total_bet_this_hand.saturating_add(amount);
+        }
+        state.pot = state.pot.saturating_add(amount);
+        state.current_bet = amount;
+        state.min_raise = amount; // Raising a straddle means at least doubling it
+        state.straddle = Some((seat, amount));
+        state.bb_has_option = true; // The option moves to the straddler
+    }
+    straddles
+}
+
+/// Opt in or out of straddling on the next deal (tables with straddles only)
+/// Opting in only covers one hand - it is cleared whenever a hand is dealt
+#[ic_cdk::update]
+fn set_straddle(enabled: bool) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        if enabled && state.config.straddles.is_none() {
+            return Err("This table does not allow straddles".to_string());
+        }
+
+        let player = state.players.iter_mut()
+            .flatten()
+            .find(|p| p.principal == caller)
+            .ok_or("Not at table")?;
+        player.straddle_next_hand = Some(enabled);
+        Ok(())
+    })
+}
+
+/// Reveal the seed and update both table state and history
+/// Called only when hand ends (showdown or single winner by fold)
+fn reveal_seed_on_hand_end(state: &mut TableState) {
+    // Get the stored seed and reveal it
+    let revealed = CURRENT_SEED.with(|s| {
+        s.borrow_mut().take().map(|seed| hex::encode(&seed))
+    });
+
+    // Player entropy tables also reveal the VRF bytes the nonces were mixed with
+    let vrf_seed = CURRENT_VRF_SEED.with(|s| {
+        s.borrow_mut().take().map(|seed| hex::encode(&seed))
+    });
+
+    // Get the expected seed_hash from the current state to ensure correct matching
+    let expected_seed_hash = state.shuffle_proof.as_ref().map(|p| p.seed_hash.clone());
+
+    // Update table state's shuffle proof
+    if let Some(ref mut proof) = state.shuffle_proof {
+        proof.revealed_seed = revealed.clone();
+        if let Some(ref mut entropy) = proof.entropy {
+            entropy.vrf_seed = vrf_seed.clone();
+        }
+    }
+
+    // Update history's shuffle proof - find entry by hand_number AND seed_hash for safety
+    HAND_HISTORY.with(|h| {
+        let mut history = h.borrow_mut();
+        // Find the entry matching this hand's hand_number and seed_hash
+        if let Some(entry) = history.iter_mut().rev().find(|e| {
+            e.hand_number == state.hand_number &&
+            expected_seed_hash.as_ref().map_or(true, |hash| &e.shuffle_proof.seed_hash == hash)
+        }) {
+            entry.shuffle_proof.revealed_seed = revealed;
+            if let Some(ref mut entropy) = entry.shuffle_proof.entropy {
+                entropy.vrf_seed = vrf_seed;
+            }
+        }
+    });
+}
+
+/// Find next active seat that can act (not folded, not all-in)
+fn find_next_active_seat(state: &TableState, from_seat: u8) -> u8 {
+    let num_seats = state.players.len();
+    let mut seat = (from_seat as usize + 1) % num_seats;
+
+    for _ in 0..num_seats {
+        if let Some(ref player) = state.players[seat] {
+            if player.status == PlayerStatus::Active && !player.has_folded && !player.is_all_in {
+                return seat as u8;
+            }
+        }
+        seat = (seat + 1) % num_seats;
+    }
+
+    from_seat
+}
+
+/// Find next active seat with chips (for dealer/blind positions)
+fn find_next_active_seat_with_chips(state: &TableState, from_seat: u8) -> u8 {
+    let num_seats = state.players.len();
+    let mut seat = (from_seat as usize + 1) % num_seats;
+
+    for _ in 0..num_seats {
+        if let Some(ref player) = state.players[seat] {
+            if player.status == PlayerStatus::Active && player.chips > 0 {
+                return seat as u8;
+            }
+        }
+        seat = (seat + 1) % num_seats;
+    }
+
+    from_seat
 }
 
 fn count_active_players(state: &TableState) -> usize {
@@ -2824,12 +4279,741 @@ fn count_active_players(state: &TableState) -> usize {
         .count()
 }
 
-fn count_players_can_act(state: &TableState) -> usize {
-    state.players.iter()
-        .filter(|p| p.as_ref().map(|p| {
-            !p.has_folded && !p.is_all_in && p.status == PlayerStatus::Active
-        }).unwrap_or(false))
-        .count()
+fn count_players_can_act(state: &TableState) -> usize {
+    state.players.iter()
+        .filter(|p| p.as_ref().map(|p| {
+            !p.has_folded && !p.is_all_in && p.status == PlayerStatus::Active
+        }).unwrap_or(false))
+        .count()
+}
+
+/// Largest total bet ("raise to") allowed under pot-limit rules.
+/// The player may raise by the size of the pot after they call, so the cap is
+/// current_bet + (pot + amount_to_call). With no bet yet this is simply the pot.
+fn pot_limit_max_bet(pot: u64, current_bet: u64, player_current_bet: u64) -> u64 {
+    let to_call = current_bet.saturating_sub(player_current_bet);
+    current_bet.saturating_add(pot.saturating_add(to_call))
+}
+
+/// Fixed-limit bet size for the current street: the small bet (big blind)
+/// preflop and on the flop, the big bet (twice the big blind) on the turn and river
+fn fixed_limit_bet_size(state: &TableState) -> u64 {
+    match state.phase {
+        GamePhase::Turn | GamePhase::River => state.config.big_blind.saturating_mul(2),
+        _ => state.config.big_blind,
+    }
+}
+
+/// Smallest and largest total bet ("bet/raise to") the player may make this street
+/// under the table's betting structure, before capping at their stack.
+/// Returns None when fixed-limit raising is capped for the street.
+fn bet_limits(state: &TableState, player_current_bet: u64) -> Option<(u64, u64)> {
+    let min_total = if state.current_bet == 0 {
+        state.config.big_blind
+    } else {
+        state.current_bet.saturating_add(state.min_raise)
+    };
+
+    match state.config.betting_structure() {
+        BettingStructure::NoLimit => Some((min_total, u64::MAX)),
+        BettingStructure::PotLimit => {
+            let max_total = pot_limit_max_bet(state.pot, state.current_bet, player_current_bet);
+            Some((min_total, max_total.max(min_total)))
+        }
+        BettingStructure::FixedLimit => {
+            let total = fixed_limit_raise_to(
+                state.current_bet,
+                fixed_limit_bet_size(state),
+                count_active_players(state),
+            )?;
+            Some((total, total))
+        }
+    }
+}
+
+/// The only total a fixed-limit bet or raise may go to, or None once the street
+/// is capped (a bet plus FIXED_LIMIT_MAX_RAISES raises; no cap heads-up).
+/// Preflop the big blind counts as the bet. Bets are counted rounding down,
+/// so an incomplete all-in raise doesn't use up a full raise.
+fn fixed_limit_raise_to(current_bet: u64, bet_size: u64, players_in_hand: usize) -> Option<u64> {
+    let bets_made = current_bet / bet_size.max(1);
+    if players_in_hand > 2 && bets_made > FIXED_LIMIT_MAX_RAISES {
+        return None;
+    }
+    Some(bets_made.saturating_add(1).saturating_mul(bet_size))
+}
+
+// ============================================================================
+// MENTAL POKER
+// ============================================================================
+// On mental_poker tables the players deal the hand between them with
+// cleardeck_sra and the table only coordinates: it orders the steps, checks
+// what it can when a step arrives, holds the ciphertexts and times every step
+// out. Before the hand each participant shuffles, then locks, the deck in seat
+// order. Cards are opened by collecting one decryption exponent per
+// participant, each checked against the hash published with the lock step -
+// hole cards get every key but the owner's, who strips the last layer
+// privately. Once the betting is over everyone reveals their secret and the
+// table checks every step they made, one player per timer tick, before the pot
+// is paid. A stalled step or a failed audit voids the hand: see void_hand_payouts.
+
+/// Where the next hand's mental-poker deal stands when a deal is attempted
+enum MentalDealStage {
+    Prepare, // No deck for the players about to be dealt in - they start shuffling
+    Deal(String), // The locked deck is ready - hex SHA-256 of its ciphertexts
+}
+
+fn mental_deal_stage(state: &TableState) -> Result<Option<MentalDealStage>, String> {
+    if state.config.mental_poker.is_none() {
+        return Ok(None);
+    }
+    let deal = match state.mental {
+        Some(ref deal) if deal.hand_number == state.hand_number + 1 => deal,
+        _ => return Ok(Some(MentalDealStage::Prepare)),
+    };
+    match deal.stage {
+        MentalStage::Shuffle | MentalStage::Lock => Err("Waiting for players to shuffle the deck".to_string()),
+        MentalStage::Ready if deal.participants.iter().map(|p| p.seat).eq(mental_participant_seats(state)) => {
+            let locked = deal.decks.last().map(|deck| deck.concat()).unwrap_or_default();
+            Ok(Some(MentalDealStage::Deal(cleardeck_shuffle::seed_hash(&locked))))
+        }
+        // Someone joined, left or sat out since the shuffle - it starts over
+        _ => Ok(Some(MentalDealStage::Prepare)),
+    }
+}
+
+/// Seats the next hand would be dealt to
+fn mental_participant_seats(state: &TableState) -> Vec<u8> {
+    state.players.iter()
+        .flatten()
+        .filter(|p| p.status == PlayerStatus::Active && p.chips > 0 && !p.is_sitting_out_next_hand)
+        .map(|p| p.seat)
+        .collect()
+}
+
+fn mental_step_deadline(state: &TableState, now: u64) -> u64 {
+    let timeout_secs = state.config.mental_poker.as_ref().map_or(0, |m| m.step_timeout_secs);
+    now + timeout_secs * 1_000_000_000
+}
+
+fn is_hex_hash(hash: &str) -> bool {
+    hash.len() == 64 && hex::decode(hash).is_ok()
+}
+
+/// Have the players about to be dealt in start shuffling the next hand's deck
+fn begin_mental_shuffle() -> Result<DealOutcome, String> {
+    let now = ic_cdk::api::time();
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let step_deadline = start_mental_shuffle(state, now)
+            .ok_or("Need at least 2 active players with chips")?;
+        Ok(DealOutcome::AwaitingShuffle { step_deadline })
+    })
+}
+
+/// Replace the table's deal with a fresh one for the next hand, starting from
+/// the plain deck. Returns the first step's deadline (None, and no deal, with
+/// fewer than 2 players to deal in).
+fn start_mental_shuffle(state: &mut TableState, now: u64) -> Option<u64> {
+    let seats = mental_participant_seats(state);
+    if seats.len() < 2 {
+        state.mental = None;
+        return None;
+    }
+
+    let participants = seats.iter()
+        .filter_map(|&seat| state.players[seat as usize].as_ref())
+        .map(|p| MentalParticipant {
+            seat: p.seat,
+            principal: p.principal,
+            secret_commitment: None,
+            key_hashes: Vec::new(),
+            secret: None,
+            audit_passed: None,
+        })
+        .collect();
+    let deck_size = create_deck(state.config.variant().deck_type()).len();
+    let plain_deck = cleardeck_sra::plain_deck(deck_size).iter().map(cleardeck_sra::to_bytes).collect();
+    let deadline = mental_step_deadline(state, now);
+    let deal_id = state.mental.as_ref().map_or(now, |deal| now.max(deal.deal_id + 1));
+
+    state.mental = Some(MentalDeal {
+        deal_id,
+        hand_number: state.hand_number + 1,
+        stage: MentalStage::Shuffle,
+        deadline: Some(deadline),
+        participants,
+        decks: vec![plain_deck],
+        cards: Vec::new(),
+        opening: Vec::new(),
+        corrupt: false,
+        forfeits: Vec::new(),
+    });
+    state.auto_deal_at = None; // Dealt as soon as the deck is locked
+    Some(deadline)
+}
+
+/// The participant taking a shuffle or lock step - it must be the caller's turn at that stage
+fn mental_turn(deal: &MentalDeal, stage: MentalStage, caller: Principal) -> Result<usize, String> {
+    if deal.stage != stage {
+        return Err(format!("The deal is at the {:?} stage", deal.stage));
+    }
+    deal.turn()
+        .filter(|&index| deal.participants[index].principal == caller)
+        .ok_or_else(|| "Not your turn".to_string())
+}
+
+/// What the table can check about a shuffled or locked deck without any keys:
+/// the right number of well-formed ciphertexts, none repeated
+fn check_mental_deck(deck: &[Vec<u8>], deck_size: usize) -> Result<(), String> {
+    if deck.len() != deck_size {
+        return Err(format!("Deck must have {} cards", deck_size));
+    }
+    if cleardeck_sra::deck_from_bytes(deck).is_none() {
+        return Err(format!("Cards must be {}-byte ciphertexts below the prime", cleardeck_sra::CIPHERTEXT_LEN));
+    }
+    let mut cards: Vec<&Vec<u8>> = deck.iter().collect();
+    cards.sort();
+    cards.dedup();
+    if cards.len() != deck_size {
+        return Err("Deck has repeated cards".to_string());
+    }
+    Ok(())
+}
+
+/// Shuffle step (mental_poker tables, on your turn): `deck` is cleardeck_sra::shuffle_step
+/// of the last deck in get_mental_deal, each card encoded with to_bytes.
+/// `secret_commitment` is the hex SHA-256 of the secret all your keys are derived from.
+#[ic_cdk::update]
+fn submit_shuffle(deal_id: u64, deck: Vec<Vec<u8>>, secret_commitment: String) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+    let commitment = secret_commitment.to_lowercase();
+    if !is_hex_hash(&commitment) {
+        return Err("Commitment must be a hex SHA-256 hash".to_string());
+    }
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let deadline = mental_step_deadline(state, now);
+        let deal = state.mental.as_mut()
+            .filter(|deal| deal.deal_id == deal_id)
+            .ok_or("No such mental-poker deal")?;
+        let index = mental_turn(deal, MentalStage::Shuffle, caller)?;
+        check_mental_deck(&deck, deal.deck_size())?;
+
+        deal.participants[index].secret_commitment = Some(commitment);
+        deal.decks.push(deck);
+        if deal.decks.len() > deal.participants.len() {
+            deal.stage = MentalStage::Lock;
+        }
+        deal.deadline = Some(deadline);
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
+/// Lock step (mental_poker tables, on your turn, once everyone has shuffled): `deck` is
+/// cleardeck_sra::lock_step of the last deck in get_mental_deal. `key_hashes` has the
+/// exponent_hash of every position key, in position order - every key you hand over
+/// later is checked against it. The hand is dealt once the last player has locked.
+#[ic_cdk::update]
+fn submit_lock(deal_id: u64, deck: Vec<Vec<u8>>, key_hashes: Vec<String>) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+    let key_hashes: Vec<String> = key_hashes.iter().map(|hash| hash.to_lowercase()).collect();
+    if !key_hashes.iter().all(|hash| is_hex_hash(hash)) {
+        return Err("Key hashes must be hex SHA-256 hashes".to_string());
+    }
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let deadline = mental_step_deadline(state, now);
+        let deal = state.mental.as_mut()
+            .filter(|deal| deal.deal_id == deal_id)
+            .ok_or("No such mental-poker deal")?;
+        let index = mental_turn(deal, MentalStage::Lock, caller)?;
+        check_mental_deck(&deck, deal.deck_size())?;
+        if key_hashes.len() != deal.deck_size() {
+            return Err(format!("Need a key hash for each of the {} positions", deal.deck_size()));
+        }
+
+        deal.participants[index].key_hashes = key_hashes;
+        deal.decks.push(deck);
+        let ready = deal.decks.len() > 2 * deal.participants.len();
+        if ready {
+            deal.stage = MentalStage::Ready;
+            deal.deadline = None;
+            state.auto_deal_at = Some(now);
+        } else {
+            deal.deadline = Some(deadline);
+        }
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
+/// A position of the locked deck, as it stood before any key was handed over
+fn mental_locked_card(deal: &MentalDeal, position: u32) -> Vec<u8> {
+    deal.decks.last()
+        .and_then(|deck| deck.get(position as usize))
+        .cloned()
+        .unwrap_or_default()
+}
+
+/// Deal the hole cards from the locked deck - in dealing order, like the plain
+/// deck - and collect everyone else's keys to them. The action timer starts once they're open.
+fn open_mental_hole_cards(state: &mut TableState, dealt_seats: &[u8], cards_per_player: usize, now: u64) {
+    let deadline = mental_step_deadline(state, now);
+    state.action_timer = None;
+    let deal = match state.mental.as_mut() {
+        Some(deal) => deal,
+        None => return,
+    };
+
+    for (i, &seat) in dealt_seats.iter().enumerate() {
+        for position in (i * cards_per_player..(i + 1) * cards_per_player).map(|p| p as u32) {
+            let partial = mental_locked_card(deal, position);
+            deal.cards.push(MentalCard { position, owner: Some(seat), keys: Vec::new(), partial, card: None });
+        }
+    }
+    deal.opening = deal.cards.iter().map(|card| card.position).collect();
+    deal.stage = MentalStage::OpenHoleCards;
+    deal.deadline = Some(deadline);
+}
+
+/// Collect everyone's keys to the next street's cards (burning one first, like
+/// the plain deck), or to the rest of the board when `runout`
+fn open_mental_board(state: &mut TableState, now: u64, runout: bool) {
+    let deadline = mental_step_deadline(state, now);
+    let mut positions = Vec::new();
+    let mut board = state.community_cards.len();
+    while board < 5 {
+        let count = if board == 0 { 3 } else { 1 };
+        state.deck_index += 1; // Burn
+        positions.extend((state.deck_index..state.deck_index + count).map(|p| p as u32));
+        state.deck_index += count;
+        board += count;
+        if !runout {
+            break;
+        }
+    }
+
+    state.action_timer = None;
+    let deal = match state.mental.as_mut() {
+        Some(deal) => deal,
+        None => return,
+    };
+    for &position in &positions {
+        let partial = mental_locked_card(deal, position);
+        deal.cards.push(MentalCard { position, owner: None, keys: Vec::new(), partial, card: None });
+    }
+    deal.opening = positions;
+    deal.stage = if runout { MentalStage::OpenRunout } else { MentalStage::OpenBoard };
+    deal.deadline = Some(deadline);
+}
+
+/// Showdown: players still in the hand hand over the last key to their own hole cards
+fn open_mental_showdown(state: &mut TableState, now: u64) {
+    let deadline = mental_step_deadline(state, now);
+    let in_hand: Vec<u8> = state.players.iter()
+        .flatten()
+        .filter(|p| !p.has_folded)
+        .map(|p| p.seat)
+        .collect();
+    state.action_timer = None;
+    let deal = match state.mental.as_mut() {
+        Some(deal) => deal,
+        None => return,
+    };
+
+    deal.opening = deal.cards.iter()
+        .filter(|card| card.card.is_none() && card.owner.is_some_and(|seat| in_hand.contains(&seat)))
+        .map(|card| card.position)
+        .collect();
+    deal.stage = MentalStage::OpenShowdown;
+    deal.deadline = Some(deadline);
+    if deal.opening.is_empty() {
+        finish_mental_open(state, now);
+    }
+}
+
+/// Hand over your keys to the cards being opened (mental_poker tables). Each exponent
+/// is cleardeck_sra::to_bytes of Key::for_position(secret, position).decryption_exponent()
+/// and must match the hash you published with your lock step. Your own hole cards
+/// are only asked for at showdown - get_mental_deal has what's left of them after
+/// everyone else's keys, for you to strip your last layer privately.
+#[ic_cdk::update]
+fn submit_card_keys(deal_id: u64, keys: Vec<MentalCardKey>) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let deck = create_deck(state.config.variant().deck_type());
+        let deal = state.mental.as_mut()
+            .filter(|deal| deal.deal_id == deal_id)
+            .ok_or("No such mental-poker deal")?;
+        if !matches!(deal.stage, MentalStage::OpenHoleCards | MentalStage::OpenBoard
+            | MentalStage::OpenRunout | MentalStage::OpenShowdown) {
+            return Err("No cards are being opened".to_string());
+        }
+        let participant = deal.participants.iter()
+            .find(|p| p.principal == caller)
+            .ok_or("You are not in this deal")?;
+        let seat = participant.seat;
+
+        // Check every key before taking any
+        let mut accepted = Vec::new();
+        for key in keys {
+            let index = deal.cards.iter()
+                .position(|card| card.position == key.position && deal.owes_key(card, seat))
+                .filter(|index| !accepted.iter().any(|(i, _, _)| i == index))
+                .ok_or_else(|| format!("No key is owed for position {}", key.position))?;
+            let exponent = cleardeck_sra::from_bytes(&key.exponent)
+                .ok_or_else(|| format!("Keys must be {} bytes", cleardeck_sra::CIPHERTEXT_LEN))?;
+            if participant.key_hashes.get(key.position as usize) != Some(&cleardeck_sra::exponent_hash(&exponent)) {
+                return Err(format!("Key for position {} doesn't match your lock step", key.position));
+            }
+            accepted.push((index, exponent, key.exponent));
+        }
+
+        let participant_count = deal.participants.len();
+        for (index, exponent, bytes) in accepted {
+            let card = &mut deal.cards[index];
+            let partial = cleardeck_sra::from_bytes(&card.partial).ok_or("Card is not a ciphertext")?;
+            let partial = cleardeck_sra::remove_layer(&partial, &exponent);
+            card.keys.push((seat, bytes));
+            if card.keys.len() == participant_count {
+                // Every layer is off - anything but a card means someone's step was bad
+                card.card = cleardeck_sra::decode_card(&partial, deck.len()).map(|i| deck[i]);
+                deal.corrupt |= card.card.is_none();
+            }
+            card.partial = cleardeck_sra::to_bytes(&partial);
+        }
+
+        if deal.owing_seats().is_empty() {
+            finish_mental_open(state, now);
+        }
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
+/// Every key for the current Open* stage is in (or the showdown timed out): carry on with the hand
+fn finish_mental_open(state: &mut TableState, now: u64) {
+    let deal = match state.mental.as_mut() {
+        Some(deal) => deal,
+        None => return,
+    };
+    let stage = deal.stage;
+    let opened: Vec<Card> = deal.opening.iter()
+        .filter_map(|&position| deal.cards.iter().find(|card| card.position == position))
+        .filter_map(|card| card.card)
+        .collect();
+    deal.opening.clear();
+    deal.deadline = None;
+    deal.stage = MentalStage::Betting;
+    if deal.corrupt {
+        begin_mental_audit(state, now);
+        return;
+    }
+
+    match stage {
+        MentalStage::OpenHoleCards => {
+            let timeout_ns = state.config.action_timeout_secs * 1_000_000_000;
+            state.action_timer = Some(ActionTimer {
+                player_seat: state.action_on,
+                started_at: now,
+                expires_at: now + timeout_ns,
+                using_time_bank: false,
+            });
+        }
+        MentalStage::OpenBoard => {
+            state.community_cards.extend(opened);
+            state.phase = match state.community_cards.len() {
+                3 => GamePhase::Flop,
+                4 => GamePhase::Turn,
+                _ => GamePhase::River,
+            };
+            start_street_action(state, now);
+        }
+        MentalStage::OpenRunout => {
+            state.community_cards.extend(opened);
+            state.phase = GamePhase::Showdown;
+            determine_winners(state);
+        }
+        MentalStage::OpenShowdown => {
+            // Hands whose every card is open are shown; the rest were mucked
+            for player in state.players.iter_mut().flatten().filter(|p| !p.has_folded) {
+                let cards: Option<Vec<Card>> = deal.cards.iter()
+                    .filter(|card| card.owner == Some(player.seat))
+                    .map(|card| card.card)
+                    .collect();
+                player.hole_cards = cards.filter(|cards| !cards.is_empty());
+            }
+            begin_mental_audit(state, now);
+        }
+        _ => {}
+    }
+}
+
+/// The betting is over: have everyone reveal their secret so the deck can be audited before the pot is paid
+fn begin_mental_audit(state: &mut TableState, now: u64) {
+    let deadline = mental_step_deadline(state, now);
+    state.action_timer = None;
+    if let Some(deal) = state.mental.as_mut() {
+        if matches!(deal.stage, MentalStage::Audit | MentalStage::Verify | MentalStage::Done) {
+            return;
+        }
+        deal.stage = MentalStage::Audit;
+        deal.opening.clear();
+        deal.deadline = Some(deadline);
+    }
+}
+
+/// Reveal the secret your keys were derived from, once the hand's betting is over
+/// (mental_poker tables). It must match your commitment; every card of the hand can be
+/// opened with it, so the whole deck is public once everyone has revealed.
+#[ic_cdk::update]
+fn reveal_mental_secret(deal_id: u64, secret: Vec<u8>) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let now = ic_cdk::api::time();
+    if secret.len() != cleardeck_sra::SECRET_LEN {
+        return Err(format!("Secret must be {} bytes", cleardeck_sra::SECRET_LEN));
+    }
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let deal = state.mental.as_mut()
+            .filter(|deal| deal.deal_id == deal_id)
+            .ok_or("No such mental-poker deal")?;
+        if deal.stage != MentalStage::Audit {
+            return Err("The deal is not being audited".to_string());
+        }
+        let participant = deal.participants.iter_mut()
+            .find(|p| p.principal == caller)
+            .ok_or("You are not in this deal")?;
+        if participant.secret.is_some() {
+            return Err("Secret already revealed".to_string());
+        }
+        if participant.secret_commitment.as_deref() != Some(cleardeck_shuffle::seed_hash(&secret).as_str()) {
+            return Err("Secret does not match your commitment".to_string());
+        }
+        participant.secret = Some(secret);
+
+        // The audit runs from the mental-poker timer, one player per tick
+        if deal.participants.iter().all(|p| p.secret.is_some()) {
+            deal.stage = MentalStage::Verify;
+            deal.deadline = Some(now);
+        }
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
+/// The current mental-poker step ran out of time - or, while verifying, the next player is due to be audited
+fn mental_poker_timeout(state: &mut TableState, now: u64) {
+    let deal = match state.mental.as_mut() {
+        Some(deal) => deal,
+        None => return,
+    };
+
+    match deal.stage {
+        MentalStage::Shuffle | MentalStage::Lock => {
+            // No chips are in play yet: sit the staller out and start over without them
+            let staller = deal.turn().map(|index| (deal.participants[index].seat, deal.participants[index].principal));
+            if let Some((seat, principal)) = staller {
+                if let Some(player) = state.players[seat as usize].as_mut().filter(|p| p.principal == principal) {
+                    player.status = PlayerStatus::SittingOut;
+                    player.sitting_out_since = Some(now);
+                }
+            }
+            start_mental_shuffle(state, now);
+        }
+        MentalStage::OpenHoleCards | MentalStage::OpenBoard | MentalStage::OpenRunout => {
+            deal.forfeits = deal.owing_seats();
+            void_mental_hand(state, now);
+        }
+        MentalStage::OpenShowdown => {
+            // Not opening your hand at showdown mucks it
+            for seat in deal.owing_seats() {
+                if let Some(ref mut player) = state.players[seat as usize] {
+                    player.has_folded = true;
+                }
+            }
+            state.side_pots.clear();
+            finish_mental_open(state, now);
+        }
+        MentalStage::Audit => {
+            // A secret that's never revealed can't be audited - its owner forfeits
+            for participant in deal.participants.iter_mut().filter(|p| p.secret.is_none()) {
+                participant.audit_passed = Some(false);
+                deal.forfeits.push(participant.seat);
+            }
+            deal.stage = MentalStage::Verify;
+            deal.deadline = Some(now);
+        }
+        MentalStage::Verify => {
+            if let Some(index) = deal.participants.iter().position(|p| p.audit_passed.is_none()) {
+                let passed = deal.audit_participant(index);
+                deal.participants[index].audit_passed = Some(passed);
+                if !passed {
+                    deal.forfeits.push(deal.participants[index].seat);
+                }
+            }
+            if deal.participants.iter().all(|p| p.audit_passed.is_some()) {
+                finish_mental_hand(state, now);
+            } else {
+                deal.deadline = Some(now);
+            }
+        }
+        MentalStage::Ready | MentalStage::Betting | MentalStage::Done => deal.deadline = None,
+    }
+}
+
+/// The audit is over: settle the hand as it was played, or void it
+fn finish_mental_hand(state: &mut TableState, now: u64) {
+    let void = match state.mental.as_mut() {
+        Some(deal) => {
+            deal.stage = MentalStage::Done;
+            deal.deadline = None;
+            deal.corrupt || !deal.forfeits.is_empty()
+        }
+        None => return,
+    };
+    let in_hand = state.players.iter().flatten().filter(|p| !p.has_folded).count();
+
+    if void || in_hand == 0 {
+        void_mental_hand(state, now);
+    } else if in_hand == 1 {
+        end_hand_single_winner(state);
+    } else {
+        determine_winners(state);
+    }
+}
+
+/// What each seat gets back from a void mental-poker hand, from the pot and every
+/// dealt seat's (seat, bet this hand, still in the hand). Everyone but the
+/// forfeiting seats gets their bet back; the rest of the pot - what the forfeiting
+/// seats put in - is split evenly between the players still in the hand (every
+/// other player if they all folded), odd chips to the earliest seats. With nobody
+/// else to pay, the forfeiting seats are refunded too.
+pub fn void_hand_payouts(pot: u64, bets: &[(u8, u64, bool)], forfeits: &[u8]) -> Vec<(u8, u64)> {
+    let refunded: Vec<(u8, u64, bool)> = bets.iter()
+        .filter(|(seat, _, _)| !forfeits.contains(seat))
+        .copied()
+        .collect();
+    if refunded.is_empty() {
+        return bets.iter().map(|&(seat, bet, _)| (seat, bet)).collect();
+    }
+
+    let mut payouts: Vec<(u8, u64)> = refunded.iter().map(|&(seat, bet, _)| (seat, bet)).collect();
+    let pool = pot.saturating_sub(payouts.iter().map(|(_, bet)| bet).sum());
+    let mut recipients: Vec<usize> = (0..refunded.len()).filter(|&i| refunded[i].2).collect();
+    if recipients.is_empty() {
+        recipients = (0..refunded.len()).collect();
+    }
+    let share = pool / recipients.len() as u64;
+    let odd_chips = pool % recipients.len() as u64;
+    for (n, &i) in recipients.iter().enumerate() {
+        payouts[i].1 += share + u64::from((n as u64) < odd_chips);
+    }
+    payouts
+}
+
+/// Call off a mental-poker hand that can't be settled as played: pay out
+/// void_hand_payouts and sit the forfeiting seats out
+fn void_mental_hand(state: &mut TableState, now: u64) {
+    reveal_seed_on_hand_end(state);
+    state.run_it_twice = None;
+
+    let forfeits = match state.mental.as_mut() {
+        Some(deal) => {
+            deal.stage = MentalStage::Done;
+            deal.deadline = None;
+            deal.opening.clear();
+            deal.forfeits.clone()
+        }
+        None => Vec::new(),
+    };
+    let bets: Vec<(u8, u64, bool)> = state.dealt_seats.iter()
+        .flatten()
+        .filter_map(|&seat| state.players[seat as usize].as_ref())
+        .map(|p| (p.seat, p.total_bet_this_hand, !p.has_folded))
+        .collect();
+
+    let mut winners = Vec::new();
+    for (seat, amount) in void_hand_payouts(state.pot, &bets, &forfeits) {
+        if let Some(ref mut p) = state.players[seat as usize] {
+            p.chips = p.chips.saturating_add(amount);
+            if amount > 0 {
+                winners.push(Winner {
+                    seat,
+                    principal: p.principal,
+                    amount,
+                    hand_rank: None,
+                    cards: None,
+                    pot_type: Some("void".to_string()),
+                    run: None,
+                });
+            }
+        }
+    }
+    for &seat in &forfeits {
+        if let Some(ref mut p) = state.players[seat as usize] {
+            p.status = PlayerStatus::SittingOut;
+            p.sitting_out_since = Some(now);
+        }
+    }
+
+    HAND_HISTORY.with(|h| {
+        if let Some(last) = h.borrow_mut().last_mut() {
+            last.winners = winners.clone();
+            last.community_cards = state.community_cards.clone();
+            CURRENT_ACTIONS.with(|a| {
+                last.actions = a.borrow().clone();
+            });
+        }
+    });
+    LAST_HAND_WINNERS.with(|w| {
+        *w.borrow_mut() = winners.clone();
+    });
+    record_hand_to_history(state, &winners, false, 0);
+
+    state.pot = 0;
+    state.side_pots.clear();
+    state.phase = GamePhase::HandComplete;
+    state.action_timer = None;
+    for player in state.players.iter_mut().flatten() {
+        if player.chips == 0 && player.broke_at.is_none() {
+            player.broke_at = Some(now);
+        } else if player.chips > 0 {
+            player.broke_at = None;
+        }
+    }
+    state.auto_deal_at = Some(now + AUTO_DEAL_DELAY_NS);
+}
+
+/// The mental-poker deal in progress or last played, with every step's deck,
+/// the keys handed over and what each card opened to - all a client needs to
+/// take its turn and to check the table's work
+#[ic_cdk::query]
+fn get_mental_deal() -> Option<MentalDeal> {
+    TABLE.with(|t| t.borrow().as_ref().and_then(|state| state.mental.clone()))
 }
 
 // ============================================================================
@@ -2848,7 +5032,7 @@ fn join_table(seat: u8) -> Result<(), String> {
         b.borrow().get(&caller).copied().unwrap_or(0)
     });
 
-    TABLE.with(|t| {
+    let result = TABLE.with(|t| {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
@@ -2866,6 +5050,13 @@ fn join_table(seat: u8) -> Result<(), String> {
             }
         }
 
+        if state.mtt.is_some() {
+            return Err("This table is running a tournament - register with the tournament".to_string());
+        }
+        if state.tournament.is_some() {
+            return register_tournament_entry(state, caller, seat, now);
+        }
+
         // Require minimum buy-in from escrow balance
         if balance < state.config.min_buy_in {
             let currency = state.config.currency;
@@ -2913,6 +5104,7 @@ fn join_table(seat: u8) -> Result<(), String> {
             is_sitting_out_next_hand: false,
             broke_at: None,
             sitting_out_since,
+            straddle_next_hand: None,
         });
 
         // Auto-start if we now have enough players and waiting for players
@@ -2928,7 +5120,10 @@ fn join_table(seat: u8) -> Result<(), String> {
         }
 
         Ok(())
-    })
+    });
+
+    schedule_table_timers();
+    result
 }
 
 /// Leave table and return chips to escrow balance
@@ -2941,11 +5136,27 @@ fn leave_table() -> Result<u64, String> {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
+        if state.mtt.is_some() {
+            return Err("Cannot leave a tournament table - your stack stays in until you bust".to_string());
+        }
+        if state.tournament.is_some() {
+            return unregister_tournament_entry(state, caller);
+        }
+
         // Find the player's seat
         let seat = state.players.iter()
             .position(|p| p.as_ref().map(|p| p.principal == caller).unwrap_or(false))
             .ok_or("Not at table")?;
 
+        // Mental poker: every participant's keys are needed until the hand is audited
+        if state.mental.as_ref().is_some_and(|deal| {
+            deal.hand_number == state.hand_number
+                && deal.stage != MentalStage::Done
+                && deal.participants.iter().any(|p| p.principal == caller)
+        }) {
+            return Err("Cannot leave before the hand is audited - your keys are needed to open the cards".to_string());
+        }
+
         let player = state.players[seat].as_ref().ok_or("Player not found")?;
         let chips = player.chips;
         let was_in_hand = !player.has_folded &&
@@ -2968,6 +5179,9 @@ fn leave_table() -> Result<u64, String> {
             // Check if only one player left - award pot
             if count_active_players(state) == 1 {
                 end_hand_single_winner(state);
+            } else if state.run_it_twice.is_some() {
+                // Leaving during a run-it-twice vote counts as declining
+                finish_run_it_twice_vote(state, false);
             } else if was_action_on {
                 // If it was this player's turn, move to next player
                 state.action_on = find_next_active_seat(state, state.action_on);
@@ -2992,6 +5206,8 @@ fn leave_table() -> Result<u64, String> {
         balances.insert(caller, current.saturating_add(chips));
     });
 
+    schedule_table_timers();
+
     Ok(chips)
 }
 
@@ -3001,7 +5217,7 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
     let caller = ic_cdk::api::msg_caller();
     let now = ic_cdk::api::time();
 
-    TABLE.with(|t| {
+    let result = TABLE.with(|t| {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
@@ -3017,6 +5233,12 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
         if state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete {
             return Err("No hand in progress".to_string());
         }
+        if state.run_it_twice.is_some() {
+            return Err("Waiting for the run-it-twice vote".to_string());
+        }
+        if state.mental.as_ref().is_some_and(|deal| deal.stage != MentalStage::Betting) {
+            return Err("Waiting for players to open the cards".to_string());
+        }
 
         // BUGFIX: Check if the action timer has expired
         // If check_timeouts hasn't been called, we still enforce the timer here
@@ -3037,13 +5259,21 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
         let mut should_reset_acted = false;
         let mut new_current_bet = state.current_bet;
 
-        // Check if this is BB acting on their option
+        // Bet/raise sizing allowed by the table's betting structure
+        let structure = state.config.betting_structure();
+        let limits = bet_limits(state, player_current_bet);
+        let capped_error = || format!("Betting is capped at {} bets this street", FIXED_LIMIT_MAX_RAISES + 1);
+
+        // Check if this is BB (or the straddler) acting on their option
         let is_bb_option = state.phase == GamePhase::PreFlop
             && state.bb_has_option
-            && player_seat == state.big_blind_seat as usize;
+            && player_seat == option_seat(state) as usize;
 
         // Validate and process action
         match action.clone() {
+            PlayerAction::Straddle(_) => {
+                return Err("Straddles are posted before the deal - use set_straddle".to_string());
+            }
             PlayerAction::Fold => {
                 let player = state.players[player_seat].as_mut().expect("Player validated at seat");
                 player.has_folded = true;
@@ -3093,12 +5323,16 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
                 if state.current_bet > 0 {
                     return Err("Cannot bet, there's already a bet. Use raise.".to_string());
                 }
-                if amount < state.config.big_blind {
-                    return Err(format!("Minimum bet is {}", state.config.currency.format_amount(state.config.big_blind)));
+                let (min_bet, max_bet) = limits.ok_or_else(capped_error)?;
+                if amount < min_bet {
+                    return Err(format!("Minimum bet is {}", state.config.currency.format_amount(min_bet)));
                 }
                 if amount > player_chips {
                     return Err("Not enough chips".to_string());
                 }
+                if amount > max_bet {
+                    return Err(format!("Maximum {} bet is {}", structure.name(), state.config.currency.format_amount(max_bet)));
+                }
 
                 let player = state.players[player_seat].as_mut().expect("Player validated at seat");
                 player.chips = player.chips.saturating_sub(amount);
@@ -3118,17 +5352,21 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
                 state.bb_has_option = false;
             }
             PlayerAction::Raise(amount) => {
+                let (min_raise_to, max_raise_to) = limits.ok_or_else(capped_error)?;
                 let raise_amount = amount.saturating_sub(state.current_bet);
-                if raise_amount < state.min_raise {
+                if amount < min_raise_to {
                     let currency = state.config.currency;
                     return Err(format!("Minimum raise is {} (to {})",
-                        currency.format_amount(state.min_raise),
-                        currency.format_amount(state.current_bet.saturating_add(state.min_raise))));
+                        currency.format_amount(min_raise_to.saturating_sub(state.current_bet)),
+                        currency.format_amount(min_raise_to)));
                 }
                 let total_needed = amount.saturating_sub(player_current_bet);
                 if total_needed > player_chips {
                     return Err("Not enough chips".to_string());
                 }
+                if amount > max_raise_to {
+                    return Err(format!("Maximum {} raise is to {}", structure.name(), state.config.currency.format_amount(max_raise_to)));
+                }
 
                 let player = state.players[player_seat].as_mut().expect("Player validated at seat");
                 player.chips = player.chips.saturating_sub(total_needed);
@@ -3148,6 +5386,15 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
                 state.bb_has_option = false;
             }
             PlayerAction::AllIn => {
+                // An all-in that raises must stay within the structure's limits
+                let all_in_total = player_current_bet.saturating_add(player_chips);
+                if all_in_total > state.current_bet {
+                    let (_, max_raise_to) = limits.ok_or_else(capped_error)?;
+                    if all_in_total > max_raise_to {
+                        return Err(format!("All-in exceeds the {} maximum. Maximum raise is to {}",
+                            structure.name(), state.config.currency.format_amount(max_raise_to)));
+                    }
+                }
                 let player = state.players[player_seat].as_mut().expect("Player validated at seat");
                 let all_in_amount = player.chips;
                 state.pot = state.pot.saturating_add(all_in_amount);
@@ -3197,6 +5444,7 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
             },
             PlayerAction::Bet(amount) => LastAction::Bet { amount },
             PlayerAction::Raise(amount) => LastAction::Raise { amount },
+            PlayerAction::Straddle(amount) => LastAction::PostBlind { amount },
             PlayerAction::AllIn => {
                 // Get the player's final bet to show in the action
                 let final_bet = state.players[player_seat].as_ref()
@@ -3216,7 +5464,7 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
         let action_amount = match action.clone() {
             PlayerAction::Fold | PlayerAction::Check => 0,
             PlayerAction::Call => state.current_bet.saturating_sub(player_current_bet).min(player_chips),
-            PlayerAction::Bet(amt) | PlayerAction::Raise(amt) => amt,
+            PlayerAction::Bet(amt) | PlayerAction::Raise(amt) | PlayerAction::Straddle(amt) => amt,
             PlayerAction::AllIn => state.players[player_seat].as_ref()
                 .map(|p| p.current_bet)
                 .unwrap_or(0),
@@ -3235,7 +5483,10 @@ fn player_action(action: PlayerAction) -> Result<(), String> {
         advance_game(state);
 
         Ok(())
-    })
+    });
+
+    schedule_table_timers();
+    result
 }
 
 // Note: reset_acted_flags is now inlined in player_action to avoid borrow conflicts
@@ -3276,12 +5527,12 @@ fn is_betting_round_complete(state: &TableState) -> bool {
     }
 
     // Special case: preflop BB option
-    // If we're preflop and BB hasn't acted yet and no one raised, BB gets option
+    // If we're preflop and BB (or the last straddler) hasn't acted yet and no one raised, they get option
     if state.phase == GamePhase::PreFlop && state.bb_has_option {
         // Check if action is on BB
-        if state.action_on == state.big_blind_seat {
+        if state.action_on == option_seat(state) {
             // BB still needs to act (check or raise)
-            if let Some(ref bb_player) = state.players[state.big_blind_seat as usize] {
+            if let Some(ref bb_player) = state.players[option_seat(state) as usize] {
                 if !bb_player.has_acted_this_round && !bb_player.is_all_in && !bb_player.has_folded {
                     return false;
                 }
@@ -3313,7 +5564,23 @@ fn run_out_board(state: &mut TableState) {
         calculate_side_pots(state);
     }
 
-    // Deal remaining cards based on current phase
+    // Mental poker: the players open the rest of the board, then the hand goes to showdown
+    if state.mental.is_some() && cards_to_river(&state.phase) > 0 {
+        open_mental_board(state, ic_cdk::api::time(), true);
+        return;
+    }
+
+    deal_remaining_streets(state);
+
+    // Go to showdown (nothing to settle if the hand was already over)
+    if state.phase == GamePhase::River || state.phase == GamePhase::Showdown {
+        state.phase = GamePhase::Showdown;
+        determine_winners(state);
+    }
+}
+
+/// Deal community cards street by street until the river is out
+fn deal_remaining_streets(state: &mut TableState) {
     loop {
         match state.phase {
             GamePhase::PreFlop => {
@@ -3345,23 +5612,112 @@ fn run_out_board(state: &mut TableState) {
                 }
                 state.phase = GamePhase::River;
             }
-            GamePhase::River => {
-                // Go to showdown
-                state.phase = GamePhase::Showdown;
-                determine_winners(state);
-                return;
-            }
-            _ => {
-                // Already at showdown or waiting - just determine winners
-                if state.phase == GamePhase::Showdown {
-                    determine_winners(state);
-                }
-                return;
-            }
+            _ => return,
         }
     }
 }
 
+/// Cards (burns included) still to come before the river is out
+fn cards_to_river(phase: &GamePhase) -> usize {
+    match phase {
+        GamePhase::PreFlop => 8,
+        GamePhase::Flop => 4,
+        GamePhase::Turn => 2,
+        _ => 0,
+    }
+}
+
+/// Everyone left is all-in with cards still to come: open a run-it-twice vote if the table
+/// offers it and the deck has enough cards for two runouts, otherwise run the board once
+fn start_all_in_runout(state: &mut TableState) {
+    let remaining = cards_to_river(&state.phase);
+    let offered = state.config.run_it_twice.unwrap_or(false)
+        && remaining > 0
+        && count_active_players(state) >= 2
+        && state.deck_index + 2 * remaining <= state.deck.len();
+
+    if !offered {
+        run_out_board(state);
+        return;
+    }
+
+    let seats = state.players.iter()
+        .flatten()
+        .filter(|p| !p.has_folded)
+        .map(|p| p.seat)
+        .collect();
+    state.action_timer = None;
+    state.run_it_twice = Some(RunItTwiceVote {
+        seats,
+        accepted: Vec::new(),
+        expires_at: ic_cdk::api::time() + RUN_IT_TWICE_WINDOW_SECS * 1_000_000_000,
+    });
+}
+
+/// Close the run-it-twice vote and run the board once or twice
+fn finish_run_it_twice_vote(state: &mut TableState, twice: bool) {
+    state.run_it_twice = None;
+    if count_active_players(state) < 2 {
+        end_hand_single_winner(state);
+    } else if twice {
+        run_out_board_twice(state);
+    } else {
+        run_out_board(state);
+    }
+}
+
+/// Deal the rest of the board twice from the same deck - the second runout continues
+/// where the first stopped, so replaying the seed reproduces both boards
+fn run_out_board_twice(state: &mut TableState) {
+    calculate_side_pots(state);
+
+    let shared_cards = state.community_cards.clone();
+    let phase = state.phase.clone();
+    deal_remaining_streets(state);
+    let first_board = std::mem::replace(&mut state.community_cards, shared_cards);
+    state.phase = phase;
+    deal_remaining_streets(state);
+    state.second_board = Some(std::mem::replace(&mut state.community_cards, first_board));
+
+    state.phase = GamePhase::Showdown;
+    determine_winners(state);
+}
+
+/// Agree or decline to run the board twice (players in the all-in hand only)
+/// Any decline runs the board once; the last agreement runs it twice
+#[ic_cdk::update]
+fn run_it_twice(agree: bool) -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let vote = state.run_it_twice.as_mut().ok_or("No run-it-twice vote in progress")?;
+
+        let seat = state.players.iter()
+            .flatten()
+            .find(|p| p.principal == caller)
+            .map(|p| p.seat)
+            .filter(|seat| vote.seats.contains(seat))
+            .ok_or("You are not in this hand")?;
+
+        if !agree {
+            finish_run_it_twice_vote(state, false);
+            return Ok(());
+        }
+        if !vote.accepted.contains(&seat) {
+            vote.accepted.push(seat);
+        }
+        if vote.seats.iter().all(|s| vote.accepted.contains(s)) {
+            finish_run_it_twice_vote(state, true);
+        }
+        Ok(())
+    });
+
+    schedule_table_timers();
+    result
+}
+
 fn advance_to_next_street(state: &mut TableState) {
     let now = ic_cdk::api::time();
 
@@ -3375,6 +5731,21 @@ fn advance_to_next_street(state: &mut TableState) {
         player.has_acted_this_round = false;
     }
 
+    // No more betting with cards still to come - decide on running it twice before the next street
+    if cards_to_river(&state.phase) > 0 && count_players_can_act(state) < 2 {
+        start_all_in_runout(state);
+        return;
+    }
+
+    // Mental poker: the street is dealt once the players open its cards
+    if state.mental.is_some() && cards_to_river(&state.phase) > 0 {
+        if state.phase == GamePhase::PreFlop {
+            calculate_side_pots(state);
+        }
+        open_mental_board(state, now, false);
+        return;
+    }
+
     match state.phase {
         GamePhase::PreFlop => {
             // Calculate side pots before dealing flop (in case of all-ins)
@@ -3417,6 +5788,11 @@ fn advance_to_next_street(state: &mut TableState) {
         _ => {}
     }
 
+    start_street_action(state, now);
+}
+
+/// A new street's cards are out: start its betting, or run out the board if nobody can bet
+fn start_street_action(state: &mut TableState, now: u64) {
     // Check if we can have more betting (need 2+ players who can act)
     if count_players_can_act(state) < 2 {
         // Run out the board without recursion
@@ -3555,29 +5931,146 @@ fn calculate_side_pots(state: &mut TableState) {
     }
 }
 
+/// Share of a pot settled on one runout: the first run of a run-it-twice hand gets
+/// the odd chip, the second the other half, and a single board takes the whole pot.
+fn run_share(amount: u64, run: Option<u8>) -> u64 {
+    match run {
+        Some(1) => amount - amount / 2,
+        Some(_) => amount / 2,
+        None => amount,
+    }
+}
+
+/// Splits a Hi-Lo pot into its (high, low) halves.
+/// The odd chip goes to the high half, and the high hand scoops when nobody qualifies for low.
+fn split_hi_lo_pot(amount: u64, has_low: bool) -> (u64, u64) {
+    if !has_low {
+        return (amount, 0);
+    }
+    let low = amount / 2;
+    (amount - low, low)
+}
+
+/// Rake owed on a pot under `config`, after the cap for the number of players dealt in
+fn calculate_rake(pot: u64, players_dealt: usize, saw_flop: bool, config: &RakeConfig) -> u64 {
+    if config.no_flop_no_drop && !saw_flop {
+        return 0;
+    }
+    let rake = (pot as u128 * config.percent_bps as u128 / 10_000) as u64;
+    let cap = config.caps.iter()
+        .filter(|c| c.min_players as usize <= players_dealt)
+        .max_by_key(|c| c.min_players)
+        .map(|c| c.cap);
+    match cap {
+        Some(cap) => rake.min(cap),
+        None => rake,
+    }
+}
+
+/// Rake for the hand that is ending - zero on tournament tables and tables without rake
+fn hand_rake(state: &TableState, pot: u64) -> u64 {
+    let config = match &state.config.rake {
+        Some(config) if !is_tournament_table(state) => config,
+        _ => return 0,
+    };
+    // Mental-poker hole cards are never on the table, so count the seats dealt in
+    let players_dealt = match state.dealt_seats {
+        Some(ref seats) => seats.len(),
+        None => state.players.iter().flatten().filter(|p| p.hole_cards.is_some()).count(),
+    };
+    calculate_rake(pot, players_dealt, state.community_cards.len() >= 3, config).min(pot)
+}
+
+/// Takes `rake` out of the contested pots, main pot first. A pot only one
+/// player can win isn't raked.
+fn take_rake_from_pots(pots: &mut [SidePot], rake: u64) {
+    let mut remaining = rake;
+    for pot in pots.iter_mut().filter(|p| p.eligible_players.len() > 1) {
+        let taken = remaining.min(pot.amount);
+        pot.amount -= taken;
+        remaining -= taken;
+    }
+}
+
+/// The part of the biggest bet nobody matched, as (seat, amount) from each
+/// seat's bets this hand. It goes back to its owner and is never raked.
+fn uncalled_bet(bets: &[(u8, u64)]) -> Option<(u8, u64)> {
+    let (seat, top) = bets.iter().copied().max_by_key(|(_, bet)| *bet)?;
+    let called = bets.iter()
+        .filter(|(s, _)| *s != seat)
+        .map(|(_, bet)| *bet)
+        .max()
+        .unwrap_or(0);
+    (top > called).then_some((seat, top - called))
+}
+
+/// Hand the unmatched part of the biggest bet back to its owner
+fn return_uncalled_bet(state: &mut TableState) -> u64 {
+    let bets: Vec<(u8, u64)> = state.players.iter()
+        .enumerate()
+        .filter_map(|(i, p)| p.as_ref().map(|p| (i as u8, p.total_bet_this_hand)))
+        .collect();
+    let Some((seat, amount)) = uncalled_bet(&bets) else {
+        return 0;
+    };
+    let amount = amount.min(state.pot);
+    if let Some(ref mut p) = state.players[seat as usize] {
+        p.chips = p.chips.saturating_add(amount);
+        p.total_bet_this_hand -= amount;
+    }
+    state.pot -= amount;
+    amount
+}
+
+fn credit_treasury(amount: u64) {
+    TREASURY_BALANCE.with(|t| {
+        let mut treasury = t.borrow_mut();
+        *treasury = treasury.saturating_add(amount);
+    });
+}
+
 fn end_hand_single_winner(state: &mut TableState) {
+    // Mental poker: nothing is paid until the deck has been audited
+    if state.mental.as_ref().is_some_and(|deal| deal.stage != MentalStage::Done) {
+        begin_mental_audit(state, ic_cdk::api::time());
+        return;
+    }
+
     // Reveal the seed now that hand is ending
     reveal_seed_on_hand_end(state);
+    state.run_it_twice = None;
 
     // Find the remaining player
     let winner = state.players.iter()
         .enumerate()
-        .find(|(_, p)| p.as_ref().map(|p| !p.has_folded).unwrap_or(false));
+        .find_map(|(i, p)| p.as_ref().filter(|p| !p.has_folded).map(|p| (i, p.principal)));
 
-    // BUGFIX: state.pot already contains all contributions
-    // side_pots are just a breakdown of the same money for eligibility tracking
-    // DO NOT add them together - that would double-pay
-    let total_pot = state.pot;
+    let mut rake = 0;
 
     let mut winners_for_history = Vec::new();
 
-    if let Some((seat, Some(player))) = winner {
+    if let Some((seat, principal)) = winner {
+        // The bet nobody called isn't won - it goes back before the rake
+        return_uncalled_bet(state);
+
+        // BUGFIX: state.pot already contains all contributions
+        // side_pots are just a breakdown of the same money for eligibility tracking
+        // DO NOT add them together - that would double-pay
+        let total_pot = state.pot;
+
+        // Rake comes out before the pot is awarded
+        rake = hand_rake(state, total_pot);
+        credit_treasury(rake);
+        let total_pot = total_pot - rake;
+
         let winner_info = Winner {
             seat: seat as u8,
-            principal: player.principal,
+            principal,
             amount: total_pot,
             hand_rank: None,
             cards: None,
+            pot_type: Some("main".to_string()),
+            run: None,
         };
 
         winners_for_history.push(winner_info.clone());
@@ -3587,25 +6080,247 @@ fn end_hand_single_winner(state: &mut TableState) {
             p.chips = p.chips.saturating_add(total_pot);
         }
 
-        // Update local history
-        HAND_HISTORY.with(|h| {
-            if let Some(last) = h.borrow_mut().last_mut() {
-                last.winners.push(winner_info.clone());
-                last.community_cards = state.community_cards.clone();
-                CURRENT_ACTIONS.with(|a| {
-                    last.actions = a.borrow().clone();
-                });
-            }
-        });
+        // Update local history
+        HAND_HISTORY.with(|h| {
+            if let Some(last) = h.borrow_mut().last_mut() {
+                last.winners.push(winner_info.clone());
+                last.community_cards = state.community_cards.clone();
+                CURRENT_ACTIONS.with(|a| {
+                    last.actions = a.borrow().clone();
+                });
+            }
+        });
+
+        // Store winners for display (separate from HAND_HISTORY)
+        LAST_HAND_WINNERS.with(|w| {
+            *w.borrow_mut() = vec![winner_info];
+        });
+    }
+
+    // Record to history canister (no showdown - single winner by fold)
+    record_hand_to_history(state, &winners_for_history, false, rake);
+
+    state.pot = 0;
+    state.side_pots.clear();
+    state.phase = GamePhase::HandComplete;
+    state.action_timer = None;
+
+    // Mark players with 0 chips as broke (start their reload timer)
+    let now = ic_cdk::api::time();
+    for player in state.players.iter_mut().flatten() {
+        if player.chips == 0 && player.broke_at.is_none() {
+            player.broke_at = Some(now);
+        } else if player.chips > 0 {
+            player.broke_at = None;
+        }
+    }
+
+    // Schedule auto-deal for next hand
+    state.auto_deal_at = Some(ic_cdk::api::time() + AUTO_DEAL_DELAY_NS);
+
+    eliminate_busted_players(state);
+}
+
+fn determine_winners(state: &mut TableState) {
+    // Mental poker: the hands are opened and the deck audited before anything is paid
+    if state.mental.as_ref().is_some_and(|deal| deal.stage != MentalStage::Done) {
+        open_mental_showdown(state, ic_cdk::api::time());
+        return;
+    }
+
+    // Reveal the seed now that hand is ending (showdown)
+    reveal_seed_on_hand_end(state);
+
+    // The bet nobody called isn't contested - it goes back before the pots are built
+    if return_uncalled_bet(state) > 0 {
+        state.side_pots.clear();
+    }
+
+    // Calculate side pots if not already done
+    if state.side_pots.is_empty() {
+        calculate_side_pots(state);
+    }
+
+    // Evaluate hands for all non-folded players on a board
+    let variant = state.config.variant();
+    let deck = variant.deck_type();
+    let evaluate_board = |board: &[Card]| -> Vec<(u8, HandRank, Principal, Vec<Card>)> {
+        state.players.iter()
+            .enumerate()
+            .filter_map(|(i, player)| {
+                let p = player.as_ref().filter(|p| !p.has_folded)?;
+                let cards = p.hole_cards.as_ref()?;
+                Some((i as u8, evaluate_hand(cards, board, variant), p.principal, cards.clone()))
+            })
+            .collect()
+    };
+    let player_hands = evaluate_board(&state.community_cards);
+
+    if player_hands.is_empty() {
+        state.phase = GamePhase::HandComplete;
+        return;
+    }
+
+    // Every board to settle: (hands, board, run) - a run-it-twice hand has two, one per runout
+    let mut runs = vec![(player_hands.clone(), state.community_cards.clone(), None)];
+    if let Some(ref second_board) = state.second_board {
+        runs[0].2 = Some(1);
+        runs.push((evaluate_board(second_board), second_board.clone(), Some(2)));
+    }
+
+    // Without side pots the whole pot is contested by every player still in the hand
+    let mut pots: Vec<SidePot> = if state.side_pots.is_empty() {
+        vec![SidePot {
+            amount: state.pot,
+            eligible_players: player_hands.iter().map(|(seat, _, _, _)| *seat).collect(),
+        }]
+    } else {
+        state.side_pots.clone()
+    };
+
+    // Rake comes out of the contested pots before any pot is awarded
+    let contested = pots.iter()
+        .filter(|p| p.eligible_players.len() > 1)
+        .map(|p| p.amount)
+        .sum();
+    let rake = hand_rake(state, contested);
+    take_rake_from_pots(&mut pots, rake);
+    credit_treasury(rake);
+
+    let hi_lo = variant.is_hi_lo();
+    let mut winner_list: Vec<Winner> = Vec::new();
+    let mut chips_awarded: HashMap<u8, u64> = HashMap::new();
+    let dealer_seat = state.dealer_seat;
+    let num_seats = state.players.len();
+
+    // Splits `amount` between the tied `seats`; the odd chip goes first clockwise from dealer
+    let mut award = |hands: &[(u8, HandRank, Principal, Vec<Card>)], amount: u64, seats: &[u8], pot_type: &str, run: Option<u8>| {
+        // Guard against division by zero
+        if seats.is_empty() || amount == 0 {
+            return;
+        }
+        let pot_share = amount / seats.len() as u64;
+        let remainder = amount % seats.len() as u64;
+        let remainder_seat = first_clockwise_from_dealer(dealer_seat, seats, num_seats);
+
+        for (seat, rank, principal, cards) in hands.iter().filter(|(s, _, _, _)| seats.contains(s)) {
+            let share = if *seat == remainder_seat { pot_share + remainder } else { pot_share };
+            let entry = chips_awarded.entry(*seat).or_insert(0);
+            *entry = entry.saturating_add(share);
+
+            // Only add to winner list once per player, pot type and run (aggregate amounts)
+            if let Some(existing) = winner_list.iter_mut()
+                .find(|w| w.seat == *seat && w.pot_type.as_deref() == Some(pot_type) && w.run == run)
+            {
+                existing.amount = existing.amount.saturating_add(share);
+            } else {
+                winner_list.push(Winner {
+                    seat: *seat,
+                    principal: *principal,
+                    amount: share,
+                    hand_rank: Some(rank.clone()),
+                    cards: Some(cards.clone()),
+                    pot_type: Some(pot_type.to_string()),
+                    run,
+                });
+            }
+        }
+    };
+
+    for (hands, board, run) in &runs {
+        // Hi-Lo: best qualifying low for each player still in the hand
+        let low_hands: HashMap<u8, Vec<u8>> = if hi_lo {
+            hands.iter()
+                .filter_map(|(seat, _, _, cards)| {
+                    evaluate_omaha_low_hand(cards, board).map(|low| (*seat, low))
+                })
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        // Process each pot separately
+        for side_pot in &pots {
+            let amount = run_share(side_pot.amount, *run);
+            let eligible_hands: Vec<_> = hands.iter()
+                .filter(|(seat, _, _, _)| side_pot.eligible_players.contains(seat))
+                .collect();
+
+            // Find the best hand(s) among eligible players
+            let best_rank = match eligible_hands.iter()
+                .map(|(_, rank, _, _)| rank)
+                .max_by(|a, b| a.compare(b, deck))
+            {
+                Some(rank) => rank,
+                None => continue, // No eligible hands for this pot
+            };
+            let high_seats: Vec<u8> = eligible_hands.iter()
+                .filter(|(_, rank, _, _)| rank == best_rank)
+                .map(|(seat, _, _, _)| *seat)
+                .collect();
+
+            if !hi_lo {
+                award(hands, amount, &high_seats, "main", *run);
+                continue;
+            }
+
+            // Lowest low wins; ties split the low half (quartering)
+            let best_low = eligible_hands.iter()
+                .filter_map(|(seat, _, _, _)| low_hands.get(seat))
+                .min();
+            let low_seats: Vec<u8> = match best_low {
+                Some(best) => eligible_hands.iter()
+                    .filter(|(seat, _, _, _)| low_hands.get(seat) == Some(best))
+                    .map(|(seat, _, _, _)| *seat)
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            let (high_amount, low_amount) = split_hi_lo_pot(amount, !low_seats.is_empty());
+            award(hands, high_amount, &high_seats, "high", *run);
+            award(hands, low_amount, &low_seats, "low", *run);
+        }
+    }
+
+    // Build showdown players list BEFORE awarding chips (need to access chips_awarded)
+    let showdown_players: Vec<ShowdownPlayer> = player_hands.iter().map(|(seat, rank, principal, cards)| {
+        let amount_won = chips_awarded.get(seat).copied().unwrap_or(0);
+        ShowdownPlayer {
+            seat: *seat,
+            principal: *principal,
+            cards: Some(cards.clone()),
+            hand_rank: Some(rank.clone()),
+            amount_won,
+        }
+    }).collect();
+
+    // Award chips to winners (with overflow protection)
+    for (seat, amount) in chips_awarded {
+        if let Some(ref mut player) = state.players[seat as usize] {
+            player.chips = player.chips.saturating_add(amount);
+        }
+    }
+
+    // Update local history
+    HAND_HISTORY.with(|h| {
+        if let Some(last) = h.borrow_mut().last_mut() {
+            last.winners = winner_list.clone();
+            last.community_cards = state.community_cards.clone();
+            last.second_board = state.second_board.clone();
+            last.showdown_players = showdown_players;
+            CURRENT_ACTIONS.with(|a| {
+                last.actions = a.borrow().clone();
+            });
+        }
+    });
 
-        // Store winners for display (separate from HAND_HISTORY)
-        LAST_HAND_WINNERS.with(|w| {
-            *w.borrow_mut() = vec![winner_info];
-        });
-    }
+    // Store winners for display (separate from HAND_HISTORY since that gets a new entry when a new hand starts)
+    LAST_HAND_WINNERS.with(|w| {
+        *w.borrow_mut() = winner_list.clone();
+    });
 
-    // Record to history canister (no showdown - single winner by fold)
-    record_hand_to_history(state, &winners_for_history, false);
+    // Record to history canister (went to showdown)
+    record_hand_to_history(state, &winner_list, true, rake);
 
     state.pot = 0;
     state.side_pots.clear();
@@ -3618,199 +6333,615 @@ fn end_hand_single_winner(state: &mut TableState) {
         if player.chips == 0 && player.broke_at.is_none() {
             player.broke_at = Some(now);
         } else if player.chips > 0 {
+            // Player has chips, clear broke status
             player.broke_at = None;
         }
     }
 
     // Schedule auto-deal for next hand
     state.auto_deal_at = Some(ic_cdk::api::time() + AUTO_DEAL_DELAY_NS);
+
+    eliminate_busted_players(state);
 }
 
-fn determine_winners(state: &mut TableState) {
-    // Reveal the seed now that hand is ending (showdown)
-    reveal_seed_on_hand_end(state);
+// ============================================================================
+// SIT & GO TOURNAMENTS
+// ============================================================================
 
-    // Calculate side pots if not already done
-    if state.side_pots.is_empty() {
-        calculate_side_pots(state);
+/// Sit & Go and multi-table tournament tables - chips are not currency
+fn is_tournament_table(state: &TableState) -> bool {
+    state.tournament.is_some() || state.mtt.is_some()
+}
+
+/// Cash tables always deal; a Sit & Go only deals once every seat is filled,
+/// and a multi-table tournament table only while its tournament hasn't paused it
+fn can_deal_hands(state: &TableState) -> bool {
+    let sit_and_go_running = state.tournament.as_ref()
+        .map(|t| t.status == TournamentStatus::Running)
+        .unwrap_or(true);
+    let mtt_running = state.mtt.as_ref().map(|m| !m.paused).unwrap_or(true);
+    sit_and_go_running && mtt_running
+}
+
+/// Register the caller for a Sit & Go: take buy-in + fee from escrow and seat
+/// them with tournament chips. The tournament starts when the last seat fills.
+fn register_tournament_entry(state: &mut TableState, caller: Principal, seat: u8, now: u64) -> Result<(), String> {
+    let config = state.config.tournament.clone().ok_or("Not a Sit & Go table")?;
+    let tournament = state.tournament.as_mut().ok_or("Tournament not initialized")?;
+    if tournament.status != TournamentStatus::Registering {
+        return Err("Registration for this Sit & Go is closed".to_string());
     }
 
-    // Evaluate hands for all non-folded players
-    let mut player_hands: Vec<(u8, HandRank, Principal, (Card, Card))> = Vec::new();
+    // Deduct buy-in + fee from escrow (atomic check and deduct)
+    let entry_cost = config.buy_in.saturating_add(config.fee);
+    let currency = state.config.currency;
+    BALANCES.with(|b| {
+        let mut balances = b.borrow_mut();
+        let balance = balances.get(&caller).copied().unwrap_or(0);
+        if balance < entry_cost {
+            return Err(format!(
+                "Insufficient balance. Need {} (buy-in {} + fee {}), have {} in escrow.",
+                currency.format_amount(entry_cost),
+                currency.format_amount(config.buy_in),
+                currency.format_amount(config.fee),
+                currency.format_amount(balance)
+            ));
+        }
+        balances.insert(caller, balance - entry_cost);
+        Ok(())
+    })?;
 
-    for (i, player) in state.players.iter().enumerate() {
-        if let Some(ref p) = player {
-            if !p.has_folded {
-                if let Some(cards) = p.hole_cards {
-                    let hand_rank = evaluate_hand(&cards, &state.community_cards);
-                    player_hands.push((i as u8, hand_rank, p.principal, cards));
-                }
-            }
+    tournament.entrants.push(caller);
+    tournament.prize_pool = tournament.prize_pool.saturating_add(config.buy_in);
+    tournament.fees_collected = tournament.fees_collected.saturating_add(config.fee);
+
+    state.players[seat as usize] = Some(Player {
+        principal: caller,
+        seat,
+        chips: config.starting_chips, // Tournament chips, not currency
+        hole_cards: None,
+        current_bet: 0,
+        total_bet_this_hand: 0,
+        has_folded: false,
+        has_acted_this_round: false,
+        is_all_in: false,
+        status: PlayerStatus::Active,
+        last_seen: now,
+        timeout_count: 0,
+        time_bank_remaining: state.config.time_bank_secs,
+        is_sitting_out_next_hand: false,
+        broke_at: None,
+        sitting_out_since: None,
+        straddle_next_hand: None,
+    });
+
+    // Start once the table is full - entries can no longer be refunded, so the fees go to the treasury
+    if state.players.iter().all(|p| p.is_some()) {
+        if let Some(tournament) = state.tournament.as_mut() {
+            credit_treasury(tournament.fees_collected);
+            tournament.status = TournamentStatus::Running;
+            tournament.started_at = Some(now);
+            tournament.level = 0;
+            tournament.level_ends_at = if config.blind_levels.len() > 1 {
+                Some(now + config.blind_levels[0].duration_secs * 1_000_000_000)
+            } else {
+                None
+            };
         }
+        apply_tournament_blinds(state);
+        state.auto_deal_at = Some(now + AUTO_DEAL_DELAY_NS);
     }
 
-    if player_hands.is_empty() {
-        state.phase = GamePhase::HandComplete;
-        return;
+    Ok(())
+}
+
+/// Give a Sit & Go entry back before the tournament starts. Returns the refund,
+/// which the caller credits to escrow like a cash-out.
+fn unregister_tournament_entry(state: &mut TableState, caller: Principal) -> Result<u64, String> {
+    let config = state.config.tournament.clone().ok_or("Not a Sit & Go table")?;
+    let tournament = state.tournament.as_mut().ok_or("Tournament not initialized")?;
+    if tournament.status != TournamentStatus::Registering {
+        return Err("Cannot leave a Sit & Go once it has started".to_string());
     }
 
-    let mut winner_list = Vec::new();
-    let mut chips_awarded: HashMap<u8, u64> = HashMap::new();
+    let seat = state.players.iter()
+        .position(|p| p.as_ref().map(|p| p.principal == caller).unwrap_or(false))
+        .ok_or("Not at table")?;
+    state.players[seat] = None;
 
-    // If no side pots, use simple main pot logic
-    if state.side_pots.is_empty() {
-        // Sort by hand rank (best first)
-        player_hands.sort_by(|a, b| b.1.cmp(&a.1));
+    tournament.entrants.retain(|p| *p != caller);
+    tournament.prize_pool = tournament.prize_pool.saturating_sub(config.buy_in);
+    tournament.fees_collected = tournament.fees_collected.saturating_sub(config.fee);
 
-        let best_rank = &player_hands[0].1;
-        let winners: Vec<_> = player_hands.iter()
-            .filter(|(_, rank, _, _)| rank == best_rank)
-            .collect();
+    Ok(config.buy_in.saturating_add(config.fee))
+}
 
-        // Guard against division by zero (should never happen but be safe)
-        if winners.is_empty() {
-            state.phase = GamePhase::HandComplete;
-            return;
+/// Copy the current blind level into the table config (done between hands only)
+fn apply_tournament_blinds(state: &mut TableState) {
+    let level = match (&state.config.tournament, &state.tournament, &state.mtt) {
+        (Some(config), Some(tournament), _) => config.blind_levels.get(tournament.level as usize).cloned(),
+        (_, _, Some(mtt)) => Some(mtt.blinds.clone()),
+        _ => None,
+    };
+    if let Some(level) = level {
+        state.config.small_blind = level.small_blind;
+        state.config.big_blind = level.big_blind;
+        state.config.ante = level.ante;
+    }
+}
+
+/// Move to the next blind level once the current one has run its time.
+/// The new blinds take effect from the next hand dealt.
+fn advance_blind_level(state: &mut TableState, now: u64) {
+    let levels = match &state.config.tournament {
+        Some(config) => &config.blind_levels,
+        None => return,
+    };
+    let tournament = match state.tournament.as_mut() {
+        Some(t) if t.status == TournamentStatus::Running => t,
+        _ => return,
+    };
+
+    while let Some(ends_at) = tournament.level_ends_at {
+        if now < ends_at {
+            break;
+        }
+        tournament.level += 1;
+        let next = tournament.level as usize;
+        // The last level has no end
+        tournament.level_ends_at = if next + 1 < levels.len() {
+            Some(ends_at + levels[next].duration_secs * 1_000_000_000)
+        } else {
+            None
+        };
+    }
+}
+
+/// Sit & Go players are always dealt in while they have chips - absent players
+/// are blinded away by action timeouts instead of sitting out
+fn deal_in_tournament_players(state: &mut TableState) {
+    for player in state.players.iter_mut().flatten() {
+        if player.chips > 0 {
+            player.status = PlayerStatus::Active;
+            player.is_sitting_out_next_hand = false;
+            player.sitting_out_since = None;
         }
-        let pot_share = state.pot / winners.len() as u64;
-        let remainder = state.pot % winners.len() as u64;
+    }
+}
 
-        // Find which winner gets the remainder (first clockwise from dealer)
-        let winner_seats: Vec<u8> = winners.iter().map(|(seat, _, _, _)| *seat).collect();
-        let remainder_seat = first_clockwise_from_dealer(
-            state.dealer_seat,
-            &winner_seats,
-            state.players.len()
-        );
+/// Finishing positions for players who busted on the same hand. The player who
+/// started the hand with more chips finishes higher.
+/// `busted` is (seat, chips at start of hand); returns (seat, position).
+fn bust_positions(players_remaining: usize, busted: &[(u8, u64)]) -> Vec<(u8, u8)> {
+    let mut order = busted.to_vec();
+    order.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    order.iter()
+        .enumerate()
+        .map(|(i, (seat, _))| (*seat, (players_remaining + 1 + i) as u8))
+        .collect()
+}
 
-        for (seat, rank, principal, cards) in winners.iter() {
-            let amount = if *seat == remainder_seat { pot_share + remainder } else { pot_share };
-            let entry = chips_awarded.entry(*seat).or_insert(0);
-            *entry = entry.saturating_add(amount);
+/// After each Sit & Go hand: remove busted players, record their finishing
+/// positions, and finish the tournament when one player is left
+fn eliminate_busted_players(state: &mut TableState) {
+    if state.mtt.is_some() {
+        report_mtt_hand(state);
+        return;
+    }
+    if !state.tournament.as_ref().map(|t| t.status == TournamentStatus::Running).unwrap_or(false) {
+        return;
+    }
 
-            winner_list.push(Winner {
-                seat: *seat,
-                principal: *principal,
-                amount,
-                hand_rank: Some(rank.clone()),
-                cards: Some(*cards),
+    let busted: Vec<(u8, u64)> = state.players.iter()
+        .enumerate()
+        .filter_map(|(i, p)| p.as_ref().filter(|p| p.chips == 0).map(|_| i as u8))
+        .map(|seat| (seat, STARTING_CHIPS.with(|s| s.borrow().get(&seat).copied().unwrap_or(0))))
+        .collect();
+    if busted.is_empty() {
+        return;
+    }
+
+    let remaining = state.players.iter().flatten().filter(|p| p.chips > 0).count();
+    for (seat, position) in bust_positions(remaining, &busted) {
+        if let Some(player) = state.players[seat as usize].take() {
+            if let Some(tournament) = state.tournament.as_mut() {
+                tournament.results.push(TournamentResult {
+                    principal: player.principal,
+                    position,
+                    prize: 0,
+                });
+            }
+        }
+    }
+
+    if remaining <= 1 {
+        finish_tournament(state);
+    }
+}
+
+/// Award the last player standing, pay prizes to escrow and record the result
+fn finish_tournament(state: &mut TableState) {
+    let now = ic_cdk::api::time();
+    let payouts_bps = match &state.config.tournament {
+        Some(config) => config.payouts_bps.clone(),
+        None => return,
+    };
+
+    // Clear the table - the winner's chips are tournament chips with no currency value
+    let winner = state.players.iter().flatten()
+        .find(|p| p.chips > 0)
+        .map(|p| p.principal);
+    for seat in state.players.iter_mut() {
+        *seat = None;
+    }
+
+    let tournament = match state.tournament.as_mut() {
+        Some(t) => t,
+        None => return,
+    };
+    if let Some(winner) = winner {
+        tournament.results.push(TournamentResult {
+            principal: winner,
+            position: 1,
+            prize: 0,
+        });
+    }
+    tournament.results.sort_by_key(|r| r.position);
+
+    let prizes = tournament_payouts(tournament.prize_pool, &payouts_bps);
+    for result in tournament.results.iter_mut() {
+        result.prize = prizes.get(result.position as usize - 1).copied().unwrap_or(0);
+        if result.prize > 0 {
+            BALANCES.with(|b| {
+                let mut balances = b.borrow_mut();
+                let current = balances.entry(result.principal).or_insert(0);
+                *current = current.saturating_add(result.prize);
             });
         }
-    } else {
-        // Process each side pot separately
-        for side_pot in &state.side_pots {
-            // Find best hand among eligible players
-            let eligible_hands: Vec<_> = player_hands.iter()
-                .filter(|(seat, _, _, _)| side_pot.eligible_players.contains(seat))
-                .collect();
+    }
 
-            if eligible_hands.is_empty() {
-                continue;
-            }
+    tournament.status = TournamentStatus::Finished;
+    tournament.finished_at = Some(now);
+    tournament.level_ends_at = None;
+    state.auto_deal_at = None;
+    state.action_timer = None;
 
-            // Find the best hand(s) among eligible players
-            let best_rank = match eligible_hands.iter().map(|(_, rank, _, _)| rank).max() {
-                Some(rank) => rank,
-                None => continue, // No eligible hands for this pot
-            };
+    record_tournament_to_history(state);
+}
 
-            let pot_winners: Vec<_> = eligible_hands.iter()
-                .filter(|(_, rank, _, _)| rank == best_rank)
-                .collect();
+// ============================================================================
+// MULTI-TABLE TOURNAMENT CONTROL - called by the tournament canister
+// ============================================================================
 
-            // Guard against division by zero
-            if pot_winners.is_empty() {
-                continue;
-            }
-            let pot_share = side_pot.amount / pot_winners.len() as u64;
-            let remainder = side_pot.amount % pot_winners.len() as u64;
-
-            // Find which winner gets the remainder (first clockwise from dealer)
-            let winner_seats: Vec<u8> = pot_winners.iter().map(|(seat, _, _, _)| *seat).collect();
-            let remainder_seat = first_clockwise_from_dealer(
-                state.dealer_seat,
-                &winner_seats,
-                state.players.len()
-            );
-
-            for (seat, rank, principal, cards) in pot_winners.iter() {
-                let amount = if *seat == remainder_seat { pot_share + remainder } else { pot_share };
-                let entry = chips_awarded.entry(*seat).or_insert(0);
-                *entry = entry.saturating_add(amount);
-
-                // Only add to winner list once per player (aggregate amounts)
-                if let Some(existing) = winner_list.iter_mut().find(|w| w.seat == *seat) {
-                    existing.amount = existing.amount.saturating_add(amount);
-                } else {
-                    winner_list.push(Winner {
-                        seat: *seat,
-                        principal: *principal,
-                        amount,
-                        hand_rank: Some(rank.clone()),
-                        cards: Some(*cards),
-                    });
-                }
+/// Set the tournament canister allowed to run this table (controller only)
+/// Pass None to revoke it
+#[ic_cdk::update]
+fn set_tournament_coordinator(coordinator: Option<Principal>) -> Result<(), String> {
+    require_controller()?;
+    TOURNAMENT_COORDINATOR.with(|c| {
+        *c.borrow_mut() = coordinator;
+    });
+    Ok(())
+}
+
+#[ic_cdk::query]
+fn get_tournament_coordinator() -> Option<Principal> {
+    TOURNAMENT_COORDINATOR.with(|c| *c.borrow())
+}
+
+fn require_coordinator() -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+    let coordinator = TOURNAMENT_COORDINATOR.with(|c| *c.borrow());
+    if coordinator != Some(caller) {
+        return Err("Unauthorized: tournament coordinator access required".to_string());
+    }
+    Ok(())
+}
+
+fn mtt_stacks(state: &TableState) -> Vec<MttStack> {
+    state.players.iter()
+        .flatten()
+        .map(|p| MttStack { principal: p.principal, seat: p.seat, chips: p.chips })
+        .collect()
+}
+
+/// Schedule the next deal if the table can start a hand now
+fn schedule_auto_deal_if_ready(state: &mut TableState) {
+    let between_hands = state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete;
+    if !between_hands || state.auto_deal_at.is_some() || !can_deal_hands(state) {
+        return;
+    }
+    let active_count = state.players.iter()
+        .filter(|p| p.as_ref().map(|p| p.status == PlayerStatus::Active && p.chips > 0).unwrap_or(false))
+        .count();
+    if active_count >= 2 {
+        state.auto_deal_at = Some(ic_cdk::api::time() + AUTO_DEAL_DELAY_NS);
+    }
+}
+
+/// Hand this table to a tournament. The table must be empty and starts paused.
+#[ic_cdk::update]
+fn mtt_attach(tournament_id: u64, blinds: BlindLevel) -> Result<(), String> {
+    require_coordinator()?;
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+
+        if state.tournament.is_some() {
+            return Err("Sit & Go tables can't join a multi-table tournament".to_string());
+        }
+        if state.config.mental_poker.is_some() {
+            return Err("Mental-poker tables can't join a multi-table tournament".to_string());
+        }
+        if let Some(ref mtt) = state.mtt {
+            if mtt.tournament_id != tournament_id {
+                return Err(format!("Table is already running tournament {}", mtt.tournament_id));
             }
         }
-    }
+        if state.mtt.is_none() && state.players.iter().any(|p| p.is_some()) {
+            return Err("Table must be empty to join a tournament".to_string());
+        }
 
-    // Build showdown players list BEFORE awarding chips (need to access chips_awarded)
-    let showdown_players: Vec<ShowdownPlayer> = player_hands.iter().map(|(seat, rank, principal, cards)| {
-        let amount_won = chips_awarded.get(seat).copied().unwrap_or(0);
-        ShowdownPlayer {
-            seat: *seat,
-            principal: *principal,
-            cards: Some(*cards),
-            hand_rank: Some(rank.clone()),
-            amount_won,
+        state.mtt = Some(MttTableState { tournament_id, paused: true, blinds });
+        state.auto_deal_at = None;
+        Ok(())
+    })?;
+    schedule_table_timers();
+    Ok(())
+}
+
+/// Release this table back to cash play once every tournament stack has left
+#[ic_cdk::update]
+fn mtt_detach() -> Result<(), String> {
+    require_coordinator()?;
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+
+        if state.players.iter().any(|p| p.is_some()) {
+            return Err("Unseat every tournament player first".to_string());
         }
-    }).collect();
+        state.mtt = None;
+        state.auto_deal_at = None;
 
-    // Award chips to winners (with overflow protection)
-    for (seat, amount) in chips_awarded {
-        if let Some(ref mut player) = state.players[seat as usize] {
-            player.chips = player.chips.saturating_add(amount);
+        // Back to the configured cash blinds
+        if let Some(config) = TABLE_CONFIG.with(|c| c.borrow().clone()) {
+            state.config.small_blind = config.small_blind;
+            state.config.big_blind = config.big_blind;
+            state.config.ante = config.ante;
+        }
+        Ok(())
+    })?;
+    schedule_table_timers();
+    Ok(())
+}
+
+/// Seat a tournament stack in the first free seat. Returns the seat.
+/// A player seated mid-hand is dealt in from the next hand.
+#[ic_cdk::update]
+fn mtt_seat_player(player: Principal, chips: u64) -> Result<u8, String> {
+    require_coordinator()?;
+    let now = ic_cdk::api::time();
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+
+        if state.mtt.is_none() {
+            return Err("Table is not running a tournament".to_string());
+        }
+        if chips == 0 {
+            return Err("Cannot seat a player with no chips".to_string());
+        }
+        if state.players.iter().flatten().any(|p| p.principal == player) {
+            return Err("Player is already seated".to_string());
+        }
+        let seat = state.players.iter()
+            .position(|p| p.is_none())
+            .ok_or("Table is full")? as u8;
+
+        let joining_during_hand = state.phase != GamePhase::WaitingForPlayers
+            && state.phase != GamePhase::HandComplete;
+        let (status, sitting_out_since) = if joining_during_hand {
+            (PlayerStatus::SittingOut, Some(now))
+        } else {
+            (PlayerStatus::Active, None)
+        };
+
+        state.players[seat as usize] = Some(Player {
+            principal: player,
+            seat,
+            chips, // Tournament chips, not currency
+            hole_cards: None,
+            current_bet: 0,
+            total_bet_this_hand: 0,
+            has_folded: false,
+            has_acted_this_round: false,
+            is_all_in: false,
+            status,
+            last_seen: now,
+            timeout_count: 0,
+            time_bank_remaining: state.config.time_bank_secs,
+            is_sitting_out_next_hand: false,
+            broke_at: None,
+            sitting_out_since,
+            straddle_next_hand: None,
+        });
+
+        schedule_auto_deal_if_ready(state);
+        Ok(seat)
+    });
+    schedule_table_timers();
+    result
+}
+
+/// Take a tournament stack off this table (to move it or end the tournament).
+/// Returns the chips. Fails while the player is still live in a hand.
+#[ic_cdk::update]
+fn mtt_unseat_player(player: Principal) -> Result<u64, String> {
+    require_coordinator()?;
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+
+        if state.mtt.is_none() {
+            return Err("Table is not running a tournament".to_string());
+        }
+        let seat = state.players.iter()
+            .position(|p| p.as_ref().map(|p| p.principal == player).unwrap_or(false))
+            .ok_or("Player is not seated here")?;
+
+        let hand_in_progress = state.phase != GamePhase::WaitingForPlayers
+            && state.phase != GamePhase::HandComplete;
+        let player_state = state.players[seat].as_ref().ok_or("Player not found")?;
+        // Dealt-in players keep their seat until the hand is over
+        if hand_in_progress && player_state.hole_cards.is_some() && !player_state.has_folded {
+            return Err("Player is in a hand".to_string());
         }
-    }
 
-    // Update local history
-    HAND_HISTORY.with(|h| {
-        if let Some(last) = h.borrow_mut().last_mut() {
-            last.winners = winner_list.clone();
-            last.community_cards = state.community_cards.clone();
-            last.showdown_players = showdown_players;
-            CURRENT_ACTIONS.with(|a| {
-                last.actions = a.borrow().clone();
-            });
+        let chips = player_state.chips;
+        state.players[seat] = None;
+        Ok(chips)
+    });
+    schedule_table_timers();
+    result
+}
+
+/// Stop (or resume) dealing new hands. A hand already being played runs to the end.
+/// Returns true while a hand is still in progress.
+#[ic_cdk::update]
+fn mtt_set_paused(paused: bool) -> Result<bool, String> {
+    require_coordinator()?;
+    let result = TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+
+        let mtt = state.mtt.as_mut().ok_or("Table is not running a tournament")?;
+        mtt.paused = paused;
+        if paused {
+            state.auto_deal_at = None;
+        } else {
+            schedule_auto_deal_if_ready(state);
         }
+
+        Ok(state.phase != GamePhase::WaitingForPlayers && state.phase != GamePhase::HandComplete)
     });
+    schedule_table_timers();
+    result
+}
 
-    // Store winners for display (separate from HAND_HISTORY since that gets a new entry when a new hand starts)
-    LAST_HAND_WINNERS.with(|w| {
-        *w.borrow_mut() = winner_list.clone();
+/// Set the blinds for the next hand (the tournament canister runs the blind clock)
+#[ic_cdk::update]
+fn mtt_set_blinds(blinds: BlindLevel) -> Result<(), String> {
+    require_coordinator()?;
+    TABLE.with(|t| {
+        let mut table = t.borrow_mut();
+        let state = table.as_mut().ok_or("Table not initialized")?;
+        let mtt = state.mtt.as_mut().ok_or("Table is not running a tournament")?;
+        mtt.blinds = blinds;
+        Ok(())
+    })
+}
+
+/// Take a tournament buy-in out of a player's escrow balance
+#[ic_cdk::update]
+fn mtt_debit_escrow(player: Principal, amount: u64) -> Result<(), String> {
+    require_coordinator()?;
+    let currency = get_table_currency();
+    BALANCES.with(|b| {
+        let mut balances = b.borrow_mut();
+        let balance = balances.get(&player).copied().unwrap_or(0);
+        if balance < amount {
+            return Err(format!("Insufficient escrow balance. Have: {}, need: {}",
+                currency.format_amount(balance),
+                currency.format_amount(amount)));
+        }
+        balances.insert(player, balance - amount);
+        Ok(())
+    })
+}
+
+/// Pay a tournament prize (or refund) into a player's escrow balance
+#[ic_cdk::update]
+fn mtt_credit_escrow(player: Principal, amount: u64) -> Result<(), String> {
+    require_coordinator()?;
+    BALANCES.with(|b| {
+        let mut balances = b.borrow_mut();
+        let current = balances.entry(player).or_insert(0);
+        *current = current.saturating_add(amount);
     });
+    Ok(())
+}
 
-    // Record to history canister (went to showdown)
-    record_hand_to_history(state, &winner_list, true);
+/// Pay tournament entry fees (taken from escrow with the buy-ins) to the house treasury
+#[ic_cdk::update]
+fn mtt_credit_treasury(amount: u64) -> Result<(), String> {
+    require_coordinator()?;
+    credit_treasury(amount);
+    Ok(())
+}
 
-    state.pot = 0;
-    state.side_pots.clear();
-    state.phase = GamePhase::HandComplete;
-    state.action_timer = None;
+#[ic_cdk::query]
+fn get_mtt_status() -> Option<MttTableStatus> {
+    TABLE.with(|t| {
+        let table = t.borrow();
+        let state = table.as_ref()?;
+        let mtt = state.mtt.as_ref()?;
+        Some(MttTableStatus {
+            tournament_id: mtt.tournament_id,
+            paused: mtt.paused,
+            hand_in_progress: state.phase != GamePhase::WaitingForPlayers
+                && state.phase != GamePhase::HandComplete,
+            hand_number: state.hand_number,
+            stacks: mtt_stacks(state),
+        })
+    })
+}
 
-    // Mark players with 0 chips as broke (start their reload timer)
-    let now = ic_cdk::api::time();
-    for player in state.players.iter_mut().flatten() {
-        if player.chips == 0 && player.broke_at.is_none() {
-            player.broke_at = Some(now);
-        } else if player.chips > 0 {
-            // Player has chips, clear broke status
-            player.broke_at = None;
+/// After each hand at a tournament table: unseat busted players and report
+/// the hand to the tournament canister. Fire and forget - a lost report is
+/// caught up by the coordinator's clock, which polls get_mtt_status.
+fn report_mtt_hand(state: &mut TableState) {
+    let tournament_id = match state.mtt {
+        Some(ref mtt) => mtt.tournament_id,
+        None => return,
+    };
+
+    let mut busted = Vec::new();
+    for seat in state.players.iter_mut() {
+        if seat.as_ref().map(|p| p.chips == 0).unwrap_or(false) {
+            if let Some(player) = seat.take() {
+                let starting_chips = STARTING_CHIPS.with(|s| s.borrow().get(&player.seat).copied().unwrap_or(0));
+                busted.push(MttBust { principal: player.principal, starting_chips });
+            }
         }
     }
 
-    // Schedule auto-deal for next hand
-    state.auto_deal_at = Some(ic_cdk::api::time() + AUTO_DEAL_DELAY_NS);
+    let coordinator = match TOURNAMENT_COORDINATOR.with(|c| *c.borrow()) {
+        Some(id) => id,
+        None => return,
+    };
+    let report = MttHandReport {
+        tournament_id,
+        hand_number: state.hand_number,
+        stacks: mtt_stacks(state),
+        busted,
+    };
+
+    ic_cdk::futures::spawn(async move {
+        let call_result = ic_cdk::call::Call::unbounded_wait(coordinator, "report_hand")
+            .with_arg(report)
+            .await;
+        match call_result {
+            Ok(response) => {
+                if let Ok(Err(e)) = response.candid::<Result<(), String>>() {
+                    ic_cdk::println!("Tournament canister rejected hand report: {}", e);
+                }
+            }
+            Err(e) => {
+                ic_cdk::println!("Failed to report hand to tournament canister: {:?}", e);
+            }
+        }
+    });
 }
 
 // ============================================================================
@@ -3826,9 +6957,16 @@ pub enum TimeoutCheckResult {
 }
 
 /// Check for timeouts, auto-fold, and auto-deal
-/// This should be called periodically or before each action
+/// The canister runs this itself from its own timers; clients may still call it
+/// to get an immediate result (e.g. before each action)
 #[ic_cdk::update]
 fn check_timeouts() -> TimeoutCheckResult {
+    let result = process_timeouts();
+    schedule_table_timers();
+    result
+}
+
+fn process_timeouts() -> TimeoutCheckResult {
     // Run periodic cleanup of unbounded maps
     periodic_cleanup();
 
@@ -3867,9 +7005,19 @@ fn check_timeouts() -> TimeoutCheckResult {
             }
         }
 
+        // Tournaments: move up the blind schedule and keep every remaining entrant dealt in
+        if is_tournament_table(state) {
+            advance_blind_level(state, now);
+            if state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete {
+                deal_in_tournament_players(state);
+            }
+        }
+
         // Auto-kick players who have been sitting out or disconnected for too long
         // Only when not in an active hand (WaitingForPlayers or HandComplete)
-        if state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete {
+        // Never in a tournament - tournament chips must not be paid out to escrow
+        if !is_tournament_table(state)
+            && (state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete) {
             let kick_timeout_ns = SITTING_OUT_KICK_SECS * 1_000_000_000;
             for i in 0..state.players.len() {
                 if let Some(ref player) = state.players[i] {
@@ -3896,9 +7044,19 @@ fn check_timeouts() -> TimeoutCheckResult {
             }
         }
 
+        // Mental poker: a step ran out of time, or the audit is due to check its next player
+        if state.mental.as_ref().and_then(|deal| deal.deadline).is_some_and(|deadline| now > deadline) {
+            mental_poker_timeout(state, now);
+            return TimeoutCheckResult::NoAction;
+        }
+
         // Check for auto-deal first
         // If auto_deal_at is not set but we have 2+ active players in WaitingForPlayers/HandComplete, set it now
-        if state.auto_deal_at.is_none() && (state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete) {
+        // (not while the players are shuffling a mental-poker deck - it's dealt once they're done)
+        let shuffling = state.mental.as_ref()
+            .is_some_and(|deal| matches!(deal.stage, MentalStage::Shuffle | MentalStage::Lock));
+        if state.auto_deal_at.is_none() && can_deal_hands(state) && !shuffling
+            && (state.phase == GamePhase::WaitingForPlayers || state.phase == GamePhase::HandComplete) {
             let active_count = state.players.iter()
                 .filter(|p| p.as_ref().map(|p| p.status == PlayerStatus::Active && p.chips > 0).unwrap_or(false))
                 .count();
@@ -3915,7 +7073,7 @@ fn check_timeouts() -> TimeoutCheckResult {
                     .count();
 
                 if active_count >= 2 {
-                    // Ready to auto-deal - frontend should call start_new_hand
+                    // Ready to auto-deal - the auto-deal timer (or a client) calls start_new_hand
                     return TimeoutCheckResult::AutoDealReady;
                 }
                 // Not enough players - clear auto-deal timer
@@ -3923,6 +7081,14 @@ fn check_timeouts() -> TimeoutCheckResult {
             }
         }
 
+        // Run-it-twice vote: no answer in time counts as declining
+        if let Some(ref vote) = state.run_it_twice {
+            if now > vote.expires_at {
+                finish_run_it_twice_vote(state, false);
+            }
+            return TimeoutCheckResult::NoAction;
+        }
+
         // Then check for player timeouts
         if let Some(ref timer) = state.action_timer {
             if now > timer.expires_at {
@@ -3963,6 +7129,136 @@ fn check_timeouts() -> TimeoutCheckResult {
     })
 }
 
+// ============================================================================
+// CANISTER TIMERS - Server-side auto-deal and action timeouts
+// ============================================================================
+
+/// Deadlines the canister timers are armed for
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TimerDeadlines {
+    pub auto_deal_at: Option<u64>,
+    pub action_expires_at: Option<u64>, // Action timer, or the run-it-twice vote while one is open
+    pub blind_level_ends_at: Option<u64>,
+    pub mental_poker_at: Option<u64>, // The current mental-poker step's deadline (now while auditing)
+}
+
+/// Which deadlines the table needs timers for. Between hands only the auto-deal
+/// matters; during a hand only the action (or run-it-twice vote) timeout. A
+/// running Sit & Go's blind clock and a mental-poker step are armed alongside
+/// either, so whichever deadline is earliest fires first.
+pub fn timer_deadlines(state: &TableState) -> TimerDeadlines {
+    let between_hands = state.phase == GamePhase::WaitingForPlayers
+        || state.phase == GamePhase::HandComplete;
+    let auto_deal_at = if between_hands { state.auto_deal_at } else { None };
+    let action_expires_at = if between_hands {
+        None
+    } else if let Some(ref vote) = state.run_it_twice {
+        Some(vote.expires_at)
+    } else {
+        state.action_timer.as_ref().map(|timer| timer.expires_at)
+    };
+    let blind_level_ends_at = state.tournament.as_ref()
+        .filter(|t| t.status == TournamentStatus::Running)
+        .and_then(|t| t.level_ends_at);
+    let mental_poker_at = state.mental.as_ref().and_then(|deal| deal.deadline);
+    TimerDeadlines { auto_deal_at, action_expires_at, blind_level_ends_at, mental_poker_at }
+}
+
+/// Arm (or re-arm) the canister timers for `auto_deal_at` and the current
+/// `ActionTimer.expires_at`, so the table keeps moving even when no client is
+/// polling check_timeouts. Cheap to call after any state change: a timer is
+/// only replaced when its deadline actually changed.
+fn schedule_table_timers() {
+    let TimerDeadlines { auto_deal_at, action_expires_at, blind_level_ends_at, mental_poker_at } =
+        TABLE.with(|t| t.borrow().as_ref().map(timer_deadlines).unwrap_or_default());
+
+    rearm_timer(&AUTO_DEAL_TIMER, auto_deal_at, on_auto_deal_timer);
+    rearm_timer(&ACTION_TIMEOUT_TIMER, action_expires_at, on_action_timeout_timer);
+    rearm_timer(&BLIND_LEVEL_TIMER, blind_level_ends_at, on_blind_level_timer);
+    rearm_timer(&MENTAL_POKER_TIMER, mental_poker_at, on_mental_poker_timer);
+    schedule_history_outbox_timer();
+}
+
+fn rearm_timer<F, Fut>(
+    slot: &'static LocalKey<RefCell<Option<(u64, TimerId)>>>,
+    deadline: Option<u64>,
+    on_fire: F,
+) where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = ()> + 'static,
+{
+    let armed = slot.with(|s| *s.borrow());
+    if armed.map(|(at, _)| at) == deadline {
+        return;
+    }
+    if let Some((_, id)) = armed {
+        ic_cdk_timers::clear_timer(id);
+    }
+
+    let new_timer = deadline.map(|at| {
+        // check_timeouts uses a strict `now > expires_at`, so fire just after the deadline
+        let delay_ns = at.saturating_sub(ic_cdk::api::time()).saturating_add(TIMER_SLACK_NS);
+        let id = ic_cdk_timers::set_timer(Duration::from_nanos(delay_ns), on_fire());
+        (at, id)
+    });
+    slot.with(|s| *s.borrow_mut() = new_timer);
+}
+
+async fn on_auto_deal_timer() {
+    AUTO_DEAL_TIMER.with(|t| *t.borrow_mut() = None);
+
+    if let TimeoutCheckResult::AutoDealReady = process_timeouts() {
+        match deal_new_hand().await {
+            // Dealt, or closed entropy commitments - the deal follows at the reveal deadline
+            Ok(_) => AUTO_DEAL_FAILURES.with(|f| *f.borrow_mut() = 0),
+            Err(e) => {
+                ic_cdk::println!("Auto-deal failed: {}", e);
+                // auto_deal_at is still in the past - without pushing it out the
+                // timer would fire again straight away and retry forever
+                let failures = AUTO_DEAL_FAILURES.with(|f| {
+                    let mut failures = f.borrow_mut();
+                    *failures = failures.saturating_add(1);
+                    *failures
+                });
+                let retry_at = ic_cdk::api::time().saturating_add(auto_deal_retry_delay_ns(failures));
+                TABLE.with(|t| {
+                    if let Some(state) = t.borrow_mut().as_mut() {
+                        if state.auto_deal_at.is_some_and(|at| at < retry_at) {
+                            state.auto_deal_at = Some(retry_at);
+                        }
+                    }
+                });
+            }
+        }
+    }
+
+    schedule_table_timers();
+}
+
+/// Backoff before the next auto-deal after `consecutive_failures` failed deals in a row
+fn auto_deal_retry_delay_ns(consecutive_failures: u32) -> u64 {
+    let doublings = consecutive_failures.saturating_sub(1).min(16);
+    AUTO_DEAL_DELAY_NS.saturating_mul(1 << doublings).min(AUTO_DEAL_RETRY_MAX_NS)
+}
+
+async fn on_action_timeout_timer() {
+    ACTION_TIMEOUT_TIMER.with(|t| *t.borrow_mut() = None);
+    process_timeouts();
+    schedule_table_timers();
+}
+
+async fn on_blind_level_timer() {
+    BLIND_LEVEL_TIMER.with(|t| *t.borrow_mut() = None);
+    process_timeouts();
+    schedule_table_timers();
+}
+
+async fn on_mental_poker_timer() {
+    MENTAL_POKER_TIMER.with(|t| *t.borrow_mut() = None);
+    process_timeouts();
+    schedule_table_timers();
+}
+
 /// Player heartbeat to show they're connected
 #[ic_cdk::update]
 fn heartbeat() -> Result<(), String> {
@@ -4039,7 +7335,7 @@ fn sit_in() -> Result<(), String> {
     let caller = ic_cdk::api::msg_caller();
     let now = ic_cdk::api::time();
 
-    TABLE.with(|t| {
+    let result = TABLE.with(|t| {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
@@ -4067,7 +7363,10 @@ fn sit_in() -> Result<(), String> {
         }
 
         Err("Not at table".to_string())
-    })
+    });
+
+    schedule_table_timers();
+    result
 }
 
 /// Request to sit out at the end of the current hand
@@ -4097,7 +7396,7 @@ fn use_time_bank() -> Result<u64, String> {
     let caller = ic_cdk::api::msg_caller();
     let now = ic_cdk::api::time();
 
-    TABLE.with(|t| {
+    let result = TABLE.with(|t| {
         let mut table = t.borrow_mut();
         let state = table.as_mut().ok_or("Table not initialized")?;
 
@@ -4138,13 +7437,16 @@ fn use_time_bank() -> Result<u64, String> {
         });
 
         Ok(0) // Time bank is now depleted
-    })
+    });
+
+    schedule_table_timers();
+    result
 }
 
 /// Voluntarily show your hole cards to the table
 /// Only allowed after you've folded or at the end of the hand
 #[ic_cdk::update]
-fn show_cards() -> Result<(Card, Card), String> {
+fn show_hole_cards() -> Result<Vec<Card>, String> {
     let caller = ic_cdk::api::msg_caller();
 
     TABLE.with(|t| {
@@ -4157,7 +7459,7 @@ fn show_cards() -> Result<(Card, Card), String> {
             .ok_or("Not at table")?;
 
         // Must have hole cards
-        let cards = player.hole_cards.ok_or("No cards to show")?;
+        let cards = player.hole_cards.clone().ok_or("No cards to show")?;
 
         // Can only show if folded or hand is complete
         if !player.has_folded && state.phase != GamePhase::HandComplete && state.phase != GamePhase::Showdown {
@@ -4177,6 +7479,25 @@ fn show_cards() -> Result<(Card, Card), String> {
     })
 }
 
+/// show_hole_cards with the two-card reply from before Omaha - kept so existing
+/// callers still decode. Four-card hands have to use show_hole_cards.
+#[ic_cdk::update]
+fn show_cards() -> Result<(Card, Card), String> {
+    let hole_cards = TABLE.with(|t| t.borrow().as_ref().map(|s| s.config.variant().hole_card_count()));
+    if hole_cards.is_some_and(|count| count != 2) {
+        return Err("This table deals more than two hole cards - use show_hole_cards".to_string());
+    }
+    show_hole_cards().and_then(|cards| card_pair(&cards).ok_or_else(|| "No cards to show".to_string()))
+}
+
+/// Hole cards as the (Card, Card) pair the pre-Omaha API returned
+fn card_pair(cards: &[Card]) -> Option<(Card, Card)> {
+    match cards {
+        [first, second] => Some((*first, *second)),
+        _ => None,
+    }
+}
+
 /// Check if a player voluntarily showed their cards this hand
 #[ic_cdk::query]
 fn did_player_show(seat: u8) -> bool {
@@ -4197,7 +7518,7 @@ fn did_player_show(seat: u8) -> bool {
 
 /// Get cards for a player who voluntarily showed them
 #[ic_cdk::query]
-fn get_shown_cards(seat: u8) -> Option<(Card, Card)> {
+fn get_shown_hole_cards(seat: u8) -> Option<Vec<Card>> {
     TABLE.with(|t| {
         let table = t.borrow();
         let state = table.as_ref()?;
@@ -4218,27 +7539,50 @@ fn get_shown_cards(seat: u8) -> Option<(Card, Card)> {
         state.players.get(seat as usize)?
             .as_ref()?
             .hole_cards
+            .clone()
     })
 }
 
+/// get_shown_hole_cards as a two-card pair - None for four-card hands
+#[ic_cdk::query]
+fn get_shown_cards(seat: u8) -> Option<(Card, Card)> {
+    get_shown_hole_cards(seat).and_then(|cards| card_pair(&cards))
+}
+
 // ============================================================================
 // QUERIES
 // ============================================================================
 
-/// Get the raw table state (admin/debug use - exposes all data)
-/// RESTRICTED: Only controllers can access this to prevent cheating
-#[ic_cdk::query]
+/// Get the table state for admin/debug use - sealed, so controllers can't see cards either.
+/// RESTRICTED: Only controllers, and every call is logged (see get_admin_access_log).
+/// An update so the log entry sticks - query-mode calls are refused.
+#[ic_cdk::update]
 fn get_table_state() -> Result<TableState, String> {
-    // SECURITY: This exposes all cards including hole cards and deck
-    // Only allow controllers to access this for debugging
-    if !is_controller() {
-        return Err("Unauthorized: controller access required".to_string());
-    }
+    require_controller()?;
+    record_admin_access("get_table_state")?;
     TABLE.with(|t| {
-        t.borrow().clone().ok_or("Table not initialized".to_string())
+        t.borrow().as_ref().map(sealed_table_state).ok_or("Table not initialized".to_string())
     })
 }
 
+/// The table state with every card nobody at the table can see left out: the
+/// deck (replay_shuffle rebuilds it once the seed is revealed) and all hole
+/// cards (shown ones are in get_table_view and the hand history like anyone's)
+pub fn sealed_table_state(state: &TableState) -> TableState {
+    let mut sealed = state.clone();
+    sealed.deck = Vec::new();
+    for player in sealed.players.iter_mut().flatten() {
+        player.hole_cards = None;
+    }
+    sealed
+}
+
+/// Every controller read of the table state, oldest first (last MAX_ADMIN_ACCESS_ENTRIES)
+#[ic_cdk::query]
+fn get_admin_access_log() -> Vec<AdminAccess> {
+    ADMIN_ACCESS_LOG.with(|log| log.borrow().clone())
+}
+
 /// Get the table view from the caller's perspective
 /// This properly hides opponent hole cards unless at showdown
 #[ic_cdk::query]
@@ -4291,13 +7635,14 @@ fn get_table_view() -> Option<TableView> {
                         principal: player.principal,
                         seat: player.seat,
                         chips: player.chips,
-                        hole_cards: if can_see_cards { player.hole_cards } else { None },
+                        hole_cards: if can_see_cards { player.hole_cards.clone() } else { None },
                         current_bet: player.current_bet,
                         has_folded: player.has_folded,
                         is_all_in: player.is_all_in,
                         status: player.status.clone(),
                         is_self,
                         display_name,
+                        straddle_next_hand: player.straddle_next_hand.unwrap_or(false),
                     }
                 })
             })
@@ -4312,14 +7657,15 @@ fn get_table_view() -> Option<TableView> {
             }
         });
 
-        // Is it my turn?
-        let is_my_turn = my_seat.map(|seat| seat == state.action_on).unwrap_or(false);
+        // Is it my turn? Nobody acts while a run-it-twice vote is open
+        let is_my_turn = state.run_it_twice.is_none()
+            && my_seat.map(|seat| seat == state.action_on).unwrap_or(false);
 
         // Get winners from the most recent completed hand
         let last_hand_winners = LAST_HAND_WINNERS.with(|w| w.borrow().clone());
 
         // Calculate call amount, can_check, can_raise for the caller
-        let (call_amount, can_check, can_raise, my_time_bank) = if let Some(seat) = my_seat {
+        let (call_amount, can_check, can_raise, my_time_bank, min_bet, max_bet) = if let Some(seat) = my_seat {
             if let Some(Some(player)) = state.players.get(seat as usize) {
                 let to_call = if state.current_bet > player.current_bet {
                     state.current_bet - player.current_bet
@@ -4327,21 +7673,29 @@ fn get_table_view() -> Option<TableView> {
                     0
                 };
 
-                // BB can check preflop if no raise
+                // BB (or the straddler) can check preflop if no raise
+                let option_bet = state.straddle.map(|(_, amount)| amount).unwrap_or(state.config.big_blind);
                 let is_bb_with_option = state.phase == GamePhase::PreFlop
                     && state.bb_has_option
-                    && seat == state.big_blind_seat
-                    && state.current_bet == state.config.big_blind;
+                    && seat == option_seat(state)
+                    && state.current_bet == option_bet;
 
                 let check_ok = to_call == 0 || is_bb_with_option;
-                let raise_ok = player.chips > to_call && !player.is_all_in;
+                let limits = bet_limits(state, player.current_bet);
+                let raise_ok = player.chips > to_call && !player.is_all_in && limits.is_some();
 
-                (to_call, check_ok, raise_ok, player.time_bank_remaining)
+                // Bet/raise-to range for the caller: the structure's limits, capped by their stack
+                let stack_max = player.current_bet.saturating_add(player.chips);
+                let (min_bet, max_bet) = limits
+                    .map(|(min, max)| (min.min(stack_max), max.min(stack_max)))
+                    .unwrap_or((0, 0));
+
+                (to_call, check_ok, raise_ok, player.time_bank_remaining, min_bet, max_bet)
             } else {
-                (0, false, false, 0)
+                (0, false, false, 0, state.config.big_blind, 0)
             }
         } else {
-            (0, false, false, 0)
+            (0, false, false, 0, state.config.big_blind, 0)
         };
 
         // Check if current action timer is using time bank
@@ -4374,14 +7728,18 @@ fn get_table_view() -> Option<TableView> {
             call_amount,
             can_check,
             can_raise,
-            min_bet: state.config.big_blind,
+            min_bet,
+            max_bet,
             last_action: state.last_action.clone(),
+            tournament: state.tournament.clone(),
+            run_it_twice: state.run_it_twice.clone(),
+            second_board: state.second_board.clone(),
         })
     })
 }
 
 #[ic_cdk::query]
-fn get_my_cards() -> Option<(Card, Card)> {
+fn get_my_hole_cards() -> Option<Vec<Card>> {
     let caller = ic_cdk::api::msg_caller();
 
     TABLE.with(|t| {
@@ -4389,7 +7747,7 @@ fn get_my_cards() -> Option<(Card, Card)> {
         if let Some(ref state) = *table {
             for player in state.players.iter().flatten() {
                 if player.principal == caller {
-                    return player.hole_cards;
+                    return player.hole_cards.clone();
                 }
             }
         }
@@ -4397,6 +7755,12 @@ fn get_my_cards() -> Option<(Card, Card)> {
     })
 }
 
+/// get_my_hole_cards as a two-card pair - None for four-card hands
+#[ic_cdk::query]
+fn get_my_cards() -> Option<(Card, Card)> {
+    get_my_hole_cards().and_then(|cards| card_pair(&cards))
+}
+
 #[ic_cdk::query]
 fn get_community_cards() -> Vec<Card> {
     TABLE.with(|t| {
@@ -4421,6 +7785,14 @@ fn get_shuffle_proof() -> Option<ShuffleProof> {
     })
 }
 
+/// Player entropy commitments and reveals for the next hand, if any
+#[ic_cdk::query]
+fn get_entropy_round() -> Option<EntropyRound> {
+    TABLE.with(|t| {
+        t.borrow().as_ref().and_then(|s| s.entropy_round.clone())
+    })
+}
+
 #[ic_cdk::query]
 fn get_hand_history(hand_number: u64) -> Option<HandHistory> {
     HAND_HISTORY.with(|h| {
@@ -4452,8 +7824,21 @@ fn get_time_remaining() -> Option<u64> {
     })
 }
 
+/// Verify a revealed seed against its committed hash and that the shuffle can be
+/// replayed. `deck_type` is the proof's deck (None for the 52-card deck).
+/// `entropy` is the proof's player entropy, if any - the seed must then be the VRF
+/// bytes mixed with the revealed nonces. `shuffle_version` is the proof's version
+/// (None for proofs made before versions existed) and must be one this build knows.
+/// The cards actually dealt are checked against replay_shuffle's deck by
+/// the history canister's verify_hand_shuffle.
 #[ic_cdk::query]
-fn verify_shuffle(seed_hash: String, revealed_seed: String) -> bool {
+fn verify_shuffle(
+    seed_hash: String,
+    revealed_seed: String,
+    deck_type: Option<DeckType>,
+    entropy: Option<PlayerEntropy>,
+    shuffle_version: Option<u32>,
+) -> bool {
     let seed_bytes = match hex::decode(&revealed_seed) {
         Ok(b) => b,
         Err(_) => return false,
@@ -4465,7 +7850,47 @@ fn verify_shuffle(seed_hash: String, revealed_seed: String) -> bool {
 
     // Case-insensitive comparison to handle potential case differences
     // from serialization/deserialization through Candid
-    computed_hash.to_lowercase() == seed_hash.to_lowercase()
+    if computed_hash.to_lowercase() != seed_hash.to_lowercase() {
+        return false;
+    }
+    if entropy.is_some_and(|entropy| !entropy_matches(&seed_bytes, &entropy)) {
+        return false;
+    }
+
+    replay_deck(&seed_bytes, deck_type.unwrap_or_default(), shuffle_version).is_ok()
+}
+
+/// Whether a seed is the proof's VRF bytes mixed with its revealed nonces
+fn entropy_matches(seed: &[u8], entropy: &PlayerEntropy) -> bool {
+    let Some(vrf_seed) = entropy.vrf_seed.as_ref().and_then(|s| hex::decode(s).ok()) else {
+        return false; // Not revealed until the hand ends
+    };
+    let nonces: Result<Vec<Option<Vec<u8>>>, _> = entropy.contributions.iter()
+        .map(|c| c.nonce.as_ref().map(hex::decode).transpose())
+        .collect();
+    let Ok(nonces) = nonces else {
+        return false;
+    };
+    let contributions: Vec<(&str, Option<&[u8]>)> = entropy.contributions.iter()
+        .zip(&nonces)
+        .map(|(c, nonce)| (c.commitment.as_str(), nonce.as_deref()))
+        .collect();
+    cleardeck_shuffle::verify_entropy(seed, &entropy.vrf_seed_hash, &vrf_seed, &contributions)
+}
+
+/// Replay the shuffle for a revealed seed and return the deck in dealing order,
+/// so anyone can check the hole cards and board that were dealt
+#[ic_cdk::query]
+fn replay_shuffle(revealed_seed: String, deck_type: Option<DeckType>, shuffle_version: Option<u32>) -> Result<Vec<Card>, String> {
+    let seed_bytes = hex::decode(&revealed_seed)
+        .map_err(|_| "Revealed seed is not valid hex".to_string())?;
+    replay_deck(&seed_bytes, deck_type.unwrap_or_default(), shuffle_version)
+}
+
+fn replay_deck(seed: &[u8], deck_type: DeckType, shuffle_version: Option<u32>) -> Result<Vec<Card>, String> {
+    let mut deck = create_deck(deck_type);
+    shuffle_deck(&mut deck, seed, shuffle_version)?;
+    Ok(deck)
 }
 
 /// Get current player count (for lobby display)
@@ -4500,25 +7925,111 @@ fn get_max_players() -> u8 {
 // STABLE MEMORY - Persistence across upgrades
 // ============================================================================
 
+/// Everything pre_upgrade saves to stable memory
 #[derive(CandidType, Deserialize)]
-struct PersistentState {
-    balances: Vec<(Principal, u64)>,
-    verified_deposits: Vec<(u64, Principal)>,
-    controllers: Vec<Principal>,
-    history_id: Option<Principal>,
+pub struct PersistentState {
+    pub balances: Vec<(Principal, u64)>,
+    pub verified_deposits: Vec<(u64, Principal)>,
+    pub controllers: Vec<Principal>,
+    pub history_id: Option<Principal>,
     #[serde(default)] // For backwards compatibility with old state
-    dev_mode: bool, // Kept for deserialization compatibility, but always ignored
-    table_config: Option<TableConfig>,
-    table_state: Option<TableState>, // Save active game state
-    hand_history: Vec<HandHistory>,
-    current_actions: Vec<ActionRecord>,
-    starting_chips: Vec<(u8, u64)>,
-    rate_limits: Vec<(Principal, (u64, u32))>,
-    shown_cards: Vec<(u64, Vec<u8>)>, // hand_number -> seats that showed
+    pub dev_mode: bool, // Kept for deserialization compatibility, but always ignored
+    pub table_config: Option<TableConfig>,
+    pub table_state: Option<TableState>, // Save active game state
+    pub hand_history: Vec<HandHistory>,
+    pub current_actions: Vec<ActionRecord>,
+    pub starting_chips: Vec<(u8, u64)>,
+    pub rate_limits: Vec<(Principal, (u64, u32))>,
+    pub shown_cards: Vec<(u64, Vec<u8>)>, // hand_number -> seats that showed
+    #[serde(default)]
+    pub current_seed: Option<Vec<u8>>, // Persist seed for mid-hand upgrades
+    #[serde(default)]
+    pub display_names: Vec<(Principal, String)>, // Custom display names
+    #[serde(default)]
+    pub tournament_coordinator: Option<Principal>,
+    #[serde(default)]
+    pub treasury_balance: Option<u64>,
+    #[serde(default)]
+    pub treasury_account: Option<Account>,
     #[serde(default)]
-    current_seed: Option<Vec<u8>>, // Persist seed for mid-hand upgrades
+    history_outbox: Option<HistoryOutbox>,
     #[serde(default)]
-    display_names: Vec<(Principal, String)>, // Custom display names
+    pub current_vrf_seed: Option<Vec<u8>>,
+    #[serde(default)] // opt, not vec: candid only fills in missing fields that are opt
+    pub admin_access_log: Option<Vec<AdminAccess>>,
+}
+
+// Builds before Omaha saved hole and showdown cards as a (Card, Card) pair.
+// Candid decodes a saved pair as None under today's Option<Vec<Card>>, which
+// would wipe live hole cards on a mid-hand upgrade, so the saved state is read
+// a second time through these views and the pairs are copied over.
+#[derive(CandidType, Deserialize)]
+struct LegacyCardsState {
+    table_state: Option<LegacyCardsTable>,
+    hand_history: Option<Vec<LegacyCardsHand>>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyCardsTable {
+    players: Option<Vec<Option<LegacyCardsSeat>>>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyCardsSeat {
+    hole_cards: Option<(Card, Card)>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyCardsHand {
+    winners: Option<Vec<LegacyCardsShown>>,
+    showdown_players: Option<Vec<LegacyCardsShown>>,
+}
+
+#[derive(CandidType, Deserialize)]
+struct LegacyCardsShown {
+    cards: Option<(Card, Card)>,
+}
+
+/// Decode the state pre_upgrade saved, including states saved by older builds
+pub fn restore_persistent_state(bytes: &[u8]) -> Result<PersistentState, String> {
+    let mut state: PersistentState = decode_saved(bytes)?;
+    if let Ok(legacy) = decode_saved::<LegacyCardsState>(bytes) {
+        restore_legacy_cards(&mut state, legacy);
+    }
+    Ok(state)
+}
+
+/// Decode the value at the start of stable memory - the bytes past it are unused pages
+fn decode_saved<T: CandidType + for<'de> Deserialize<'de>>(bytes: &[u8]) -> Result<T, String> {
+    let mut de = candid::de::IDLDeserialize::new(bytes).map_err(|e| format!("{:?}", e))?;
+    de.get_value::<T>().map_err(|e| format!("{:?}", e))
+}
+
+fn restore_legacy_cards(state: &mut PersistentState, legacy: LegacyCardsState) {
+    fn copy_pair(cards: &mut Option<Vec<Card>>, pair: Option<(Card, Card)>) {
+        if let (None, Some((c1, c2))) = (&cards, pair) {
+            *cards = Some(vec![c1, c2]);
+        }
+    }
+
+    if let (Some(table), Some(legacy_table)) = (state.table_state.as_mut(), legacy.table_state) {
+        let legacy_players = legacy_table.players.unwrap_or_default();
+        for (player, legacy_player) in table.players.iter_mut().zip(legacy_players) {
+            if let (Some(player), Some(legacy_player)) = (player.as_mut(), legacy_player) {
+                copy_pair(&mut player.hole_cards, legacy_player.hole_cards);
+            }
+        }
+    }
+
+    for (hand, legacy_hand) in state.hand_history.iter_mut().zip(legacy.hand_history.unwrap_or_default()) {
+        for (winner, legacy_winner) in hand.winners.iter_mut().zip(legacy_hand.winners.unwrap_or_default()) {
+            copy_pair(&mut winner.cards, legacy_winner.cards);
+        }
+        let legacy_showdown = legacy_hand.showdown_players.unwrap_or_default();
+        for (player, legacy_player) in hand.showdown_players.iter_mut().zip(legacy_showdown) {
+            copy_pair(&mut player.cards, legacy_player.cards);
+        }
+    }
 }
 
 #[ic_cdk::pre_upgrade]
@@ -4538,6 +8049,12 @@ fn pre_upgrade() {
         shown_cards: SHOWN_CARDS.with(|s| s.borrow().iter().map(|(k, v)| (*k, v.clone())).collect()),
         current_seed: CURRENT_SEED.with(|s| s.borrow().clone()), // Save seed for mid-hand upgrades
         display_names: DISPLAY_NAMES.with(|d| d.borrow().iter().map(|(k, v)| (*k, v.clone())).collect()),
+        tournament_coordinator: TOURNAMENT_COORDINATOR.with(|c| *c.borrow()),
+        treasury_balance: Some(TREASURY_BALANCE.with(|t| *t.borrow())),
+        treasury_account: TREASURY_ACCOUNT.with(|t| *t.borrow()),
+        history_outbox: Some(HISTORY_OUTBOX.with(|o| o.borrow().clone())),
+        current_vrf_seed: CURRENT_VRF_SEED.with(|s| s.borrow().clone()),
+        admin_access_log: Some(ADMIN_ACCESS_LOG.with(|log| log.borrow().clone())),
     };
 
     if let Err(e) = ic_cdk::storage::stable_save((state,)) {
@@ -4549,10 +8066,10 @@ fn pre_upgrade() {
 
 #[ic_cdk::post_upgrade]
 fn post_upgrade() {
-    let restore_result: Result<(PersistentState,), _> = ic_cdk::storage::stable_restore();
+    let restore_result = restore_persistent_state(&ic_cdk::stable::stable_bytes());
 
     let state = match restore_result {
-        Ok((s,)) => s,
+        Ok(s) => s,
         Err(e) => {
             // FAIL LOUDLY - do NOT silently lose user funds!
             // If this panics, the upgrade will be rejected and the old code will remain.
@@ -4586,6 +8103,20 @@ fn post_upgrade() {
         *h.borrow_mut() = state.history_id;
     });
 
+    TOURNAMENT_COORDINATOR.with(|c| {
+        *c.borrow_mut() = state.tournament_coordinator;
+    });
+
+    TREASURY_BALANCE.with(|t| {
+        *t.borrow_mut() = state.treasury_balance.unwrap_or(0);
+    });
+    TREASURY_ACCOUNT.with(|t| {
+        *t.borrow_mut() = state.treasury_account;
+    });
+    HISTORY_OUTBOX.with(|o| {
+        *o.borrow_mut() = state.history_outbox.unwrap_or_default();
+    });
+
     // dev_mode is intentionally NOT restored - it's permanently disabled
     // The field is kept in PersistentState only for backwards compatibility
     let _ = state.dev_mode; // Explicitly ignore
@@ -4644,6 +8175,12 @@ fn post_upgrade() {
     CURRENT_SEED.with(|s| {
         *s.borrow_mut() = state.current_seed;
     });
+    CURRENT_VRF_SEED.with(|s| {
+        *s.borrow_mut() = state.current_vrf_seed;
+    });
+    ADMIN_ACCESS_LOG.with(|log| {
+        *log.borrow_mut() = state.admin_access_log.unwrap_or_default();
+    });
 
     // Restore display names
     DISPLAY_NAMES.with(|d| {
@@ -4652,6 +8189,8 @@ fn post_upgrade() {
             names.insert(k, v);
         }
     });
+    // Timers don't survive upgrades - re-arm auto-deal / action timeout from restored state
+    schedule_table_timers();
 }
 
 // ============================================================================
@@ -59,12 +59,70 @@ pub enum HandRank {
     RoyalFlush,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeckType {
+    Standard,
+    ShortDeck,
+}
+
+impl DeckType {
+    fn deck_size(&self) -> usize {
+        match self {
+            DeckType::Standard => 52,
+            DeckType::ShortDeck => 36,
+        }
+    }
+}
+
+impl HandRank {
+    fn category(&self, deck: DeckType) -> u8 {
+        match self {
+            HandRank::HighCard(_) => 0,
+            HandRank::Pair(_, _) => 1,
+            HandRank::TwoPair(_, _, _) => 2,
+            HandRank::ThreeOfAKind(_, _) => 3,
+            HandRank::Straight(_) => 4,
+            HandRank::Flush(_) if deck == DeckType::ShortDeck => 6,
+            HandRank::Flush(_) => 5,
+            HandRank::FullHouse(_, _) if deck == DeckType::ShortDeck => 5,
+            HandRank::FullHouse(_, _) => 6,
+            HandRank::FourOfAKind(_, _) => 7,
+            HandRank::StraightFlush(_) => 8,
+            HandRank::RoyalFlush => 9,
+        }
+    }
+
+    fn compare(&self, other: &HandRank, deck: DeckType) -> std::cmp::Ordering {
+        self.category(deck).cmp(&other.category(deck))
+            .then_with(|| self.cmp(other))
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct SidePot {
     pub amount: u64,
     pub eligible_players: Vec<u8>,
 }
 
+#[derive(Clone, Debug)]
+pub struct StraddleConfig {
+    pub max_straddles: u8,
+    pub allow_button_straddle: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct RakeCap {
+    pub min_players: u8,
+    pub cap: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct RakeConfig {
+    pub percent_bps: u32,
+    pub caps: Vec<RakeCap>,
+    pub no_flop_no_drop: bool,
+}
+
 #[derive(Clone, Debug)]
 pub struct PlayerContribution {
     pub seat: u8,
@@ -77,18 +135,22 @@ pub struct PlayerContribution {
 // DECK CREATION AND SHUFFLE
 // =============================================================================
 
-fn create_deck() -> Vec<Card> {
+fn create_deck(deck_type: DeckType) -> Vec<Card> {
     let suits = [Suit::Hearts, Suit::Diamonds, Suit::Clubs, Suit::Spades];
     let ranks = [
         Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
         Rank::Seven, Rank::Eight, Rank::Nine, Rank::Ten,
         Rank::Jack, Rank::Queen, Rank::King, Rank::Ace,
     ];
+    let lowest_rank = match deck_type {
+        DeckType::Standard => Rank::Two,
+        DeckType::ShortDeck => Rank::Six,
+    };
 
-    let mut deck = Vec::with_capacity(52);
+    let mut deck = Vec::with_capacity(deck_type.deck_size());
     for suit in suits {
-        for rank in ranks {
-            deck.push(Card { suit, rank });
+        for rank in ranks.iter().filter(|r| r.value() >= lowest_rank.value()) {
+            deck.push(Card { suit, rank: *rank });
         }
     }
     deck
@@ -151,62 +213,41 @@ fn combinations(cards: &[Card], k: usize) -> Vec<Vec<Card>> {
     result
 }
 
-fn check_straight(ranks: &[u8]) -> bool {
-    let mut sorted_ranks = ranks.to_vec();
-    sorted_ranks.sort();
-    sorted_ranks.dedup();
+fn detect_straight(ranks: &[u8], deck: DeckType) -> Option<u8> {
+    let mut sorted: Vec<u8> = ranks.to_vec();
+    sorted.sort_by(|a, b| b.cmp(a));
+    sorted.dedup();
 
-    if sorted_ranks.len() < 5 {
-        return false;
+    if sorted.len() < 5 {
+        return None;
     }
 
-    // Check for regular straight
-    for window in sorted_ranks.windows(5) {
-        if window[4] - window[0] == 4 {
-            return true;
-        }
+    let wheel_low_cards: [u8; 4] = match deck {
+        DeckType::Standard => [2, 3, 4, 5],
+        DeckType::ShortDeck => [6, 7, 8, 9],
+    };
+    if sorted.contains(&14) && wheel_low_cards.iter().all(|r| sorted.contains(r)) {
+        return Some(wheel_low_cards[3]);
     }
 
-    // Check for wheel (A-2-3-4-5)
-    if sorted_ranks.contains(&14) && sorted_ranks.contains(&2) &&
-       sorted_ranks.contains(&3) && sorted_ranks.contains(&4) &&
-       sorted_ranks.contains(&5) {
-        return true;
+    for window in sorted.windows(5) {
+        if window[0] - window[4] == 4 {
+            return Some(window[0]);
+        }
     }
 
-    false
+    None
 }
 
-fn get_straight_high(ranks: &[u8]) -> u8 {
-    let mut sorted_ranks = ranks.to_vec();
-    sorted_ranks.sort();
-    sorted_ranks.dedup();
-
-    // Check for wheel first (high card is 5, not Ace)
-    if sorted_ranks.contains(&14) && sorted_ranks.contains(&2) &&
-       sorted_ranks.contains(&3) && sorted_ranks.contains(&4) &&
-       sorted_ranks.contains(&5) {
-        // Check if there's a higher straight
-        for window in sorted_ranks.windows(5) {
-            if window[4] - window[0] == 4 && window[4] > 5 {
-                return window[4];
-            }
-        }
-        return 5; // Wheel
-    }
-
-    // Find highest straight
-    for i in (0..=sorted_ranks.len().saturating_sub(5)).rev() {
-        let window = &sorted_ranks[i..i+5];
-        if window[4] - window[0] == 4 {
-            return window[4];
-        }
-    }
+fn check_straight(ranks: &[u8], deck: DeckType) -> bool {
+    detect_straight(ranks, deck).is_some()
+}
 
-    0
+fn get_straight_high(ranks: &[u8], deck: DeckType) -> u8 {
+    detect_straight(ranks, deck).unwrap_or(0)
 }
 
-fn evaluate_five_cards(cards: &[Card]) -> HandRank {
+fn evaluate_five_cards(cards: &[Card], deck: DeckType) -> HandRank {
     let mut ranks: Vec<u8> = cards.iter().map(|c| c.rank.value()).collect();
     ranks.sort_by(|a, b| b.cmp(a));
 
@@ -219,8 +260,8 @@ fn evaluate_five_cards(cards: &[Card]) -> HandRank {
     }
 
     let is_flush = suits.values().any(|&count| count >= 5);
-    let is_straight = check_straight(&ranks);
-    let straight_high = if is_straight { get_straight_high(&ranks) } else { 0 };
+    let is_straight = check_straight(&ranks, deck);
+    let straight_high = if is_straight { get_straight_high(&ranks, deck) } else { 0 };
 
     if is_flush && is_straight && straight_high == 14 {
         return HandRank::RoyalFlush;
@@ -293,18 +334,21 @@ fn evaluate_five_cards(cards: &[Card]) -> HandRank {
 }
 
 fn evaluate_hand(hole_cards: &(Card, Card), community: &[Card]) -> HandRank {
+    evaluate_holdem_hand(&[hole_cards.0, hole_cards.1], community, DeckType::Standard)
+}
+
+fn evaluate_holdem_hand(hole_cards: &[Card], community: &[Card], deck: DeckType) -> HandRank {
     let mut all_cards: Vec<Card> = Vec::with_capacity(7);
-    all_cards.push(hole_cards.0);
-    all_cards.push(hole_cards.1);
+    all_cards.extend_from_slice(hole_cards);
     all_cards.extend_from_slice(community);
 
     let mut best_rank: Option<HandRank> = None;
 
     for combo in combinations(&all_cards, 5) {
-        let rank = evaluate_five_cards(&combo);
+        let rank = evaluate_five_cards(&combo, deck);
         match &best_rank {
             None => best_rank = Some(rank),
-            Some(current) if rank > *current => best_rank = Some(rank),
+            Some(current) if rank.compare(current, deck).is_gt() => best_rank = Some(rank),
             _ => {}
         }
     }
@@ -312,6 +356,166 @@ fn evaluate_hand(hole_cards: &(Card, Card), community: &[Card]) -> HandRank {
     best_rank.unwrap_or(HandRank::HighCard(vec![]))
 }
 
+fn evaluate_omaha_hand(hole_cards: &[Card], community: &[Card], deck: DeckType) -> HandRank {
+    let mut best_rank: Option<HandRank> = None;
+
+    for hole_pair in combinations(hole_cards, 2) {
+        for board_three in combinations(community, 3) {
+            let mut five = hole_pair.clone();
+            five.extend_from_slice(&board_three);
+            let rank = evaluate_five_cards(&five, deck);
+            match &best_rank {
+                None => best_rank = Some(rank),
+                Some(current) if rank.compare(current, deck).is_gt() => best_rank = Some(rank),
+                _ => {}
+            }
+        }
+    }
+
+    best_rank.unwrap_or(HandRank::HighCard(vec![]))
+}
+
+fn evaluate_low_five_cards(cards: &[Card]) -> Option<Vec<u8>> {
+    let mut ranks: Vec<u8> = cards.iter()
+        .map(|c| if c.rank == Rank::Ace { 1 } else { c.rank.value() })
+        .collect();
+    ranks.sort_by(|a, b| b.cmp(a));
+    ranks.dedup();
+
+    if ranks.len() != 5 || ranks[0] > 8 {
+        return None;
+    }
+
+    Some(ranks)
+}
+
+fn evaluate_omaha_low_hand(hole_cards: &[Card], community: &[Card]) -> Option<Vec<u8>> {
+    let mut best_low: Option<Vec<u8>> = None;
+
+    for hole_pair in combinations(hole_cards, 2) {
+        for board_three in combinations(community, 3) {
+            let mut five = hole_pair.clone();
+            five.extend_from_slice(&board_three);
+            if let Some(low) = evaluate_low_five_cards(&five) {
+                match &best_low {
+                    Some(current) if low >= *current => {}
+                    _ => best_low = Some(low),
+                }
+            }
+        }
+    }
+
+    best_low
+}
+
+fn split_hi_lo_pot(amount: u64, has_low: bool) -> (u64, u64) {
+    if !has_low {
+        return (amount, 0);
+    }
+    let low = amount / 2;
+    (amount - low, low)
+}
+
+fn run_share(amount: u64, run: Option<u8>) -> u64 {
+    match run {
+        Some(1) => amount - amount / 2,
+        Some(_) => amount / 2,
+        None => amount,
+    }
+}
+
+fn pot_limit_max_bet(pot: u64, current_bet: u64, player_current_bet: u64) -> u64 {
+    let to_call = current_bet.saturating_sub(player_current_bet);
+    current_bet.saturating_add(pot.saturating_add(to_call))
+}
+
+const FIXED_LIMIT_MAX_RAISES: u64 = 3;
+
+fn fixed_limit_raise_to(current_bet: u64, bet_size: u64, players_in_hand: usize) -> Option<u64> {
+    let bets_made = current_bet / bet_size.max(1);
+    if players_in_hand > 2 && bets_made > FIXED_LIMIT_MAX_RAISES {
+        return None;
+    }
+    Some(bets_made.saturating_add(1).saturating_mul(bet_size))
+}
+
+// =============================================================================
+// SIT & GO FINISHING ORDER (payouts are tested in cleardeck_payouts)
+// =============================================================================
+
+fn bust_positions(players_remaining: usize, busted: &[(u8, u64)]) -> Vec<(u8, u8)> {
+    let mut order = busted.to_vec();
+    order.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    order.iter()
+        .enumerate()
+        .map(|(i, (seat, _))| (*seat, (players_remaining + 1 + i) as u8))
+        .collect()
+}
+
+// =============================================================================
+// STRADDLES
+// =============================================================================
+
+fn plan_straddles(order: &[(u8, u64, bool)], big_blind: u64, config: &StraddleConfig) -> Vec<(u8, u64)> {
+    let straddle = big_blind.saturating_mul(2);
+    if config.allow_button_straddle {
+        if let Some(&(seat, chips, true)) = order.last() {
+            if chips > straddle {
+                return vec![(seat, straddle)];
+            }
+        }
+    }
+
+    let mut amount = straddle;
+    let mut straddles = Vec::new();
+    for &(seat, chips, opted_in) in order.iter().take(config.max_straddles as usize) {
+        if !opted_in || chips <= amount {
+            break;
+        }
+        straddles.push((seat, amount));
+        amount = amount.saturating_mul(2);
+    }
+    straddles
+}
+
+// =============================================================================
+// RAKE
+// =============================================================================
+
+fn calculate_rake(pot: u64, players_dealt: usize, saw_flop: bool, config: &RakeConfig) -> u64 {
+    if config.no_flop_no_drop && !saw_flop {
+        return 0;
+    }
+    let rake = (pot as u128 * config.percent_bps as u128 / 10_000) as u64;
+    let cap = config.caps.iter()
+        .filter(|c| c.min_players as usize <= players_dealt)
+        .max_by_key(|c| c.min_players)
+        .map(|c| c.cap);
+    match cap {
+        Some(cap) => rake.min(cap),
+        None => rake,
+    }
+}
+
+fn take_rake_from_pots(pots: &mut [SidePot], rake: u64) {
+    let mut remaining = rake;
+    for pot in pots.iter_mut().filter(|p| p.eligible_players.len() > 1) {
+        let taken = remaining.min(pot.amount);
+        pot.amount -= taken;
+        remaining -= taken;
+    }
+}
+
+fn uncalled_bet(bets: &[(u8, u64)]) -> Option<(u8, u64)> {
+    let (seat, top) = bets.iter().copied().max_by_key(|(_, bet)| *bet)?;
+    let called = bets.iter()
+        .filter(|(s, _)| *s != seat)
+        .map(|(_, bet)| *bet)
+        .max()
+        .unwrap_or(0);
+    (top > called).then_some((seat, top - called))
+}
+
 // =============================================================================
 // SIDE POT CALCULATION
 // =============================================================================
@@ -389,6 +593,25 @@ fn calculate_side_pots(contributions: &[PlayerContribution], total_pot: u64) ->
     side_pots
 }
 
+const HISTORY_RETRY_BASE_NS: u64 = 5_000_000_000;
+const HISTORY_RETRY_MAX_NS: u64 = 600_000_000_000;
+
+fn history_retry_delay_ns(consecutive_failures: u32) -> u64 {
+    if consecutive_failures == 0 {
+        return 0;
+    }
+    let doublings = (consecutive_failures - 1).min(16);
+    HISTORY_RETRY_BASE_NS.saturating_mul(1 << doublings).min(HISTORY_RETRY_MAX_NS)
+}
+
+const AUTO_DEAL_DELAY_NS: u64 = 3_000_000_000;
+const AUTO_DEAL_RETRY_MAX_NS: u64 = 300_000_000_000;
+
+fn auto_deal_retry_delay_ns(consecutive_failures: u32) -> u64 {
+    let doublings = consecutive_failures.saturating_sub(1).min(16);
+    AUTO_DEAL_DELAY_NS.saturating_mul(1 << doublings).min(AUTO_DEAL_RETRY_MAX_NS)
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -403,13 +626,13 @@ mod tests {
 
     #[test]
     fn test_create_deck_has_52_cards() {
-        let deck = create_deck();
+        let deck = create_deck(DeckType::Standard);
         assert_eq!(deck.len(), 52);
     }
 
     #[test]
     fn test_create_deck_has_all_suits() {
-        let deck = create_deck();
+        let deck = create_deck(DeckType::Standard);
         let suits: Vec<Suit> = deck.iter().map(|c| c.suit).collect();
 
         assert_eq!(suits.iter().filter(|&&s| s == Suit::Hearts).count(), 13);
@@ -420,7 +643,7 @@ mod tests {
 
     #[test]
     fn test_create_deck_has_all_ranks() {
-        let deck = create_deck();
+        let deck = create_deck(DeckType::Standard);
         let ranks: Vec<Rank> = deck.iter().map(|c| c.rank).collect();
 
         for rank in [Rank::Two, Rank::Three, Rank::Four, Rank::Five, Rank::Six,
@@ -432,7 +655,7 @@ mod tests {
 
     #[test]
     fn test_create_deck_no_duplicates() {
-        let deck = create_deck();
+        let deck = create_deck(DeckType::Standard);
         let mut seen = std::collections::HashSet::new();
         for card in &deck {
             let key = (card.suit, card.rank);
@@ -445,8 +668,8 @@ mod tests {
     fn test_shuffle_deterministic() {
         let seed = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
 
-        let mut deck1 = create_deck();
-        let mut deck2 = create_deck();
+        let mut deck1 = create_deck(DeckType::Standard);
+        let mut deck2 = create_deck(DeckType::Standard);
 
         shuffle_deck(&mut deck1, &seed);
         shuffle_deck(&mut deck2, &seed);
@@ -459,8 +682,8 @@ mod tests {
         let seed1 = vec![1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16];
         let seed2 = vec![16, 15, 14, 13, 12, 11, 10, 9, 8, 7, 6, 5, 4, 3, 2, 1];
 
-        let mut deck1 = create_deck();
-        let mut deck2 = create_deck();
+        let mut deck1 = create_deck(DeckType::Standard);
+        let mut deck2 = create_deck(DeckType::Standard);
 
         shuffle_deck(&mut deck1, &seed1);
         shuffle_deck(&mut deck2, &seed2);
@@ -471,7 +694,7 @@ mod tests {
     #[test]
     fn test_shuffle_preserves_all_cards() {
         let seed = vec![42u8; 32];
-        let original_deck = create_deck();
+        let original_deck = create_deck(DeckType::Standard);
         let mut shuffled_deck = original_deck.clone();
 
         shuffle_deck(&mut shuffled_deck, &seed);
@@ -944,4 +1167,1058 @@ mod tests {
 
         assert!(pair_with_ace > pair_with_king);
     }
+
+    // =========================================================================
+    // POT-LIMIT OMAHA TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_omaha_board_flush_needs_two_suited_hole_cards() {
+        // Four hearts on board plus one heart in hand is not a flush in Omaha
+        let hole = vec![
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::King, Suit::Clubs),
+            card(Rank::Seven, Suit::Diamonds),
+            card(Rank::Two, Suit::Spades),
+        ];
+        let community = vec![
+            card(Rank::Three, Suit::Hearts),
+            card(Rank::Six, Suit::Hearts),
+            card(Rank::Nine, Suit::Hearts),
+            card(Rank::Jack, Suit::Hearts),
+            card(Rank::Queen, Suit::Clubs),
+        ];
+
+        let result = evaluate_omaha_hand(&hole, &community, DeckType::Standard);
+        assert!(!matches!(result, HandRank::Flush(_)));
+        assert!(result < HandRank::Flush(vec![14, 11, 9, 6, 3]));
+    }
+
+    #[test]
+    fn test_omaha_must_use_exactly_two_hole_cards() {
+        // Four aces in hand only play as a pair of aces
+        let hole = vec![
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Ace, Suit::Diamonds),
+            card(Rank::Ace, Suit::Clubs),
+            card(Rank::Ace, Suit::Spades),
+        ];
+        let community = vec![
+            card(Rank::King, Suit::Hearts),
+            card(Rank::Eight, Suit::Diamonds),
+            card(Rank::Five, Suit::Clubs),
+            card(Rank::Three, Suit::Spades),
+            card(Rank::Two, Suit::Hearts),
+        ];
+
+        let result = evaluate_omaha_hand(&hole, &community, DeckType::Standard);
+        assert_eq!(result, HandRank::Pair(14, vec![13, 8, 5]));
+    }
+
+    #[test]
+    fn test_omaha_hand_with_two_hole_cards() {
+        let hole = vec![
+            card(Rank::Ten, Suit::Spades),
+            card(Rank::Nine, Suit::Spades),
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::Two, Suit::Diamonds),
+        ];
+        let community = vec![
+            card(Rank::Eight, Suit::Hearts),
+            card(Rank::Seven, Suit::Clubs),
+            card(Rank::Six, Suit::Diamonds),
+            card(Rank::King, Suit::Spades),
+            card(Rank::Ace, Suit::Hearts),
+        ];
+
+        let result = evaluate_omaha_hand(&hole, &community, DeckType::Standard);
+        assert_eq!(result, HandRank::Straight(10));
+    }
+
+    #[test]
+    fn test_pot_limit_max_bet_preflop() {
+        // 1/2 blinds, first to act: calling 2 makes the pot 5, so raise to 2 + 5 = 7
+        assert_eq!(pot_limit_max_bet(3, 2, 0), 7);
+        // Small blind: calling 1 more makes the pot 4, so raise to 2 + 4 = 6
+        assert_eq!(pot_limit_max_bet(3, 2, 1), 6);
+    }
+
+    #[test]
+    fn test_pot_limit_max_bet_opening_bet() {
+        // No bet yet: a pot-sized bet equals the pot
+        assert_eq!(pot_limit_max_bet(100, 0, 0), 100);
+    }
+
+    #[test]
+    fn test_pot_limit_max_bet_facing_bet() {
+        // Pot 100, opponent bets 50 (pot now 150): call 50 makes 200, raise to 50 + 200 = 250
+        assert_eq!(pot_limit_max_bet(150, 50, 0), 250);
+    }
+
+    // =========================================================================
+    // OMAHA HI-LO TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_low_wheel_is_best() {
+        let wheel = evaluate_low_five_cards(&[
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::Three, Suit::Diamonds),
+            card(Rank::Four, Suit::Spades),
+            card(Rank::Five, Suit::Hearts),
+        ]);
+        let six_low = evaluate_low_five_cards(&[
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::Three, Suit::Diamonds),
+            card(Rank::Four, Suit::Spades),
+            card(Rank::Six, Suit::Hearts),
+        ]);
+
+        assert_eq!(wheel, Some(vec![5, 4, 3, 2, 1]));
+        assert!(wheel < six_low);
+    }
+
+    #[test]
+    fn test_low_requires_eight_or_better() {
+        let nine_low = evaluate_low_five_cards(&[
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::Three, Suit::Diamonds),
+            card(Rank::Four, Suit::Spades),
+            card(Rank::Nine, Suit::Hearts),
+        ]);
+        assert_eq!(nine_low, None);
+    }
+
+    #[test]
+    fn test_low_ignores_paired_hands() {
+        let paired = evaluate_low_five_cards(&[
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Ace, Suit::Clubs),
+            card(Rank::Three, Suit::Diamonds),
+            card(Rank::Four, Suit::Spades),
+            card(Rank::Five, Suit::Hearts),
+        ]);
+        assert_eq!(paired, None);
+    }
+
+    #[test]
+    fn test_low_compares_from_highest_card() {
+        // 8-5-4-3-2 beats 8-6-3-2-A
+        let eight_five = vec![8, 5, 4, 3, 2];
+        let eight_six = vec![8, 6, 3, 2, 1];
+        assert!(eight_five < eight_six);
+    }
+
+    #[test]
+    fn test_omaha_low_uses_two_hole_cards() {
+        // Board has five low cards but only one low card in hand: no low
+        let hole = vec![
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::King, Suit::Clubs),
+            card(Rank::Queen, Suit::Diamonds),
+            card(Rank::Jack, Suit::Spades),
+        ];
+        let community = vec![
+            card(Rank::Two, Suit::Hearts),
+            card(Rank::Three, Suit::Clubs),
+            card(Rank::Four, Suit::Diamonds),
+            card(Rank::Five, Suit::Spades),
+            card(Rank::Six, Suit::Hearts),
+        ];
+        assert_eq!(evaluate_omaha_low_hand(&hole, &community), None);
+
+        let hole = vec![
+            card(Rank::Ace, Suit::Hearts),
+            card(Rank::Two, Suit::Clubs),
+            card(Rank::King, Suit::Diamonds),
+            card(Rank::King, Suit::Spades),
+        ];
+        let community = vec![
+            card(Rank::Three, Suit::Hearts),
+            card(Rank::Four, Suit::Clubs),
+            card(Rank::Eight, Suit::Diamonds),
+            card(Rank::Queen, Suit::Spades),
+            card(Rank::Jack, Suit::Hearts),
+        ];
+        assert_eq!(evaluate_omaha_low_hand(&hole, &community), Some(vec![8, 4, 3, 2, 1]));
+    }
+
+    #[test]
+    fn test_split_hi_lo_pot_odd_chip_to_high() {
+        assert_eq!(split_hi_lo_pot(100, true), (50, 50));
+        assert_eq!(split_hi_lo_pot(101, true), (51, 50));
+    }
+
+    #[test]
+    fn test_split_hi_lo_pot_no_low_scoops() {
+        assert_eq!(split_hi_lo_pot(101, false), (101, 0));
+    }
+
+    // =========================================================================
+    // SHORT DECK TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_short_deck_has_36_cards() {
+        let deck = create_deck(DeckType::ShortDeck);
+        assert_eq!(deck.len(), 36);
+        assert!(deck.iter().all(|c| c.rank.value() >= 6));
+    }
+
+    #[test]
+    fn test_short_deck_shuffle_preserves_all_cards() {
+        let original_deck = create_deck(DeckType::ShortDeck);
+        let mut shuffled_deck = original_deck.clone();
+        shuffle_deck(&mut shuffled_deck, b"short_deck_seed");
+
+        assert_eq!(shuffled_deck.len(), 36);
+        for card in &original_deck {
+            assert!(shuffled_deck.contains(card));
+        }
+    }
+
+    #[test]
+    fn test_short_deck_flush_beats_full_house() {
+        let flush = HandRank::Flush(vec![13, 11, 9, 8, 6]);
+        let full_house = HandRank::FullHouse(14, 13);
+
+        assert!(flush.compare(&full_house, DeckType::ShortDeck).is_gt());
+        assert!(flush.compare(&full_house, DeckType::Standard).is_lt());
+    }
+
+    #[test]
+    fn test_short_deck_wheel_is_a_6_7_8_9() {
+        let hole = [card(Rank::Ace, Suit::Hearts), card(Rank::Six, Suit::Clubs)];
+        let community = vec![
+            card(Rank::Seven, Suit::Diamonds),
+            card(Rank::Eight, Suit::Spades),
+            card(Rank::Nine, Suit::Hearts),
+            card(Rank::King, Suit::Clubs),
+            card(Rank::King, Suit::Diamonds),
+        ];
+
+        assert_eq!(evaluate_holdem_hand(&hole, &community, DeckType::ShortDeck), HandRank::Straight(9));
+        // Not a straight with standard rules
+        assert_eq!(evaluate_holdem_hand(&hole, &community, DeckType::Standard), HandRank::Pair(13, vec![14, 9, 8]));
+    }
+
+
+    // =========================================================================
+    // FIXED-LIMIT TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_fixed_limit_opening_bet_is_one_bet() {
+        // 2/4 limit on the turn: the big bet is 8
+        assert_eq!(fixed_limit_raise_to(0, 8, 4), Some(8));
+    }
+
+    #[test]
+    fn test_fixed_limit_preflop_big_blind_counts_as_bet() {
+        // 2/4 limit preflop: blind is 4, raise goes to 8, then 12, then 16 (cap)
+        assert_eq!(fixed_limit_raise_to(4, 4, 6), Some(8));
+        assert_eq!(fixed_limit_raise_to(8, 4, 6), Some(12));
+        assert_eq!(fixed_limit_raise_to(12, 4, 6), Some(16));
+        assert_eq!(fixed_limit_raise_to(16, 4, 6), None);
+    }
+
+    #[test]
+    fn test_fixed_limit_uncapped_heads_up() {
+        assert_eq!(fixed_limit_raise_to(16, 4, 2), Some(20));
+        assert_eq!(fixed_limit_raise_to(40, 4, 2), Some(44));
+    }
+
+    #[test]
+    fn test_fixed_limit_incomplete_all_in_raise() {
+        // Short all-in raised 4 to 6: next raise completes to 8, not 10
+        assert_eq!(fixed_limit_raise_to(6, 4, 3), Some(8));
+    }
+
+    // =========================================================================
+    // SIT & GO TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_bust_positions_single_elimination() {
+        // 3 players left after the bust: the busted player finishes 4th
+        assert_eq!(bust_positions(3, &[(5, 1500)]), vec![(5, 4)]);
+    }
+
+    #[test]
+    fn test_bust_positions_bigger_stack_finishes_higher() {
+        // Two players bust on the same hand heads-up with the winner
+        let positions = bust_positions(1, &[(2, 800), (4, 1200)]);
+        assert_eq!(positions, vec![(4, 2), (2, 3)]);
+    }
+
+    // =========================================================================
+    // RAKE TESTS
+    // =========================================================================
+
+    fn rake_config(no_flop_no_drop: bool) -> RakeConfig {
+        RakeConfig {
+            percent_bps: 500,
+            caps: vec![
+                RakeCap { min_players: 2, cap: 50 },
+                RakeCap { min_players: 5, cap: 150 },
+            ],
+            no_flop_no_drop,
+        }
+    }
+
+    #[test]
+    fn test_rake_percentage_rounds_down() {
+        // 5% of 1010 is 50.5
+        assert_eq!(calculate_rake(1010, 6, true, &rake_config(false)), 50);
+        assert_eq!(calculate_rake(19, 6, true, &rake_config(false)), 0);
+    }
+
+    #[test]
+    fn test_rake_cap_depends_on_players_dealt() {
+        let config = rake_config(false);
+        // 5% of 10000 = 500, capped at 50 heads-up and 150 with 5+ players
+        assert_eq!(calculate_rake(10_000, 2, true, &config), 50);
+        assert_eq!(calculate_rake(10_000, 4, true, &config), 50);
+        assert_eq!(calculate_rake(10_000, 5, true, &config), 150);
+        assert_eq!(calculate_rake(10_000, 9, true, &config), 150);
+    }
+
+    #[test]
+    fn test_rake_no_flop_no_drop() {
+        assert_eq!(calculate_rake(1000, 6, false, &rake_config(true)), 0);
+        assert_eq!(calculate_rake(1000, 6, true, &rake_config(true)), 50);
+        // Without the rule preflop pots are raked too
+        assert_eq!(calculate_rake(1000, 6, false, &rake_config(false)), 50);
+    }
+
+    #[test]
+    fn test_rake_taken_from_main_pot_first() {
+        let mut pots = vec![
+            SidePot { amount: 30, eligible_players: vec![0, 1, 2] },
+            SidePot { amount: 200, eligible_players: vec![1, 2] },
+        ];
+        take_rake_from_pots(&mut pots, 40);
+        assert_eq!(pots[0].amount, 0);
+        assert_eq!(pots[1].amount, 190);
+    }
+
+    #[test]
+    fn test_rake_skips_pot_only_one_player_can_win() {
+        let mut pots = vec![
+            SidePot { amount: 300, eligible_players: vec![0, 1] },
+            SidePot { amount: 150, eligible_players: vec![1] },
+        ];
+        take_rake_from_pots(&mut pots, 15);
+        assert_eq!(pots[0].amount, 285);
+        assert_eq!(pots[1].amount, 150);
+    }
+
+    #[test]
+    fn test_uncalled_bet_goes_back_unraked() {
+        // Shove for 500 into a 200 all-in: 300 was never called
+        assert_eq!(uncalled_bet(&[(0, 200), (1, 500), (2, 50)]), Some((1, 300)));
+        // Blinds folded around: the big blind's extra 5 comes back
+        assert_eq!(uncalled_bet(&[(0, 5), (1, 10)]), Some((1, 5)));
+        // Called bets are all contested
+        assert_eq!(uncalled_bet(&[(0, 400), (1, 400), (2, 100)]), None);
+        assert_eq!(uncalled_bet(&[]), None);
+    }
+
+    // =========================================================================
+    // RUN IT TWICE TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_run_share_splits_pot_between_runs() {
+        assert_eq!(run_share(1000, None), 1000);
+        assert_eq!(run_share(1000, Some(1)), 500);
+        assert_eq!(run_share(1000, Some(2)), 500);
+    }
+
+    #[test]
+    fn test_run_share_odd_chip_to_first_run() {
+        assert_eq!(run_share(1001, Some(1)), 501);
+        assert_eq!(run_share(1001, Some(2)), 500);
+    }
+
+    #[test]
+    fn test_run_it_twice_hi_lo_quarters() {
+        // Each run's half is then split high/low: 1001 -> 501 (251/250) + 500 (250/250)
+        let first = split_hi_lo_pot(run_share(1001, Some(1)), true);
+        let second = split_hi_lo_pot(run_share(1001, Some(2)), true);
+        assert_eq!(first, (251, 250));
+        assert_eq!(second, (250, 250));
+        assert_eq!(first.0 + first.1 + second.0 + second.1, 1001);
+    }
+
+    // =========================================================================
+    // STRADDLE TESTS
+    // =========================================================================
+
+    fn straddle_config(max_straddles: u8, allow_button_straddle: bool) -> StraddleConfig {
+        StraddleConfig { max_straddles, allow_button_straddle }
+    }
+
+    #[test]
+    fn test_utg_straddle_doubles_big_blind() {
+        // UTG (seat 3) opts in at 10/20
+        let order = [(3, 1000, true), (4, 1000, false), (0, 1000, false)];
+        assert_eq!(plan_straddles(&order, 20, &straddle_config(1, false)), vec![(3, 40)]);
+    }
+
+    #[test]
+    fn test_re_straddles_stop_at_cap_and_first_decline() {
+        let order = [(3, 1000, true), (4, 1000, true), (5, 1000, true), (0, 1000, true)];
+        assert_eq!(plan_straddles(&order, 20, &straddle_config(2, false)), vec![(3, 40), (4, 80)]);
+        // Seat 4 passing ends the chain even though seat 5 opted in
+        let order = [(3, 1000, true), (4, 1000, false), (5, 1000, true)];
+        assert_eq!(plan_straddles(&order, 20, &straddle_config(3, false)), vec![(3, 40)]);
+        // Nobody straddles when UTG doesn't
+        let order = [(3, 1000, false), (4, 1000, true)];
+        assert!(plan_straddles(&order, 20, &straddle_config(3, false)).is_empty());
+    }
+
+    #[test]
+    fn test_straddle_needs_chips_behind() {
+        let order = [(3, 40, true)];
+        assert!(plan_straddles(&order, 20, &straddle_config(1, false)).is_empty());
+    }
+
+    #[test]
+    fn test_button_straddle_takes_priority() {
+        let order = [(3, 1000, true), (4, 1000, false), (0, 1000, true)];
+        assert_eq!(plan_straddles(&order, 20, &straddle_config(2, true)), vec![(0, 40)]);
+        // Not allowed at this table: UTG straddles instead
+        assert_eq!(plan_straddles(&order, 20, &straddle_config(2, false)), vec![(3, 40)]);
+    }
+
+    // =========================================================================
+    // HISTORY OUTBOX TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_history_retry_backoff_doubles() {
+        assert_eq!(history_retry_delay_ns(0), 0);
+        assert_eq!(history_retry_delay_ns(1), 5_000_000_000);
+        assert_eq!(history_retry_delay_ns(2), 10_000_000_000);
+        assert_eq!(history_retry_delay_ns(4), 40_000_000_000);
+    }
+
+    #[test]
+    fn test_history_retry_backoff_capped() {
+        assert_eq!(history_retry_delay_ns(8), HISTORY_RETRY_MAX_NS);
+        assert_eq!(history_retry_delay_ns(u32::MAX), HISTORY_RETRY_MAX_NS);
+    }
+
+    // =========================================================================
+    // AUTO-DEAL TIMER TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_auto_deal_retry_backs_off() {
+        // A failed deal never retries sooner than the normal deal delay
+        assert_eq!(auto_deal_retry_delay_ns(0), AUTO_DEAL_DELAY_NS);
+        assert_eq!(auto_deal_retry_delay_ns(1), AUTO_DEAL_DELAY_NS);
+        assert_eq!(auto_deal_retry_delay_ns(3), 4 * AUTO_DEAL_DELAY_NS);
+        assert_eq!(auto_deal_retry_delay_ns(u32::MAX), AUTO_DEAL_RETRY_MAX_NS);
+    }
+}
+
+// =============================================================================
+// UPGRADE COMPATIBILITY
+// =============================================================================
+// States saved by the last build before the variant work, decoded through the
+// canister's own restore_persistent_state. Candid only fills in missing fields
+// that are opt - anything added to a persisted struct since has to stay opt.
+
+#[cfg(test)]
+mod upgrade_tests {
+    use candid::{CandidType, Encode, Principal};
+    use table_canister::{
+        restore_persistent_state, ActionRecord, ActionTimer, Card, Currency, GamePhase, HandRank, LastActionInfo,
+        PlayerStatus, Rank, SidePot, Suit, TableState,
+    };
+
+    #[derive(CandidType)]
+    struct SavedConfig {
+        small_blind: u64,
+        big_blind: u64,
+        min_buy_in: u64,
+        max_buy_in: u64,
+        max_players: u8,
+        action_timeout_secs: u64,
+        ante: u64,
+        time_bank_secs: u64,
+        currency: Currency,
+    }
+
+    #[derive(CandidType)]
+    struct SavedShuffleProof {
+        seed_hash: String,
+        revealed_seed: Option<String>,
+        timestamp: u64,
+    }
+
+    #[derive(CandidType)]
+    struct SavedPlayer {
+        principal: Principal,
+        seat: u8,
+        chips: u64,
+        hole_cards: Option<(Card, Card)>,
+        current_bet: u64,
+        total_bet_this_hand: u64,
+        has_folded: bool,
+        has_acted_this_round: bool,
+        is_all_in: bool,
+        status: PlayerStatus,
+        last_seen: u64,
+        timeout_count: u8,
+        time_bank_remaining: u64,
+        is_sitting_out_next_hand: bool,
+        broke_at: Option<u64>,
+        sitting_out_since: Option<u64>,
+    }
+
+    #[derive(CandidType)]
+    struct SavedTable {
+        id: u64,
+        config: SavedConfig,
+        players: Vec<Option<SavedPlayer>>,
+        community_cards: Vec<Card>,
+        deck: Vec<Card>,
+        deck_index: usize,
+        pot: u64,
+        side_pots: Vec<SidePot>,
+        current_bet: u64,
+        min_raise: u64,
+        phase: GamePhase,
+        dealer_seat: u8,
+        small_blind_seat: u8,
+        big_blind_seat: u8,
+        action_on: u8,
+        action_timer: Option<ActionTimer>,
+        shuffle_proof: Option<SavedShuffleProof>,
+        hand_number: u64,
+        last_aggressor: Option<u8>,
+        bb_has_option: bool,
+        first_hand: bool,
+        auto_deal_at: Option<u64>,
+        last_action: Option<LastActionInfo>,
+    }
+
+    #[derive(CandidType)]
+    struct SavedWinner {
+        seat: u8,
+        principal: Principal,
+        amount: u64,
+        hand_rank: Option<HandRank>,
+        cards: Option<(Card, Card)>,
+    }
+
+    #[derive(CandidType)]
+    struct SavedShowdownPlayer {
+        seat: u8,
+        principal: Principal,
+        cards: Option<(Card, Card)>,
+        hand_rank: Option<HandRank>,
+        amount_won: u64,
+    }
+
+    #[derive(CandidType)]
+    struct SavedHand {
+        hand_number: u64,
+        shuffle_proof: SavedShuffleProof,
+        actions: Vec<ActionRecord>,
+        winners: Vec<SavedWinner>,
+        community_cards: Vec<Card>,
+        showdown_players: Vec<SavedShowdownPlayer>,
+    }
+
+    #[derive(CandidType)]
+    struct SavedState {
+        balances: Vec<(Principal, u64)>,
+        verified_deposits: Vec<(u64, Principal)>,
+        controllers: Vec<Principal>,
+        history_id: Option<Principal>,
+        dev_mode: bool,
+        table_config: Option<SavedConfig>,
+        table_state: Option<SavedTable>,
+        hand_history: Vec<SavedHand>,
+        current_actions: Vec<ActionRecord>,
+        starting_chips: Vec<(u8, u64)>,
+        rate_limits: Vec<(Principal, (u64, u32))>,
+        shown_cards: Vec<(u64, Vec<u8>)>,
+        current_seed: Option<Vec<u8>>,
+        display_names: Vec<(Principal, String)>,
+    }
+
+    fn player() -> Principal {
+        Principal::from_slice(&[7])
+    }
+
+    fn saved_config() -> SavedConfig {
+        SavedConfig {
+            small_blind: 10,
+            big_blind: 20,
+            min_buy_in: 400,
+            max_buy_in: 2_000,
+            max_players: 6,
+            action_timeout_secs: 60,
+            ante: 0,
+            time_bank_secs: 30,
+            currency: Currency::ICP,
+        }
+    }
+
+    fn saved_player(seat: u8, chips: u64, hole_cards: Option<(Card, Card)>) -> SavedPlayer {
+        SavedPlayer {
+            principal: player(),
+            seat,
+            chips,
+            hole_cards,
+            current_bet: 0,
+            total_bet_this_hand: 0,
+            has_folded: false,
+            has_acted_this_round: false,
+            is_all_in: false,
+            status: PlayerStatus::Active,
+            last_seen: 0,
+            timeout_count: 0,
+            time_bank_remaining: 30,
+            is_sitting_out_next_hand: false,
+            broke_at: None,
+            sitting_out_since: None,
+        }
+    }
+
+    /// Two-seat table as the canister restores it in post_upgrade
+    pub(super) fn restored_table(phase: GamePhase, auto_deal_at: Option<u64>, action_timer: Option<ActionTimer>) -> TableState {
+        let players = vec![Some(saved_player(0, 480, None)), Some(saved_player(1, 480, None))];
+        let table = SavedTable { auto_deal_at, action_timer, ..saved_table(phase, players) };
+        restore_persistent_state(&saved_state(Some(table), vec![])).unwrap().table_state.unwrap()
+    }
+
+    fn saved_table(phase: GamePhase, players: Vec<Option<SavedPlayer>>) -> SavedTable {
+        SavedTable {
+            id: 1,
+            config: saved_config(),
+            players,
+            community_cards: vec![],
+            deck: vec![],
+            deck_index: 0,
+            pot: 0,
+            side_pots: vec![],
+            current_bet: 0,
+            min_raise: 20,
+            phase,
+            dealer_seat: 0,
+            small_blind_seat: 0,
+            big_blind_seat: 1,
+            action_on: 0,
+            action_timer: None,
+            shuffle_proof: None,
+            hand_number: 12,
+            last_aggressor: None,
+            bb_has_option: false,
+            first_hand: false,
+            auto_deal_at: None,
+            last_action: None,
+        }
+    }
+
+    fn saved_state(table_state: Option<SavedTable>, hand_history: Vec<SavedHand>) -> Vec<u8> {
+        let state = SavedState {
+            balances: vec![(player(), 1_000)],
+            verified_deposits: vec![],
+            controllers: vec![player()],
+            history_id: None,
+            dev_mode: false,
+            table_config: Some(saved_config()),
+            table_state,
+            hand_history,
+            current_actions: vec![],
+            starting_chips: vec![],
+            rate_limits: vec![],
+            shown_cards: vec![],
+            current_seed: None,
+            display_names: vec![],
+        };
+        // Stable memory is whole pages - the saved state is followed by zeros
+        let mut bytes = Encode!(&state).unwrap();
+        bytes.resize(bytes.len() + 64, 0);
+        bytes
+    }
+
+    #[test]
+    fn test_restore_state_saved_before_admin_access_log() {
+        let state = restore_persistent_state(&saved_state(None, vec![])).unwrap();
+        assert_eq!(state.balances, vec![(player(), 1_000)]);
+        assert!(state.admin_access_log.is_none());
+        assert!(state.treasury_balance.is_none());
+    }
+
+    #[test]
+    fn test_restore_keeps_players_saved_before_straddles() {
+        let table = saved_table(GamePhase::WaitingForPlayers, vec![Some(saved_player(0, 500, None)), None]);
+        let state = restore_persistent_state(&saved_state(Some(table), vec![])).unwrap();
+
+        let players = state.table_state.unwrap().players;
+        let seated = players[0].as_ref().expect("seat survives the upgrade");
+        assert_eq!(seated.chips, 500);
+        assert_eq!(seated.straddle_next_hand, None);
+        assert!(players[1].is_none());
+    }
+
+    #[test]
+    fn test_restore_hole_cards_saved_as_pairs() {
+        let ace = Card { suit: Suit::Spades, rank: Rank::Ace };
+        let king = Card { suit: Suit::Hearts, rank: Rank::King };
+        let players = vec![Some(saved_player(0, 480, Some((ace, king)))), Some(saved_player(1, 480, None))];
+        let table = saved_table(GamePhase::Flop, players);
+        let hand = SavedHand {
+            hand_number: 11,
+            shuffle_proof: SavedShuffleProof { seed_hash: "ab".to_string(), revealed_seed: Some("cd".to_string()), timestamp: 0 },
+            actions: vec![],
+            winners: vec![SavedWinner { seat: 0, principal: player(), amount: 40, hand_rank: None, cards: Some((king, ace)) }],
+            community_cards: vec![],
+            showdown_players: vec![SavedShowdownPlayer {
+                seat: 0,
+                principal: player(),
+                cards: Some((king, ace)),
+                hand_rank: None,
+                amount_won: 40,
+            }],
+        };
+        let state = restore_persistent_state(&saved_state(Some(table), vec![hand])).unwrap();
+
+        // Mid-hand: the live hole cards are still there to play the hand out
+        let players = state.table_state.unwrap().players;
+        assert_eq!(players[0].as_ref().unwrap().hole_cards, Some(vec![ace, king]));
+        assert_eq!(players[1].as_ref().unwrap().hole_cards, None);
+
+        assert_eq!(state.hand_history[0].winners[0].cards, Some(vec![king, ace]));
+        assert_eq!(state.hand_history[0].showdown_players[0].cards, Some(vec![king, ace]));
+    }
+}
+
+// =============================================================================
+// TIMER DEADLINES
+// =============================================================================
+// Timers are dropped by an upgrade; post_upgrade re-arms whatever
+// timer_deadlines picks from the restored state.
+
+#[cfg(test)]
+mod timer_tests {
+    use super::upgrade_tests::restored_table;
+    use table_canister::{
+        timer_deadlines, ActionTimer, GamePhase, RunItTwiceVote, TimerDeadlines, TournamentState, TournamentStatus,
+    };
+
+    const SECOND: u64 = 1_000_000_000;
+
+    fn action_timer(expires_at: u64) -> ActionTimer {
+        ActionTimer { player_seat: 0, started_at: expires_at - 30 * SECOND, expires_at, using_time_bank: false }
+    }
+
+    fn sit_and_go(status: TournamentStatus, level_ends_at: Option<u64>) -> TournamentState {
+        TournamentState {
+            status,
+            entrants: vec![],
+            prize_pool: 0,
+            fees_collected: 0,
+            level: 0,
+            level_ends_at,
+            started_at: None,
+            finished_at: None,
+            results: vec![],
+        }
+    }
+
+    #[test]
+    fn test_between_hands_only_auto_deal_is_armed() {
+        // A stale action timer from the last hand must not fire into the next one
+        let state = restored_table(GamePhase::HandComplete, Some(100 * SECOND), Some(action_timer(90 * SECOND)));
+        let expected = TimerDeadlines { auto_deal_at: Some(100 * SECOND), ..Default::default() };
+        assert_eq!(timer_deadlines(&state), expected);
+    }
+
+    #[test]
+    fn test_mid_hand_only_action_timeout_is_armed() {
+        let state = restored_table(GamePhase::Turn, Some(100 * SECOND), Some(action_timer(130 * SECOND)));
+        let expected = TimerDeadlines { action_expires_at: Some(130 * SECOND), ..Default::default() };
+        assert_eq!(timer_deadlines(&state), expected);
+    }
+
+    #[test]
+    fn test_run_it_twice_vote_replaces_action_timeout() {
+        let mut state = restored_table(GamePhase::Turn, None, Some(action_timer(130 * SECOND)));
+        state.run_it_twice = Some(RunItTwiceVote { seats: vec![0, 1], accepted: vec![], expires_at: 110 * SECOND });
+        assert_eq!(timer_deadlines(&state).action_expires_at, Some(110 * SECOND));
+    }
+
+    #[test]
+    fn test_blind_level_armed_alongside_hand_deadlines() {
+        let mut state = restored_table(GamePhase::Flop, None, Some(action_timer(130 * SECOND)));
+        state.tournament = Some(sit_and_go(TournamentStatus::Running, Some(120 * SECOND)));
+        // The level ends before the action times out - both are armed, the level fires first
+        let expected = TimerDeadlines {
+            action_expires_at: Some(130 * SECOND),
+            blind_level_ends_at: Some(120 * SECOND),
+            ..Default::default()
+        };
+        assert_eq!(timer_deadlines(&state), expected);
+
+        state.phase = GamePhase::HandComplete;
+        state.auto_deal_at = Some(105 * SECOND);
+        let expected = TimerDeadlines {
+            auto_deal_at: Some(105 * SECOND),
+            blind_level_ends_at: Some(120 * SECOND),
+            ..Default::default()
+        };
+        assert_eq!(timer_deadlines(&state), expected);
+    }
+
+    #[test]
+    fn test_blind_clock_idle_unless_tournament_running() {
+        let mut state = restored_table(GamePhase::WaitingForPlayers, None, None);
+        state.tournament = Some(sit_and_go(TournamentStatus::Registering, Some(120 * SECOND)));
+        assert_eq!(timer_deadlines(&state), TimerDeadlines::default());
+        state.tournament = Some(sit_and_go(TournamentStatus::Finished, Some(120 * SECOND)));
+        assert_eq!(timer_deadlines(&state), TimerDeadlines::default());
+    }
+
+    #[test]
+    fn test_timers_rearmed_from_state_saved_mid_hand() {
+        // Saved by a build before timers existed: the deadlines come back from the
+        // persisted action timer alone, so the stalled hand still times out
+        let state = restored_table(GamePhase::PreFlop, None, Some(action_timer(130 * SECOND)));
+        assert_eq!(timer_deadlines(&state).action_expires_at, Some(130 * SECOND));
+
+        let state = restored_table(GamePhase::WaitingForPlayers, Some(100 * SECOND), None);
+        assert_eq!(timer_deadlines(&state).auto_deal_at, Some(100 * SECOND));
+    }
+}
+
+// ============================================================================
+// MENTAL POKER
+// ============================================================================
+// The table side of the protocol: whose step is due, who still owes a key, how
+// a void hand is paid out and when the step timer fires. The SRA maths itself
+// is tested in cleardeck_sra.
+
+#[cfg(test)]
+mod mental_poker_tests {
+    use super::upgrade_tests::restored_table;
+    use candid::Principal;
+    use table_canister::{
+        timer_deadlines, void_hand_payouts, GamePhase, MentalCard, MentalDeal, MentalParticipant, MentalStage,
+    };
+
+    fn participant(seat: u8) -> MentalParticipant {
+        MentalParticipant {
+            seat,
+            principal: Principal::from_slice(&[seat + 1]),
+            secret_commitment: None,
+            key_hashes: vec![],
+            secret: None,
+            audit_passed: None,
+        }
+    }
+
+    /// A deal between `seats` with `decks` decks of 4 placeholder cards so far
+    fn deal(stage: MentalStage, seats: &[u8], decks: usize) -> MentalDeal {
+        MentalDeal {
+            deal_id: 1,
+            hand_number: 13,
+            stage,
+            deadline: None,
+            participants: seats.iter().map(|&seat| participant(seat)).collect(),
+            decks: vec![vec![vec![0; 4]; 4]; decks],
+            cards: vec![],
+            opening: vec![],
+            corrupt: false,
+            forfeits: vec![],
+        }
+    }
+
+    fn card(position: u32, owner: Option<u8>, keys_from: &[u8]) -> MentalCard {
+        MentalCard {
+            position,
+            owner,
+            keys: keys_from.iter().map(|&seat| (seat, vec![])).collect(),
+            partial: vec![],
+            card: None,
+        }
+    }
+
+    #[test]
+    fn test_steps_go_round_in_seat_order() {
+        assert_eq!(deal(MentalStage::Shuffle, &[0, 3, 5], 1).turn(), Some(0));
+        assert_eq!(deal(MentalStage::Shuffle, &[0, 3, 5], 3).turn(), Some(2));
+        // Locking starts over from the first seat once everyone has shuffled
+        assert_eq!(deal(MentalStage::Lock, &[0, 3, 5], 4).turn(), Some(0));
+        assert_eq!(deal(MentalStage::Lock, &[0, 3, 5], 6).turn(), Some(2));
+        assert_eq!(deal(MentalStage::Ready, &[0, 3, 5], 7).turn(), None);
+        assert_eq!(deal(MentalStage::Ready, &[0, 3, 5], 7).deck_size(), 4);
+    }
+
+    #[test]
+    fn test_hole_card_keys_owed_by_everyone_but_the_owner() {
+        let mut deal = deal(MentalStage::OpenHoleCards, &[0, 1, 2], 7);
+        deal.cards = vec![card(0, Some(0), &[]), card(1, Some(1), &[])];
+        deal.opening = vec![0, 1];
+        assert!(!deal.owes_key(&deal.cards[0], 0));
+        assert!(deal.owes_key(&deal.cards[1], 0));
+        assert_eq!(deal.owing_seats(), vec![0, 1, 2]);
+
+        deal.cards = vec![card(0, Some(0), &[1, 2]), card(1, Some(1), &[0])];
+        assert_eq!(deal.owing_seats(), vec![2]);
+        deal.cards[1].keys.push((2, vec![]));
+        assert!(deal.owing_seats().is_empty());
+
+        // At showdown the owner hands over the last key to their own cards
+        deal.stage = MentalStage::OpenShowdown;
+        deal.opening = vec![0];
+        assert_eq!(deal.owing_seats(), vec![0]);
+    }
+
+    #[test]
+    fn test_board_keys_owed_by_everyone() {
+        let mut deal = deal(MentalStage::OpenBoard, &[0, 1], 5);
+        deal.cards = vec![card(4, Some(0), &[1]), card(5, None, &[1])];
+        deal.opening = vec![5];
+        assert!(!deal.owes_key(&deal.cards[0], 0)); // Not being opened
+        assert!(deal.owes_key(&deal.cards[1], 0));
+        assert_eq!(deal.owing_seats(), vec![0]);
+    }
+
+    #[test]
+    fn test_void_hand_refunds_all_but_forfeits() {
+        // Seat 1 stalled: seats 0 and 2 get their bets back, seat 1's bet goes to seat 0 (still in)
+        let bets = [(0, 100, true), (1, 100, true), (2, 50, false)];
+        assert_eq!(void_hand_payouts(250, &bets, &[1]), vec![(0, 200), (2, 50)]);
+    }
+
+    #[test]
+    fn test_void_hand_odd_chips_to_earliest_seat() {
+        let bets = [(2, 40, true), (4, 101, true), (7, 40, true)];
+        assert_eq!(void_hand_payouts(181, &bets, &[4]), vec![(2, 91), (7, 90)]);
+    }
+
+    #[test]
+    fn test_void_hand_everyone_else_folded() {
+        // The forfeiting seat was the last one in - everyone else shares its bet
+        let bets = [(0, 10, false), (1, 60, true), (2, 20, false)];
+        assert_eq!(void_hand_payouts(90, &bets, &[1]), vec![(0, 40), (2, 50)]);
+    }
+
+    #[test]
+    fn test_void_hand_nobody_to_pay() {
+        // Everyone stalled, or nobody forfeited (a corrupt deck): bets go back
+        let bets = [(0, 10, true), (1, 20, true)];
+        assert_eq!(void_hand_payouts(30, &bets, &[0, 1]), vec![(0, 10), (1, 20)]);
+        assert_eq!(void_hand_payouts(30, &bets, &[]), vec![(0, 10), (1, 20)]);
+    }
+
+    #[test]
+    fn test_step_deadline_armed_alongside_hand_deadlines() {
+        let mut state = restored_table(GamePhase::HandComplete, None, None);
+        assert!(state.mental.is_none() && state.config.mental_poker.is_none());
+        assert_eq!(timer_deadlines(&state).mental_poker_at, None);
+
+        let mut shuffling = deal(MentalStage::Shuffle, &[0, 1], 1);
+        shuffling.deadline = Some(42);
+        state.mental = Some(shuffling);
+        assert_eq!(timer_deadlines(&state).mental_poker_at, Some(42));
+
+        state.phase = GamePhase::Flop;
+        state.mental.as_mut().unwrap().stage = MentalStage::OpenBoard;
+        assert_eq!(timer_deadlines(&state).mental_poker_at, Some(42));
+        assert_eq!(timer_deadlines(&state).action_expires_at, None);
+    }
+}
+
+// ============================================================================
+// ADMIN ACCESS
+// ============================================================================
+// Controllers read a sealed copy of the table state, and every read lands in a
+// public log capped at MAX_ADMIN_ACCESS_ENTRIES.
+
+#[cfg(test)]
+mod admin_access_tests {
+    use super::upgrade_tests::restored_table;
+    use candid::Principal;
+    use table_canister::{
+        admin_access_entry, push_admin_access, sealed_table_state, Card, GamePhase, Rank, Suit,
+        MAX_ADMIN_ACCESS_ENTRIES,
+    };
+
+    fn controller() -> Principal {
+        Principal::from_slice(&[9])
+    }
+
+    fn card(rank: Rank, suit: Suit) -> Card {
+        Card { suit, rank }
+    }
+
+    #[test]
+    fn test_sealed_state_hides_deck_and_hole_cards_mid_hand() {
+        let mut state = restored_table(GamePhase::Flop, None, None);
+        state.community_cards = vec![card(Rank::Two, Suit::Clubs), card(Rank::Seven, Suit::Hearts), card(Rank::Nine, Suit::Spades)];
+        state.deck = vec![card(Rank::Ace, Suit::Spades), card(Rank::King, Suit::Spades), card(Rank::Queen, Suit::Spades)];
+        state.deck_index = 1;
+        state.players[0].as_mut().unwrap().hole_cards = Some(vec![card(Rank::Ace, Suit::Hearts), card(Rank::Ace, Suit::Clubs)]);
+        state.players[1].as_mut().unwrap().hole_cards = Some(vec![card(Rank::Ten, Suit::Diamonds), card(Rank::Jack, Suit::Diamonds)]);
+
+        let sealed = sealed_table_state(&state);
+        assert!(sealed.deck.is_empty());
+        assert!(sealed.players.iter().flatten().all(|p| p.hole_cards.is_none()));
+
+        // Everything public is left as it was, and the live table keeps its cards
+        assert_eq!(sealed.community_cards, state.community_cards);
+        assert_eq!(sealed.deck_index, 1);
+        assert_eq!(sealed.players[0].as_ref().unwrap().chips, 480);
+        assert_eq!(state.deck.len(), 3);
+        assert!(state.players.iter().flatten().all(|p| p.hole_cards.is_some()));
+    }
+
+    #[test]
+    fn test_query_mode_read_refused() {
+        let state = restored_table(GamePhase::Flop, None, None);
+        let err = admin_access_entry(Some(&state), "get_table_state", false, controller(), 5).unwrap_err();
+        assert!(err.contains("update"));
+    }
+
+    #[test]
+    fn test_entry_flags_reads_during_a_hand() {
+        let state = restored_table(GamePhase::Turn, None, None);
+        let entry = admin_access_entry(Some(&state), "get_table_state", true, controller(), 5).unwrap();
+        assert_eq!((entry.timestamp, entry.caller, entry.method.as_str()), (5, controller(), "get_table_state"));
+        assert_eq!(entry.hand_number, 12);
+        assert!(entry.hand_in_progress);
+
+        let state = restored_table(GamePhase::HandComplete, None, None);
+        assert!(!admin_access_entry(Some(&state), "get_table_state", true, controller(), 5).unwrap().hand_in_progress);
+        let entry = admin_access_entry(None, "get_table_state", true, controller(), 5).unwrap();
+        assert_eq!((entry.hand_number, entry.hand_in_progress), (0, false));
+    }
+
+    #[test]
+    fn test_log_capped_dropping_oldest() {
+        let mut log = Vec::new();
+        for now in 0..MAX_ADMIN_ACCESS_ENTRIES as u64 {
+            push_admin_access(&mut log, admin_access_entry(None, "get_table_state", true, controller(), now).unwrap());
+        }
+        assert_eq!(log.len(), MAX_ADMIN_ACCESS_ENTRIES);
+        assert_eq!(log[0].timestamp, 0);
+
+        let next = MAX_ADMIN_ACCESS_ENTRIES as u64;
+        push_admin_access(&mut log, admin_access_entry(None, "get_table_state", true, controller(), next).unwrap());
+        assert_eq!(log.len(), MAX_ADMIN_ACCESS_ENTRIES);
+        assert_eq!(log[0].timestamp, 1);
+        assert_eq!(log.last().unwrap().timestamp, next);
+    }
 }
@@ -0,0 +1,1336 @@
+// ============================================================================
+// ⚠️  CRITICAL DEPLOYMENT WARNING ⚠️
+// ============================================================================
+// When upgrading canisters:
+//
+// ✅ ALWAYS use: dfx canister install <name> --mode upgrade --network ic
+// ❌ NEVER use:  dfx canister install <name> --mode reinstall --network ic
+//
+// --mode reinstall DESTROYS ALL STATE, including registrations and unpaid prizes!
+// ============================================================================
+//
+// Multi-table tournament coordinator. Players register here; buy-ins come out
+// of (and prizes go back into) the escrow balances of one table canister.
+// The coordinator seats players across a pool of table canisters through their
+// mtt_* control API, runs the shared blind clock, and breaks and balances
+// tables as players bust until everyone left fits at the final table.
+
+use candid::{CandidType, Deserialize, Principal};
+use cleardeck_payouts::tournament_payouts;
+use ic_cdk::management_canister::raw_rand;
+use sha2::{Sha256, Digest};
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::Duration;
+
+const TICK_INTERVAL_SECS: u64 = 5; // Blind clock, scheduled start and seating retries
+const MAX_TOURNAMENT_PLAYERS: u32 = 1000;
+
+// ============================================================================
+// TYPES
+// ============================================================================
+
+/// One level of the blind schedule - matches table_canister BlindLevel
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct BlindLevel {
+    pub small_blind: u64,
+    pub big_blind: u64,
+    pub ante: u64,
+    pub duration_secs: u64, // The last level lasts until the tournament ends
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentConfig {
+    pub name: String,
+    pub escrow_table: Principal, // Table canister whose escrow balances pay buy-ins and receive prizes
+    pub buy_in: u64, // Goes into the prize pool (e8s or satoshis)
+    pub fee: u64, // Kept by the house - paid to the escrow table's treasury once the tournament starts
+    pub starting_chips: u64, // Tournament chips - no currency value
+    pub blind_levels: Vec<BlindLevel>,
+    pub payouts_bps: Vec<u32>, // Prize pool share per finishing place in basis points
+    pub start_time: u64, // Nanoseconds since epoch - the tournament starts on the first tick after this
+    pub min_players: u32, // Cancelled and refunded if fewer have registered at start_time
+    pub max_players: u32,
+    pub seats_per_table: u8,
+    pub late_registration_levels: u32, // Registration stays open for this many levels after the start
+    pub max_reentries: u32, // Extra buy-ins allowed after busting, while registration is open
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize, PartialEq, Eq)]
+pub enum TournamentStatus {
+    Registering,
+    Running,
+    Finished,
+    Cancelled, // Not enough players - every entry was refunded
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct Entrant {
+    pub principal: Principal,
+    pub entries: u32, // Buy-ins paid (1 + re-entries)
+    pub chips: u64, // Last reported stack
+    pub table: Option<Principal>, // None while waiting for a seat, being moved, or busted
+    pub busted: bool,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentResult {
+    pub principal: Principal,
+    pub position: u32, // 1 = winner, 0 = refunded entry
+    pub prize: u64,
+    pub paid: bool, // Credited to escrow on the escrow table (retried until it is)
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TableSeating {
+    pub table_id: Principal,
+    pub players: Vec<Principal>,
+    pub paused: bool,
+}
+
+/// Public view of the tournament
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct TournamentInfo {
+    pub tournament_id: u64,
+    pub config: TournamentConfig,
+    pub status: TournamentStatus,
+    pub registration_open: bool,
+    pub entrants: u32,
+    pub total_entries: u32, // Including re-entries
+    pub players_remaining: u32,
+    pub prize_pool: u64,
+    pub level: u32, // Index into config.blind_levels
+    pub level_ends_at: Option<u64>,
+    pub started_at: Option<u64>,
+    pub finished_at: Option<u64>,
+    pub tables: Vec<TableSeating>,
+    pub results: Vec<TournamentResult>, // Ordered by position once finished
+}
+
+// Table canister control API types (see table_canister MULTI-TABLE TOURNAMENT CONTROL)
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttStack {
+    pub principal: Principal,
+    pub seat: u8,
+    pub chips: u64,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttBust {
+    pub principal: Principal,
+    pub starting_chips: u64,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttHandReport {
+    pub tournament_id: u64,
+    pub hand_number: u64,
+    pub stacks: Vec<MttStack>,
+    pub busted: Vec<MttBust>,
+}
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+pub struct MttTableStatus {
+    pub tournament_id: u64,
+    pub paused: bool,
+    pub hand_in_progress: bool,
+    pub hand_number: u64,
+    pub stacks: Vec<MttStack>,
+}
+
+// ============================================================================
+// STATE
+// ============================================================================
+
+#[derive(Clone, Debug, CandidType, Deserialize)]
+struct Tournament {
+    id: u64,
+    config: TournamentConfig,
+    status: TournamentStatus,
+    entrants: BTreeMap<Principal, Entrant>,
+    seating: BTreeMap<Principal, Vec<Principal>>, // Table -> players seated there
+    paused_tables: BTreeSet<Principal>,
+    prize_pool: u64,
+    fees_collected: u64, // Taken from escrow and not yet paid to the escrow table's treasury
+    level: u32,
+    level_ends_at: Option<u64>,
+    started_at: Option<u64>,
+    finished_at: Option<u64>,
+    bust_order: Vec<Principal>, // First player out first
+    results: Vec<TournamentResult>,
+}
+
+impl Tournament {
+    fn registration_open(&self) -> bool {
+        registration_open(&self.status, self.level, self.config.late_registration_levels)
+    }
+
+    fn players_remaining(&self) -> usize {
+        self.entrants.values().filter(|e| !e.busted).count()
+    }
+
+    fn current_blinds(&self) -> BlindLevel {
+        let last = self.config.blind_levels.len().saturating_sub(1);
+        self.config.blind_levels[(self.level as usize).min(last)].clone()
+    }
+
+    fn table_counts(&self) -> Vec<(Principal, usize)> {
+        self.seating.iter().map(|(table, players)| (*table, players.len())).collect()
+    }
+}
+
+#[derive(Default)]
+struct CoordinatorState {
+    admin: Option<Principal>,
+    table_pool: Vec<Principal>, // Table canisters this coordinator may run
+    next_tournament_id: u64,
+    tournament: Option<Tournament>, // Current (or last finished) tournament
+    // Players with a buy-in, refund or seat move in flight - not persisted
+    in_flight: BTreeSet<Principal>,
+    pending_entries: u32, // New entrants whose buy-in is in flight - each holds a place
+    rebalancing: bool,
+    rebalance_again: bool,
+}
+
+thread_local! {
+    static STATE: RefCell<CoordinatorState> = RefCell::new(CoordinatorState::default());
+}
+
+fn is_admin() -> bool {
+    let caller = ic_cdk::api::msg_caller();
+    STATE.with(|s| s.borrow().admin == Some(caller))
+}
+
+fn require_admin() -> Result<(), String> {
+    if !is_admin() {
+        return Err("Unauthorized: admin access required".to_string());
+    }
+    Ok(())
+}
+
+fn with_tournament<R>(f: impl FnOnce(&mut Tournament) -> R) -> Option<R> {
+    STATE.with(|s| s.borrow_mut().tournament.as_mut().map(f))
+}
+
+// ============================================================================
+// PURE HELPERS
+// ============================================================================
+
+fn registration_open(status: &TournamentStatus, level: u32, late_registration_levels: u32) -> bool {
+    match status {
+        TournamentStatus::Registering => true,
+        TournamentStatus::Running => level < late_registration_levels,
+        _ => false,
+    }
+}
+
+/// Table with the fewest players that still has a free seat
+fn pick_table<T: Copy>(tables: &[(T, usize)], seats_per_table: usize) -> Option<T> {
+    tables.iter()
+        .filter(|(_, count)| *count < seats_per_table)
+        .min_by_key(|(_, count)| *count)
+        .map(|(table, _)| *table)
+}
+
+/// Player moves (from table, to table) that break and balance the field:
+/// while everyone fits at one table fewer, the shortest table is broken up;
+/// then players move from the biggest to the smallest table until no two
+/// tables differ by more than one. This ends at a single final table.
+fn plan_table_moves<T: Copy + PartialEq>(tables: &[(T, usize)], seats_per_table: usize) -> Vec<(T, T)> {
+    let seats = seats_per_table.max(1);
+    let mut counts = tables.to_vec();
+    let mut moves = Vec::new();
+    let total: usize = counts.iter().map(|(_, n)| n).sum();
+
+    // Break tables
+    while counts.len() > 1 && counts.len() > total.div_ceil(seats).max(1) {
+        let shortest = counts.iter()
+            .enumerate()
+            .min_by_key(|(_, (_, n))| *n)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let (broken, players) = counts.remove(shortest);
+        for _ in 0..players {
+            let to = counts.iter_mut()
+                .filter(|(_, n)| *n < seats)
+                .min_by_key(|(_, n)| *n);
+            if let Some((table, n)) = to {
+                *n += 1;
+                moves.push((broken, *table));
+            }
+        }
+    }
+
+    // Balance tables
+    loop {
+        let biggest = counts.iter().enumerate().max_by_key(|(_, (_, n))| *n).map(|(i, _)| i);
+        let smallest = counts.iter().enumerate().min_by_key(|(_, (_, n))| *n).map(|(i, _)| i);
+        match (biggest, smallest) {
+            (Some(b), Some(s)) if counts[b].1 > counts[s].1 + 1 => {
+                counts[b].1 -= 1;
+                counts[s].1 += 1;
+                moves.push((counts[b].0, counts[s].0));
+            }
+            _ => break,
+        }
+    }
+
+    moves
+}
+
+/// Final standings: the winner first, then busted players latest-out first
+fn finishing_positions<T: Copy + PartialEq>(bust_order: &[T], winner: Option<T>) -> Vec<(T, u32)> {
+    winner.into_iter()
+        .chain(bust_order.iter().rev().copied().filter(|p| Some(*p) != winner))
+        .enumerate()
+        .map(|(i, p)| (p, i as u32 + 1))
+        .collect()
+}
+
+/// Players the coordinator still has at a table that the table no longer seats -
+/// busted on a hand whose report was lost. Only players seated before the table
+/// was asked count, so a seat taken while the call was out isn't taken for a bust.
+fn missed_busts<T: Copy + PartialEq>(seated_before: &[T], seated_now: &[T], on_table: &[T]) -> Vec<T> {
+    seated_now.iter()
+        .filter(|p| seated_before.contains(p) && !on_table.contains(p))
+        .copied()
+        .collect()
+}
+
+/// Random seat draw: order players by SHA-256(seed || principal)
+fn seat_draw(players: &mut [Principal], seed: &[u8]) {
+    players.sort_by_cached_key(|p| {
+        let mut hasher = Sha256::new();
+        hasher.update(seed);
+        hasher.update(p.as_slice());
+        hasher.finalize().to_vec()
+    });
+}
+
+fn validate_config(config: &TournamentConfig, table_pool: usize) -> Result<(), String> {
+    if config.buy_in == 0 {
+        return Err("buy_in must be greater than 0".to_string());
+    }
+    if config.blind_levels.is_empty() {
+        return Err("Tournament needs at least one blind level".to_string());
+    }
+    for (i, level) in config.blind_levels.iter().enumerate() {
+        if level.small_blind == 0 || level.big_blind < level.small_blind {
+            return Err(format!("blind level {} must have 0 < small_blind <= big_blind", i + 1));
+        }
+        if level.ante > level.big_blind {
+            return Err(format!("blind level {} ante cannot exceed big_blind", i + 1));
+        }
+        if level.duration_secs < 60 {
+            return Err(format!("blind level {} must last at least 60 seconds", i + 1));
+        }
+    }
+    if config.starting_chips < config.blind_levels[0].big_blind * 10 {
+        return Err("starting_chips should be at least 10 big blinds of the first level".to_string());
+    }
+    if config.payouts_bps.is_empty() || config.payouts_bps.len() > config.max_players as usize {
+        return Err("payouts_bps must have between 1 and max_players entries".to_string());
+    }
+    if config.payouts_bps.iter().map(|&b| b as u64).sum::<u64>() != 10_000 {
+        return Err("payouts_bps must add up to 10000".to_string());
+    }
+    if config.seats_per_table < 2 || config.seats_per_table > 10 {
+        return Err("seats_per_table must be between 2 and 10".to_string());
+    }
+    if config.min_players < 2 || config.max_players < config.min_players {
+        return Err("Need 2 <= min_players <= max_players".to_string());
+    }
+    if config.max_players > MAX_TOURNAMENT_PLAYERS {
+        return Err(format!("max_players cannot exceed {}", MAX_TOURNAMENT_PLAYERS));
+    }
+    if config.max_players as usize > table_pool * config.seats_per_table as usize {
+        return Err("Not enough tables in the pool to seat max_players".to_string());
+    }
+    Ok(())
+}
+
+// ============================================================================
+// TABLE CANISTER CALLS
+// ============================================================================
+
+async fn call_table<A, R>(table: Principal, method: &str, args: A) -> Result<R, String>
+where
+    A: candid::utils::ArgumentEncoder,
+    R: CandidType + for<'de> Deserialize<'de>,
+{
+    let response = ic_cdk::call::Call::unbounded_wait(table, method)
+        .with_args(&args)
+        .await
+        .map_err(|e| format!("{} call to {} failed: {:?}", method, table, e))?;
+    response.candid::<Result<R, String>>()
+        .map_err(|e| format!("Failed to decode {} response: {:?}", method, e))?
+}
+
+/// Seat a player on a table and record it. Leaves the player waiting on failure.
+async fn seat_on_table(table: Principal, player: Principal, chips: u64) -> Result<(), String> {
+    call_table::<_, u8>(table, "mtt_seat_player", (player, chips)).await?;
+    with_tournament(|t| {
+        if let Some(entrant) = t.entrants.get_mut(&player) {
+            entrant.table = Some(table);
+        }
+        t.seating.entry(table).or_default().push(player);
+    });
+    Ok(())
+}
+
+/// Open a table from the pool for this tournament. It starts paused.
+async fn open_table(tournament_id: u64, blinds: BlindLevel) -> Result<Principal, String> {
+    let table = STATE.with(|s| {
+        let s = s.borrow();
+        let in_use: Vec<Principal> = s.tournament.as_ref()
+            .map(|t| t.seating.keys().copied().collect())
+            .unwrap_or_default();
+        s.table_pool.iter().copied().find(|t| !in_use.contains(t))
+    }).ok_or("No free table in the pool")?;
+
+    call_table::<_, ()>(table, "mtt_attach", (tournament_id, blinds)).await?;
+    with_tournament(|t| {
+        t.seating.entry(table).or_default();
+        t.paused_tables.insert(table);
+    });
+    Ok(table)
+}
+
+async fn get_table_status(table: Principal) -> Result<Option<MttTableStatus>, String> {
+    let response = ic_cdk::call::Call::unbounded_wait(table, "get_mtt_status")
+        .await
+        .map_err(|e| format!("get_mtt_status call to {} failed: {:?}", table, e))?;
+    response.candid::<Option<MttTableStatus>>()
+        .map_err(|e| format!("Failed to decode get_mtt_status response: {:?}", e))
+}
+
+async fn set_table_paused(table: Principal, paused: bool) -> Result<bool, String> {
+    let hand_in_progress = call_table::<_, bool>(table, "mtt_set_paused", (paused,)).await?;
+    with_tournament(|t| {
+        if paused {
+            t.paused_tables.insert(table);
+        } else {
+            t.paused_tables.remove(&table);
+        }
+    });
+    Ok(hand_in_progress)
+}
+
+// ============================================================================
+// INITIALIZATION & ADMIN
+// ============================================================================
+
+#[ic_cdk::init]
+fn init() {
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.admin = Some(ic_cdk::api::msg_caller());
+        state.next_tournament_id = 1;
+    });
+    start_clock();
+}
+
+fn start_clock() {
+    ic_cdk_timers::set_timer_interval_serial(Duration::from_secs(TICK_INTERVAL_SECS), async || tick().await);
+}
+
+/// Add a table canister to the pool. The table must have this canister set as
+/// its tournament coordinator (set_tournament_coordinator).
+#[ic_cdk::update]
+fn add_table(table: Principal) -> Result<(), String> {
+    require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if !state.table_pool.contains(&table) {
+            state.table_pool.push(table);
+        }
+    });
+    Ok(())
+}
+
+#[ic_cdk::update]
+fn remove_table(table: Principal) -> Result<(), String> {
+    require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        let in_use = state.tournament.as_ref()
+            .map(|t| t.seating.contains_key(&table))
+            .unwrap_or(false);
+        if in_use {
+            return Err("Table is in use by the running tournament".to_string());
+        }
+        state.table_pool.retain(|t| *t != table);
+        Ok(())
+    })
+}
+
+#[ic_cdk::query]
+fn get_table_pool() -> Vec<Principal> {
+    STATE.with(|s| s.borrow().table_pool.clone())
+}
+
+/// Announce a new tournament and open registration
+#[ic_cdk::update]
+fn create_tournament(config: TournamentConfig) -> Result<u64, String> {
+    require_admin()?;
+    STATE.with(|s| {
+        let mut state = s.borrow_mut();
+
+        if let Some(ref t) = state.tournament {
+            if t.status == TournamentStatus::Registering || t.status == TournamentStatus::Running {
+                return Err("A tournament is already in progress".to_string());
+            }
+            if t.results.iter().any(|r| r.prize > 0 && !r.paid) {
+                return Err("Previous tournament still has unpaid prizes".to_string());
+            }
+            if t.fees_collected > 0 {
+                return Err("Previous tournament still has fees to pay to the treasury".to_string());
+            }
+        }
+        if !state.in_flight.is_empty() {
+            return Err("Registration changes are still in flight".to_string());
+        }
+        validate_config(&config, state.table_pool.len())?;
+
+        let id = state.next_tournament_id;
+        state.next_tournament_id = id + 1;
+        state.tournament = Some(Tournament {
+            id,
+            config,
+            status: TournamentStatus::Registering,
+            entrants: BTreeMap::new(),
+            seating: BTreeMap::new(),
+            paused_tables: BTreeSet::new(),
+            prize_pool: 0,
+            fees_collected: 0,
+            level: 0,
+            level_ends_at: None,
+            started_at: None,
+            finished_at: None,
+            bust_order: Vec::new(),
+            results: Vec::new(),
+        });
+        Ok(id)
+    })
+}
+
+/// Start now instead of waiting for start_time (admin only)
+#[ic_cdk::update]
+async fn start_tournament() -> Result<(), String> {
+    require_admin()?;
+    start_now().await
+}
+
+/// Cancel during registration and refund every entry (admin only)
+#[ic_cdk::update]
+async fn cancel_tournament() -> Result<(), String> {
+    require_admin()?;
+    let status = with_tournament(|t| t.status.clone()).ok_or("No tournament")?;
+    if status != TournamentStatus::Registering {
+        return Err("Only a tournament that hasn't started can be cancelled".to_string());
+    }
+    cancel_and_refund().await;
+    Ok(())
+}
+
+// ============================================================================
+// REGISTRATION
+// ============================================================================
+
+/// Register (or re-enter after busting) - buy-in + fee come from your escrow
+/// balance on the tournament's escrow table. Returns how many entries you have.
+#[ic_cdk::update]
+async fn register() -> Result<u32, String> {
+    let caller = ic_cdk::api::msg_caller();
+    if caller == Principal::anonymous() {
+        return Err("Anonymous principals cannot register".to_string());
+    }
+
+    // Validate and reserve before any await
+    let (tournament_id, escrow_table, cost, new_entrant) = STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.in_flight.contains(&caller) {
+            return Err("Registration already in progress".to_string());
+        }
+        let t = state.tournament.as_ref().ok_or("No tournament")?;
+        if !t.registration_open() {
+            return Err("Registration is closed".to_string());
+        }
+        match t.entrants.get(&caller) {
+            Some(e) if !e.busted => return Err("Already registered".to_string()),
+            Some(e) if e.entries > t.config.max_reentries => {
+                return Err("No re-entries left".to_string());
+            }
+            Some(_) => {}
+            None => {
+                if t.entrants.len() as u32 + state.pending_entries >= t.config.max_players {
+                    return Err("Tournament is full".to_string());
+                }
+            }
+        }
+        let new_entrant = !t.entrants.contains_key(&caller);
+        let result = (t.id, t.config.escrow_table, t.config.buy_in.saturating_add(t.config.fee), new_entrant);
+        state.in_flight.insert(caller);
+        if new_entrant {
+            state.pending_entries += 1;
+        }
+        Ok(result)
+    })?;
+    let release = || STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        state.in_flight.remove(&caller);
+        if new_entrant {
+            state.pending_entries = state.pending_entries.saturating_sub(1);
+        }
+    });
+
+    let debit = call_table::<_, ()>(escrow_table, "mtt_debit_escrow", (caller, cost)).await;
+    if let Err(e) = debit {
+        release();
+        return Err(e);
+    }
+
+    // Registration may have closed, or the tournament been cancelled, during the call
+    let entered = with_tournament(|t| {
+        if t.id != tournament_id || !t.registration_open() {
+            return None;
+        }
+        let chips = t.config.starting_chips;
+        let entrant = t.entrants.entry(caller).or_insert(Entrant {
+            principal: caller,
+            entries: 0,
+            chips,
+            table: None,
+            busted: false,
+        });
+        entrant.entries += 1;
+        entrant.chips = chips;
+        entrant.busted = false;
+        let entries = entrant.entries;
+        // A re-entry replaces the earlier bust
+        t.bust_order.retain(|p| *p != caller);
+        t.prize_pool = t.prize_pool.saturating_add(t.config.buy_in);
+        t.fees_collected = t.fees_collected.saturating_add(t.config.fee);
+        Some((entries, t.status == TournamentStatus::Running))
+    }).flatten();
+    let (entries, running) = match entered {
+        Some(e) => e,
+        None => {
+            refund_entry(tournament_id, escrow_table, caller, cost).await;
+            release();
+            return Err("Registration closed while the buy-in was being taken - it has been refunded".to_string());
+        }
+    };
+    release();
+
+    // Late registration: seat straight away (the clock retries if this fails)
+    if running {
+        seat_waiting_players().await;
+    }
+
+    Ok(entries)
+}
+
+/// Give back a buy-in taken after registration closed. If the credit fails it
+/// is paid like a cancellation refund, retried by the clock.
+async fn refund_entry(tournament_id: u64, escrow_table: Principal, player: Principal, cost: u64) {
+    let credit = call_table::<_, ()>(escrow_table, "mtt_credit_escrow", (player, cost)).await;
+    if let Err(e) = credit {
+        ic_cdk::println!("Failed to refund {} to {}: {}", cost, player, e);
+        with_tournament(|t| {
+            if t.id == tournament_id {
+                t.results.push(TournamentResult { principal: player, position: 0, prize: cost, paid: false });
+            }
+        });
+    }
+}
+
+/// Withdraw before the start - buy-in + fee go back to escrow
+#[ic_cdk::update]
+async fn unregister() -> Result<(), String> {
+    let caller = ic_cdk::api::msg_caller();
+
+    let (escrow_table, refund, entrant) = STATE.with(|s| {
+        let mut state = s.borrow_mut();
+        if state.in_flight.contains(&caller) {
+            return Err("Registration change already in progress".to_string());
+        }
+        let t = state.tournament.as_mut().ok_or("No tournament")?;
+        if t.status != TournamentStatus::Registering {
+            return Err("Cannot unregister once the tournament has started".to_string());
+        }
+        let entrant = t.entrants.remove(&caller).ok_or("Not registered")?;
+        let refund = t.config.buy_in.saturating_add(t.config.fee).saturating_mul(entrant.entries as u64);
+        t.prize_pool = t.prize_pool.saturating_sub(t.config.buy_in * entrant.entries as u64);
+        t.fees_collected = t.fees_collected.saturating_sub(t.config.fee * entrant.entries as u64);
+        let escrow_table = t.config.escrow_table;
+        state.in_flight.insert(caller);
+        Ok((escrow_table, refund, entrant))
+    })?;
+
+    let credit = call_table::<_, ()>(escrow_table, "mtt_credit_escrow", (caller, refund)).await;
+    if let Err(e) = credit {
+        // Put the entry back - the player is still registered
+        with_tournament(|t| {
+            t.prize_pool = t.prize_pool.saturating_add(t.config.buy_in * entrant.entries as u64);
+            t.fees_collected = t.fees_collected.saturating_add(t.config.fee * entrant.entries as u64);
+            t.entrants.insert(caller, entrant);
+        });
+        STATE.with(|s| s.borrow_mut().in_flight.remove(&caller));
+        return Err(e);
+    }
+
+    STATE.with(|s| s.borrow_mut().in_flight.remove(&caller));
+    Ok(())
+}
+
+// ============================================================================
+// TOURNAMENT FLOW
+// ============================================================================
+
+/// Clock tick: scheduled start, blind levels, table polling, seating retries, fee and prize retries
+async fn tick() {
+    let now = ic_cdk::api::time();
+    let status = match with_tournament(|t| (t.status.clone(), t.config.start_time)) {
+        Some(s) => s,
+        None => return,
+    };
+
+    match status {
+        (TournamentStatus::Registering, start_time) if now >= start_time => {
+            if let Err(e) = start_now().await {
+                ic_cdk::println!("Tournament start failed: {}", e);
+            }
+        }
+        (TournamentStatus::Running, _) => {
+            advance_blind_clock(now).await;
+            poll_tables().await;
+            seat_waiting_players().await;
+            rebalance().await;
+            pay_fees().await;
+            pay_prizes().await; // Refunds of entries that came too late
+            maybe_finish().await;
+        }
+        (TournamentStatus::Finished, _) => {
+            pay_fees().await;
+            pay_prizes().await;
+        }
+        (TournamentStatus::Cancelled, _) => pay_prizes().await,
+        _ => {}
+    }
+}
+
+async fn start_now() -> Result<(), String> {
+    let (tournament_id, mut players, min_players, blinds) = with_tournament(|t| {
+        if t.status != TournamentStatus::Registering {
+            return Err("Tournament is not open for registration".to_string());
+        }
+        let players: Vec<Principal> = t.entrants.keys().copied().collect();
+        Ok((t.id, players, t.config.min_players, t.current_blinds()))
+    }).ok_or("No tournament")??;
+
+    if (players.len() as u32) < min_players {
+        cancel_and_refund().await;
+        return Err(format!("Only {} of {} required players registered - cancelled", players.len(), min_players));
+    }
+
+    let seed = raw_rand().await.map_err(|e| format!("Failed to get randomness: {:?}", e))?;
+    seat_draw(&mut players, &seed);
+
+    // Status flips before the first await on the tables so a second start can't race in
+    let now = ic_cdk::api::time();
+    let (seats, tables_needed) = with_tournament(|t| {
+        t.status = TournamentStatus::Running;
+        t.started_at = Some(now);
+        t.level = 0;
+        t.level_ends_at = if t.config.blind_levels.len() > 1 {
+            Some(now + t.config.blind_levels[0].duration_secs * 1_000_000_000)
+        } else {
+            None
+        };
+        let seats = t.config.seats_per_table as usize;
+        (seats, players.len().div_ceil(seats))
+    }).ok_or("No tournament")?;
+
+    // Open the tables and deal players round the tables in draw order
+    let mut tables = Vec::new();
+    for _ in 0..tables_needed {
+        match open_table(tournament_id, blinds.clone()).await {
+            Ok(table) => tables.push(table),
+            Err(e) => ic_cdk::println!("Failed to open tournament table: {}", e),
+        }
+    }
+    if tables.is_empty() {
+        return Err("Could not open any table - players will be seated by the clock".to_string());
+    }
+
+    let starting_chips = with_tournament(|t| t.config.starting_chips).unwrap_or(0);
+    for (i, player) in players.iter().enumerate() {
+        let table = tables[i % tables.len()];
+        if i / tables.len() >= seats {
+            break; // Too few tables opened - the rest wait for a seat
+        }
+        if let Err(e) = seat_on_table(table, *player, starting_chips).await {
+            ic_cdk::println!("Failed to seat {}: {}", player, e);
+        }
+    }
+
+    for table in tables {
+        if let Err(e) = set_table_paused(table, false).await {
+            ic_cdk::println!("Failed to start table {}: {}", table, e);
+        }
+    }
+
+    Ok(())
+}
+
+async fn cancel_and_refund() {
+    let refunds = with_tournament(|t| {
+        t.status = TournamentStatus::Cancelled;
+        let cost = t.config.buy_in.saturating_add(t.config.fee);
+        // Refunds are paid like prizes so a failed credit is retried by the clock
+        t.results = t.entrants.values()
+            .map(|e| TournamentResult {
+                principal: e.principal,
+                position: 0,
+                prize: cost.saturating_mul(e.entries as u64),
+                paid: false,
+            })
+            .collect();
+        t.prize_pool = 0;
+        t.fees_collected = 0;
+    });
+    if refunds.is_some() {
+        pay_prizes().await;
+    }
+}
+
+/// Move to the next blind level when its time is up and push it to every table
+async fn advance_blind_clock(now: u64) {
+    let changed = with_tournament(|t| {
+        let mut changed = false;
+        while let Some(ends_at) = t.level_ends_at {
+            if now < ends_at {
+                break;
+            }
+            t.level += 1;
+            let next = t.level as usize;
+            t.level_ends_at = if next + 1 < t.config.blind_levels.len() {
+                Some(ends_at + t.config.blind_levels[next].duration_secs * 1_000_000_000)
+            } else {
+                None
+            };
+            changed = true;
+        }
+        changed.then(|| (t.current_blinds(), t.seating.keys().copied().collect::<Vec<_>>()))
+    }).flatten();
+
+    if let Some((blinds, tables)) = changed {
+        for table in tables {
+            if let Err(e) = call_table::<_, ()>(table, "mtt_set_blinds", (blinds.clone(),)).await {
+                ic_cdk::println!("Failed to set blinds on {}: {}", table, e);
+            }
+        }
+    }
+}
+
+/// Catch up with every table's stacks and busts. Tables report each hand, but
+/// a report can be lost (the call failed or was rejected), so the tick asks too.
+async fn poll_tables() {
+    let tables = with_tournament(|t| {
+        if t.status != TournamentStatus::Running {
+            return Vec::new();
+        }
+        t.seating.iter().map(|(table, players)| (*table, players.clone())).collect()
+    }).unwrap_or_default();
+
+    for (table, seated_before) in tables {
+        let status = match get_table_status(table).await {
+            Ok(Some(status)) => status,
+            Ok(None) => continue, // Not attached yet
+            Err(e) => {
+                ic_cdk::println!("Failed to poll {}: {}", table, e);
+                continue;
+            }
+        };
+
+        STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let s = &mut *s;
+            let t = match s.tournament.as_mut() {
+                Some(t) if t.id == status.tournament_id && t.status == TournamentStatus::Running => t,
+                _ => return,
+            };
+            let on_table: Vec<Principal> = status.stacks.iter().map(|stack| stack.principal).collect();
+            let seated_now: Vec<Principal> = match t.seating.get(&table) {
+                Some(players) => players.iter().filter(|p| !s.in_flight.contains(p)).copied().collect(),
+                None => return,
+            };
+
+            // Stacks only mean something between hands
+            if !status.hand_in_progress {
+                update_stacks(t, table, &status.stacks);
+            }
+            // The last stack we heard of stands in for the starting stack of the lost hand
+            let busted = missed_busts(&seated_before, &seated_now, &on_table).into_iter()
+                .map(|principal| MttBust {
+                    principal,
+                    starting_chips: t.entrants.get(&principal).map(|e| e.chips).unwrap_or(0),
+                })
+                .collect();
+            record_busts(t, table, busted);
+        });
+    }
+}
+
+/// Seat registered players who don't have a seat yet (late registration, failed moves)
+async fn seat_waiting_players() {
+    loop {
+        let next = STATE.with(|s| {
+            let s = s.borrow();
+            let t = s.tournament.as_ref().filter(|t| t.status == TournamentStatus::Running)?;
+            let entrant = t.entrants.values()
+                .find(|e| !e.busted && e.table.is_none() && !s.in_flight.contains(&e.principal))?;
+            let table = pick_table(&t.table_counts(), t.config.seats_per_table as usize);
+            Some((t.id, entrant.principal, entrant.chips, table, t.current_blinds()))
+        });
+        let (tournament_id, player, chips, table, blinds) = match next {
+            Some(n) => n,
+            None => return,
+        };
+
+        STATE.with(|s| s.borrow_mut().in_flight.insert(player));
+        let table = match table {
+            Some(table) => Ok(table),
+            None => match open_table(tournament_id, blinds).await {
+                Ok(table) => set_table_paused(table, false).await.map(|_| table),
+                Err(e) => Err(e),
+            },
+        };
+        let seated = match table {
+            Ok(table) => seat_on_table(table, player, chips).await,
+            Err(e) => Err(e),
+        };
+        STATE.with(|s| s.borrow_mut().in_flight.remove(&player));
+
+        if let Err(e) = seated {
+            ic_cdk::println!("Could not seat {}: {}", player, e);
+            return; // Retried on the next tick
+        }
+    }
+}
+
+/// Break and balance tables. Tables that have to give up players are paused;
+/// a table still playing a hand is handled when it reports the hand.
+async fn rebalance() {
+    let already_running = STATE.with(|s| {
+        let mut s = s.borrow_mut();
+        if s.rebalancing {
+            s.rebalance_again = true;
+            true
+        } else {
+            s.rebalancing = true;
+            s.rebalance_again = false;
+            false
+        }
+    });
+    if already_running {
+        return;
+    }
+
+    loop {
+        rebalance_pass().await;
+        let again = STATE.with(|s| {
+            let mut s = s.borrow_mut();
+            let again = s.rebalance_again;
+            s.rebalance_again = false;
+            again
+        });
+        if !again {
+            break;
+        }
+    }
+
+    STATE.with(|s| s.borrow_mut().rebalancing = false);
+}
+
+async fn rebalance_pass() {
+    let moves = with_tournament(|t| {
+        if t.status != TournamentStatus::Running {
+            return Vec::new();
+        }
+        plan_table_moves(&t.table_counts(), t.config.seats_per_table as usize)
+    }).unwrap_or_default();
+
+    let mut busy_tables = BTreeSet::new();
+    for (from, to) in moves {
+        if busy_tables.contains(&from) {
+            continue;
+        }
+        match set_table_paused(from, true).await {
+            Ok(false) => {}
+            Ok(true) => {
+                busy_tables.insert(from); // Still playing - it reports when the hand is over
+                continue;
+            }
+            Err(e) => {
+                ic_cdk::println!("Failed to pause {}: {}", from, e);
+                busy_tables.insert(from);
+                continue;
+            }
+        }
+
+        // Move the most recently seated player
+        let player = STATE.with(|s| {
+            let s = s.borrow();
+            s.tournament.as_ref()
+                .and_then(|t| t.seating.get(&from))
+                .and_then(|players| players.iter().rev().find(|p| !s.in_flight.contains(p)).copied())
+        });
+        let player = match player {
+            Some(p) => p,
+            None => continue,
+        };
+
+        STATE.with(|s| s.borrow_mut().in_flight.insert(player));
+        match call_table::<_, u64>(from, "mtt_unseat_player", (player,)).await {
+            Ok(chips) => {
+                with_tournament(|t| {
+                    if let Some(players) = t.seating.get_mut(&from) {
+                        players.retain(|p| *p != player);
+                    }
+                    if let Some(entrant) = t.entrants.get_mut(&player) {
+                        entrant.chips = chips;
+                        entrant.table = None;
+                    }
+                });
+                if let Err(e) = seat_on_table(to, player, chips).await {
+                    ic_cdk::println!("Failed to move {} to {}: {}", player, to, e);
+                }
+            }
+            Err(e) => {
+                ic_cdk::println!("Failed to unseat {} from {}: {}", player, from, e);
+                busy_tables.insert(from);
+            }
+        }
+        STATE.with(|s| s.borrow_mut().in_flight.remove(&player));
+    }
+
+    // Release broken tables and restart tables that no longer owe players
+    let (empty, resume) = with_tournament(|t| {
+        let still_moving: Vec<Principal> = plan_table_moves(&t.table_counts(), t.config.seats_per_table as usize)
+            .into_iter()
+            .map(|(from, _)| from)
+            .collect();
+        let empty: Vec<Principal> = t.seating.iter()
+            .filter(|(_, players)| players.is_empty())
+            .map(|(table, _)| *table)
+            .collect();
+        let resume: Vec<Principal> = t.paused_tables.iter()
+            .filter(|table| !empty.contains(table) && !still_moving.contains(table))
+            .copied()
+            .collect();
+        (empty, resume)
+    }).unwrap_or_default();
+
+    for table in empty {
+        match call_table::<_, ()>(table, "mtt_detach", ()).await {
+            Ok(()) => {
+                with_tournament(|t| {
+                    if t.seating.get(&table).map(|p| p.is_empty()).unwrap_or(false) {
+                        t.seating.remove(&table);
+                        t.paused_tables.remove(&table);
+                    }
+                });
+            }
+            Err(e) => ic_cdk::println!("Failed to release table {}: {}", table, e),
+        }
+    }
+    for table in resume {
+        if let Err(e) = set_table_paused(table, false).await {
+            ic_cdk::println!("Failed to resume table {}: {}", table, e);
+        }
+    }
+}
+
+/// Finish once one player is left and nobody else can still enter
+async fn maybe_finish() {
+    let finished = with_tournament(|t| {
+        if t.status != TournamentStatus::Running || t.registration_open() || t.players_remaining() > 1 {
+            return None;
+        }
+        let now = ic_cdk::api::time();
+        t.status = TournamentStatus::Finished;
+        t.finished_at = Some(now);
+        t.level_ends_at = None;
+
+        let winner = t.entrants.values().find(|e| !e.busted).map(|e| e.principal);
+        let prizes = tournament_payouts(t.prize_pool, &t.config.payouts_bps);
+        // Keep refunds (position 0) of late entries
+        t.results.retain(|r| r.position == 0);
+        t.results.extend(finishing_positions(&t.bust_order, winner).into_iter()
+            .map(|(principal, position)| TournamentResult {
+                principal,
+                position,
+                prize: prizes.get(position as usize - 1).copied().unwrap_or(0),
+                paid: false,
+            }));
+
+        let seated: Vec<(Principal, Vec<Principal>)> = t.seating.iter()
+            .map(|(table, players)| (*table, players.clone()))
+            .collect();
+        Some(seated)
+    }).flatten();
+
+    let seated = match finished {
+        Some(s) => s,
+        None => return,
+    };
+
+    // The winner's chips have no currency value - clear the tables and release them
+    for (table, players) in seated {
+        for player in players {
+            if let Err(e) = call_table::<_, u64>(table, "mtt_unseat_player", (player,)).await {
+                ic_cdk::println!("Failed to unseat {} from {}: {}", player, table, e);
+            }
+        }
+        if let Err(e) = call_table::<_, ()>(table, "mtt_detach", ()).await {
+            ic_cdk::println!("Failed to release table {}: {}", table, e);
+        }
+    }
+    with_tournament(|t| {
+        t.seating.clear();
+        t.paused_tables.clear();
+        for entrant in t.entrants.values_mut() {
+            entrant.table = None;
+        }
+    });
+
+    pay_prizes().await;
+}
+
+/// Pay entry fees taken since the last payment to the escrow table's treasury.
+/// Entries can't be refunded once the tournament has started, so only fees
+/// from then on are paid - a cancelled tournament refunds them with the buy-ins.
+async fn pay_fees() {
+    let (escrow_table, fees) = match with_tournament(|t| (t.config.escrow_table, t.fees_collected)) {
+        Some((_, 0)) | None => return,
+        Some(p) => p,
+    };
+    match call_table::<_, ()>(escrow_table, "mtt_credit_treasury", (fees,)).await {
+        // Late entries may have added fees during the call - only take off what was paid
+        Ok(()) => {
+            with_tournament(|t| t.fees_collected = t.fees_collected.saturating_sub(fees));
+        }
+        Err(e) => ic_cdk::println!("Failed to pay {} in fees to the treasury: {}", fees, e),
+    }
+}
+
+/// Credit unpaid prizes (and cancellation refunds) to escrow on the escrow table
+async fn pay_prizes() {
+    let (escrow_table, unpaid) = match with_tournament(|t| {
+        let unpaid: Vec<(Principal, u64)> = t.results.iter()
+            .filter(|r| r.prize > 0 && !r.paid)
+            .map(|r| (r.principal, r.prize))
+            .collect();
+        (t.config.escrow_table, unpaid)
+    }) {
+        Some(p) => p,
+        None => return,
+    };
+
+    for (player, prize) in unpaid {
+        match call_table::<_, ()>(escrow_table, "mtt_credit_escrow", (player, prize)).await {
+            Ok(()) => {
+                with_tournament(|t| {
+                    if let Some(result) = t.results.iter_mut().find(|r| r.principal == player && r.prize == prize && !r.paid) {
+                        result.paid = true;
+                    }
+                });
+            }
+            Err(e) => ic_cdk::println!("Failed to pay {} to {}: {}", prize, player, e),
+        }
+    }
+}
+
+// ============================================================================
+// TABLE REPORTS
+// ============================================================================
+
+/// Called by a tournament table after every hand
+#[ic_cdk::update]
+async fn report_hand(report: MttHandReport) -> Result<(), String> {
+    let table = ic_cdk::api::msg_caller();
+
+    with_tournament(|t| {
+        if t.id != report.tournament_id || t.status != TournamentStatus::Running {
+            return Err("Not the running tournament".to_string());
+        }
+        if !t.seating.contains_key(&table) {
+            return Err("Unauthorized: not a table of this tournament".to_string());
+        }
+
+        update_stacks(t, table, &report.stacks);
+        record_busts(t, table, report.busted);
+        Ok(())
+    }).ok_or("No tournament")??;
+
+    rebalance().await;
+    maybe_finish().await;
+    Ok(())
+}
+
+fn update_stacks(t: &mut Tournament, table: Principal, stacks: &[MttStack]) {
+    for stack in stacks {
+        if let Some(entrant) = t.entrants.get_mut(&stack.principal) {
+            if entrant.table == Some(table) {
+                entrant.chips = stack.chips;
+            }
+        }
+    }
+}
+
+/// Knock out players a table has unseated. A bust already picked up by
+/// polling (or reported twice) is skipped - the player is no longer seated there.
+fn record_busts(t: &mut Tournament, table: Principal, mut busted: Vec<MttBust>) {
+    // Busted on the same hand: the smaller starting stack is out first
+    busted.sort_by_key(|b| b.starting_chips);
+    for bust in busted {
+        let seated = t.seating.get_mut(&table);
+        match seated {
+            Some(players) if players.contains(&bust.principal) => players.retain(|p| *p != bust.principal),
+            _ => continue,
+        }
+        if let Some(entrant) = t.entrants.get_mut(&bust.principal) {
+            entrant.busted = true;
+            entrant.chips = 0;
+            entrant.table = None;
+        }
+        t.bust_order.push(bust.principal);
+    }
+}
+
+// ============================================================================
+// QUERIES
+// ============================================================================
+
+#[ic_cdk::query]
+fn get_tournament() -> Option<TournamentInfo> {
+    STATE.with(|s| {
+        let s = s.borrow();
+        let t = s.tournament.as_ref()?;
+        let mut results = t.results.clone();
+        results.sort_by_key(|r| r.position);
+        Some(TournamentInfo {
+            tournament_id: t.id,
+            config: t.config.clone(),
+            status: t.status.clone(),
+            registration_open: t.registration_open(),
+            entrants: t.entrants.len() as u32,
+            total_entries: t.entrants.values().map(|e| e.entries).sum(),
+            players_remaining: t.players_remaining() as u32,
+            prize_pool: t.prize_pool,
+            level: t.level,
+            level_ends_at: t.level_ends_at,
+            started_at: t.started_at,
+            finished_at: t.finished_at,
+            tables: t.seating.iter()
+                .map(|(table, players)| TableSeating {
+                    table_id: *table,
+                    players: players.clone(),
+                    paused: t.paused_tables.contains(table),
+                })
+                .collect(),
+            results,
+        })
+    })
+}
+
+/// Chip standings - players still in by stack, then busted players
+#[ic_cdk::query]
+fn get_entrants() -> Vec<Entrant> {
+    STATE.with(|s| {
+        let s = s.borrow();
+        let mut entrants: Vec<Entrant> = s.tournament.as_ref()
+            .map(|t| t.entrants.values().cloned().collect())
+            .unwrap_or_default();
+        entrants.sort_by(|a, b| a.busted.cmp(&b.busted).then(b.chips.cmp(&a.chips)));
+        entrants
+    })
+}
+
+#[ic_cdk::query]
+fn get_my_entry() -> Option<Entrant> {
+    let caller = ic_cdk::api::msg_caller();
+    STATE.with(|s| {
+        s.borrow().tournament.as_ref().and_then(|t| t.entrants.get(&caller).cloned())
+    })
+}
+
+// ============================================================================
+// UPGRADE HOOKS - Persist state across upgrades
+// ============================================================================
+
+#[derive(CandidType, Deserialize)]
+struct PersistentState {
+    admin: Option<Principal>,
+    table_pool: Vec<Principal>,
+    next_tournament_id: u64,
+    tournament: Option<Tournament>,
+}
+
+#[ic_cdk::pre_upgrade]
+fn pre_upgrade() {
+    let state = STATE.with(|s| {
+        let s = s.borrow();
+        PersistentState {
+            admin: s.admin,
+            table_pool: s.table_pool.clone(),
+            next_tournament_id: s.next_tournament_id,
+            tournament: s.tournament.clone(),
+        }
+    });
+
+    if let Err(e) = ic_cdk::storage::stable_save((state,)) {
+        ic_cdk::println!("CRITICAL: Failed to save state to stable memory: {:?}", e);
+    }
+}
+
+#[ic_cdk::post_upgrade]
+fn post_upgrade() {
+    let restore_result: Result<(PersistentState,), _> = ic_cdk::storage::stable_restore();
+
+    let state = match restore_result {
+        Ok((s,)) => s,
+        Err(e) => {
+            // FAIL LOUDLY - registrations and unpaid prizes must not be lost
+            panic!("CRITICAL: Failed to restore state from stable memory: {:?}. \
+                    Upgrade REJECTED to protect tournament state.", e);
+        }
+    };
+
+    STATE.with(|s| {
+        let mut new_state = CoordinatorState {
+            admin: state.admin,
+            table_pool: state.table_pool,
+            next_tournament_id: state.next_tournament_id,
+            tournament: state.tournament,
+            ..Default::default()
+        };
+        // Players in flight during the upgrade are picked up again by the clock
+        if let Some(ref mut t) = new_state.tournament {
+            let seated: BTreeSet<Principal> = t.seating.values().flatten().copied().collect();
+            for entrant in t.entrants.values_mut() {
+                if !seated.contains(&entrant.principal) {
+                    entrant.table = None;
+                }
+            }
+        }
+        *s.borrow_mut() = new_state;
+    });
+
+    start_clock();
+}
+
+// Candid export
+ic_cdk::export_candid!();
@@ -0,0 +1,202 @@
+// Unit tests for tournament canister core logic
+// These tests verify pure functions without IC infrastructure
+
+// =============================================================================
+// TYPE DEFINITIONS (mirror the canister types for testing)
+// =============================================================================
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TournamentStatus {
+    Registering,
+    Running,
+    Finished,
+    Cancelled,
+}
+
+// =============================================================================
+// HELPERS (mirror the canister functions for testing)
+// =============================================================================
+
+fn registration_open(status: &TournamentStatus, level: u32, late_registration_levels: u32) -> bool {
+    match status {
+        TournamentStatus::Registering => true,
+        TournamentStatus::Running => level < late_registration_levels,
+        _ => false,
+    }
+}
+
+fn pick_table<T: Copy>(tables: &[(T, usize)], seats_per_table: usize) -> Option<T> {
+    tables.iter()
+        .filter(|(_, count)| *count < seats_per_table)
+        .min_by_key(|(_, count)| *count)
+        .map(|(table, _)| *table)
+}
+
+fn plan_table_moves<T: Copy + PartialEq>(tables: &[(T, usize)], seats_per_table: usize) -> Vec<(T, T)> {
+    let seats = seats_per_table.max(1);
+    let mut counts = tables.to_vec();
+    let mut moves = Vec::new();
+    let total: usize = counts.iter().map(|(_, n)| n).sum();
+
+    // Break tables
+    while counts.len() > 1 && counts.len() > total.div_ceil(seats).max(1) {
+        let shortest = counts.iter()
+            .enumerate()
+            .min_by_key(|(_, (_, n))| *n)
+            .map(|(i, _)| i)
+            .unwrap_or(0);
+        let (broken, players) = counts.remove(shortest);
+        for _ in 0..players {
+            let to = counts.iter_mut()
+                .filter(|(_, n)| *n < seats)
+                .min_by_key(|(_, n)| *n);
+            if let Some((table, n)) = to {
+                *n += 1;
+                moves.push((broken, *table));
+            }
+        }
+    }
+
+    // Balance tables
+    loop {
+        let biggest = counts.iter().enumerate().max_by_key(|(_, (_, n))| *n).map(|(i, _)| i);
+        let smallest = counts.iter().enumerate().min_by_key(|(_, (_, n))| *n).map(|(i, _)| i);
+        match (biggest, smallest) {
+            (Some(b), Some(s)) if counts[b].1 > counts[s].1 + 1 => {
+                counts[b].1 -= 1;
+                counts[s].1 += 1;
+                moves.push((counts[b].0, counts[s].0));
+            }
+            _ => break,
+        }
+    }
+
+    moves
+}
+
+fn finishing_positions<T: Copy + PartialEq>(bust_order: &[T], winner: Option<T>) -> Vec<(T, u32)> {
+    winner.into_iter()
+        .chain(bust_order.iter().rev().copied().filter(|p| Some(*p) != winner))
+        .enumerate()
+        .map(|(i, p)| (p, i as u32 + 1))
+        .collect()
+}
+
+fn missed_busts<T: Copy + PartialEq>(seated_before: &[T], seated_now: &[T], on_table: &[T]) -> Vec<T> {
+    seated_now.iter()
+        .filter(|p| seated_before.contains(p) && !on_table.contains(p))
+        .copied()
+        .collect()
+}
+
+/// Apply planned moves to table counts (test helper)
+fn apply_moves(tables: &[(u32, usize)], moves: &[(u32, u32)]) -> Vec<(u32, usize)> {
+    let mut counts = tables.to_vec();
+    for (from, to) in moves {
+        counts.iter_mut().find(|(t, _)| t == from).unwrap().1 -= 1;
+        counts.iter_mut().find(|(t, _)| t == to).unwrap().1 += 1;
+    }
+    counts.retain(|(_, n)| *n > 0);
+    counts
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // =========================================================================
+    // REGISTRATION TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_late_registration_window() {
+        assert!(registration_open(&TournamentStatus::Registering, 0, 0));
+        // Open through the first 3 levels after the start
+        assert!(registration_open(&TournamentStatus::Running, 2, 3));
+        assert!(!registration_open(&TournamentStatus::Running, 3, 3));
+        // No late registration at all
+        assert!(!registration_open(&TournamentStatus::Running, 0, 0));
+        assert!(!registration_open(&TournamentStatus::Finished, 0, 5));
+        assert!(!registration_open(&TournamentStatus::Cancelled, 0, 5));
+    }
+
+    // =========================================================================
+    // SEATING TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_pick_table_fewest_players_with_free_seat() {
+        assert_eq!(pick_table(&[(1u32, 5), (2, 3), (3, 4)], 6), Some(2));
+        assert_eq!(pick_table(&[(1u32, 6), (2, 6)], 6), None);
+        assert_eq!(pick_table::<u32>(&[], 6), None);
+    }
+
+    #[test]
+    fn test_balanced_tables_need_no_moves() {
+        assert!(plan_table_moves(&[(1u32, 6), (2, 5), (3, 6)], 6).is_empty());
+    }
+
+    #[test]
+    fn test_balance_moves_from_biggest_to_smallest() {
+        // 9 and 6 at 9-handed tables: one player moves over
+        let moves = plan_table_moves(&[(1u32, 9), (2, 6)], 9);
+        assert_eq!(moves, vec![(1, 2)]);
+        assert_eq!(apply_moves(&[(1, 9), (2, 6)], &moves), vec![(1, 8), (2, 7)]);
+    }
+
+    #[test]
+    fn test_break_table_when_field_fits_one_table_fewer() {
+        // 14 players on three 6-max tables fit on 3 tables; 12 fit on 2
+        assert!(plan_table_moves(&[(1u32, 5), (2, 5), (3, 4)], 6).is_empty());
+        let tables = [(1u32, 4), (2, 4), (3, 4)];
+        let moves = plan_table_moves(&tables, 6);
+        assert_eq!(moves.len(), 4);
+        assert!(moves.iter().all(|(from, _)| *from == 1));
+        assert_eq!(apply_moves(&tables, &moves), vec![(2, 6), (3, 6)]);
+    }
+
+    #[test]
+    fn test_final_table_consolidation() {
+        // 9 players left across three 9-max tables end up at one table
+        let tables = [(1u32, 3), (2, 4), (3, 2)];
+        let moves = plan_table_moves(&tables, 9);
+        let remaining = apply_moves(&tables, &moves);
+        assert_eq!(remaining.len(), 1);
+        assert_eq!(remaining[0].1, 9);
+    }
+
+    #[test]
+    fn test_heads_up_across_two_tables() {
+        let moves = plan_table_moves(&[(1u32, 1), (2, 1)], 6);
+        assert_eq!(moves.len(), 1);
+    }
+
+    // =========================================================================
+    // TABLE POLLING TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_missed_busts_from_polled_table() {
+        // 3 busted without a report; 5 was seated while the poll was out
+        let busts = missed_busts(&[1u32, 2, 3], &[1, 2, 3, 5], &[1, 2]);
+        assert_eq!(busts, vec![3]);
+
+        // Moved away during the poll - no longer ours to bust
+        assert!(missed_busts(&[1u32, 2, 3], &[1, 2], &[1, 2]).is_empty());
+        assert!(missed_busts(&[1u32, 2], &[1, 2], &[1, 2]).is_empty());
+    }
+
+    // =========================================================================
+    // RESULTS TESTS
+    // =========================================================================
+
+    #[test]
+    fn test_finishing_positions_last_out_finishes_second() {
+        let positions = finishing_positions(&[10u32, 11, 12], Some(13));
+        assert_eq!(positions, vec![(13, 1), (12, 2), (11, 3), (10, 4)]);
+    }
+}